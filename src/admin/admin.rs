@@ -1,15 +1,27 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use conduwuit::Result;
 
 use crate::{
-	appservice, appservice::AppserviceCommand, check, check::CheckCommand, command::Command,
-	debug, debug::DebugCommand, federation, federation::FederationCommand, media,
-	media::MediaCommand, query, query::QueryCommand, room, room::RoomCommand, server,
-	server::ServerCommand, user, user::UserCommand,
+	appservice, appservice::AppserviceCommand, audit, audit::AuditCommand, check,
+	check::CheckCommand, command::Command, debug, debug::DebugCommand, federation,
+	federation::FederationCommand, jobs, jobs::JobsCommand, media, media::MediaCommand, policy,
+	policy::PolicyCommand, query, query::QueryCommand, room, room::RoomCommand, server,
+	server::ServerCommand, token, token::TokenCommand, user, user::UserCommand,
 };
 
 #[derive(Debug, Parser)]
 #[command(name = "conduwuit", version = conduwuit::version())]
+pub(super) struct AdminArgs {
+	/// Also return the result as JSON: attached as a file to the reply in
+	/// rooms, or printed in place of the reply by the console and --execute.
+	#[arg(long, global = true)]
+	pub(super) json: bool,
+
+	#[command(subcommand)]
+	pub(super) command: AdminCommand,
+}
+
+#[derive(Debug, Subcommand)]
 pub(super) enum AdminCommand {
 	#[command(subcommand)]
 	/// - Commands for managing appservices
@@ -19,6 +31,10 @@ pub(super) enum AdminCommand {
 	/// - Commands for managing local users
 	Users(UserCommand),
 
+	#[command(subcommand)]
+	/// - Commands for managing registration tokens
+	Tokens(TokenCommand),
+
 	#[command(subcommand)]
 	/// - Commands for managing rooms
 	Rooms(RoomCommand),
@@ -27,6 +43,10 @@ pub(super) enum AdminCommand {
 	/// - Commands for managing federation
 	Federation(FederationCommand),
 
+	#[command(subcommand)]
+	/// - Commands for managing policy list subscriptions
+	Policy(PolicyCommand),
+
 	#[command(subcommand)]
 	/// - Commands for managing the server
 	Server(ServerCommand),
@@ -35,6 +55,14 @@ pub(super) enum AdminCommand {
 	/// - Commands for managing media
 	Media(MediaCommand),
 
+	#[command(subcommand)]
+	/// - Commands for managing scheduled maintenance jobs
+	Jobs(JobsCommand),
+
+	#[command(subcommand)]
+	/// - Commands for querying the audit log
+	Audit(AuditCommand),
+
 	#[command(subcommand)]
 	/// - Commands for checking integrity
 	Check(CheckCommand),
@@ -56,12 +84,16 @@ pub(super) async fn process(command: AdminCommand, context: &Command<'_>) -> Res
 		| Appservices(command) => appservice::process(command, context).await?,
 		| Media(command) => media::process(command, context).await?,
 		| Users(command) => user::process(command, context).await?,
+		| Tokens(command) => token::process(command, context).await?,
 		| Rooms(command) => room::process(command, context).await?,
 		| Federation(command) => federation::process(command, context).await?,
+		| Policy(command) => policy::process(command, context).await?,
 		| Server(command) => server::process(command, context).await?,
 		| Debug(command) => debug::process(command, context).await?,
 		| Query(command) => query::process(command, context).await?,
 		| Check(command) => check::process(command, context).await?,
+		| Jobs(command) => jobs::process(command, context).await?,
+		| Audit(command) => audit::process(command, context).await?,
 	}
 
 	Ok(())
@@ -0,0 +1,71 @@
+use conduwuit::{Result, err, utils::time};
+use futures::StreamExt;
+use ruma::{MilliSecondsSinceUnixEpoch, UserId, events::room::message::RoomMessageEventContent};
+use service::audit::{Entry, Filter};
+
+use crate::{Table, admin_command};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+#[admin_command]
+pub(super) async fn query_audit(
+	&self,
+	actor: Option<String>,
+	target: Option<String>,
+	action: Option<String>,
+	since: Option<String>,
+	until: Option<String>,
+	limit: usize,
+) -> Result<RoomMessageEventContent> {
+	let actor = actor
+		.as_deref()
+		.map(UserId::parse)
+		.transpose()
+		.map_err(|e| err!("Invalid actor user ID: {e}"))?;
+
+	let filter = Filter {
+		actor: actor.as_deref(),
+		action: action.as_deref(),
+		target: target.as_deref(),
+		since: since.as_deref().map(time_ago).transpose()?,
+		until: until.as_deref().map(time_ago).transpose()?,
+	};
+
+	let entries: Vec<Entry> = self
+		.services
+		.audit
+		.entries(&filter)
+		.take(limit)
+		.collect()
+		.await;
+
+	let mut table = Table::new(&["ts", "actor", "action", "target", "params"]);
+	for entry in entries {
+		let ts = entry
+			.ts
+			.to_system_time()
+			.map(|ts| time::format(ts, TIME_FORMAT));
+
+		table.row([
+			ts.into(),
+			entry.actor.as_str().into(),
+			entry.action.into(),
+			entry.target.into(),
+			entry.params.to_string().into(),
+		]);
+	}
+
+	self.write_str(&format!("Audit log entries ({}):\n\n", table.len()))
+		.await?;
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+fn time_ago(ago: &str) -> Result<MilliSecondsSinceUnixEpoch> {
+	let ago = time::parse_duration(ago)?;
+	let time = time::timepoint_ago(ago)?;
+
+	MilliSecondsSinceUnixEpoch::from_system_time(time)
+		.ok_or_else(|| err!(Arithmetic("Time {time:?} is out of range")))
+}
@@ -0,0 +1,39 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum AuditCommand {
+	/// - Query the audit log of admin commands and security-sensitive actions,
+	///   most recent first
+	#[clap(alias = "query")]
+	QueryAudit {
+		/// Only entries of actions taken by this user
+		#[arg(long)]
+		actor: Option<String>,
+
+		/// Only entries of actions taken on this user, room or other target
+		#[arg(long)]
+		target: Option<String>,
+
+		/// Only entries of actions starting with this (e.g. admin.users)
+		#[arg(long)]
+		action: Option<String>,
+
+		/// Only entries from this long ago or later (e.g. 7d)
+		#[arg(long)]
+		since: Option<String>,
+
+		/// Only entries from before this long ago (e.g. 1d)
+		#[arg(long)]
+		until: Option<String>,
+
+		/// Maximum number of entries
+		#[arg(long, default_value = "50")]
+		limit: usize,
+	},
+}
@@ -1,7 +1,10 @@
+use std::iter::once;
+
 use conduwuit::Result;
 use conduwuit_macros::implement;
 use futures::StreamExt;
 use ruma::events::room::message::RoomMessageEventContent;
+use service::fsck::Report;
 
 use crate::Command;
 
@@ -26,3 +29,64 @@ pub(super) async fn check_all_users(&self) -> Result<RoomMessageEventContent> {
 
 	Ok(RoomMessageEventContent::notice_markdown(message))
 }
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn all(&self, fix: bool) -> Result<RoomMessageEventContent> {
+	let reports = self.services.fsck.check_all(fix).await?;
+
+	Ok(report(reports.iter()))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn event_pdus(&self, fix: bool) -> Result<RoomMessageEventContent> {
+	let report = self.services.fsck.check_event_pdus(fix).await?;
+
+	Ok(self::report(once(&report)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn pdu_events(&self, fix: bool) -> Result<RoomMessageEventContent> {
+	let report = self.services.fsck.check_pdu_events(fix).await?;
+
+	Ok(self::report(once(&report)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn short_event_ids(&self, fix: bool) -> Result<RoomMessageEventContent> {
+	let report = self.services.fsck.check_short_event_ids(fix).await?;
+
+	Ok(self::report(once(&report)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn state_diffs(&self) -> Result<RoomMessageEventContent> {
+	let report = self.services.fsck.check_state_diffs().await?;
+
+	Ok(self::report(once(&report)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn room_memberships(&self, fix: bool) -> Result<RoomMessageEventContent> {
+	let report = self.services.fsck.check_room_memberships(fix).await?;
+
+	Ok(self::report(once(&report)))
+}
+
+#[implement(Command, params = "<'_>")]
+pub(super) async fn joined_counts(&self, fix: bool) -> Result<RoomMessageEventContent> {
+	let report = self.services.fsck.check_joined_counts(fix).await?;
+
+	Ok(self::report(once(&report)))
+}
+
+fn report<'a, I>(reports: I) -> RoomMessageEventContent
+where
+	I: Iterator<Item = &'a Report>,
+{
+	let out: String = reports
+		.map(ToString::to_string)
+		.collect::<Vec<_>>()
+		.join("\n");
+
+	RoomMessageEventContent::notice_markdown(format!("```\n{out}```"))
+}
@@ -9,4 +9,46 @@
 #[derive(Debug, Subcommand)]
 pub(super) enum CheckCommand {
 	CheckAllUsers,
+
+	/// - Run every database consistency check; problems are only reported
+	///   unless --fix is given
+	All {
+		/// Repair the problems which can be repaired
+		#[arg(long)]
+		fix: bool,
+	},
+
+	/// - Check that every event's PDU id points at an existing PDU
+	EventPdus {
+		#[arg(long)]
+		fix: bool,
+	},
+
+	/// - Check that every PDU is indexed by its event id
+	PduEvents {
+		#[arg(long)]
+		fix: bool,
+	},
+
+	/// - Check that short event ids and event ids map to each other
+	ShortEventIds {
+		#[arg(long)]
+		fix: bool,
+	},
+
+	/// - Check that the parent of every state diff and the current state of
+	///   every room exist; these can only be reported
+	StateDiffs,
+
+	/// - Check that the joined members of each room agree with its state
+	RoomMemberships {
+		#[arg(long)]
+		fix: bool,
+	},
+
+	/// - Check that the joined and invited counts of each room are correct
+	JoinedCounts {
+		#[arg(long)]
+		fix: bool,
+	},
 }
@@ -8,6 +8,9 @@
 	lock::Mutex,
 };
 use ruma::EventId;
+use serde_json::Value as JsonValue;
+
+use crate::output::Output;
 
 pub(crate) struct Command<'a> {
 	pub(crate) services: &'a Services,
@@ -15,6 +18,7 @@ pub(crate) struct Command<'a> {
 	pub(crate) timer: SystemTime,
 	pub(crate) reply_id: Option<&'a EventId>,
 	pub(crate) output: Mutex<BufWriter<Vec<u8>>>,
+	pub(crate) json: Mutex<Vec<JsonValue>>,
 }
 
 impl Command<'_> {
@@ -36,4 +40,35 @@ pub(crate) fn write_str<'a>(
 			output.write_all(s.as_bytes()).await.map_err(Into::into)
 		})
 	}
+
+	/// Writes a structured result as markdown to the output, keeping it for
+	/// the JSON result of the command.
+	pub(crate) async fn write_output<O: Into<Output>>(&self, output: O) -> Result {
+		let output = output.into();
+		self.write_str(&output.markdown()).await?;
+		self.json.lock().await.push(output.json());
+
+		Ok(())
+	}
+
+	/// Whether only the admin running the command can read its output: it was
+	/// run from the console or from a room they are alone in.
+	pub(crate) async fn output_is_private(&self) -> bool {
+		let Some(reply_id) = self.reply_id else {
+			return true;
+		};
+
+		let Ok(pdu) = self.services.rooms.timeline.get_pdu(reply_id).await else {
+			return false;
+		};
+
+		!self.services.admin.is_admin_room(&pdu.room_id).await
+			&& self
+				.services
+				.rooms
+				.state_cache
+				.room_joined_count(&pdu.room_id)
+				.await
+				.is_ok_and(|count| count <= 1)
+	}
 }
@@ -410,8 +410,9 @@ pub(super) async fn force_device_list_updates(&self) -> Result<RoomMessageEventC
 	self.services
 		.users
 		.stream()
-		.for_each(|user_id| self.services.users.mark_device_key_update(user_id))
-		.await;
+		.map(Ok)
+		.try_for_each(|user_id| self.services.users.mark_device_key_update(user_id))
+		.await?;
 
 	Ok(RoomMessageEventContent::text_plain(
 		"Marked all devices for all users as having new keys to update",
@@ -979,7 +980,12 @@ pub(super) async fn database_files(
 	map: Option<String>,
 	level: Option<i32>,
 ) -> Result<RoomMessageEventContent> {
-	let mut files: Vec<_> = self.services.db.db.file_list().collect::<Result<_>>()?;
+	let mut files: Vec<_> = self
+		.services
+		.db
+		.engine()?
+		.file_list()
+		.collect::<Result<_>>()?;
 
 	files.sort_by_key(|f| f.name.clone());
 
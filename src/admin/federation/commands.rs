@@ -1,6 +1,6 @@
-use std::fmt::Write;
+use std::{fmt::Write, sync::atomic::Ordering, time::Duration};
 
-use conduwuit::Result;
+use conduwuit::{Result, utils::time::pretty};
 use futures::StreamExt;
 use ruma::{
 	OwnedRoomId, RoomId, ServerName, UserId, events::room::message::RoomMessageEventContent,
@@ -20,6 +20,78 @@ pub(super) async fn enable_room(&self, room_id: Box<RoomId>) -> Result<RoomMessa
 	Ok(RoomMessageEventContent::text_plain("Room enabled."))
 }
 
+#[admin_command]
+pub(super) async fn inbound_queue(
+	&self,
+	server_name: Option<Box<ServerName>>,
+) -> Result<RoomMessageEventContent> {
+	let mut origins = self.services.federation.inbound_origins();
+	origins.retain(|(origin, _)| server_name.as_deref().is_none_or(|name| name == origin));
+	origins.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+	let mut msg = format!(
+		"{} origins:\n| Origin | Paused | Queued | Active | Transactions | Rejected | PDUs | \
+		 EDUs | Busy | Waited |\n| --- | --- | --- | --- | --- | --- | --- | --- | --- | --- |\n",
+		origins.len()
+	);
+
+	for (origin, entry) in origins {
+		let busy = Duration::from_micros(entry.busy.load(Ordering::Relaxed));
+		let waited = Duration::from_micros(entry.waited.load(Ordering::Relaxed));
+		writeln!(
+			msg,
+			"| {origin} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+			entry.is_paused(),
+			entry.queued.load(Ordering::Relaxed),
+			entry.active.load(Ordering::Relaxed),
+			entry.transactions.load(Ordering::Relaxed),
+			entry.rejected.load(Ordering::Relaxed),
+			entry.pdus.load(Ordering::Relaxed),
+			entry.edus.load(Ordering::Relaxed),
+			pretty(busy),
+			pretty(waited),
+		)?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(msg))
+}
+
+#[admin_command]
+pub(super) async fn pause_origin(
+	&self,
+	server_name: Box<ServerName>,
+) -> Result<RoomMessageEventContent> {
+	if self.services.globals.server_is_ours(&server_name) {
+		return Ok(RoomMessageEventContent::text_plain("Cannot pause our own server."));
+	}
+
+	if !self.services.federation.pause_origin(&server_name) {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Transactions from this server are already paused.",
+		));
+	}
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Paused inbound transactions from {server_name}."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn resume_origin(
+	&self,
+	server_name: Box<ServerName>,
+) -> Result<RoomMessageEventContent> {
+	if !self.services.federation.resume_origin(&server_name) {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Transactions from this server are not paused.",
+		));
+	}
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Resumed inbound transactions from {server_name}."
+	)))
+}
+
 #[admin_command]
 pub(super) async fn incoming_federation(&self) -> Result<RoomMessageEventContent> {
 	let map = self
@@ -39,4 +39,21 @@ pub(super) enum FederationCommand {
 	RemoteUserInRooms {
 		user_id: Box<UserId>,
 	},
+
+	/// - Show the inbound transaction queue of every origin, or of one origin
+	InboundQueue {
+		server_name: Option<Box<ServerName>>,
+	},
+
+	/// - Refuse inbound transactions from a server until resumed
+	///
+	/// The server is asked to retry later, so nothing it sends is lost.
+	PauseOrigin {
+		server_name: Box<ServerName>,
+	},
+
+	/// - Resume accepting inbound transactions from a paused server
+	ResumeOrigin {
+		server_name: Box<ServerName>,
+	},
 }
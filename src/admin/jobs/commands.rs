@@ -0,0 +1,104 @@
+use conduwuit::{Result, utils::time};
+use ruma::events::room::message::RoomMessageEventContent;
+use service::jobs::{Job, Run};
+
+use crate::{Table, admin_command};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+#[admin_command]
+pub(super) async fn status(&self) -> Result<RoomMessageEventContent> {
+	let mut table = Table::new(&["job", "schedule", "state", "next_run", "last_result"]);
+	for status in self.services.jobs.status() {
+		let schedule = status
+			.schedule
+			.as_ref()
+			.map_or_else(|| "manual".to_owned(), ToString::to_string);
+
+		let state = match (status.running, status.paused) {
+			| (true, _) => "running",
+			| (false, true) => "paused",
+			| (false, false) => "idle",
+		};
+
+		let next = status.next.map(|next| time::format(next, TIME_FORMAT));
+
+		let last = status.history.first().map(result);
+
+		table.row([
+			status.job.name().into(),
+			schedule.into(),
+			state.into(),
+			next.into(),
+			last.into(),
+		]);
+	}
+
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn history(&self, job: Job) -> Result<RoomMessageEventContent> {
+	let history = self
+		.services
+		.jobs
+		.status()
+		.into_iter()
+		.find(|status| status.job == job)
+		.map(|status| status.history)
+		.unwrap_or_default();
+
+	if history.is_empty() {
+		self.write_str(&format!("Job {job} has not run yet.\n\n"))
+			.await?;
+	}
+
+	let mut table = Table::new(&["started", "duration", "trigger", "result"]);
+	for run in &history {
+		let trigger = if run.manual { "manual" } else { "schedule" };
+		table.row([
+			time::format(run.started, TIME_FORMAT).into(),
+			time::pretty(run.elapsed).into(),
+			trigger.into(),
+			result(run).into(),
+		]);
+	}
+
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn run(&self, job: Job) -> Result<RoomMessageEventContent> {
+	let run = self.services.jobs.trigger(job).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Job {job} ran for {}: {}",
+		time::pretty(run.elapsed),
+		result(&run),
+	)))
+}
+
+#[admin_command]
+pub(super) async fn pause(&self, job: Job) -> Result<RoomMessageEventContent> {
+	self.services.jobs.pause(job)?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!("Paused job {job}.")))
+}
+
+#[admin_command]
+pub(super) async fn resume(&self, job: Job) -> Result<RoomMessageEventContent> {
+	self.services.jobs.resume(job)?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!("Resumed job {job}.")))
+}
+
+fn result(run: &Run) -> String {
+	match &run.result {
+		| Ok(summary) => summary.replace('\n', " "),
+		| Err(e) => format!("failed: {e}"),
+	}
+}
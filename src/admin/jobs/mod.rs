@@ -0,0 +1,36 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+use service::jobs::Job;
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum JobsCommand {
+	/// - Show every maintenance job with its schedule, state, next run and
+	///   last result
+	Status,
+
+	/// - Show the most recent runs of a job
+	History {
+		job: Job,
+	},
+
+	/// - Run a job now and wait for it to complete
+	Run {
+		job: Job,
+	},
+
+	/// - Stop running a job on its schedule until it is resumed or the server
+	///   restarts
+	Pause {
+		job: Job,
+	},
+
+	/// - Run a paused job on its schedule again
+	Resume {
+		job: Job,
+	},
+}
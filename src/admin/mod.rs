@@ -5,18 +5,23 @@
 
 pub(crate) mod admin;
 pub(crate) mod command;
+pub(crate) mod output;
 pub(crate) mod processor;
 mod tests;
 pub(crate) mod utils;
 
 pub(crate) mod appservice;
+pub(crate) mod audit;
 pub(crate) mod check;
 pub(crate) mod debug;
 pub(crate) mod federation;
+pub(crate) mod jobs;
 pub(crate) mod media;
+pub(crate) mod policy;
 pub(crate) mod query;
 pub(crate) mod room;
 pub(crate) mod server;
+pub(crate) mod token;
 pub(crate) mod user;
 
 extern crate conduwuit_api as api;
@@ -28,6 +33,7 @@
 
 pub(crate) use crate::{
 	command::Command,
+	output::{Record, Table},
 	utils::{escape_html, get_room_info},
 };
 
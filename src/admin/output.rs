@@ -0,0 +1,155 @@
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+
+/// Structured result of a command, written with `Command::write_output()`.
+/// Rendered as markdown into the reply, and returned as JSON when the command
+/// was given `--json`.
+pub(crate) enum Output {
+	Table(Table),
+	Record(Record),
+}
+
+/// Rows of values under named columns. Rendered as a markdown table; in JSON
+/// an array with an object per row keyed by column.
+pub(crate) struct Table {
+	columns: Vec<&'static str>,
+	rows: Vec<Vec<Value>>,
+}
+
+/// Named values, followed by named tables. Rendered as a markdown list with a
+/// titled table after it for each table; in JSON an object with a field for
+/// each value and table.
+#[derive(Default)]
+pub(crate) struct Record {
+	fields: Vec<(&'static str, Value)>,
+	tables: Vec<(&'static str, Table)>,
+}
+
+impl Output {
+	pub(crate) fn markdown(&self) -> String {
+		match self {
+			| Self::Table(table) => table.markdown(),
+			| Self::Record(record) => record.markdown(),
+		}
+	}
+
+	pub(crate) fn json(self) -> Value {
+		match self {
+			| Self::Table(table) => table.json(),
+			| Self::Record(record) => record.json(),
+		}
+	}
+}
+
+impl From<Table> for Output {
+	fn from(table: Table) -> Self { Self::Table(table) }
+}
+
+impl From<Record> for Output {
+	fn from(record: Record) -> Self { Self::Record(record) }
+}
+
+impl Table {
+	pub(crate) fn new(columns: &[&'static str]) -> Self {
+		Self {
+			columns: columns.to_vec(),
+			rows: Vec::new(),
+		}
+	}
+
+	/// Adds a row with a value for each column, in order.
+	pub(crate) fn row<I>(&mut self, row: I)
+	where
+		I: IntoIterator<Item = Value>,
+	{
+		let row: Vec<Value> = row.into_iter().collect();
+		debug_assert_eq!(row.len(), self.columns.len(), "a value for each column");
+		self.rows.push(row);
+	}
+
+	#[must_use]
+	pub(crate) fn len(&self) -> usize { self.rows.len() }
+
+	fn markdown(&self) -> String {
+		let mut out = String::new();
+		writeln!(out, "| {} |", self.columns.join(" | ")).expect("written");
+		writeln!(out, "|{}", " --- |".repeat(self.columns.len())).expect("written");
+		for row in &self.rows {
+			let row: Vec<_> = row.iter().map(cell).collect();
+			writeln!(out, "| {} |", row.join(" | ")).expect("written");
+		}
+
+		out
+	}
+
+	fn json(self) -> Value {
+		self.rows
+			.into_iter()
+			.map(|row| {
+				let row = self.columns.iter().map(ToString::to_string).zip(row);
+				Value::Object(row.collect())
+			})
+			.collect()
+	}
+}
+
+impl Record {
+	#[must_use]
+	pub(crate) fn new() -> Self { Self::default() }
+
+	#[must_use]
+	pub(crate) fn field<V: Into<Value>>(mut self, name: &'static str, value: V) -> Self {
+		self.fields.push((name, value.into()));
+		self
+	}
+
+	/// Adds a table, titled in markdown by its capitalized name.
+	#[must_use]
+	pub(crate) fn table(mut self, name: &'static str, table: Table) -> Self {
+		self.tables.push((name, table));
+		self
+	}
+
+	fn markdown(&self) -> String {
+		let mut out = String::new();
+		for (name, value) in &self.fields {
+			writeln!(out, "- {name}: {}", cell(value)).expect("written");
+		}
+
+		for (name, table) in &self.tables {
+			let (first, rest) = name.split_at(1);
+			let title = first.to_uppercase() + rest;
+			write!(out, "\n{title}:\n\n{}", table.markdown()).expect("written");
+		}
+
+		out
+	}
+
+	fn json(self) -> Value {
+		let tables = self
+			.tables
+			.into_iter()
+			.map(|(name, table)| (name, table.json()));
+
+		Value::Object(
+			self.fields
+				.into_iter()
+				.chain(tables)
+				.map(|(name, value)| (name.to_owned(), value))
+				.collect::<Map<_, _>>(),
+		)
+	}
+}
+
+/// Renders a value for a markdown table cell or list item.
+fn cell(value: &Value) -> String {
+	let text = match value {
+		| Value::Null => String::new(),
+		| Value::String(string) => string.clone(),
+		| Value::Array(values) => values.iter().map(cell).collect::<Vec<_>>().join(", "),
+		| value => value.to_string(),
+	};
+
+	text.replace('|', "\\|").replace('\n', " ")
+}
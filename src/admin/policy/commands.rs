@@ -0,0 +1,131 @@
+use std::fmt::Write;
+
+use api::client::join_room_by_id_helper;
+use conduwuit::{Result, utils::stream::IterStream};
+use futures::StreamExt;
+use ruma::{
+	OwnedRoomId, OwnedRoomOrAliasId, UserId, events::room::message::RoomMessageEventContent,
+};
+use service::policy::Kind;
+
+use crate::{admin_command, get_room_info};
+
+#[admin_command]
+pub(super) async fn subscribe(
+	&self,
+	room: OwnedRoomOrAliasId,
+) -> Result<RoomMessageEventContent> {
+	let (room_id, mut servers) = self
+		.services
+		.rooms
+		.alias
+		.resolve_with_servers(&room, None)
+		.await?;
+
+	if let Some(server_name) = room.server_name() {
+		servers.push(server_name.to_owned());
+	}
+
+	let server_user = &self.services.globals.server_user;
+	if !self
+		.services
+		.rooms
+		.state_cache
+		.is_joined(server_user, &room_id)
+		.await
+	{
+		join_room_by_id_helper(
+			self.services,
+			server_user,
+			&room_id,
+			Some("Subscribing to this policy list".to_owned()),
+			&servers,
+			None,
+			&None,
+		)
+		.await?;
+	}
+
+	let rules = self.services.policy.subscribe(&room_id).await;
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Subscribed to policy list {room_id} with {rules} ban rules."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unsubscribe(
+	&self,
+	room: OwnedRoomOrAliasId,
+) -> Result<RoomMessageEventContent> {
+	let room_id = self.services.rooms.alias.resolve(&room).await?;
+	if !self.services.policy.unsubscribe(&room_id).await {
+		return Ok(RoomMessageEventContent::text_plain("Not subscribed to this policy list."));
+	}
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Unsubscribed from policy list {room_id}. The server user is still in the room."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn list(&self) -> Result<RoomMessageEventContent> {
+	let rooms: Vec<OwnedRoomId> = self
+		.services
+		.policy
+		.subscriptions()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let rooms: Vec<_> = rooms
+		.iter()
+		.stream()
+		.then(|room_id| get_room_info(self.services, room_id))
+		.collect()
+		.await;
+
+	let mut msg = format!("Subscribed to {} policy lists:\n", rooms.len());
+	for (room_id, _, name) in rooms {
+		let rules = self.services.policy.room_rule_count(&room_id);
+		writeln!(msg, "- {room_id} ({name}): {rules} ban rules")?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(msg))
+}
+
+#[admin_command]
+pub(super) async fn check(&self, entity: String) -> Result<RoomMessageEventContent> {
+	let mut rules = self.services.policy.matching_rules(&entity);
+	if let Ok(user_id) = UserId::parse(&entity) {
+		rules.extend(
+			self.services
+				.policy
+				.matching_rules(user_id.server_name().host())
+				.into_iter()
+				.filter(|rule| rule.kind == Kind::Server),
+		);
+	}
+
+	if rules.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"{entity} is not banned by any subscribed policy list."
+		)));
+	}
+
+	let mut msg = format!(
+		"{entity} matches {} rules:\n| Kind | Entity | Reason | Policy room |\n| --- | --- | \
+		 --- | --- |\n",
+		rules.len()
+	);
+
+	for rule in rules {
+		writeln!(
+			msg,
+			"| {:?} | `{}` | {} | {} |",
+			rule.kind, rule.entity, rule.reason, rule.room_id
+		)?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(msg))
+}
@@ -0,0 +1,37 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+use ruma::OwnedRoomOrAliasId;
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum PolicyCommand {
+	/// - Subscribe to a policy list room and enforce its ban rules
+	///
+	/// The server user joins the room if we are not already in it. Users,
+	/// rooms and servers matching an `m.ban` rule are refused invites, joins
+	/// and federation from then on.
+	Subscribe {
+		/// The room in the format of `!roomid:example.com` or a room alias in
+		/// the format of `#roomalias:example.com`
+		room: OwnedRoomOrAliasId,
+	},
+
+	/// - Stop enforcing the rules of a policy list room
+	Unsubscribe {
+		/// The room in the format of `!roomid:example.com` or a room alias in
+		/// the format of `#roomalias:example.com`
+		room: OwnedRoomOrAliasId,
+	},
+
+	/// - List the subscribed policy list rooms
+	List,
+
+	/// - Show the rules matching a user ID, room ID or server name
+	Check {
+		entity: String,
+	},
+}
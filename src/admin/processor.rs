@@ -6,7 +6,7 @@
 	time::SystemTime,
 };
 
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, parser::ValueSource};
 use conduwuit::{
 	Error, Result, debug, error,
 	log::{
@@ -26,6 +26,7 @@
 		room::message::{Relation::Reply, RoomMessageEventContent},
 	},
 };
+use serde_json::Value as JsonValue;
 use service::{
 	Services,
 	admin::{CommandInput, CommandOutput, ProcessorFuture, ProcessorResult},
@@ -33,10 +34,13 @@
 use tracing::Level;
 use tracing_subscriber::{EnvFilter, filter::LevelFilter};
 
-use crate::{Command, admin, admin::AdminCommand};
+use crate::{
+	Command, admin,
+	admin::{AdminArgs, AdminCommand},
+};
 
 #[must_use]
-pub(super) fn complete(line: &str) -> String { complete_command(AdminCommand::command(), line) }
+pub(super) fn complete(line: &str) -> String { complete_command(AdminArgs::command(), line) }
 
 #[must_use]
 pub(super) fn dispatch(services: Arc<Services>, command: CommandInput) -> ProcessorFuture {
@@ -53,20 +57,24 @@ async fn handle_command(services: Arc<Services>, command: CommandInput) -> Proce
 }
 
 async fn process_command(services: Arc<Services>, input: &CommandInput) -> ProcessorResult {
-	let (command, args, body) = match parse(&services, input) {
+	let (AdminArgs { command, json }, args, body) = match parse(&services, input) {
 		| Err(error) => return Err(error),
 		| Ok(parsed) => parsed,
 	};
 
+	let json = json || input.json;
+
 	let context = Command {
 		services: &services,
 		body: &body,
 		timer: SystemTime::now(),
 		reply_id: input.reply_id.as_deref(),
 		output: BufWriter::new(Vec::new()).into(),
+		json: Vec::new().into(),
 	};
 
 	let (result, mut logs) = process(&context, command, &args).await;
+	audit(&services, input, &args, body.len(), result.is_ok()).await;
 
 	let output = &mut context.output.lock().await;
 	output.flush().await.expect("final flush of output stream");
@@ -74,23 +82,133 @@ async fn process_command(services: Arc<Services>, input: &CommandInput) -> Proce
 	let output =
 		String::from_utf8(take(output.get_mut())).expect("invalid utf8 in command output stream");
 
+	let results = take(&mut *context.json.lock().await);
+	let json = json.then(|| match &result {
+		| Ok(()) => json_output(results, &output),
+		| Err(error) => serde_json::json!({ "error": error.to_string() }),
+	});
+
 	match result {
-		| Ok(()) if logs.is_empty() =>
-			Ok(Some(reply(RoomMessageEventContent::notice_markdown(output), context.reply_id))),
+		| Ok(()) if logs.is_empty() => Ok(Some(reply(
+			RoomMessageEventContent::notice_markdown(output),
+			context.reply_id,
+			json,
+		))),
 
 		| Ok(()) => {
 			logs.write_str(output.as_str()).expect("output buffer");
-			Ok(Some(reply(RoomMessageEventContent::notice_markdown(logs), context.reply_id)))
+			Ok(Some(reply(
+				RoomMessageEventContent::notice_markdown(logs),
+				context.reply_id,
+				json,
+			)))
 		},
 		| Err(error) => {
 			write!(&mut logs, "Command failed with error:\n```\n{error:#?}\n```")
 				.expect("output buffer");
 
-			Err(reply(RoomMessageEventContent::notice_markdown(logs), context.reply_id))
+			Err(reply(RoomMessageEventContent::notice_markdown(logs), context.reply_id, json))
 		},
 	}
 }
 
+/// Arguments naming what a command acts on; the first one given is recorded as
+/// the target in the audit log.
+const AUDIT_TARGET_ARGS: &[&str] = &[
+	"user_id",
+	"username",
+	"room_id",
+	"room",
+	"server_name",
+	"server",
+	"event_id",
+	"mxc",
+	"appservice_identifier",
+];
+
+/// Records a command in the audit log, with the arguments given to it except
+/// secrets.
+async fn audit(
+	services: &Services,
+	input: &CommandInput,
+	args: &[String],
+	lines: usize,
+	ok: bool,
+) {
+	let Ok(matches) = AdminArgs::command().try_get_matches_from(args) else {
+		return;
+	};
+
+	let mut action = vec!["admin"];
+	let mut matches = &matches;
+	while let Some((name, subcommand)) = matches.subcommand() {
+		action.push(name);
+		matches = subcommand;
+	}
+
+	let mut target = None;
+	let mut params = serde_json::Map::new();
+	for id in matches.ids().map(clap::Id::as_str) {
+		if matches.value_source(id) != Some(ValueSource::CommandLine) {
+			continue;
+		}
+
+		let Ok(Some(values)) = matches.try_get_raw(id) else {
+			continue;
+		};
+
+		let values: Vec<String> = values
+			.map(|value| value.to_string_lossy().into_owned())
+			.collect();
+
+		if is_secret_arg(id) {
+			params.insert(id.to_owned(), "<redacted>".into());
+			continue;
+		}
+
+		if target.is_none() && AUDIT_TARGET_ARGS.contains(&id) {
+			target = values.first().cloned();
+		}
+
+		let value = match <[String; 1]>::try_from(values) {
+			| Ok([value]) => value.into(),
+			| Err(values) => values.into(),
+		};
+
+		params.insert(id.to_owned(), value);
+	}
+
+	if lines > 0 {
+		params.insert("body_lines".to_owned(), lines.into());
+	}
+
+	params.insert("ok".to_owned(), ok.into());
+
+	let actor = input
+		.sender
+		.as_deref()
+		.unwrap_or(&*services.globals.server_user);
+
+	services
+		.audit
+		.record(actor, &action.join("."), target.as_deref(), params.into())
+		.await;
+}
+
+/// Arguments holding passwords or tokens, which are redacted from the audit
+/// log.
+pub(crate) fn is_secret_arg(id: &str) -> bool { id.contains("password") || id.contains("token") }
+
+/// The JSON result of a command: the structured results it wrote, or its text
+/// when it wrote none.
+fn json_output(mut results: Vec<JsonValue>, output: &str) -> JsonValue {
+	match results.len() {
+		| 0 => serde_json::json!({ "output": output }),
+		| 1 => results.pop().expect("one result"),
+		| _ => JsonValue::Array(results),
+	}
+}
+
 #[allow(clippy::result_large_err)]
 fn handle_panic(error: &Error, command: &CommandInput) -> ProcessorResult {
 	let link =
@@ -98,7 +216,11 @@ fn handle_panic(error: &Error, command: &CommandInput) -> ProcessorResult {
 	let msg = format!("Panic occurred while processing command:\n```\n{error:#?}\n```\n{link}");
 	let content = RoomMessageEventContent::notice_markdown(msg);
 	error!("Panic while processing command: {error:?}");
-	Err(reply(content, command.reply_id.as_deref()))
+	let json = command
+		.json
+		.then(|| serde_json::json!({ "error": error.to_string() }));
+
+	Err(reply(content, command.reply_id.as_deref(), json))
 }
 
 /// Parse and process a message from the admin room
@@ -170,7 +292,7 @@ fn capture_create(context: &Command<'_>) -> (Arc<Capture>, Arc<Mutex<String>>) {
 fn parse<'a>(
 	services: &Arc<Services>,
 	input: &'a CommandInput,
-) -> Result<(AdminCommand, Vec<String>, Vec<&'a str>), CommandOutput> {
+) -> Result<(AdminArgs, Vec<String>, Vec<&'a str>), CommandOutput> {
 	let lines = input.command.lines().filter(|line| !line.trim().is_empty());
 	let command_line = lines.clone().next().expect("command missing first line");
 	let body = lines.skip(1).collect();
@@ -180,14 +302,22 @@ fn parse<'a>(
 			let message = error
 				.to_string()
 				.replace("server.name", services.globals.server_name().as_str());
-			Err(reply(RoomMessageEventContent::notice_plain(message), input.reply_id.as_deref()))
+			let json = input
+				.json
+				.then(|| serde_json::json!({ "error": message.clone() }));
+
+			Err(reply(
+				RoomMessageEventContent::notice_plain(message),
+				input.reply_id.as_deref(),
+				json,
+			))
 		},
 	}
 }
 
-fn parse_command(line: &str) -> Result<(AdminCommand, Vec<String>)> {
+fn parse_command(line: &str) -> Result<(AdminArgs, Vec<String>)> {
 	let argv = parse_line(line);
-	let command = AdminCommand::try_parse_from(&argv)?;
+	let command = AdminArgs::try_parse_from(&argv)?;
 	Ok((command, argv))
 }
 
@@ -281,10 +411,11 @@ fn parse_line(command_line: &str) -> Vec<String> {
 fn reply(
 	mut content: RoomMessageEventContent,
 	reply_id: Option<&EventId>,
-) -> RoomMessageEventContent {
+	json: Option<JsonValue>,
+) -> CommandOutput {
 	content.relates_to = reply_id.map(|event_id| Reply {
 		in_reply_to: InReplyTo { event_id: event_id.to_owned() },
 	});
 
-	content
+	CommandOutput { content, json }
 }
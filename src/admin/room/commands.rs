@@ -1,8 +1,11 @@
-use conduwuit::Result;
+use std::fmt::Write;
+
+use conduwuit::{Err, Result};
 use futures::StreamExt;
-use ruma::{OwnedRoomId, events::room::message::RoomMessageEventContent};
+use ruma::{OwnedRoomId, OwnedServerName, events::room::message::RoomMessageEventContent};
+use service::rooms::event_handler::StateRepair;
 
-use crate::{PAGE_SIZE, admin_command, get_room_info};
+use crate::{PAGE_SIZE, Table, admin_command, get_room_info};
 
 #[admin_command]
 pub(super) async fn list_rooms(
@@ -40,25 +43,30 @@ pub(super) async fn list_rooms(
 		.take(PAGE_SIZE)
 		.collect::<Vec<_>>();
 
-	if rooms.is_empty() {
-		return Ok(RoomMessageEventContent::text_plain("No more rooms."));
+	let heading = if rooms.is_empty() {
+		"No more rooms.".to_owned()
+	} else {
+		format!("Rooms ({}):", rooms.len())
+	};
+
+	let mut table = if no_details {
+		Table::new(&["room_id"])
+	} else {
+		Table::new(&["room_id", "members", "name"])
+	};
+
+	for (room_id, members, name) in rooms {
+		if no_details {
+			table.row([room_id.as_str().into()]);
+		} else {
+			table.row([room_id.as_str().into(), members.into(), name.into()]);
+		}
 	}
 
-	let output_plain = format!(
-		"Rooms ({}):\n```\n{}\n```",
-		rooms.len(),
-		rooms
-			.iter()
-			.map(|(id, members, name)| if no_details {
-				format!("{id}")
-			} else {
-				format!("{id}\tMembers: {members}\tName: {name}")
-			})
-			.collect::<Vec<_>>()
-			.join("\n")
-	);
+	self.write_str(&format!("{heading}\n\n")).await?;
+	self.write_output(table).await?;
 
-	Ok(RoomMessageEventContent::notice_markdown(output_plain))
+	Ok(RoomMessageEventContent::text_plain(""))
 }
 
 #[admin_command]
@@ -67,3 +75,173 @@ pub(super) async fn exists(&self, room_id: OwnedRoomId) -> Result<RoomMessageEve
 
 	Ok(RoomMessageEventContent::notice_markdown(format!("{result}")))
 }
+
+#[admin_command]
+pub(super) async fn recompute_memberships(
+	&self,
+	room_id: Option<OwnedRoomId>,
+) -> Result<RoomMessageEventContent> {
+	let state_cache = &self.services.rooms.state_cache;
+	let rooms: Vec<OwnedRoomId> = match room_id {
+		| Some(room_id) => vec![room_id],
+		| None =>
+			self.services
+				.rooms
+				.metadata
+				.iter_ids()
+				.map(ToOwned::to_owned)
+				.collect()
+				.await,
+	};
+
+	let (mut unchanged, mut skipped, mut failed) = (0_usize, 0_usize, 0_usize);
+	let mut out = String::new();
+	for room_id in &rooms {
+		match state_cache.recompute_memberships(room_id).await {
+			| Ok(None) => skipped = skipped.saturating_add(1),
+			| Ok(Some(recomputed)) if recomputed.is_unchanged() =>
+				unchanged = unchanged.saturating_add(1),
+			| Ok(Some(recomputed)) => write!(out, "{recomputed}")?,
+			| Err(e) => {
+				failed = failed.saturating_add(1);
+				writeln!(out, "{room_id}: failed: {e}")?;
+			},
+		}
+	}
+
+	let changed = rooms
+		.len()
+		.saturating_sub(unchanged)
+		.saturating_sub(skipped)
+		.saturating_sub(failed);
+
+	let mut summary = format!(
+		"Recomputed the memberships of {} rooms: {changed} changed, {unchanged} unchanged, \
+		 {skipped} without state skipped, {failed} failed.",
+		rooms.len(),
+	);
+
+	if !out.is_empty() {
+		write!(summary, "\n\n```\n{out}```")?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(summary))
+}
+
+#[admin_command]
+pub(super) async fn repair_state(
+	&self,
+	room_id: OwnedRoomId,
+	server: Vec<OwnedServerName>,
+	apply: bool,
+	cancel: bool,
+) -> Result<RoomMessageEventContent> {
+	let event_handler = &self.services.rooms.event_handler;
+	if cancel {
+		return Ok(RoomMessageEventContent::notice_markdown(
+			if event_handler.cancel_state_repair(&room_id) {
+				"Discarded the pending state repair."
+			} else {
+				"There is no pending state repair for this room."
+			},
+		));
+	}
+
+	if apply {
+		let repair = event_handler.apply_state_repair(&room_id).await?;
+		return Ok(RoomMessageEventContent::notice_markdown(format!(
+			"Applied the resolved room state ({} changed entries) with {} as the forward \
+			 extremity.",
+			repair.changes.len(),
+			repair.event_id,
+		)));
+	}
+
+	if !self
+		.services
+		.rooms
+		.state_cache
+		.server_in_room(&self.services.server.name, &room_id)
+		.await
+	{
+		return Err!("We are not participating in the room / we don't know about the room ID.");
+	}
+
+	let repair = event_handler.prepare_state_repair(&room_id, server).await?;
+
+	let mut out = format_state_repair(&repair)?;
+	writeln!(
+		out,
+		"\nNothing has been changed yet. Run this command again with `--apply` within 15 \
+		 minutes to apply the resolved state, or with `--cancel` to discard it."
+	)?;
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+fn format_state_repair(repair: &StateRepair) -> Result<String> {
+	let mut out = format!(
+		"State resolved at {}\n\n| server | state events |\n| --- | --- |\n",
+		repair.event_id
+	);
+	for (server, result) in &repair.servers {
+		match result {
+			| Ok(count) => writeln!(out, "| {server} | {count} |")?,
+			| Err(e) => writeln!(out, "| {server} | error: {e} |")?,
+		}
+	}
+
+	writeln!(
+		out,
+		"\n{} of {} state entries differ from our current state.",
+		repair.changes.len(),
+		repair.resolved.len()
+	)?;
+
+	let removed: Vec<_> = repair
+		.changes
+		.iter()
+		.filter(|change| change.after.is_none())
+		.collect();
+
+	if !removed.is_empty() {
+		writeln!(out, "\nRemoved from our state:\n")?;
+		writeln!(out, "| event type | state key | event (ours) |")?;
+		writeln!(out, "| --- | --- | --- |")?;
+		for change in removed {
+			let before = change
+				.before
+				.as_ref()
+				.map_or("none", |event_id| event_id.as_str());
+
+			writeln!(out, "| {} | {} | {before} |", change.event_type, change.state_key)?;
+		}
+	}
+
+	if !repair.membership.is_empty() {
+		writeln!(out, "\n| user | membership (ours) | membership (resolved) |")?;
+		writeln!(out, "| --- | --- | --- |")?;
+		for change in &repair.membership {
+			let before = change
+				.before
+				.as_ref()
+				.map_or("none", |membership| membership.as_str());
+			let after = change
+				.after
+				.as_ref()
+				.map_or("none", |membership| membership.as_str());
+
+			writeln!(out, "| {} | {before} | {after} |", change.user_id)?;
+		}
+	}
+
+	if !repair.power_levels.is_empty() {
+		writeln!(out, "\n| user | power level (ours) | power level (resolved) |")?;
+		writeln!(out, "| --- | --- | --- |")?;
+		for change in &repair.power_levels {
+			writeln!(out, "| {} | {} | {} |", change.user_id, change.before, change.after)?;
+		}
+	}
+
+	Ok(out)
+}
@@ -6,7 +6,7 @@
 
 use clap::Subcommand;
 use conduwuit::Result;
-use ruma::OwnedRoomId;
+use ruma::{OwnedRoomId, OwnedServerName};
 
 use self::{
 	alias::RoomAliasCommand, directory::RoomDirectoryCommand, info::RoomInfoCommand,
@@ -56,4 +56,45 @@ pub(super) enum RoomCommand {
 	Exists {
 		room_id: OwnedRoomId,
 	},
+
+	/// - Recompute the membership caches of a room, or of every room, from its
+	///   current state
+	///
+	/// The joined, invited, knocked and left users, the joined and invited
+	/// counts and the servers in the room are rebuilt. The differences found
+	/// are listed.
+	RecomputeMemberships {
+		room_id: Option<OwnedRoomId>,
+	},
+
+	/// - Repair our copy of the room state by resolving it against the state of
+	///   other servers in the room
+	///
+	/// The room state after the latest event we know of is requested from
+	/// several joined servers via `/_matrix/federation/v1/state_ids` and
+	/// resolved with our current state using state resolution. The membership
+	/// and power level differences and the entries removed from our state are
+	/// shown without changing anything.
+	///
+	/// Run again with `--apply` to confirm; the resolved state then replaces
+	/// our current state and the latest event is kept among the forward
+	/// extremities. If the room received events in between, the repair is
+	/// refused and has to be prepared again. No event is sent: our copy of
+	/// the state is overwritten in place and each overwritten entry is logged.
+	RepairState {
+		room_id: OwnedRoomId,
+
+		/// Servers to ask instead of the healthiest joined servers (may be
+		/// given multiple times)
+		#[arg(long)]
+		server: Vec<OwnedServerName>,
+
+		/// Apply the repair prepared by the previous run of this command
+		#[arg(long, conflicts_with_all = ["server", "cancel"])]
+		apply: bool,
+
+		/// Discard the repair prepared by the previous run of this command
+		#[arg(long, conflicts_with = "server")]
+		cancel: bool,
+	},
 }
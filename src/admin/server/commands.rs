@@ -1,4 +1,4 @@
-use std::{fmt::Write, path::PathBuf, sync::Arc};
+use std::{fmt::Write, path::PathBuf};
 
 use conduwuit::{Err, Result, info, utils::time, warn};
 use ruma::events::room::message::RoomMessageEventContent;
@@ -74,7 +74,11 @@ pub(super) async fn list_features(
 #[admin_command]
 pub(super) async fn memory_usage(&self) -> Result<RoomMessageEventContent> {
 	let services_usage = self.services.memory_usage().await?;
-	let database_usage = self.services.db.db.memory_usage()?;
+	let database_usage = match self.services.db.engine() {
+		| Ok(engine) => engine.memory_usage()?,
+		// every column is in memory; there is no cache to report
+		| Err(_) => "Not reported by the in-memory storage engine.\n".to_owned(),
+	};
 	let allocator_usage =
 		conduwuit::alloc::memory_usage().map_or(String::new(), |s| format!("\nAllocator:\n{s}"));
 
@@ -92,7 +96,7 @@ pub(super) async fn clear_caches(&self) -> Result<RoomMessageEventContent> {
 
 #[admin_command]
 pub(super) async fn list_backups(&self) -> Result<RoomMessageEventContent> {
-	let result = self.services.db.db.backup_list()?;
+	let result = self.services.db.engine()?.backup_list()?;
 
 	if result.is_empty() {
 		Ok(RoomMessageEventContent::text_plain("No backups found."))
@@ -103,24 +107,36 @@ pub(super) async fn list_backups(&self) -> Result<RoomMessageEventContent> {
 
 #[admin_command]
 pub(super) async fn backup_database(&self) -> Result<RoomMessageEventContent> {
-	let db = Arc::clone(&self.services.db);
-	let mut result = self
-		.services
-		.server
-		.runtime()
-		.spawn_blocking(move || match db.db.backup() {
-			| Ok(()) => String::new(),
-			| Err(e) => e.to_string(),
-		})
-		.await?;
+	let result = match self.services.backup.backup().await {
+		| Ok(summary) => summary,
+		| Err(e) => e.to_string(),
+	};
 
-	if result.is_empty() {
-		result = self.services.db.db.backup_list()?;
-	}
+	Ok(RoomMessageEventContent::notice_markdown(result))
+}
+
+#[admin_command]
+pub(super) async fn verify_backup(
+	&self,
+	backup_id: Option<u32>,
+) -> Result<RoomMessageEventContent> {
+	let result = self.services.backup.verify(backup_id).await?;
 
 	Ok(RoomMessageEventContent::notice_markdown(result))
 }
 
+#[admin_command]
+pub(super) async fn export_database(&self, path: PathBuf) -> Result<RoomMessageEventContent> {
+	let manifest = self.services.db.export(&path).await?;
+	let records: u64 = manifest.columns.iter().map(|column| column.records).sum();
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Exported {records} records from {} columns to `{}`.",
+		manifest.columns.len(),
+		path.display(),
+	)))
+}
+
 #[admin_command]
 pub(super) async fn admin_notice(&self, message: Vec<String>) -> Result<RoomMessageEventContent> {
 	let message = message.join(" ");
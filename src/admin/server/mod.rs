@@ -40,12 +40,27 @@ pub(super) enum ServerCommand {
 	ClearCaches,
 
 	/// - Performs an online backup of the database (only available for RocksDB
-	///   at the moment)
+	///   at the moment), then uploads it to the remote backup target if one is
+	///   configured
 	BackupDatabase,
 
 	/// - List database backups
 	ListBackups,
 
+	/// - Verify a backup by restoring it into a temporary directory and reading
+	///   every column
+	VerifyBackup {
+		/// The backup to verify; defaults to the latest
+		backup_id: Option<u32>,
+	},
+
+	/// - Export the database to a portable file which can be imported into a
+	///   new database with the `--import` argument
+	ExportDatabase {
+		/// Path of the file to create
+		path: PathBuf,
+	},
+
 	/// - Send a message to the admin room.
 	AdminNotice {
 		message: Vec<String>,
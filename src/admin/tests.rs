@@ -12,9 +12,9 @@
 fn get_help_inner(input: &str) {
 	use clap::Parser;
 
-	use crate::admin::AdminCommand;
+	use crate::admin::AdminArgs;
 
-	let Err(error) = AdminCommand::try_parse_from(["argv[0] doesn't matter", input]) else {
+	let Err(error) = AdminArgs::try_parse_from(["argv[0] doesn't matter", input]) else {
 		panic!("no error!");
 	};
 
@@ -24,3 +24,97 @@ fn get_help_inner(input: &str) {
 	assert!(error.contains("Commands:"));
 	assert!(error.contains("Options:"));
 }
+
+#[test]
+fn parse_json_flag() {
+	use clap::Parser;
+
+	use crate::admin::AdminArgs;
+
+	let args = AdminArgs::try_parse_from(["admin", "users", "list-users", "--json"])
+		.expect("parsed with --json");
+	assert!(args.json);
+
+	let args = AdminArgs::try_parse_from(["admin", "users", "list-users"]).expect("parsed");
+	assert!(!args.json);
+}
+
+#[test]
+fn output_table() {
+	use serde_json::json;
+
+	use crate::{Table, output::Output};
+
+	let mut table = Table::new(&["room_id", "members"]);
+	table.row(["!a|b:example.com".into(), 3.into()]);
+	let output = Output::from(table);
+
+	assert_eq!(
+		output.markdown(),
+		"| room_id | members |\n| --- | --- |\n| !a\\|b:example.com | 3 |\n"
+	);
+	assert_eq!(output.json(), json!([{ "room_id": "!a|b:example.com", "members": 3 }]));
+}
+
+#[test]
+fn output_record() {
+	use serde_json::json;
+
+	use crate::{Record, output::Output};
+
+	let record = Record::new()
+		.field("user_id", "@alice:example.com")
+		.field("admin", true)
+		.field("displayname", None::<String>);
+	let output = Output::from(record);
+
+	assert_eq!(
+		output.markdown(),
+		"- user_id: @alice:example.com\n- admin: true\n- displayname: \n"
+	);
+	assert_eq!(
+		output.json(),
+		json!({ "user_id": "@alice:example.com", "admin": true, "displayname": null })
+	);
+}
+
+#[test]
+fn secret_args_redacted() {
+	use crate::processor::is_secret_arg;
+
+	assert!(is_secret_arg("password"));
+	assert!(is_secret_arg("new_password"));
+	assert!(is_secret_arg("token"));
+	assert!(is_secret_arg("access_token"));
+	assert!(!is_secret_arg("user_id"));
+	assert!(!is_secret_arg("room_id"));
+}
+
+#[test]
+fn output_record_with_tables() {
+	use serde_json::json;
+
+	use crate::{Record, Table, output::Output};
+
+	let mut devices = Table::new(&["device_id", "name"]);
+	devices.row(["PHONE".into(), "Phone".into()]);
+	let record = Record::new()
+		.field("user_id", "@alice:example.com")
+		.table("devices", devices)
+		.table("connections", Table::new(&["device_id", "ip"]));
+	let output = Output::from(record);
+
+	assert_eq!(
+		output.markdown(),
+		"- user_id: @alice:example.com\n\nDevices:\n\n| device_id | name |\n| --- | --- |\n| \
+		 PHONE | Phone |\n\nConnections:\n\n| device_id | ip |\n| --- | --- |\n"
+	);
+	assert_eq!(
+		output.json(),
+		json!({
+			"user_id": "@alice:example.com",
+			"devices": [{ "device_id": "PHONE", "name": "Phone" }],
+			"connections": [],
+		})
+	);
+}
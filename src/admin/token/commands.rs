@@ -0,0 +1,147 @@
+use conduwuit::{Result, err, utils::time};
+use futures::StreamExt;
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedUserId, events::room::message::RoomMessageEventContent,
+};
+use serde_json::Value as JsonValue;
+use service::registration_tokens::RegistrationToken;
+
+use crate::{Record, Table, admin_command};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+#[admin_command]
+pub(super) async fn create_token(
+	&self,
+	token: Option<String>,
+	uses_allowed: Option<u64>,
+	expires_in: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let expiry_time = expires_in.as_deref().map(expiry_time).transpose()?;
+	let token = self
+		.services
+		.registration_tokens
+		.create(token.as_deref(), uses_allowed, expiry_time)
+		.await?;
+
+	self.write_str("Created registration token:\n\n").await?;
+	self.write_output(record(&token)).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn list_tokens(&self) -> Result<RoomMessageEventContent> {
+	let tokens: Vec<RegistrationToken> =
+		self.services.registration_tokens.tokens().collect().await;
+
+	let mut table =
+		Table::new(&["token", "uses_allowed", "pending", "completed", "expiry_time", "valid"]);
+
+	for token in &tokens {
+		table.row([
+			token.token.as_str().into(),
+			token.uses_allowed.into(),
+			token.pending.into(),
+			token.completed.into(),
+			format_expiry(token),
+			token.is_valid().into(),
+		]);
+	}
+
+	self.write_str(&format!("Registration tokens ({}):\n\n", table.len()))
+		.await?;
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn show_token(&self, token: String) -> Result<RoomMessageEventContent> {
+	let token = self.services.registration_tokens.get(&token).await?;
+	let user_ids: Vec<OwnedUserId> = self
+		.services
+		.registration_tokens
+		.users(&token.token)
+		.collect()
+		.await;
+
+	let mut users = Table::new(&["user_id"]);
+	for user_id in user_ids {
+		users.row([user_id.as_str().into()]);
+	}
+
+	self.write_output(record(&token)).await?;
+	self.write_str(&format!("\nRegistered with the token ({}):\n\n", users.len()))
+		.await?;
+	self.write_output(users).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn update_token(
+	&self,
+	token: String,
+	uses_allowed: Option<u64>,
+	unlimited: bool,
+	expires_in: Option<String>,
+	no_expiry: bool,
+) -> Result<RoomMessageEventContent> {
+	let uses_allowed = if unlimited { Some(None) } else { uses_allowed.map(Some) };
+	let expiry_time = if no_expiry {
+		Some(None)
+	} else {
+		expires_in
+			.as_deref()
+			.map(expiry_time)
+			.transpose()?
+			.map(Some)
+	};
+
+	let token = self
+		.services
+		.registration_tokens
+		.update(&token, uses_allowed, expiry_time)
+		.await?;
+
+	self.write_str("Updated registration token:\n\n").await?;
+	self.write_output(record(&token)).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn revoke_token(&self, token: String) -> Result<RoomMessageEventContent> {
+	self.services.registration_tokens.revoke(&token).await?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"Revoked registration token {token}."
+	)))
+}
+
+fn record(token: &RegistrationToken) -> Record {
+	Record::new()
+		.field("token", token.token.as_str())
+		.field("uses_allowed", token.uses_allowed)
+		.field("pending", token.pending)
+		.field("completed", token.completed)
+		.field("expiry_time", format_expiry(token))
+		.field("valid", token.is_valid())
+}
+
+fn format_expiry(token: &RegistrationToken) -> JsonValue {
+	token
+		.expiry_time
+		.and_then(MilliSecondsSinceUnixEpoch::to_system_time)
+		.map(|expiry_time| time::format(expiry_time, TIME_FORMAT))
+		.into()
+}
+
+fn expiry_time(expires_in: &str) -> Result<MilliSecondsSinceUnixEpoch> {
+	let expires_in = time::parse_duration(expires_in)?;
+	let expiry_time = time::timepoint_from_now(expires_in)?;
+
+	MilliSecondsSinceUnixEpoch::from_system_time(expiry_time)
+		.ok_or_else(|| err!(Arithmetic("Expiry time {expiry_time:?} is out of range")))
+}
@@ -0,0 +1,66 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum TokenCommand {
+	/// - Create a registration token. Registration requires one when
+	///   `registration_requires_token` is set
+	#[clap(alias = "create")]
+	CreateToken {
+		/// The token; a random one is generated if omitted
+		token: Option<String>,
+
+		/// Number of registrations the token can be used for; unlimited if
+		/// omitted
+		#[arg(long)]
+		uses_allowed: Option<u64>,
+
+		/// The token expires after this long (e.g. 7d); never if omitted
+		#[arg(long)]
+		expires_in: Option<String>,
+	},
+
+	/// - List registration tokens with their limits and uses
+	#[clap(alias = "list")]
+	ListTokens,
+
+	/// - Show a registration token and the users who registered with it
+	#[clap(alias = "show")]
+	ShowToken {
+		token: String,
+	},
+
+	/// - Change the limits of a registration token
+	#[clap(alias = "update")]
+	UpdateToken {
+		token: String,
+
+		/// Number of registrations the token can be used for, including those
+		/// it was already used for
+		#[arg(long, conflicts_with = "unlimited")]
+		uses_allowed: Option<u64>,
+
+		/// Allow the token to be used for any number of registrations
+		#[arg(long)]
+		unlimited: bool,
+
+		/// The token expires after this long from now (e.g. 7d)
+		#[arg(long, conflicts_with = "no_expiry")]
+		expires_in: Option<String>,
+
+		/// The token never expires
+		#[arg(long)]
+		no_expiry: bool,
+	},
+
+	/// - Revoke a registration token so it can no longer be used
+	#[clap(alias = "revoke")]
+	RevokeToken {
+		token: String,
+	},
+}
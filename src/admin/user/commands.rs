@@ -2,7 +2,7 @@
 
 use api::client::{full_user_deactivate, join_room_by_id_helper, leave_room};
 use conduwuit::{
-	Result, debug, debug_warn, error, info, is_equal_to,
+	Err, Result, debug, debug_warn, err, error, info, is_equal_to,
 	matrix::pdu::PduBuilder,
 	utils::{self, ReadyExt},
 	warn,
@@ -10,7 +10,8 @@
 use conduwuit_api::client::{leave_all_rooms, update_avatar_url, update_displayname};
 use futures::StreamExt;
 use ruma::{
-	EventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId, RoomId, UserId,
+	EventId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedRoomId, OwnedRoomOrAliasId,
+	OwnedUserId, RoomId, UserId,
 	events::{
 		RoomAccountDataEventType, StateEventType,
 		room::{
@@ -21,9 +22,14 @@
 		tag::{TagEvent, TagEventContent, TagInfo},
 	},
 };
+use serde_json::Value as JsonValue;
+use service::{
+	Services,
+	users::{DevicePolicy, Restriction},
+};
 
 use crate::{
-	admin_command, get_room_info,
+	Record, Table, admin_command, get_room_info,
 	utils::{parse_active_local_user_id, parse_local_user_id},
 };
 
@@ -40,11 +46,14 @@ pub(super) async fn list_users(&self) -> Result<RoomMessageEventContent> {
 		.collect()
 		.await;
 
-	let mut plain_msg = format!("Found {} local user account(s):\n```\n", users.len());
-	plain_msg += users.join("\n").as_str();
-	plain_msg += "\n```";
+	let mut table = Table::new(&["user_id"]);
+	for user_id in users {
+		table.row([user_id.into()]);
+	}
 
-	self.write_str(plain_msg.as_str()).await?;
+	self.write_str(&format!("Found {} local user account(s):\n\n", table.len()))
+		.await?;
+	self.write_output(table).await?;
 
 	Ok(RoomMessageEventContent::text_plain(""))
 }
@@ -411,17 +420,16 @@ pub(super) async fn list_joined_rooms(&self, user_id: String) -> Result<RoomMess
 	rooms.sort_by_key(|r| r.1);
 	rooms.reverse();
 
-	let output_plain = format!(
-		"Rooms {user_id} Joined ({}):\n```\n{}\n```",
-		rooms.len(),
-		rooms
-			.iter()
-			.map(|(id, members, name)| format!("{id}\tMembers: {members}\tName: {name}"))
-			.collect::<Vec<_>>()
-			.join("\n")
-	);
+	let mut table = Table::new(&["room_id", "members", "name"]);
+	for (room_id, members, name) in rooms {
+		table.row([room_id.as_str().into(), members.into(), name.into()]);
+	}
 
-	Ok(RoomMessageEventContent::notice_markdown(output_plain))
+	self.write_str(&format!("Rooms {user_id} Joined ({}):\n\n", table.len()))
+		.await?;
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
 }
 
 #[admin_command]
@@ -798,6 +806,364 @@ pub(super) async fn make_user_admin(&self, user_id: String) -> Result<RoomMessag
 	)))
 }
 
+#[admin_command]
+pub(super) async fn lock_account(
+	&self,
+	user_id: String,
+	reason: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if self.services.users.is_locked(&user_id).await {
+		return Ok(RoomMessageEventContent::notice_plain(format!(
+			"{user_id} is already locked."
+		)));
+	}
+
+	self.services
+		.users
+		.lock_account(&user_id, &Restriction::new(reason));
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"{user_id} has been locked; every request of the account will be refused until it is \
+		 unlocked."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unlock_account(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if !self.services.users.is_locked(&user_id).await {
+		return Ok(RoomMessageEventContent::notice_plain(format!("{user_id} is not locked.")));
+	}
+
+	self.services.users.unlock_account(&user_id);
+
+	Ok(RoomMessageEventContent::notice_plain(format!("{user_id} has been unlocked.")))
+}
+
+#[admin_command]
+pub(super) async fn suspend_account(
+	&self,
+	user_id: String,
+	reason: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if self.services.users.is_suspended(&user_id).await {
+		return Ok(RoomMessageEventContent::notice_plain(format!(
+			"{user_id} is already suspended."
+		)));
+	}
+
+	self.services
+		.users
+		.suspend_account(&user_id, &Restriction::new(reason));
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"{user_id} has been suspended; the account can still read, but cannot send, join, \
+		 invite, upload or change its profile until it is unsuspended."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unsuspend_account(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if !self.services.users.is_suspended(&user_id).await {
+		return Ok(RoomMessageEventContent::notice_plain(format!("{user_id} is not suspended.")));
+	}
+
+	self.services.users.unsuspend_account(&user_id);
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"{user_id} has been unsuspended."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn shadow_ban_user(
+	&self,
+	user_id: String,
+	reason: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if self.services.users.is_shadow_banned(&user_id).await {
+		return Ok(RoomMessageEventContent::notice_plain(format!(
+			"{user_id} is already shadow-banned."
+		)));
+	}
+
+	self.services
+		.users
+		.shadow_ban(&user_id, &Restriction::new(reason));
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"{user_id} has been shadow-banned; their messages, invites and room creations will \
+		 appear to succeed without being sent."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn unshadow_ban_user(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if !self.services.users.is_shadow_banned(&user_id).await {
+		return Ok(RoomMessageEventContent::notice_plain(format!(
+			"{user_id} is not shadow-banned."
+		)));
+	}
+
+	self.services.users.unshadow_ban(&user_id);
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"{user_id} is no longer shadow-banned."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn impersonate_user(
+	&self,
+	user_id: String,
+	expires_in: String,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	let expires_in = utils::time::parse_duration(&expires_in)?;
+	let expires_at = utils::time::timepoint_from_now(expires_in)?;
+	let expires_at = MilliSecondsSinceUnixEpoch::from_system_time(expires_at)
+		.ok_or_else(|| err!(Arithmetic("Expiry time {expires_at:?} is out of range")))?;
+
+	if !self.output_is_private().await {
+		return Err!(
+			"The access token of an impersonation session is only shown to the admin creating \
+			 it. Run this command from the server console, or escaped as `\\!admin` in a room \
+			 you are alone in."
+		);
+	}
+
+	let (impersonation, token) = self
+		.services
+		.users
+		.create_impersonation(&user_id, expires_at)
+		.await?;
+
+	let device_id = &impersonation.device_id;
+	let expires_at = format_time(Some(impersonation.expires_at));
+	warn!(%user_id, %device_id, "Admin impersonation session created");
+	self.services
+		.admin
+		.send_text(&format!(
+			"Impersonation session `{device_id}` of {user_id} was created, expiring at \
+			 {expires_at}. It can be revoked with `!admin users revoke-impersonation {user_id} \
+			 {device_id}`."
+		))
+		.await;
+
+	let record = Record::new()
+		.field("user_id", user_id.as_str())
+		.field("device_id", device_id.as_str())
+		.field("access_token", token)
+		.field("expires_at", expires_at);
+
+	self.write_str("Created impersonation session:\n\n").await?;
+	self.write_output(record).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn list_impersonations(
+	&self,
+	user_id: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = user_id
+		.as_deref()
+		.map(|user_id| parse_local_user_id(self.services, user_id))
+		.transpose()?;
+
+	let impersonations: Vec<_> = self
+		.services
+		.users
+		.impersonations(user_id.as_deref())
+		.collect()
+		.await;
+
+	let mut table = Table::new(&["user_id", "device_id", "created", "expires_at"]);
+	for impersonation in impersonations {
+		table.row([
+			impersonation.user_id.as_str().into(),
+			impersonation.device_id.as_str().into(),
+			format_time(Some(impersonation.created)).into(),
+			format_time(Some(impersonation.expires_at)).into(),
+		]);
+	}
+
+	self.write_str(&format!("Impersonation sessions ({}):\n\n", table.len()))
+		.await?;
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn revoke_impersonation(
+	&self,
+	user_id: String,
+	device_id: OwnedDeviceId,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	self.services
+		.users
+		.revoke_impersonation(&user_id, &device_id)
+		.await?;
+
+	warn!(%user_id, %device_id, "Admin impersonation session revoked");
+	self.services
+		.admin
+		.send_text(&format!("Impersonation session `{device_id}` of {user_id} was revoked."))
+		.await;
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"Revoked impersonation session {device_id} of {user_id}."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn whois(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let devices: Vec<_> = self
+		.services
+		.users
+		.all_devices_metadata(&user_id)
+		.collect()
+		.await;
+
+	let user = Record::new()
+		.field("user_id", user_id.as_str())
+		.field("admin", self.services.users.is_admin(&user_id).await)
+		.field("deactivated", self.services.users.is_deactivated(&user_id).await?)
+		.field("locked", restriction(self.services.users.locked(&user_id).await))
+		.field("suspended", restriction(self.services.users.suspended(&user_id).await))
+		.field("shadow_banned", restriction(self.services.users.shadow_banned(&user_id).await))
+		.field(
+			"registration_token",
+			self.services.registration_tokens.user_token(&user_id).await,
+		);
+
+	let mut device_table = Table::new(&["device_id", "name", "last_seen", "last_seen_ip"]);
+	let mut connection_table = Table::new(&["device_id", "ip", "last_seen", "user_agent"]);
+	for device in &devices {
+		device_table.row([
+			device.device_id.as_str().into(),
+			device.display_name.clone().into(),
+			format_time(device.last_seen_ts).into(),
+			device.last_seen_ip.clone().into(),
+		]);
+
+		let connections = self
+			.services
+			.users
+			.device_connections(&user_id, &device.device_id)
+			.await;
+
+		for connection in connections {
+			connection_table.row([
+				device.device_id.as_str().into(),
+				connection.ip.into(),
+				format_time(Some(connection.last_seen_ts)).into(),
+				connection.user_agent.into(),
+			]);
+		}
+	}
+
+	let user = user
+		.table("devices", device_table)
+		.table("connections", connection_table);
+
+	self.write_output(user).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn list_stale_devices(
+	&self,
+	user_id: String,
+	inactive_for: Option<String>,
+	max_devices: Option<usize>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let policy = device_policy(self.services, inactive_for.as_deref(), max_devices)?;
+	let devices = self.services.users.stale_devices(&user_id, &policy).await;
+
+	let mut table = Table::new(&["device_id", "last_seen", "name"]);
+	for device in devices {
+		table.row([
+			device.device_id.as_str().into(),
+			format_time(device.last_seen_ts).into(),
+			device.display_name.into(),
+		]);
+	}
+
+	self.write_str(&format!("{user_id} has {} stale devices:\n\n", table.len()))
+		.await?;
+	self.write_output(table).await?;
+
+	Ok(RoomMessageEventContent::text_plain(""))
+}
+
+#[admin_command]
+pub(super) async fn prune_devices(
+	&self,
+	user_id: String,
+	inactive_for: Option<String>,
+	max_devices: Option<usize>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let policy = device_policy(self.services, inactive_for.as_deref(), max_devices)?;
+	let removed = self.services.users.prune_devices(&user_id, &policy).await?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"Removed {} stale devices of {user_id}.",
+		removed.len()
+	)))
+}
+
+/// Describes a lock, suspension or shadow-ban for `whois`: since when and why,
+/// or false.
+fn restriction(restriction: Option<Restriction>) -> JsonValue {
+	let Some(restriction) = restriction else {
+		return false.into();
+	};
+
+	let since = format_time(Some(restriction.since));
+	match restriction.reason {
+		| Some(reason) => format!("since {since}: {reason}").into(),
+		| None => format!("since {since}").into(),
+	}
+}
+
+fn format_time(ts: Option<MilliSecondsSinceUnixEpoch>) -> String {
+	ts.and_then(MilliSecondsSinceUnixEpoch::to_system_time)
+		.map_or_else(|| "never".to_owned(), |ts| utils::time::format(ts, "%Y-%m-%d %H:%M:%S UTC"))
+}
+
+/// The policy of the device pruning job with the given limits overriding its
+/// own.
+fn device_policy(
+	services: &Services,
+	inactive_for: Option<&str>,
+	max_devices: Option<usize>,
+) -> Result<DevicePolicy> {
+	let mut policy = DevicePolicy::from_config(&services.server.config.jobs)?;
+	if let Some(inactive_for) = inactive_for {
+		let inactive_for = utils::time::parse_duration(inactive_for)?;
+		policy.inactive_before = Some(DevicePolicy::cutoff(inactive_for)?);
+	}
+
+	if let Some(max_devices) = max_devices {
+		policy.max_devices = Some(max_devices);
+	}
+
+	Ok(policy)
+}
+
 #[admin_command]
 pub(super) async fn put_room_tag(
 	&self,
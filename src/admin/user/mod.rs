@@ -2,7 +2,7 @@
 
 use clap::Subcommand;
 use conduwuit::Result;
-use ruma::{EventId, OwnedRoomOrAliasId, RoomId};
+use ruma::{EventId, OwnedDeviceId, OwnedRoomOrAliasId, RoomId};
 
 use crate::admin_command_dispatch;
 
@@ -93,6 +93,122 @@ pub(super) enum UserCommand {
 		user_id: String,
 	},
 
+	/// - Lock a local user's account, refusing all of its requests until it is
+	///   unlocked. Unlike deactivation this can be undone
+	#[clap(alias = "lock")]
+	LockAccount {
+		user_id: String,
+
+		/// Reason for the lock, shown in `whois`
+		#[arg(short, long)]
+		reason: Option<String>,
+	},
+
+	/// - Unlock a locked local user's account
+	#[clap(alias = "unlock")]
+	UnlockAccount {
+		user_id: String,
+	},
+
+	/// - Suspend a local user's account. A suspended account can still read,
+	///   but cannot send, join, invite, upload or change its profile until it
+	///   is unsuspended
+	#[clap(alias = "suspend")]
+	SuspendAccount {
+		user_id: String,
+
+		/// Reason for the suspension, shown in `whois`
+		#[arg(short, long)]
+		reason: Option<String>,
+	},
+
+	/// - Unsuspend a suspended local user's account
+	#[clap(alias = "unsuspend")]
+	UnsuspendAccount {
+		user_id: String,
+	},
+
+	/// - Shadow-ban a local user. Their messages, invites and room creations
+	///   appear to succeed, but nothing is sent or created
+	#[clap(alias = "shadow-ban")]
+	ShadowBanUser {
+		user_id: String,
+
+		/// Reason for the shadow-ban, shown in `whois`
+		#[arg(short, long)]
+		reason: Option<String>,
+	},
+
+	/// - Lift the shadow-ban of a local user
+	#[clap(alias = "unshadow-ban")]
+	UnshadowBanUser {
+		user_id: String,
+	},
+
+	/// - Create a time-limited access token to act as a local user, e.g. to see
+	///   their exact sync while debugging an issue they reported
+	///
+	/// The token is for a hidden device which is not announced to the user's
+	/// other devices or over federation, and which cannot upload keys. It is
+	/// only shown on the server console or in a room the admin is alone in.
+	#[clap(alias = "impersonate")]
+	ImpersonateUser {
+		user_id: String,
+
+		/// How long the token is valid for (e.g. 30m)
+		#[arg(long, default_value = "1h")]
+		expires_in: String,
+	},
+
+	/// - List impersonation sessions, of every user or of one
+	ListImpersonations {
+		user_id: Option<String>,
+	},
+
+	/// - Revoke an impersonation session before it expires
+	RevokeImpersonation {
+		user_id: String,
+		device_id: OwnedDeviceId,
+	},
+
+	/// - Show the devices of a user with the addresses and user agents each was
+	///   seen with
+	Whois {
+		user_id: String,
+	},
+
+	/// - List the stale devices of a local user. Unless limits are given, those
+	///   of the device pruning job in the `[global.jobs]` config section are
+	///   used
+	ListStaleDevices {
+		user_id: String,
+
+		/// Devices not seen for this long (e.g. 30d) are stale
+		#[arg(long)]
+		inactive_for: Option<String>,
+
+		/// Devices beyond this many, keeping the most recently seen, are
+		/// stale
+		#[arg(long)]
+		max_devices: Option<usize>,
+	},
+
+	/// - Remove the stale devices of a local user, along with their tokens,
+	///   keys and pending to-device events. Unless limits are given, those of
+	///   the device pruning job in the `[global.jobs]` config section are used
+	PruneDevices {
+		user_id: String,
+
+		/// Devices not seen for this long (e.g. 30d) are stale
+		#[arg(long)]
+		inactive_for: Option<String>,
+
+		/// Devices beyond this many, keeping the most recently seen, are
+		/// stale
+		#[arg(long)]
+		max_devices: Option<usize>,
+	},
+
 	/// - Puts a room tag for the specified user and room ID.
 	///
 	/// This is primarily useful if you'd like to set your admin room
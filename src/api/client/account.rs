@@ -10,7 +10,7 @@
 	warn,
 };
 use conduwuit_service::Services;
-use futures::{FutureExt, StreamExt};
+use futures::{FutureExt, StreamExt, TryStreamExt};
 use register::RegistrationKind;
 use ruma::{
 	OwnedRoomId, UserId,
@@ -22,7 +22,7 @@
 			request_3pid_management_token_via_email, request_3pid_management_token_via_msisdn,
 			whoami,
 		},
-		uiaa::{AuthFlow, AuthType, UiaaInfo},
+		uiaa::{AuthData, AuthFlow, AuthType, UiaaInfo},
 	},
 	events::{
 		GlobalAccountDataEventType, StateEventType,
@@ -169,8 +169,7 @@ pub(crate) async fn register_route(
 
 	if is_guest
 		&& (!services.config.allow_guest_registration
-			|| (services.config.allow_registration
-				&& services.globals.registration_token.is_some()))
+			|| (services.config.allow_registration && services.registration_tokens.required()))
 	{
 		info!(
 			"Guest registration disabled / registration enabled with token configured, \
@@ -258,6 +257,13 @@ pub(crate) async fn register_route(
 		},
 	};
 
+	if body.appservice_info.is_none() && !emergency_mode_enabled {
+		services
+			.spam_checker
+			.check_username_for_spam(&user_id)
+			.await?;
+	}
+
 	if body.body.login_type == Some(LoginType::ApplicationService) {
 		match body.appservice_info {
 			| Some(ref info) =>
@@ -277,7 +283,7 @@ pub(crate) async fn register_route(
 
 	// UIAA
 	let mut uiaainfo;
-	let skip_auth = if services.globals.registration_token.is_some() {
+	let skip_auth = if services.registration_tokens.required() {
 		// Registration token required
 		uiaainfo = UiaaInfo {
 			flows: vec![AuthFlow {
@@ -301,6 +307,7 @@ pub(crate) async fn register_route(
 		body.appservice_info.is_some() || is_guest
 	};
 
+	let mut uiaa_session = None;
 	if !skip_auth {
 		match &body.auth {
 			| Some(auth) => {
@@ -318,6 +325,7 @@ pub(crate) async fn register_route(
 					return Err(Error::Uiaa(uiaainfo));
 				}
 				// Success!
+				uiaa_session = uiaainfo.session;
 			},
 			| _ => match body.json_body {
 				| Some(ref json) => {
@@ -338,10 +346,39 @@ pub(crate) async fn register_route(
 		}
 	}
 
+	// Hold a use of a database registration token until the user is created
+	let registration_token = match body.auth.as_ref().filter(|_| !skip_auth) {
+		| Some(AuthData::RegistrationToken(auth)) => uiaa_session
+			.as_deref()
+			.map(|session| (auth.token.trim(), session)),
+		| _ => None,
+	};
+
+	if let Some((token, session)) = registration_token {
+		if !services.registration_tokens.begin(token, session).await
+			&& !services.uiaa.read_tokens().await?.contains(token)
+		{
+			return Err!(Request(Forbidden("Registration token is no longer valid.")));
+		}
+	}
+
 	let password = if is_guest { None } else { body.password.as_deref() };
 
 	// Create user
-	services.users.create(&user_id, password)?;
+	if let Err(e) = services.users.create(&user_id, password) {
+		if let Some((_, session)) = registration_token {
+			services.registration_tokens.release(session);
+		}
+
+		return Err(e);
+	}
+
+	if let Some((token, session)) = registration_token {
+		services
+			.registration_tokens
+			.complete(token, session, &user_id)
+			.await;
+	}
 
 	// Default to pretty displayname
 	let mut displayname = user_id.localpart().to_owned();
@@ -626,14 +663,28 @@ pub(crate) async fn change_password_route(
 		.users
 		.set_password(sender_user, Some(&body.new_password))?;
 
+	services
+		.audit
+		.record(
+			sender_user,
+			"user.password_change",
+			Some(sender_user.as_str()),
+			serde_json::json!({
+				"device_id": sender_device,
+				"logout_devices": body.logout_devices,
+			}),
+		)
+		.await;
+
 	if body.logout_devices {
 		// Logout all devices except the current one
 		services
 			.users
 			.all_device_ids(sender_user)
 			.ready_filter(|id| *id != sender_device)
-			.for_each(|id| services.users.remove_device(sender_user, id))
-			.await;
+			.map(Ok)
+			.try_for_each(|id| services.users.remove_device(sender_user, id))
+			.await?;
 
 		// Remove all pushers except the ones associated with this session
 		services
@@ -766,6 +817,15 @@ pub(crate) async fn deactivate_route(
 	full_user_deactivate(&services, sender_user, &all_joined_rooms).await?;
 
 	info!("User {sender_user} deactivated their account.");
+	services
+		.audit
+		.record(
+			sender_user,
+			"user.deactivate",
+			Some(sender_user.as_str()),
+			serde_json::json!({ "device_id": sender_device }),
+		)
+		.await;
 
 	if services.server.config.admin_room_notices {
 		services
@@ -825,17 +885,19 @@ pub(crate) async fn request_3pid_management_token_via_msisdn_route(
 ///
 /// Checks if the provided registration token is valid at the time of checking
 ///
-/// Currently does not have any ratelimiting, and this isn't very practical as
-/// there is only one registration token allowed.
+/// Currently does not have any ratelimiting.
 pub(crate) async fn check_registration_token_validity(
 	State(services): State<crate::State>,
 	body: Ruma<check_registration_token_validity::v1::Request>,
 ) -> Result<check_registration_token_validity::v1::Response> {
-	let Some(reg_token) = services.globals.registration_token.clone() else {
+	if !services.registration_tokens.required() {
 		return Err!(Request(Forbidden("Server does not allow token registration")));
-	};
+	}
+
+	let valid = services.registration_tokens.is_valid(&body.token).await
+		|| services.uiaa.read_tokens().await?.contains(&body.token);
 
-	Ok(check_registration_token_validity::v1::Response { valid: reg_token == body.token })
+	Ok(check_registration_token_validity::v1::Response { valid })
 }
 
 /// Runs through all the deactivation steps:
@@ -0,0 +1,88 @@
+use axum::extract::State;
+use conduwuit::{Result, err};
+use ruma::api::client::dehydrated_device::{
+	delete_dehydrated_device, get_dehydrated_device, get_events, put_dehydrated_device,
+};
+
+use crate::Ruma;
+
+/// Most to-device events returned in one batch of the dehydrated device's
+/// events.
+const EVENTS_BATCH_SIZE: usize = 50;
+
+/// # `PUT /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Creates or replaces the dehydrated device of the sender user.
+pub(crate) async fn put_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<put_dehydrated_device::unstable::Request>,
+) -> Result<put_dehydrated_device::unstable::Response> {
+	let sender_user = body.sender_user().to_owned();
+	let device_id = body.body.device_id.clone();
+
+	services
+		.users
+		.set_dehydrated_device(&sender_user, body.body)
+		.await?;
+
+	Ok(put_dehydrated_device::unstable::Response { device_id })
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Gets the dehydrated device of the sender user, for a new device to
+/// rehydrate.
+pub(crate) async fn get_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_dehydrated_device::unstable::Request>,
+) -> Result<get_dehydrated_device::unstable::Response> {
+	let dehydrated_device = services
+		.users
+		.dehydrated_device(body.sender_user())
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device."))))?;
+
+	Ok(get_dehydrated_device::unstable::Response {
+		device_id: dehydrated_device.device_id,
+		device_data: dehydrated_device.device_data,
+	})
+}
+
+/// # `DELETE /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Deletes the dehydrated device of the sender user, with its keys and
+/// to-device events.
+pub(crate) async fn delete_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_dehydrated_device::unstable::Request>,
+) -> Result<delete_dehydrated_device::unstable::Response> {
+	let device_id = services
+		.users
+		.remove_dehydrated_device(body.sender_user())
+		.await?;
+
+	Ok(delete_dehydrated_device::unstable::Response { device_id })
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device/{deviceId}/events`
+///
+/// Gets a batch of the to-device events the dehydrated device received.
+///
+/// - `next_batch` of the previous batch gets the events after it, and removes
+///   the events up to it, which the client has received
+pub(crate) async fn get_dehydrated_events_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_events::unstable::Request>,
+) -> Result<get_events::unstable::Response> {
+	let (events, next_batch) = services
+		.users
+		.dehydrated_device_events(
+			body.sender_user(),
+			&body.body.device_id,
+			body.body.next_batch.as_deref(),
+			EVENTS_BATCH_SIZE,
+		)
+		.await?;
+
+	Ok(get_events::unstable::Response { next_batch, events })
+}
@@ -1,6 +1,9 @@
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
-use conduwuit::{Err, Error, Result, debug, err, utils};
+use conduwuit::{
+	Err, Error, Result, debug, err,
+	utils::{self, ReadyExt},
+};
 use futures::StreamExt;
 use ruma::{
 	MilliSecondsSinceUnixEpoch, OwnedDeviceId,
@@ -16,16 +19,19 @@
 
 /// # `GET /_matrix/client/r0/devices`
 ///
-/// Get metadata on all devices of the sender user.
+/// Get metadata on all devices of the sender user, without their dehydrated
+/// device.
 pub(crate) async fn get_devices_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_devices::v3::Request>,
 ) -> Result<get_devices::v3::Response> {
 	let sender_user = body.sender_user.as_ref().expect("user is authenticated");
+	let dehydrated_id = services.users.dehydrated_device_id(sender_user).await.ok();
 
 	let devices: Vec<device::Device> = services
 		.users
 		.all_devices_metadata(sender_user)
+		.ready_filter(|device| dehydrated_id.as_ref() != Some(&device.device_id))
 		.collect()
 		.await;
 
@@ -138,7 +144,7 @@ pub(crate) async fn delete_device_route(
 		services
 			.users
 			.remove_device(sender_user, &body.device_id)
-			.await;
+			.await?;
 
 		return Ok(delete_device::v3::Response {});
 	}
@@ -182,7 +188,7 @@ pub(crate) async fn delete_device_route(
 	services
 		.users
 		.remove_device(sender_user, &body.device_id)
-		.await;
+		.await?;
 
 	Ok(delete_device::v3::Response {})
 }
@@ -213,7 +219,7 @@ pub(crate) async fn delete_devices_route(
 			 enabled"
 		);
 		for device_id in &body.devices {
-			services.users.remove_device(sender_user, device_id).await;
+			services.users.remove_device(sender_user, device_id).await?;
 		}
 
 		return Ok(delete_devices::v3::Response {});
@@ -256,7 +262,7 @@ pub(crate) async fn delete_devices_route(
 	}
 
 	for device_id in &body.devices {
-		services.users.remove_device(sender_user, device_id).await;
+		services.users.remove_device(sender_user, device_id).await?;
 	}
 
 	Ok(delete_devices::v3::Response {})
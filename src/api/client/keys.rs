@@ -96,13 +96,13 @@ pub(crate) async fn upload_keys_route(
 				services
 					.users
 					.add_device_keys(sender_user, sender_device, device_keys)
-					.await;
+					.await?;
 			}
 		} else {
 			services
 				.users
 				.add_device_keys(sender_user, sender_device, device_keys)
-				.await;
+				.await?;
 		}
 	}
 
@@ -152,7 +152,8 @@ pub(crate) async fn claim_keys_route(
 ///
 /// Uploads end-to-end key information for the sender user.
 ///
-/// - Requires UIAA to verify password
+/// - Verifies the keys are the sender's and signed by their master key
+/// - Requires UIAA to verify password, unless the keys are new or unchanged
 pub(crate) async fn upload_signing_keys_route(
 	State(services): State<crate::State>,
 	body: Ruma<upload_signing_keys::v3::Request>,
@@ -160,6 +161,17 @@ pub(crate) async fn upload_signing_keys_route(
 	let sender_user = body.sender_user.as_ref().expect("user is authenticated");
 	let sender_device = body.sender_device.as_ref().expect("user is authenticated");
 
+	// Refuse invalid keys before any UIA, so they are never stored
+	services
+		.users
+		.verify_cross_signing_keys(
+			sender_user,
+			body.master_key.as_ref(),
+			body.self_signing_key.as_ref(),
+			body.user_signing_key.as_ref(),
+		)
+		.await?;
+
 	// UIAA
 	let mut uiaainfo = UiaaInfo {
 		flows: vec![AuthFlow { stages: vec![AuthType::Password] }],
@@ -308,9 +320,10 @@ async fn check_for_new_keys(
 ///
 /// Uploads end-to-end key signatures from the sender user.
 ///
-/// TODO: clean this timo-code up more and integrate failures. tried to improve
-/// it a bit to stop exploding the entire request on bad sigs, but needs way
-/// more work.
+/// - Verifies each signature against the sender's signing keys, and adds the
+///   valid ones to the keys
+/// - Keys with an invalid signature, or which differ from the stored key, are
+///   reported in `failures`
 pub(crate) async fn upload_signatures_route(
 	State(services): State<crate::State>,
 	body: Ruma<upload_signatures::v3::Request>,
@@ -321,46 +334,43 @@ pub(crate) async fn upload_signatures_route(
 	}
 
 	let sender_user = body.sender_user();
+	let mut failures: BTreeMap<OwnedUserId, BTreeMap<String, _>> = BTreeMap::new();
 
 	for (user_id, keys) in &body.signed_keys {
 		for (key_id, key) in keys {
-			let Ok(key) = serde_json::to_value(key)
-				.inspect_err(|e| debug_warn!(?key_id, "Invalid \"key\" JSON: {e}"))
-			else {
-				continue;
-			};
-
-			let Some(signatures) = key.get("signatures") else {
-				continue;
-			};
-
-			let Some(sender_user_val) = signatures.get(sender_user.to_string()) else {
-				continue;
-			};
-
-			let Some(sender_user_object) = sender_user_val.as_object() else {
-				continue;
+			let result = match serde_json::to_value(key) {
+				| Ok(key) =>
+					services
+						.users
+						.add_signatures(sender_user, user_id, key_id, &key)
+						.await,
+				| Err(e) => Err!(Request(BadJson("Invalid key JSON: {e}"))),
 			};
 
-			for (signature, val) in sender_user_object.clone() {
-				let Some(val) = val.as_str().map(ToOwned::to_owned) else {
-					continue;
-				};
-				let signature = (signature, val);
-
-				if let Err(_e) = services
-					.users
-					.sign_key(user_id, key_id, signature, sender_user)
-					.await
-					.inspect_err(|e| debug_warn!("{e}"))
-				{
-					continue;
-				}
+			if let Err(e) = result {
+				debug_warn!(%user_id, ?key_id, "Rejected key signatures: {e}");
+				failures
+					.entry(user_id.clone())
+					.or_default()
+					.insert(key_id.clone(), signature_failure(&e)?);
 			}
 		}
 	}
 
-	Ok(upload_signatures::v3::Response { failures: BTreeMap::new() })
+	Ok(upload_signatures::v3::Response { failures })
+}
+
+fn signature_failure(error: &Error) -> Result<upload_signatures::v3::Failure> {
+	let errcode = match error.kind() {
+		| ErrorKind::Forbidden { .. } => "M_INVALID_SIGNATURE",
+		| ErrorKind::NotFound => "M_NOT_FOUND",
+		| ErrorKind::BadJson => "M_BAD_JSON",
+		| _ => "M_INVALID_PARAM",
+	};
+
+	let failure = json!({ "errcode": errcode, "error": error.message() });
+
+	serde_json::from_value(failure).map_err(Into::into)
 }
 
 /// # `POST /_matrix/client/r0/keys/changes`
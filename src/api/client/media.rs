@@ -61,6 +61,11 @@ pub(crate) async fn create_content_route(
 		media_id: &utils::random_string(MXC_LENGTH),
 	};
 
+	services
+		.spam_checker
+		.check_media_file_for_spam(user, content_type, filename, &body.file)
+		.await?;
+
 	services
 		.media
 		.create(mxc, Some(user), Some(&content_disposition), content_type, &body.file)
@@ -76,12 +76,23 @@ async fn banned_room_check(
 		return Ok(());
 	}
 
+	if services.policy.is_user_banned(user_id) {
+		return Err!(Request(Forbidden(warn!(
+			"User {user_id} is banned by a policy list and attempted to send an invite for or \
+			 join a room."
+		))));
+	}
+
 	if let Some(room_id) = room_id {
 		if services.rooms.metadata.is_banned(room_id).await
+			|| services.policy.is_room_banned(room_id)
 			|| services
 				.config
 				.forbidden_remote_server_names
 				.is_match(room_id.server_name().unwrap().host())
+			|| services
+				.policy
+				.is_server_banned(room_id.server_name().unwrap())
 		{
 			warn!(
 				"User {user_id} who is not an admin attempted to send an invite for or \
@@ -122,6 +133,7 @@ async fn banned_room_check(
 			.config
 			.forbidden_remote_server_names
 			.is_match(server_name.host())
+			|| services.policy.is_server_banned(server_name)
 		{
 			warn!(
 				"User {user_id} who is not an admin tried joining a room which has the server \
@@ -484,6 +496,8 @@ pub(crate) async fn leave_room_route(
 /// # `POST /_matrix/client/r0/rooms/{roomId}/invite`
 ///
 /// Tries to send an invite event into the room.
+///
+/// Invites of shadow-banned users are dropped while pretending to succeed.
 #[tracing::instrument(skip_all, fields(%client), name = "invite")]
 pub(crate) async fn invite_user_route(
 	State(services): State<crate::State>,
@@ -533,12 +547,28 @@ pub(crate) async fn invite_user_route(
 				}
 			}
 
+			if services.policy.is_user_banned(user_id) {
+				return Err!(Request(Forbidden(
+					"User is banned by a policy list on this server."
+				)));
+			}
+
+			services
+				.spam_checker
+				.user_may_invite(sender_user, user_id, &body.room_id)
+				.await?;
+
 			if recipient_ignored_by_sender {
 				// silently drop the invite to the recipient if they've been ignored by the
 				// sender, pretend it worked
 				return Ok(invite_user::v3::Response {});
 			}
 
+			if services.users.is_shadow_banned(sender_user).await {
+				// silently drop the invites of shadow-banned users, pretend it worked
+				return Ok(invite_user::v3::Response {});
+			}
+
 			invite_helper(
 				&services,
 				sender_user,
@@ -655,6 +685,16 @@ pub(crate) async fn ban_user_route(
 
 	drop(state_lock);
 
+	services
+		.audit
+		.record(
+			sender_user,
+			"room.ban",
+			Some(body.user_id.as_str()),
+			serde_json::json!({ "room_id": body.room_id, "reason": body.reason }),
+		)
+		.await;
+
 	Ok(ban_user::v3::Response::new())
 }
 
@@ -916,6 +956,19 @@ pub async fn join_room_by_id_helper(
 		return Err!(Request(Forbidden("Guests are not allowed to join this room")));
 	}
 
+	if appservice_info.is_none() {
+		let is_invited = services
+			.rooms
+			.state_cache
+			.is_invited(sender_user, room_id)
+			.await;
+
+		services
+			.spam_checker
+			.user_may_join_room(sender_user, room_id, is_invited)
+			.await?;
+	}
+
 	if services
 		.rooms
 		.state_cache
@@ -5,6 +5,7 @@
 pub(super) mod backup;
 pub(super) mod capabilities;
 pub(super) mod context;
+pub(super) mod dehydrated_device;
 pub(super) mod device;
 pub(super) mod directory;
 pub(super) mod filter;
@@ -19,7 +20,9 @@
 pub(super) mod push;
 pub(super) mod read_marker;
 pub(super) mod redact;
+pub(super) mod replica;
 pub(super) mod relations;
+pub(super) mod rendezvous;
 pub(super) mod report;
 pub(super) mod room;
 pub(super) mod search;
@@ -47,6 +50,7 @@
 pub(super) use backup::*;
 pub(super) use capabilities::*;
 pub(super) use context::*;
+pub(super) use dehydrated_device::*;
 pub(super) use device::*;
 pub(super) use directory::*;
 pub(super) use filter::*;
@@ -63,7 +67,9 @@
 pub(super) use push::*;
 pub(super) use read_marker::*;
 pub(super) use redact::*;
+pub(super) use replica::*;
 pub(super) use relations::*;
+pub(super) use rendezvous::*;
 pub(super) use report::*;
 pub(super) use room::*;
 pub(super) use search::*;
@@ -46,7 +46,8 @@ pub(crate) async fn set_read_marker_route(
 		services
 			.rooms
 			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+			.reset_notification_counts(sender_user, &body.room_id, &ReceiptThread::Unthreaded)
+			.await?;
 	}
 
 	// ping presence
@@ -97,10 +98,12 @@ pub(crate) async fn set_read_marker_route(
 			)));
 		};
 
-		services
-			.rooms
-			.read_receipt
-			.private_read_set(&body.room_id, sender_user, count);
+		services.rooms.read_receipt.private_read_set(
+			&body.room_id,
+			sender_user,
+			count,
+			&ReceiptThread::Unthreaded,
+		);
 	}
 
 	Ok(set_read_marker::v3::Response {})
@@ -109,6 +112,8 @@ pub(crate) async fn set_read_marker_route(
 /// # `POST /_matrix/client/r0/rooms/{roomId}/receipt/{receiptType}/{eventId}`
 ///
 /// Sets private read marker and public read receipt EDU.
+///
+/// - Receipts with a `thread_id` only clear the notifications of that thread
 pub(crate) async fn create_receipt_route(
 	State(services): State<crate::State>,
 	body: Ruma<create_receipt::v3::Request>,
@@ -122,7 +127,8 @@ pub(crate) async fn create_receipt_route(
 		services
 			.rooms
 			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+			.reset_notification_counts(sender_user, &body.room_id, &body.thread)
+			.await?;
 	}
 
 	// ping presence
@@ -159,7 +165,7 @@ pub(crate) async fn create_receipt_route(
 						sender_user.to_owned(),
 						ruma::events::receipt::Receipt {
 							ts: Some(MilliSecondsSinceUnixEpoch::now()),
-							thread: ReceiptThread::Unthreaded,
+							thread: body.thread.clone(),
 						},
 					)]),
 				)]),
@@ -192,10 +198,12 @@ pub(crate) async fn create_receipt_route(
 				)));
 			};
 
-			services
-				.rooms
-				.read_receipt
-				.private_read_set(&body.room_id, sender_user, count);
+			services.rooms.read_receipt.private_read_set(
+				&body.room_id,
+				sender_user,
+				count,
+				&body.thread,
+			);
 		},
 		| _ => {
 			return Err!(Request(InvalidParam(warn!(
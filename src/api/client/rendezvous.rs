@@ -0,0 +1,145 @@
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::{IntoResponse, Response},
+};
+use axum_extra::headers::{ETag, Expires, HeaderMapExt, LastModified};
+use bytes::Bytes;
+use conduwuit::{Err, Result, err};
+use conduwuit_service::rendezvous::Session;
+use http::{
+	HeaderMap, HeaderName, HeaderValue, StatusCode,
+	header::{CACHE_CONTROL, CONTENT_TYPE, IF_MATCH, IF_NONE_MATCH},
+};
+
+const RENDEZVOUS_PATH: &str = "/_matrix/client/unstable/org.matrix.msc4108/rendezvous";
+
+/// Content type of sessions created or updated without one.
+const DEFAULT_CONTENT_TYPE: &str = "text/plain";
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc4108/rendezvous`
+///
+/// Creates a rendezvous session (MSC4108) holding the request body, for a
+/// device to sign in a new device by QR code. Responds with the URL of the
+/// session, which both devices then read and write.
+pub(crate) async fn create_rendezvous_route(
+	State(services): State<crate::State>,
+	headers: HeaderMap,
+	body: Bytes,
+) -> Result<Response> {
+	let (session_id, session) = services.rendezvous.create(body, content_type(&headers))?;
+
+	let config = &services.server.config;
+	let base_url = config
+		.well_known
+		.client
+		.as_ref()
+		.map_or_else(|| format!("https://{}", config.server_name), ToString::to_string);
+
+	let url = format!("{}{RENDEZVOUS_PATH}/{session_id}", base_url.trim_end_matches('/'));
+
+	Ok((
+		StatusCode::CREATED,
+		session_headers(&session)?,
+		Json(serde_json::json!({ "url": url })),
+	)
+		.into_response())
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc4108/rendezvous/{sessionId}`
+///
+/// Reads the content of a rendezvous session.
+///
+/// - Responds with 304 Not Modified while the ETag is the one in
+///   `If-None-Match`
+pub(crate) async fn get_rendezvous_route(
+	State(services): State<crate::State>,
+	Path(session_id): Path<String>,
+	headers: HeaderMap,
+) -> Result<Response> {
+	let session = services.rendezvous.get(&session_id)?;
+	let session_headers = session_headers(&session)?;
+
+	if if_match(&headers, IF_NONE_MATCH).is_some_and(|etag| etag == session.etag) {
+		return Ok((StatusCode::NOT_MODIFIED, session_headers).into_response());
+	}
+
+	let content_type = HeaderValue::from_str(&session.content_type)
+		.map_err(|e| err!(Request(Unknown("Invalid content type: {e}"))))?;
+
+	Ok((session_headers, [(CONTENT_TYPE, content_type)], session.content).into_response())
+}
+
+/// # `PUT /_matrix/client/unstable/org.matrix.msc4108/rendezvous/{sessionId}`
+///
+/// Replaces the content of a rendezvous session.
+///
+/// - Requires `If-Match` with the ETag last read; responds with 412
+///   M_CONCURRENT_WRITE when the session was written since
+pub(crate) async fn update_rendezvous_route(
+	State(services): State<crate::State>,
+	Path(session_id): Path<String>,
+	headers: HeaderMap,
+	body: Bytes,
+) -> Result<Response> {
+	let Some(etag) = if_match(&headers, IF_MATCH) else {
+		return Err!(Request(MissingParam("Missing If-Match header.")));
+	};
+
+	let session = services
+		.rendezvous
+		.update(&session_id, etag, body, content_type(&headers))?;
+
+	let Some(session) = session else {
+		let error = serde_json::json!({
+			"errcode": "M_CONCURRENT_WRITE",
+			"error": "The rendezvous session was written since it was read.",
+		});
+
+		return Ok((StatusCode::PRECONDITION_FAILED, Json(error)).into_response());
+	};
+
+	Ok((StatusCode::ACCEPTED, session_headers(&session)?).into_response())
+}
+
+/// # `DELETE /_matrix/client/unstable/org.matrix.msc4108/rendezvous/{sessionId}`
+///
+/// Ends a rendezvous session.
+pub(crate) async fn delete_rendezvous_route(
+	State(services): State<crate::State>,
+	Path(session_id): Path<String>,
+) -> Result<Response> {
+	services.rendezvous.delete(&session_id)?;
+
+	Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn session_headers(session: &Session) -> Result<HeaderMap> {
+	let etag: ETag = format!("\"{}\"", session.etag)
+		.parse()
+		.map_err(|e| err!("Invalid ETag {:?}: {e:?}", session.etag))?;
+
+	let mut headers = HeaderMap::new();
+	headers.typed_insert(etag);
+	headers.typed_insert(Expires::from(session.expires));
+	headers.typed_insert(LastModified::from(session.last_modified));
+	headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+	Ok(headers)
+}
+
+/// The ETag in an `If-Match` or `If-None-Match` header, without its quotes.
+fn if_match(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+	headers
+		.get(name)
+		.and_then(|value| value.to_str().ok())
+		.map(|etag| etag.trim().trim_start_matches("W/").trim_matches('"'))
+}
+
+fn content_type(headers: &HeaderMap) -> String {
+	headers
+		.get(CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.unwrap_or(DEFAULT_CONTENT_TYPE)
+		.to_owned()
+}
@@ -0,0 +1,43 @@
+use axum::{Json, extract::State};
+use axum_extra::{
+	TypedHeader,
+	headers::{Authorization, authorization::Bearer},
+};
+use conduwuit::{Result, matrix::pdu::PduCount};
+use conduwuit_service::replica::Forwarded;
+
+/// # `POST /_conduwuit/replica`
+///
+/// Receives the writes and operations forwarded by a read replica, which
+/// authenticates with the shared `replica_secret`. Only served on the internal
+/// listener at `replica_listen`.
+pub(crate) async fn conduwuit_replica_route(
+	State(services): State<crate::State>,
+	TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+	Json(forwarded): Json<Forwarded>,
+) -> Result<Json<serde_json::Value>> {
+	services.replica.authorize(bearer.token())?;
+
+	match forwarded {
+		| Forwarded::Writes { writes } => {
+			services.db.apply(&writes)?;
+
+			// The writes bypassed the services; drop what they cached from the
+			// columns written.
+			let columns: Vec<_> = writes.iter().map(|write| write.column.as_str()).collect();
+			services.clear_cache_of(&columns).await;
+		},
+		| Forwarded::Presence { user_id, presence } => {
+			services.presence.ping_presence(&user_id, &presence).await?;
+		},
+		| Forwarded::Backfill { room_id, from } => {
+			services
+				.rooms
+				.timeline
+				.backfill_if_required(&room_id, PduCount::from_signed(from))
+				.await?;
+		},
+	}
+
+	Ok(Json(serde_json::json!({})))
+}
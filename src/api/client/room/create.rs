@@ -51,6 +51,7 @@
 /// - Send events listed in initial state
 /// - Send events implied by `name` and `topic`
 /// - Send invite events
+/// - Pretends to succeed without creating anything if the user is shadow-banned
 #[allow(clippy::large_stack_frames)]
 pub(crate) async fn create_room_route(
 	State(services): State<crate::State>,
@@ -70,6 +71,13 @@ pub(crate) async fn create_room_route(
 		));
 	}
 
+	if body.appservice_info.is_none() {
+		services
+			.spam_checker
+			.user_may_create_room(sender_user)
+			.await?;
+	}
+
 	let room_id: OwnedRoomId = match &body.room_id {
 		| Some(custom_room_id) => custom_room_id_check(&services, custom_room_id)?,
 		| _ => RoomId::new(&services.server.name),
@@ -108,6 +116,11 @@ pub(crate) async fn create_room_route(
 		return Err!(Request(Forbidden("Publishing rooms to the room directory is not allowed")));
 	}
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend the room was created without creating anything
+		return Ok(create_room::v3::Response::new(room_id));
+	}
+
 	let _short_id = services
 		.rooms
 		.short
@@ -2,6 +2,7 @@
 
 use axum::extract::State;
 use conduwuit::{Err, Result, err, matrix::pdu::PduBuilder, utils};
+use conduwuit_service::users::shadow_event_id;
 use ruma::{api::client::message::send_message_event, events::MessageLikeEventType};
 use serde_json::from_str;
 
@@ -16,6 +17,7 @@
 /// - The only requirement for the content is that it has to be valid json
 /// - Tries to send the event into the room, auth rules will determine if it is
 ///   allowed
+/// - Pretends to succeed without sending anything if the user is shadow-banned
 pub(crate) async fn send_message_event_route(
 	State(services): State<crate::State>,
 	body: Ruma<send_message_event::v3::Request>,
@@ -65,6 +67,19 @@ pub(crate) async fn send_message_event_route(
 	let content = from_str(body.body.body.json().get())
 		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
 
+	if services.users.is_shadow_banned(sender_user).await {
+		// pretend it worked, answering retries of the transaction with the same ID
+		let event_id = shadow_event_id();
+		services.transaction_ids.add_txnid(
+			sender_user,
+			sender_device,
+			&body.txn_id,
+			event_id.as_bytes(),
+		);
+
+		return Ok(send_message_event::v3::Response { event_id });
+	}
+
 	let event_id = services
 		.rooms
 		.timeline
@@ -7,7 +7,7 @@
 	utils::{ReadyExt, hash},
 };
 use conduwuit_service::uiaa::SESSION_ID_LENGTH;
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 use ruma::{
 	UserId,
 	api::client::{
@@ -192,6 +192,10 @@ pub(crate) async fn login_route(
 		},
 	};
 
+	if services.users.is_locked(&user_id).await {
+		return Err!(Request(UserLocked("This account has been locked.")));
+	}
+
 	// Generate new device id if the user didn't specify one
 	let device_id = body
 		.device_id
@@ -313,6 +317,15 @@ pub(crate) async fn login_token_route(
 
 	let login_token = utils::random_string(TOKEN_LENGTH);
 	let expires_in = services.users.create_login_token(sender_user, &login_token);
+	services
+		.audit
+		.record(
+			sender_user,
+			"user.login_token",
+			Some(sender_user.as_str()),
+			serde_json::json!({ "device_id": sender_device, "expires_in_ms": expires_in }),
+		)
+		.await;
 
 	Ok(get_login_token::v1::Response {
 		expires_in: Duration::from_millis(expires_in),
@@ -341,7 +354,7 @@ pub(crate) async fn logout_route(
 	services
 		.users
 		.remove_device(sender_user, sender_device)
-		.await;
+		.await?;
 
 	Ok(logout::v3::Response::new())
 }
@@ -370,8 +383,9 @@ pub(crate) async fn logout_all_route(
 	services
 		.users
 		.all_device_ids(sender_user)
-		.for_each(|device_id| services.users.remove_device(sender_user, device_id))
-		.await;
+		.map(Ok)
+		.try_for_each(|device_id| services.users.remove_device(sender_user, device_id))
+		.await?;
 
 	Ok(logout_all::v3::Response::new())
 }
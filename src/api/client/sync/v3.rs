@@ -34,7 +34,7 @@
 	future::{OptionFuture, join, join3, join4, join5, try_join, try_join4},
 };
 use ruma::{
-	DeviceId, EventId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
+	DeviceId, EventId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UInt, UserId,
 	api::client::{
 		filter::FilterDefinition,
 		sync::sync_events::{
@@ -305,6 +305,7 @@ pub(crate) async fn build_sync_events(
 	let to_device_events = services
 		.users
 		.get_to_device_events(sender_user, sender_device, Some(since), Some(next_batch))
+		.map(at!(1))
 		.collect::<Vec<_>>();
 
 	let device_one_time_keys_count = services
@@ -820,6 +821,39 @@ async fn load_joined_room(
 	let (room_events, account_data_events, typing_events) = events;
 	let (notification_count, highlight_count) = unread_notifications;
 
+	// With unread thread notifications, the room counts are those outside threads
+	let unread_thread_notifications: BTreeMap<_, _> =
+		if send_notification_counts && filter.room.timeline.unread_thread_notifications {
+			services
+				.rooms
+				.user
+				.thread_notification_counts(sender_user, room_id)
+				.map(|(root_id, notifications, highlights)| {
+					(root_id, UnreadNotificationsCount {
+						highlight_count: Some(ruma_from_u64(highlights)),
+						notification_count: Some(ruma_from_u64(notifications)),
+					})
+				})
+				.collect()
+				.await
+		} else {
+			BTreeMap::new()
+		};
+
+	let (notification_count, highlight_count) = unread_thread_notifications.values().fold(
+		(notification_count, highlight_count),
+		|(notification_count, highlight_count), thread| {
+			let sub = |count: Option<UInt>, thread_count: Option<UInt>| {
+				count.map(|count| count.saturating_sub(thread_count.unwrap_or_default()))
+			};
+
+			(
+				sub(notification_count, thread.notification_count),
+				sub(highlight_count, thread.highlight_count),
+			)
+		},
+	);
+
 	device_list_updates.extend(device_updates);
 
 	let last_privateread_update = services
@@ -878,7 +912,7 @@ async fn load_joined_room(
 				.collect(),
 		},
 		ephemeral: Ephemeral { events: edus },
-		unread_thread_notifications: BTreeMap::new(),
+		unread_thread_notifications,
 	};
 
 	Ok((joined_room, device_list_updates, left_encrypted_users))
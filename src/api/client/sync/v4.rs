@@ -6,7 +6,7 @@
 
 use axum::extract::State;
 use conduwuit::{
-	Error, PduCount, PduEvent, Result, debug, error, extract_variant,
+	Error, PduCount, PduEvent, Result, at, debug, error, extract_variant,
 	utils::{
 		BoolExt, IterStream, ReadyExt, TryFutureExtExt,
 		math::{ruma_from_usize, usize_from_ruma, usize_from_u64_truncated},
@@ -791,6 +791,7 @@ pub(crate) async fn sync_events_v4_route(
 							Some(globalsince),
 							Some(next_batch),
 						)
+						.map(at!(1))
 						.collect()
 						.await,
 					next_batch: next_batch.to_string(),
@@ -1,13 +1,115 @@
 use axum::extract::State;
 use conduwuit::{
-	Result, at,
+	Err, Result, at, err,
 	matrix::pdu::{PduCount, PduEvent},
 };
+use conduwuit_service::Services;
 use futures::StreamExt;
-use ruma::{api::client::threads::get_threads, uint};
+use ruma::{EventId, RoomId, UserId, api::client::threads::get_threads, uint};
 
+use self::thread_subscription::{
+	delete_thread_subscription, get_thread_subscription, put_thread_subscription,
+};
 use crate::Ruma;
 
+/// Endpoints of thread subscriptions (MSC4306), which ruma does not have yet.
+pub(crate) mod thread_subscription {
+	pub(crate) mod put_thread_subscription {
+		use ruma::{
+			OwnedEventId, OwnedRoomId,
+			api::{request, response},
+			metadata,
+		};
+
+		const METADATA: ruma::api::Metadata = metadata! {
+			method: PUT,
+			rate_limited: true,
+			authentication: AccessToken,
+			history: {
+				unstable => "/_matrix/client/unstable/io.element.msc4306/rooms/:room_id/thread/:thread_root/subscription",
+			}
+		};
+
+		#[request(error = ruma::api::client::Error)]
+		pub struct Request {
+			#[ruma_api(path)]
+			pub room_id: OwnedRoomId,
+
+			#[ruma_api(path)]
+			pub thread_root: OwnedEventId,
+
+			/// The event that made the client subscribe the user automatically.
+			#[serde(skip_serializing_if = "Option::is_none")]
+			pub automatic: Option<OwnedEventId>,
+		}
+
+		#[response(error = ruma::api::client::Error)]
+		#[derive(Default)]
+		pub struct Response {}
+	}
+
+	pub(crate) mod get_thread_subscription {
+		use ruma::{
+			OwnedEventId, OwnedRoomId,
+			api::{request, response},
+			metadata,
+		};
+
+		const METADATA: ruma::api::Metadata = metadata! {
+			method: GET,
+			rate_limited: false,
+			authentication: AccessToken,
+			history: {
+				unstable => "/_matrix/client/unstable/io.element.msc4306/rooms/:room_id/thread/:thread_root/subscription",
+			}
+		};
+
+		#[request(error = ruma::api::client::Error)]
+		pub struct Request {
+			#[ruma_api(path)]
+			pub room_id: OwnedRoomId,
+
+			#[ruma_api(path)]
+			pub thread_root: OwnedEventId,
+		}
+
+		#[response(error = ruma::api::client::Error)]
+		pub struct Response {
+			pub automatic: bool,
+		}
+	}
+
+	pub(crate) mod delete_thread_subscription {
+		use ruma::{
+			OwnedEventId, OwnedRoomId,
+			api::{request, response},
+			metadata,
+		};
+
+		const METADATA: ruma::api::Metadata = metadata! {
+			method: DELETE,
+			rate_limited: true,
+			authentication: AccessToken,
+			history: {
+				unstable => "/_matrix/client/unstable/io.element.msc4306/rooms/:room_id/thread/:thread_root/subscription",
+			}
+		};
+
+		#[request(error = ruma::api::client::Error)]
+		pub struct Request {
+			#[ruma_api(path)]
+			pub room_id: OwnedRoomId,
+
+			#[ruma_api(path)]
+			pub thread_root: OwnedEventId,
+		}
+
+		#[response(error = ruma::api::client::Error)]
+		#[derive(Default)]
+		pub struct Response {}
+	}
+}
+
 /// # `GET /_matrix/client/r0/rooms/{roomId}/threads`
 pub(crate) async fn get_threads_route(
 	State(services): State<crate::State>,
@@ -60,3 +162,99 @@ pub(crate) async fn get_threads_route(
 			.collect(),
 	})
 }
+
+/// # `PUT /_matrix/client/unstable/io.element.msc4306/rooms/{roomId}/thread/{threadRoot}/subscription`
+///
+/// Subscribes the sender user to a thread, to be notified of its events.
+///
+/// - With `automatic`, the client subscribes the user on their behalf because
+///   of that event; this fails with 409 if the user unsubscribed since
+pub(crate) async fn put_thread_subscription_route(
+	State(services): State<crate::State>,
+	body: Ruma<put_thread_subscription::Request>,
+) -> Result<put_thread_subscription::Response> {
+	let sender_user = body.sender_user();
+	check_thread_root(&services, sender_user, &body.room_id, &body.thread_root).await?;
+
+	services
+		.rooms
+		.threads
+		.subscribe_thread(
+			sender_user,
+			&body.room_id,
+			&body.thread_root,
+			body.automatic.as_deref(),
+		)
+		.await?;
+
+	Ok(put_thread_subscription::Response {})
+}
+
+/// # `GET /_matrix/client/unstable/io.element.msc4306/rooms/{roomId}/thread/{threadRoot}/subscription`
+///
+/// Gets the subscription of the sender user to a thread.
+pub(crate) async fn get_thread_subscription_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_thread_subscription::Request>,
+) -> Result<get_thread_subscription::Response> {
+	let sender_user = body.sender_user();
+	check_thread_root(&services, sender_user, &body.room_id, &body.thread_root).await?;
+
+	let subscription = services
+		.rooms
+		.threads
+		.thread_subscription(sender_user, &body.room_id, &body.thread_root)
+		.await
+		.map_err(|_| err!(Request(NotFound("Not subscribed to this thread."))))?;
+
+	Ok(get_thread_subscription::Response { automatic: subscription.automatic })
+}
+
+/// # `DELETE /_matrix/client/unstable/io.element.msc4306/rooms/{roomId}/thread/{threadRoot}/subscription`
+///
+/// Unsubscribes the sender user from a thread, including a thread they
+/// participate in.
+pub(crate) async fn delete_thread_subscription_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_thread_subscription::Request>,
+) -> Result<delete_thread_subscription::Response> {
+	let sender_user = body.sender_user();
+	check_thread_root(&services, sender_user, &body.room_id, &body.thread_root).await?;
+
+	services
+		.rooms
+		.threads
+		.unsubscribe_thread(sender_user, &body.room_id, &body.thread_root)?;
+
+	Ok(delete_thread_subscription::Response {})
+}
+
+async fn check_thread_root(
+	services: &Services,
+	user_id: &UserId,
+	room_id: &RoomId,
+	root_id: &EventId,
+) -> Result {
+	if !services.server.config.allow_thread_subscriptions {
+		return Err!(FeatureDisabled("thread_subscriptions"));
+	}
+
+	let in_room = services
+		.rooms
+		.timeline
+		.get_pdu(root_id)
+		.await
+		.is_ok_and(|pdu| *pdu.room_id == *room_id);
+
+	if !in_room
+		|| !services
+			.rooms
+			.state_accessor
+			.user_can_see_event(user_id, room_id, root_id)
+			.await
+	{
+		return Err!(Request(NotFound("Thread root not found.")));
+	}
+
+	Ok(())
+}
@@ -3,7 +3,7 @@
 use axum::extract::State;
 use conduwuit::{Error, Result};
 use conduwuit_service::sending::EduBuf;
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 use ruma::{
 	api::{
 		client::{error::ErrorKind, to_device::send_event_to_device},
@@ -79,7 +79,7 @@ pub(crate) async fn send_event_to_device_route(
 							event_type,
 							event,
 						)
-						.await;
+						.await?;
 				},
 
 				| DeviceIdOrAllDevices::AllDevices => {
@@ -87,7 +87,8 @@ pub(crate) async fn send_event_to_device_route(
 					services
 						.users
 						.all_device_ids(target_user_id)
-						.for_each(|target_device_id| {
+						.map(Ok)
+						.try_for_each(|target_device_id| {
 							services.users.add_to_device_event(
 								sender_user,
 								target_user_id,
@@ -96,7 +97,7 @@ pub(crate) async fn send_event_to_device_route(
 								event.clone(),
 							)
 						})
-						.await;
+						.await?;
 				},
 			}
 		}
@@ -4,6 +4,7 @@
 mod request;
 mod response;
 pub mod state;
+mod tests;
 
 use std::str::FromStr;
 
@@ -19,6 +20,12 @@
 pub(super) use self::{args::Args as Ruma, response::RumaResponse, state::State};
 use crate::{client, server};
 
+/// Routes of the primary's internal listener for its read replicas, which is
+/// separate from the client listener.
+pub fn build_replica(router: Router<State>) -> Router<State> {
+	router.route(service::replica::FORWARD_PATH, post(client::conduwuit_replica_route))
+}
+
 pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 	let config = &server.config;
 	let mut router = router
@@ -163,6 +170,10 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::update_device_route)
 		.ruma_route(&client::delete_device_route)
 		.ruma_route(&client::delete_devices_route)
+		.ruma_route(&client::put_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_device_route)
+		.ruma_route(&client::delete_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_events_route)
 		.ruma_route(&client::get_tags_route)
 		.ruma_route(&client::update_tag_route)
 		.ruma_route(&client::delete_tag_route)
@@ -173,6 +184,9 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::set_pushers_route)
 		.ruma_route(&client::upgrade_room_route)
 		.ruma_route(&client::get_threads_route)
+		.ruma_route(&client::put_thread_subscription_route)
+		.ruma_route(&client::get_thread_subscription_route)
+		.ruma_route(&client::delete_thread_subscription_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_and_event_type_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_route)
 		.ruma_route(&client::get_relating_events_route)
@@ -185,6 +199,16 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		)
 		.ruma_route(&client::well_known_support)
 		.ruma_route(&client::well_known_client)
+		.route(
+			"/_matrix/client/unstable/org.matrix.msc4108/rendezvous",
+			post(client::create_rendezvous_route),
+		)
+		.route(
+			"/_matrix/client/unstable/org.matrix.msc4108/rendezvous/:session_id",
+			get(client::get_rendezvous_route)
+				.put(client::update_rendezvous_route)
+				.delete(client::delete_rendezvous_route),
+		)
 		.route("/_conduwuit/server_version", get(client::conduwuit_server_version))
 		.ruma_route(&client::room_initial_sync_route)
 		.route("/client/server.json", get(client::syncv3_client_server_json));
@@ -1,9 +1,11 @@
 use std::{mem, ops::Deref};
 
 use async_trait::async_trait;
-use axum::{body::Body, extract::FromRequest};
+use axum::{RequestPartsExt, body::Body, extract::FromRequest};
+use axum_client_ip::InsecureClientIp;
 use bytes::{BufMut, Bytes, BytesMut};
 use conduwuit::{Error, Result, debug, debug_warn, err, trace, utils::string::EMPTY};
+use http::header::USER_AGENT;
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, DeviceId, OwnedDeviceId, OwnedServerName,
 	OwnedUserId, ServerName, UserId, api::IncomingRequest,
@@ -109,6 +111,10 @@ async fn from_request(
 			json_body = Some(CanonicalJsonValue::Object(CanonicalJsonObject::new()));
 		}
 		let auth = auth::auth(services, &mut request, json_body.as_ref(), &T::METADATA).await?;
+		if let (Some(user_id), Some(device_id)) = (&auth.sender_user, &auth.sender_device) {
+			device_seen(services, &mut request, user_id, device_id).await;
+		}
+
 		Ok(Self {
 			body: make_body::<T>(services, &mut request, json_body.as_mut(), &auth)?,
 			origin: auth.origin,
@@ -120,6 +126,29 @@ async fn from_request(
 	}
 }
 
+/// Record the address and user agent the device was seen with.
+async fn device_seen(
+	services: &Services,
+	request: &mut Request,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) {
+	let Ok(InsecureClientIp(ip)) = request.parts.extract::<InsecureClientIp>().await else {
+		return;
+	};
+
+	let user_agent = request
+		.parts
+		.headers
+		.get(USER_AGENT)
+		.and_then(|user_agent| user_agent.to_str().ok());
+
+	services
+		.users
+		.device_seen(user_id, device_id, ip, user_agent)
+		.await;
+}
+
 fn make_body<T>(
 	services: &Services,
 	request: &mut Request,
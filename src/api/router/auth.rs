@@ -10,11 +10,21 @@
 	api::{
 		AuthScheme, IncomingRequest, Metadata,
 		client::{
+			alias::create_alias,
 			directory::get_public_rooms,
 			error::ErrorKind,
+			keys::{upload_keys, upload_signatures, upload_signing_keys},
+			media::{create_content, create_content_async, create_mxc_uri},
+			membership::{invite_user, join_room_by_id, join_room_by_id_or_alias, knock_room},
+			message::send_message_event,
 			profile::{
 				get_avatar_url, get_display_name, get_profile, get_profile_key, get_timezone_key,
+				set_avatar_url, set_display_name, set_profile_key, set_timezone_key,
 			},
+			room::{create_room, upgrade_room},
+			session::{logout, logout_all},
+			state::send_state_event,
+			to_device::send_event_to_device,
 			voip::get_turn_server_info,
 		},
 		federation::{authentication::XMatrix, openid::get_openid_userinfo},
@@ -23,6 +33,7 @@
 use service::{
 	Services,
 	server_keys::{PubKeyMap, PubKeys},
+	users::is_impersonation_device,
 };
 
 use super::request::Request;
@@ -112,7 +123,7 @@ pub(super) async fn auth(
 		}
 	}
 
-	match (metadata.authentication, token) {
+	let auth = match (metadata.authentication, token) {
 		| (AuthScheme::AccessToken, Token::Appservice(info)) =>
 			Ok(auth_appservice(services, request, info).await?),
 		| (
@@ -192,6 +203,86 @@ pub(super) async fn auth(
 			ErrorKind::UnknownToken { soft_logout: false },
 			"Unknown access token.",
 		)),
+	}?;
+
+	if let Some(sender_user) = &auth.sender_user {
+		check_restrictions(services, sender_user, metadata).await?;
+	}
+
+	if auth
+		.sender_device
+		.as_deref()
+		.is_some_and(is_impersonation_device)
+	{
+		check_impersonation(metadata)?;
+	}
+
+	Ok(auth)
+}
+
+/// Refuses requests of locked accounts, except to log out, and requests of
+/// suspended accounts which would send, create or upgrade rooms or aliases,
+/// join, invite, upload or change the profile.
+async fn check_restrictions(
+	services: &Services,
+	sender_user: &UserId,
+	metadata: &Metadata,
+) -> Result {
+	if !services.globals.user_is_local(sender_user) {
+		return Ok(());
+	}
+
+	if !allowed_when_locked(metadata) && services.users.is_locked(sender_user).await {
+		return Err!(Request(UserLocked("This account has been locked.")));
+	}
+
+	if forbidden_when_suspended(metadata) && services.users.is_suspended(sender_user).await {
+		return Err!(Request(UserSuspended("This account has been suspended.")));
+	}
+
+	Ok(())
+}
+
+/// Requests a locked account can still make.
+pub(super) fn allowed_when_locked(metadata: &Metadata) -> bool {
+	matches!(metadata, &logout::v3::Request::METADATA | &logout_all::v3::Request::METADATA)
+}
+
+/// Requests a suspended account cannot make.
+pub(super) fn forbidden_when_suspended(metadata: &Metadata) -> bool {
+	matches!(
+		metadata,
+		&send_message_event::v3::Request::METADATA
+			| &send_state_event::v3::Request::METADATA
+			| &create_room::v3::Request::METADATA
+			| &upgrade_room::v3::Request::METADATA
+			| &create_alias::v3::Request::METADATA
+			| &join_room_by_id::v3::Request::METADATA
+			| &join_room_by_id_or_alias::v3::Request::METADATA
+			| &knock_room::v3::Request::METADATA
+			| &invite_user::v3::Request::METADATA
+			| &create_content::v3::Request::METADATA
+			| &create_content_async::v3::Request::METADATA
+			| &create_mxc_uri::v1::Request::METADATA
+			| &set_display_name::v3::Request::METADATA
+			| &set_avatar_url::v3::Request::METADATA
+			| &set_profile_key::unstable::Request::METADATA
+			| &set_timezone_key::unstable::Request::METADATA
+	)
+}
+
+/// Refuses requests of impersonation sessions which would make the session
+/// visible to the user's other devices or to other users as a device of the
+/// user.
+fn check_impersonation(metadata: &Metadata) -> Result {
+	match metadata {
+		| &upload_keys::v3::Request::METADATA
+		| &upload_signatures::v3::Request::METADATA
+		| &upload_signing_keys::v3::Request::METADATA
+		| &send_event_to_device::v3::Request::METADATA => Err!(Request(Forbidden(
+			"Impersonation sessions cannot upload keys or send to-device events."
+		))),
+		| _ => Ok(()),
 	}
 }
 
@@ -326,6 +417,12 @@ fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result<()> {
 		))));
 	}
 
+	if services.policy.is_server_banned(origin) {
+		return Err!(Request(Forbidden(debug_warn!(
+			"Federation requests from {origin} denied by a policy list."
+		))));
+	}
+
 	Ok(())
 }
 
@@ -0,0 +1,87 @@
+#![cfg(test)]
+
+use conduwuit::{Error, err};
+use ruma::api::{
+	IncomingRequest, OutgoingResponse,
+	client::{
+		alias::create_alias,
+		authenticated_media::get_content,
+		error::{Error as RumaError, ErrorBody},
+		media::{create_content, create_content_async, create_mxc_uri},
+		message::send_message_event,
+		profile::{set_display_name, set_timezone_key},
+		room::upgrade_room,
+		session::{logout, logout_all},
+		sync::sync_events,
+		uiaa::UiaaResponse,
+	},
+};
+use serde_json::{Value as JsonValue, json};
+
+use super::auth::{allowed_when_locked, forbidden_when_suspended};
+
+#[test]
+fn locked_may_only_log_out() {
+	assert!(allowed_when_locked(&logout::v3::Request::METADATA));
+	assert!(allowed_when_locked(&logout_all::v3::Request::METADATA));
+	assert!(!allowed_when_locked(&sync_events::v3::Request::METADATA));
+	assert!(!allowed_when_locked(&send_message_event::v3::Request::METADATA));
+}
+
+#[test]
+fn suspended_may_not_send_or_upload() {
+	for metadata in [
+		&send_message_event::v3::Request::METADATA,
+		&upgrade_room::v3::Request::METADATA,
+		&create_alias::v3::Request::METADATA,
+		&create_content::v3::Request::METADATA,
+		&create_content_async::v3::Request::METADATA,
+		&create_mxc_uri::v1::Request::METADATA,
+		&set_display_name::v3::Request::METADATA,
+		&set_timezone_key::unstable::Request::METADATA,
+	] {
+		assert!(forbidden_when_suspended(metadata), "{metadata:?}");
+	}
+}
+
+#[test]
+fn suspended_may_read() {
+	for metadata in [
+		&sync_events::v3::Request::METADATA,
+		&get_content::v1::Request::METADATA,
+		&logout::v3::Request::METADATA,
+	] {
+		assert!(!forbidden_when_suspended(metadata), "{metadata:?}");
+	}
+}
+
+#[test]
+fn user_locked_is_soft_logout() {
+	let response: UiaaResponse =
+		err!(Request(UserLocked("This account has been locked."))).into();
+	let response = response
+		.try_into_http_response::<Vec<u8>>()
+		.expect("error response");
+
+	assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+	let body: JsonValue = serde_json::from_slice(response.body()).expect("JSON body");
+	assert_eq!(body["errcode"], "M_USER_LOCKED");
+	assert_eq!(body["soft_logout"], true);
+}
+
+#[test]
+fn unknown_errcode_is_kept() {
+	let body = ErrorBody::Json(json!({
+		"errcode": "IO.ELEMENT.MSC4306.M_CONFLICTING_UNSUBSCRIPTION",
+		"error": "The user unsubscribed from the thread after this event.",
+	}));
+	let error: Error = RumaError::new(http::StatusCode::CONFLICT, body).into();
+	let response: UiaaResponse = error.into();
+	let response = response
+		.try_into_http_response::<Vec<u8>>()
+		.expect("error response");
+
+	assert_eq!(response.status(), http::StatusCode::CONFLICT);
+	let body: JsonValue = serde_json::from_slice(response.body()).expect("JSON body");
+	assert_eq!(body["errcode"], "IO.ELEMENT.MSC4306.M_CONFLICTING_UNSUBSCRIPTION");
+}
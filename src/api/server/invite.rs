@@ -96,16 +96,32 @@ pub(crate) async fn create_invite_route(
 		.try_into()
 		.map_err(|e| err!(Request(InvalidParam("Invalid sender property: {e}"))))?;
 
-	if services.rooms.metadata.is_banned(&body.room_id).await
+	if (services.rooms.metadata.is_banned(&body.room_id).await
+		|| services.policy.is_room_banned(&body.room_id))
 		&& !services.users.is_admin(&invited_user).await
 	{
 		return Err!(Request(Forbidden("This room is banned on this homeserver.")));
 	}
 
+	if services.policy.is_user_banned(sender) {
+		warn!(
+			"Received federated/remote invite from {sender} for room ID {} who is banned by a \
+			 policy list. Rejecting.",
+			body.room_id
+		);
+
+		return Err!(Request(Forbidden("Sender is banned on this homeserver.")));
+	}
+
 	if services.config.block_non_admin_invites && !services.users.is_admin(&invited_user).await {
 		return Err!(Request(Forbidden("This server does not allow room invites.")));
 	}
 
+	services
+		.spam_checker
+		.user_may_invite(sender, &invited_user, &body.room_id)
+		.await?;
+
 	let mut invite_state = body.invite_room_state.clone();
 
 	let mut event: JsonObject = serde_json::from_str(body.event.get())
@@ -68,6 +68,17 @@ pub(crate) async fn create_join_event_template_route(
 		}
 	}
 
+	if services.policy.is_user_banned(&body.user_id) {
+		return Err!(Request(Forbidden(warn!(
+			"Remote user {} tried joining room ID {} but is banned by a policy list. Rejecting.",
+			&body.user_id, &body.room_id,
+		))));
+	}
+
+	if services.policy.is_room_banned(&body.room_id) {
+		return Err!(Request(Forbidden("This room is banned on this homeserver.")));
+	}
+
 	let room_version_id = services.rooms.state.get_room_version(&body.room_id).await?;
 	if !body.ver.contains(&room_version_id) {
 		return Err(Error::BadRequest(
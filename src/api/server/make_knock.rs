@@ -57,6 +57,17 @@ pub(crate) async fn create_knock_event_template_route(
 		}
 	}
 
+	if services.policy.is_user_banned(&body.user_id) {
+		return Err!(Request(Forbidden(warn!(
+			"Remote user {} tried knocking room ID {} but is banned by a policy list. Rejecting.",
+			&body.user_id, &body.room_id,
+		))));
+	}
+
+	if services.policy.is_room_banned(&body.room_id) {
+		return Err!(Request(Forbidden("This room is banned on this homeserver.")));
+	}
+
 	let room_version_id = services.rooms.state.get_room_version(&body.room_id).await?;
 
 	if matches!(room_version_id, V1 | V2 | V3 | V4 | V5 | V6) {
@@ -16,6 +16,7 @@
 };
 use conduwuit_service::{
 	Services,
+	federation::Ticket,
 	sending::{EDU_LIMIT, PDU_LIMIT},
 };
 use futures::{FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt};
@@ -78,6 +79,10 @@ pub(crate) async fn send_transaction_message_route(
 		)));
 	}
 
+	let ticket = services
+		.federation
+		.admit(body.origin(), body.pdus.len(), body.edus.len())?;
+
 	let txn_start_time = Instant::now();
 	trace!(
 		pdus = body.pdus.len(),
@@ -104,7 +109,8 @@ pub(crate) async fn send_transaction_message_route(
 		.filter_map(Result::ok)
 		.stream();
 
-	let results = handle(&services, &client, body.origin(), txn_start_time, pdus, edus).await?;
+	let results =
+		handle(&services, &client, body.origin(), &ticket, txn_start_time, pdus, edus).await?;
 
 	debug!(
 		pdus = body.pdus.len(),
@@ -134,10 +140,34 @@ async fn handle(
 	services: &Services,
 	client: &IpAddr,
 	origin: &ServerName,
+	ticket: &Ticket,
 	started: Instant,
 	pdus: impl Stream<Item = Pdu> + Send,
 	edus: impl Stream<Item = Edu> + Send,
 ) -> Result<ResolvedMap> {
+	let results = handle_pdus(services, client, origin, ticket, started, pdus)
+		.boxed()
+		.await?;
+
+	// evaluate edus after pdus, at least for now; they may refer to pdus of the
+	// same transaction. their lane only keeps them from waiting on other pdus.
+	handle_edus(services, client, origin, ticket, edus)
+		.boxed()
+		.await;
+
+	Ok(results)
+}
+
+async fn handle_pdus(
+	services: &Services,
+	client: &IpAddr,
+	origin: &ServerName,
+	ticket: &Ticket,
+	started: Instant,
+	pdus: impl Stream<Item = Pdu> + Send,
+) -> Result<ResolvedMap> {
+	let _permit = ticket.pdus().await;
+
 	// group pdus by room
 	let pdus = pdus
 		.collect()
@@ -150,8 +180,7 @@ async fn handle(
 		.await;
 
 	// we can evaluate rooms concurrently
-	let results: ResolvedMap = pdus
-		.into_iter()
+	pdus.into_iter()
 		.try_stream()
 		.broad_and_then(|(room_id, pdus): (_, Vec<_>)| {
 			handle_room(services, client, origin, started, room_id, pdus.into_iter())
@@ -160,15 +189,20 @@ async fn handle(
 		})
 		.try_flatten()
 		.try_collect()
-		.boxed()
-		.await?;
+		.await
+}
+
+async fn handle_edus(
+	services: &Services,
+	client: &IpAddr,
+	origin: &ServerName,
+	ticket: &Ticket,
+	edus: impl Stream<Item = Edu> + Send,
+) {
+	let _permit = ticket.edus().await;
 
-	// evaluate edus after pdus, at least for now.
 	edus.for_each_concurrent(automatic_width(), |edu| handle_edu(services, client, origin, edu))
-		.boxed()
 		.await;
-
-	Ok(results)
 }
 
 async fn handle_room(
@@ -454,7 +488,12 @@ async fn handle_edu_device_list_update(
 		return;
 	}
 
-	services.users.mark_device_key_update(&user_id).await;
+	services
+		.users
+		.mark_device_key_update(&user_id)
+		.await
+		.log_err()
+		.ok();
 }
 
 async fn handle_edu_direct_to_device(
@@ -544,14 +583,17 @@ async fn handle_edu_direct_to_device_event(
 			services
 				.users
 				.add_to_device_event(sender, target_user_id, target_device_id, ev_type, event)
-				.await;
+				.await
+				.log_err()
+				.ok();
 		},
 
 		| DeviceIdOrAllDevices::AllDevices => {
 			services
 				.users
 				.all_device_ids(target_user_id)
-				.for_each(|target_device_id| {
+				.map(Ok)
+				.try_for_each(|target_device_id| {
 					services.users.add_to_device_event(
 						sender,
 						target_user_id,
@@ -560,7 +602,9 @@ async fn handle_edu_direct_to_device_event(
 						event.clone(),
 					)
 				})
-				.await;
+				.await
+				.log_err()
+				.ok();
 		},
 	}
 }
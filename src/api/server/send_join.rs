@@ -137,6 +137,17 @@ async fn create_join_event(
 		return Err!(Request(BadJson("State key does not match sender user.")));
 	}
 
+	if services.policy.is_user_banned(&sender) {
+		return Err!(Request(Forbidden(warn!(
+			"Remote user {} tried joining room ID {} but is banned by a policy list. Rejecting.",
+			&sender, room_id,
+		))));
+	}
+
+	if services.policy.is_room_banned(room_id) {
+		return Err!(Request(Forbidden("This room is banned on this homeserver.")));
+	}
+
 	if let Some(authorising_user) = content.join_authorized_via_users_server {
 		use ruma::RoomVersionId::*;
 
@@ -141,6 +141,17 @@ pub(crate) async fn create_knock_event_v1_route(
 		return Err!(Request(InvalidParam("state_key does not match sender user of event.")));
 	}
 
+	if services.policy.is_user_banned(&sender) {
+		return Err!(Request(Forbidden(warn!(
+			"Remote user {} tried knocking room ID {} but is banned by a policy list. Rejecting.",
+			&sender, &body.room_id,
+		))));
+	}
+
+	if services.policy.is_room_banned(&body.room_id) {
+		return Err!(Request(Forbidden("This room is banned on this homeserver.")));
+	}
+
 	let origin: OwnedServerName = serde_json::from_value(
 		value
 			.get("origin")
@@ -114,6 +114,46 @@ pub fn check(config: &Config) -> Result {
 		));
 	}
 
+	if config.rocksdb_secondary && config.replica_primary_url.is_none() {
+		return Err!(Config(
+			"replica_primary_url",
+			"A read replica (rocksdb_secondary) requires the address of its primary."
+		));
+	}
+
+	if config.rocksdb_secondary && config.replica_secret.as_ref().is_none_or(String::is_empty) {
+		return Err!(Config(
+			"replica_secret",
+			"A read replica (rocksdb_secondary) requires the secret shared with its primary."
+		));
+	}
+
+	if config.rocksdb_secondary && config.replica_listen.is_none() {
+		return Err!(Config(
+			"replica_listen",
+			"A read replica (rocksdb_secondary) requires the address of its primary's internal \
+			 listener."
+		));
+	}
+
+	if config
+		.replica_listen
+		.is_some_and(|addr| config.get_bind_addrs().contains(&addr))
+	{
+		return Err!(Config(
+			"replica_listen",
+			"The internal listener for read replicas must not share the client listener's \
+			 address."
+		));
+	}
+
+	if config.rocksdb_secondary && config.database_backend != "rocksdb" {
+		return Err!(Config(
+			"database_backend",
+			"A read replica (rocksdb_secondary) requires the rocksdb storage engine."
+		));
+	}
+
 	// yeah, unless the user built a debug build hopefully for local testing only
 	if cfg!(not(debug_assertions)) && config.server_name == "your.server.name" {
 		return Err!(Config(
@@ -182,6 +222,7 @@ pub fn check(config: &Config) -> Result {
 
 	if config.allow_registration
 		&& !config.yes_i_am_very_very_sure_i_want_an_open_registration_server_prone_to_abuse
+		&& !config.registration_requires_token
 		&& config.registration_token.is_none()
 		&& config.registration_token_file.is_none()
 	{
@@ -199,6 +240,7 @@ pub fn check(config: &Config) -> Result {
 
 	if config.allow_registration
 		&& config.yes_i_am_very_very_sure_i_want_an_open_registration_server_prone_to_abuse
+		&& !config.registration_requires_token
 		&& config.registration_token.is_none()
 		&& config.registration_token_file.is_none()
 	{
@@ -25,7 +25,11 @@
 
 use self::proxy::ProxyConfig;
 pub use self::{check::check, manager::Manager};
-use crate::{Result, err, error::Error, utils::sys};
+use crate::{
+	Result, err,
+	error::Error,
+	utils::{sys, time::Schedule},
+};
 
 /// All the config options for conduwuit.
 #[allow(clippy::struct_excessive_bools)]
@@ -52,7 +56,7 @@
 ### For more information, see:
 ### https://conduwuit.puppyirl.gay/configuration.html
 "#,
-	ignore = "catchall well_known tls blurhashing allow_invalid_tls_certificates_yes_i_know_what_the_fuck_i_am_doing_with_this_and_i_know_this_is_insecure"
+	ignore = "catchall well_known tls blurhashing jobs allow_invalid_tls_certificates_yes_i_know_what_the_fuck_i_am_doing_with_this_and_i_know_this_is_insecure"
 )]
 pub struct Config {
 	/// The server_name is the pretty name of this server. It is used as a
@@ -129,6 +133,16 @@ pub struct Config {
 	/// example: "/var/lib/conduwuit"
 	pub database_path: PathBuf,
 
+	/// Storage engine of the database: "rocksdb", or "memory" to keep the
+	/// entire database in memory. Nothing is persisted with the memory engine
+	/// and "database_path" is then only used for media. It is intended for
+	/// tests and ephemeral deployments; backups and RocksDB statistics are not
+	/// available with it.
+	///
+	/// default: "rocksdb"
+	#[serde(default = "default_database_backend")]
+	pub database_backend: String,
+
 	/// conduwuit supports online database backups using RocksDB's Backup engine
 	/// API. To use this, set a database backup path that conduwuit can write
 	/// to.
@@ -146,6 +160,84 @@ pub struct Config {
 	#[serde(default = "default_database_backups_to_keep")]
 	pub database_backups_to_keep: i16,
 
+	/// Backups older than this many seconds are removed after each backup, in
+	/// addition to the limit of "database_backups_to_keep". The newest backup
+	/// is always kept.
+	///
+	/// Set to 0 to disable.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub database_backup_max_age: u64,
+
+	/// Directory backups are restored into to verify them with `!admin server
+	/// verify-backup`. The restored copy is removed afterwards but needs as
+	/// much space as the database while it exists.
+	///
+	/// Defaults to the system's temporary directory.
+	///
+	/// example: "/var/lib/conduwuit-verify"
+	pub database_backup_restore_path: Option<PathBuf>,
+
+	/// Directory to copy new backup files to after each backup, such as a
+	/// network filesystem mounted from another host. Files of backups removed
+	/// locally by the retention limits are removed here as well, so the
+	/// directory mirrors "database_backup_path".
+	///
+	/// example: "/mnt/offsite/conduwuit-db-backups"
+	pub database_backup_remote_path: Option<PathBuf>,
+
+	/// S3-compatible endpoint to upload new backup files to after each backup,
+	/// e.g. AWS S3 or MinIO. Like "database_backup_remote_path", the bucket
+	/// mirrors "database_backup_path". Objects are addressed path-style.
+	///
+	/// example: "https://s3.eu-central-1.amazonaws.com"
+	pub database_backup_s3_endpoint: Option<Url>,
+
+	/// Bucket to upload backups to when "database_backup_s3_endpoint" is set.
+	///
+	/// default: ""
+	#[serde(default)]
+	pub database_backup_s3_bucket: String,
+
+	/// Prefix of the object keys backup files are uploaded under, e.g.
+	/// "conduwuit/".
+	///
+	/// default: ""
+	#[serde(default)]
+	pub database_backup_s3_prefix: String,
+
+	/// Region of the S3 bucket, used to sign requests.
+	///
+	/// default: "us-east-1"
+	#[serde(default = "default_database_backup_s3_region")]
+	pub database_backup_s3_region: String,
+
+	/// Access key ID used to sign requests to the S3 endpoint.
+	///
+	/// default: ""
+	#[serde(default)]
+	pub database_backup_s3_access_key: String,
+
+	/// Secret access key used to sign requests to the S3 endpoint.
+	///
+	/// display: sensitive
+	#[serde(default)]
+	pub database_backup_s3_secret_key: String,
+
+	/// Path of a database export to load into a new, empty database at
+	/// startup, before migrations run. Exports are made with the
+	/// `!admin server export-database` command. Usually given with the
+	/// `--import` command line argument rather than set here. A completed
+	/// import is recorded in the database and skipped on later startups; a
+	/// failed one is removed again so it can be retried.
+	///
+	/// For more information, see:
+	/// https://conduwuit.puppyirl.gay/maintenance.html#portable-exports
+	///
+	/// example: "/opt/conduwuit-export"
+	pub database_import_path: Option<PathBuf>,
+
 	/// Text which will be added to the end of the user's displayname upon
 	/// registration with a space before the text. In Conduit, this was the
 	/// lightning bolt emoji.
@@ -260,6 +352,14 @@ pub struct Config {
 	#[serde(default = "default_roomid_spacehierarchy_cache_capacity")]
 	pub roomid_spacehierarchy_cache_capacity: u32,
 
+	/// Number of devices remembered as recently seen, which are only recorded
+	/// as seen again after "device_last_seen_interval". The least recently
+	/// seen devices are forgotten first and recorded on their next request.
+	///
+	/// default: varies by system
+	#[serde(default = "default_device_last_seen_cache_capacity")]
+	pub device_last_seen_cache_capacity: u32,
+
 	/// Maximum entries stored in DNS memory-cache. The size of an entry may
 	/// vary so please take care if raising this value excessively. Only
 	/// decrease this when using an external DNS cache. Please note that
@@ -362,6 +462,23 @@ pub struct Config {
 	#[serde(default = "default_max_fetch_prev_events")]
 	pub max_fetch_prev_events: u16,
 
+	/// Maximum number of servers asked for an event, its state, or backfill
+	/// before giving up. The origin server is asked first followed by the
+	/// other servers joined to the room, ranked by how reliably they have been
+	/// answering our requests.
+	///
+	/// default: 8
+	#[serde(default = "default_federation_fetch_servers")]
+	pub federation_fetch_servers: usize,
+
+	/// Number of servers asked concurrently when fetching missing events,
+	/// state or backfill from the servers in a room. The first valid response
+	/// wins.
+	///
+	/// default: 3
+	#[serde(default = "default_federation_fetch_concurrency")]
+	pub federation_fetch_concurrency: usize,
+
 	/// Default/base connection timeout (seconds). This is used only by URL
 	/// previews and update/news endpoint checks.
 	///
@@ -453,6 +570,44 @@ pub struct Config {
 	#[serde(default = "default_sender_retry_backoff_limit")]
 	pub sender_retry_backoff_limit: u64,
 
+	/// Maximum number of incoming federation transactions having their PDUs
+	/// processed at once across all origins. Transactions beyond this wait
+	/// their turn, served in arrival order with each origin limited by
+	/// `federation_inbound_per_origin`, so a single busy server cannot starve
+	/// the others.
+	///
+	/// This defaults to 4 * CPU core count.
+	///
+	/// default: varies by system
+	#[serde(default = "default_federation_inbound_concurrency")]
+	pub federation_inbound_concurrency: usize,
+
+	/// Maximum number of incoming federation transactions having their EDUs
+	/// (typing, receipts, presence, to-device, device lists) processed at
+	/// once. EDUs have a limit of their own so they are not delayed behind slow
+	/// event handling in other transactions; within a transaction they are
+	/// still processed after its PDUs.
+	///
+	/// This defaults to 4 * CPU core count.
+	///
+	/// default: varies by system
+	#[serde(default = "default_federation_inbound_edu_concurrency")]
+	pub federation_inbound_edu_concurrency: usize,
+
+	/// Maximum number of transactions from the same origin processed at once.
+	///
+	/// default: 1
+	#[serde(default = "default_federation_inbound_per_origin")]
+	pub federation_inbound_per_origin: usize,
+
+	/// Maximum number of transactions from the same origin allowed to be
+	/// queued (including those being processed). Further transactions are
+	/// refused with M_LIMIT_EXCEEDED and the remote server retries them later.
+	///
+	/// default: 8
+	#[serde(default = "default_federation_inbound_queue")]
+	pub federation_inbound_queue: usize,
+
 	/// Appservice URL request connection timeout. Defaults to 35 seconds as
 	/// generally appservices are hosted within the same network.
 	///
@@ -510,7 +665,8 @@ pub struct Config {
 	/// `yes_i_am_very_very_sure_i_want_an_open_registration_server_prone_to_abuse`
 	///
 	/// If you would like registration only via token reg, please configure
-	/// `registration_token` or `registration_token_file`.
+	/// `registration_requires_token`, `registration_token` or
+	/// `registration_token_file`.
 	#[serde(default)]
 	pub allow_registration: bool,
 
@@ -541,6 +697,14 @@ pub struct Config {
 	/// example: "/etc/conduwuit/.reg_token"
 	pub registration_token_file: Option<PathBuf>,
 
+	/// Require a registration token to register, using the tokens created
+	/// with `!admin tokens create`. Unlike `registration_token`, these tokens
+	/// can be limited to a number of uses and an expiry time, and record the
+	/// users who registered with them. Tokens from `registration_token` and
+	/// `registration_token_file` are accepted as well.
+	#[serde(default)]
+	pub registration_requires_token: bool,
+
 	/// Controls whether encrypted rooms and events are allowed.
 	#[serde(default = "true_fn")]
 	pub allow_encryption: bool,
@@ -610,6 +774,24 @@ pub struct Config {
 	#[serde(default)]
 	pub allow_device_name_federation: bool,
 
+	/// Minimum time in seconds between two records of a device being seen,
+	/// with the address and user agent of the request, as shown by
+	/// `GET /_matrix/client/v3/devices` and `!admin users whois`. A change of
+	/// address or user agent is recorded regardless.
+	///
+	/// default: 300
+	#[serde(default = "default_device_last_seen_interval")]
+	pub device_last_seen_interval: u64,
+
+	/// Time in seconds after which the addresses and user agents devices were
+	/// seen with are removed by the "ip_retention" job, for privacy.
+	///
+	/// Set to 0 to keep them.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub device_ip_retention: u64,
+
 	/// Config option to allow or disallow incoming federation requests that
 	/// obtain the profiles of our local users from
 	/// `/_matrix/federation/v1/query/profile`
@@ -823,6 +1005,31 @@ pub struct Config {
 	#[serde(default = "default_login_token_ttl")]
 	pub login_token_ttl: u64,
 
+	/// Allow signing in a new device from an existing one by scanning a QR
+	/// code (MSC4108). The two devices exchange messages through a short-lived
+	/// rendezvous session on this server, held in memory.
+	#[serde(default = "true_fn")]
+	pub allow_rendezvous: bool,
+
+	/// Rendezvous session expiration/TTL in seconds.
+	///
+	/// default: 60
+	#[serde(default = "default_rendezvous_ttl")]
+	pub rendezvous_ttl: u64,
+
+	/// Maximum size in bytes of the content of a rendezvous session.
+	///
+	/// default: 4096
+	#[serde(default = "default_rendezvous_max_content_length")]
+	pub rendezvous_max_content_length: usize,
+
+	/// Maximum number of rendezvous sessions held at once. Further sessions
+	/// are refused until some expire.
+	///
+	/// default: 1024
+	#[serde(default = "default_rendezvous_max_sessions")]
+	pub rendezvous_max_sessions: usize,
+
 	/// Static TURN username to provide the client if not using a shared secret
 	/// ("turn_secret"), It is recommended to use a shared secret over static
 	/// credentials.
@@ -904,6 +1111,13 @@ pub struct Config {
 	#[serde(default)]
 	pub auto_deactivate_banned_room_attempts: bool,
 
+	/// Redact messages sent by users banned by a subscribed policy list
+	/// (`!admin policy subscribe`). Redactions are sent by the server user,
+	/// so this only takes effect in rooms it is joined to with enough power
+	/// to redact other users' events.
+	#[serde(default)]
+	pub policy_list_auto_redact: bool,
+
 	/// RocksDB log level. This is not the same as conduwuit's log level. This
 	/// is the log level for the RocksDB engine/library which show up in your
 	/// database folder/path as `LOG` files. conduwuit will log RocksDB errors
@@ -1118,9 +1332,56 @@ pub struct Config {
 	#[serde(default)]
 	pub rocksdb_read_only: bool,
 
+	/// Open the database as a RocksDB secondary instance following another
+	/// conduwuit process (the primary) which uses the same "database_path".
+	/// The process then runs as a read replica: it serves read-heavy client
+	/// requests such as /sync, /messages and media downloads, and forwards
+	/// everything else to the primary at "replica_primary_url".
+	///
+	/// For more information, see:
+	/// https://conduwuit.puppyirl.gay/maintenance.html#read-replicas
 	#[serde(default)]
 	pub rocksdb_secondary: bool,
 
+	/// Directory for the files of the secondary instance itself. It must not
+	/// be shared with the primary or any other secondary. Defaults to
+	/// "database_path", which is only suitable for a single secondary.
+	///
+	/// example: "/var/lib/conduwuit-replica"
+	pub rocksdb_secondary_path: Option<PathBuf>,
+
+	/// Interval in milliseconds at which a read replica catches up with the
+	/// primary's writes and wakes the requests waiting on them.
+	///
+	/// default: 250
+	#[serde(default = "default_replica_catchup_interval_ms")]
+	pub replica_catchup_interval_ms: u64,
+
+	/// Address of the primary's client listener, used by a read replica to
+	/// forward client requests. This should be a local address, such as
+	/// "http://127.0.0.1:8008".
+	///
+	/// example: "http://127.0.0.1:8008"
+	pub replica_primary_url: Option<Url>,
+
+	/// Secret shared between the primary and its read replicas which
+	/// authenticates the writes forwarded by the replicas. It must be set to
+	/// the same value on both; the primary refuses forwarded writes when it is
+	/// not set.
+	///
+	/// display: sensitive
+	pub replica_secret: Option<String>,
+
+	/// Address of the primary's internal listener for the writes forwarded by
+	/// its read replicas. The primary listens on it in addition to the client
+	/// listener, and the replicas send their writes to it, so it must be set
+	/// to the same value on both. It must not be reachable by clients; bind it
+	/// to a loopback or private address. Forwarding writes is disabled when it
+	/// is not set.
+	///
+	/// example: "127.0.0.1:8009"
+	pub replica_listen: Option<SocketAddr>,
+
 	/// Enables idle CPU priority for compaction thread. This is not enabled by
 	/// default to prevent compaction from falling too far behind on busy
 	/// systems.
@@ -1233,6 +1494,13 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub allow_outgoing_read_receipts: bool,
 
+	/// Allow users to subscribe to threads (MSC4306), and only notify them of
+	/// events in threads they are subscribed to. Users are subscribed to the
+	/// threads they started or replied to, unless they unsubscribed. Events
+	/// that highlight, such as mentions, notify regardless.
+	#[serde(default)]
+	pub allow_thread_subscriptions: bool,
+
 	/// Allow outgoing typing updates to federation.
 	#[serde(default = "true_fn")]
 	pub allow_outgoing_typing: bool,
@@ -1536,6 +1804,28 @@ pub struct Config {
 	#[serde(default, with = "serde_regex")]
 	pub forbidden_usernames: RegexSet,
 
+	/// List of regex patterns matched against the body of messages. Messages
+	/// from local users which match are rejected and messages received over
+	/// federation which match are soft failed.
+	///
+	/// example: ["19dollarfortnitecards", "b[a4]dphr[a4]se"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub spam_content_filters: RegexSet,
+
+	/// List of domains which messages may not link to. Subdomains of the
+	/// listed domains are denied too, as are domains written without a scheme,
+	/// which clients turn into links. Messages from local users containing
+	/// such a link are rejected and those received over federation are soft
+	/// failed.
+	///
+	/// example: ["badsite.example", "spam.example"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub spam_link_denylist: Vec<String>,
+
 	/// Retry failed and incomplete messages to remote servers immediately upon
 	/// startup. This is called bursting. If this is disabled, said messages may
 	/// not be delivered until more messages are queued for that server. Do not
@@ -1599,6 +1889,15 @@ pub struct Config {
 	#[serde(default)]
 	pub admin_execute_errors_ignore: bool,
 
+	/// Print the output of startup commands (`--execute` / `admin_execute`)
+	/// as JSON rather than markdown, for scripts. Commands which return
+	/// tables or records print them as JSON arrays and objects; others print
+	/// their text as `{"output": "..."}`, and failures as `{"error": "..."}`.
+	///
+	/// This option can also be enabled with the `--json` conduwuit argument.
+	#[serde(default)]
+	pub admin_execute_json: bool,
+
 	/// List of admin commands to execute on SIGUSR2.
 	///
 	/// Similar to admin_execute, but these commands are executed when the
@@ -1695,6 +1994,19 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub admin_room_notices: bool,
 
+	/// Also append each entry of the audit log (admin commands and
+	/// security-sensitive actions such as password changes) to this file as a
+	/// line of JSON. The audit log is always kept in the database and can be
+	/// queried with `!admin audit query`.
+	///
+	/// example: "/var/log/conduwuit/audit.log"
+	pub audit_log_file: Option<PathBuf>,
+
+	/// Also send each entry of the audit log to the local syslog daemon
+	/// through /dev/log, with the authpriv facility.
+	#[serde(default)]
+	pub audit_log_syslog: bool,
+
 	/// Enable database pool affinity support. On supporting systems, block
 	/// device queue topologies are detected and the request pool is optimized
 	/// for the hardware; db_pool_workers is determined automatically.
@@ -1816,6 +2128,10 @@ pub struct Config {
 	// external structure; separate section
 	#[serde(default)]
 	pub blurhashing: BlurhashConfig,
+
+	// external structure; separate section
+	#[serde(default)]
+	pub jobs: JobsConfig,
 	#[serde(flatten)]
 	#[allow(clippy::zero_sized_map_values)]
 	// this is a catchall, the map shouldn't be zero at runtime
@@ -1891,6 +2207,108 @@ pub struct BlurhashConfig {
 	pub blurhash_max_raw_size: u64,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+#[config_example_generator(filename = "conduwuit-example.toml", section = "global.jobs")]
+pub struct JobsConfig {
+	/// Schedule of the media retention job, which deletes all media, local
+	/// and remote, older than "media_retention_max_age".
+	///
+	/// Schedules are written like a crontab entry: "minute hour
+	/// day-of-month month day-of-week", in UTC, or one of "@hourly",
+	/// "@daily", "@weekly", "@monthly" and "@yearly". A job without a
+	/// schedule only runs when triggered with `!admin jobs run`.
+	///
+	/// example: "0 4 * * *"
+	pub media_retention: Option<Schedule>,
+
+	/// Age in seconds after which the media retention job deletes media.
+	///
+	/// default: 7776000
+	#[serde(default = "default_media_retention_max_age")]
+	pub media_retention_max_age: u64,
+
+	/// Schedule of the remote media eviction job, which deletes media
+	/// fetched from other servers older than "remote_media_max_age". It is
+	/// fetched again when requested.
+	///
+	/// example: "30 4 * * *"
+	pub remote_media_eviction: Option<Schedule>,
+
+	/// Age in seconds after which the remote media eviction job deletes
+	/// remote media.
+	///
+	/// default: 2592000
+	#[serde(default = "default_remote_media_max_age")]
+	pub remote_media_max_age: u64,
+
+	/// Schedule of the database backup job, which backs up the database like
+	/// `!admin server backup-database`. Requires "database_backup_path".
+	///
+	/// example: "0 3 * * *"
+	pub database_backup: Option<Schedule>,
+
+	/// Schedule of the compaction job, which compacts the database columns
+	/// listed in "compaction_columns".
+	///
+	/// example: "0 5 * * 0"
+	pub compaction: Option<Schedule>,
+
+	/// Database columns compacted by the compaction job; all of them if
+	/// empty.
+	///
+	/// default: []
+	#[serde(default)]
+	pub compaction_columns: Vec<String>,
+
+	/// Schedule of the expired token job, which deletes expired OpenID and
+	/// login tokens.
+	///
+	/// default: "@hourly"
+	#[serde(default = "default_expired_tokens_schedule")]
+	pub expired_tokens: Option<Schedule>,
+
+	/// Schedule of the IP retention job, which removes the addresses and user
+	/// agents devices were seen with longer than "device_ip_retention" ago.
+	///
+	/// default: "@daily"
+	#[serde(default = "default_ip_retention_schedule")]
+	pub ip_retention: Option<Schedule>,
+
+	/// Schedule of the device pruning job, which removes the stale devices of
+	/// local users along with their tokens, keys and pending to-device
+	/// events. A device is stale if it was not seen for
+	/// "device_max_inactivity", or if its user has more than
+	/// "device_max_per_user" devices and it is not among the most recently
+	/// seen of them. The same limits are used by default by
+	/// `!admin users prune-devices`.
+	///
+	/// example: "0 6 * * *"
+	pub device_pruning: Option<Schedule>,
+
+	/// Time in seconds after which a device that was not seen is stale.
+	///
+	/// Set to 0 to disable.
+	///
+	/// default: 7776000
+	#[serde(default = "default_device_max_inactivity")]
+	pub device_max_inactivity: u64,
+
+	/// Number of devices a user may have before the least recently seen
+	/// ones are stale, e.g. for bots which log in every time they start.
+	///
+	/// Set to 0 to disable.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub device_max_per_user: usize,
+
+	/// Number of runs kept in the history of each job.
+	///
+	/// default: 10
+	#[serde(default = "default_job_history")]
+	pub history: usize,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(transparent)]
 struct ListeningPort {
@@ -1994,8 +2412,14 @@ fn default_port() -> ListeningPort { ListeningPort { ports: Left(8008) } }
 
 fn default_unix_socket_perms() -> u32 { 660 }
 
+fn default_replica_catchup_interval_ms() -> u64 { 250 }
+
+fn default_database_backend() -> String { "rocksdb".to_owned() }
+
 fn default_database_backups_to_keep() -> i16 { 1 }
 
+fn default_database_backup_s3_region() -> String { "us-east-1".to_owned() }
+
 fn default_db_write_buffer_capacity_mb() -> f64 { 48.0 + parallelism_scaled_f64(4.0) }
 
 fn default_db_cache_capacity_mb() -> f64 { 128.0 + parallelism_scaled_f64(64.0) }
@@ -2036,6 +2460,8 @@ fn default_stateinfo_cache_capacity() -> u32 { parallelism_scaled_u32(100) }
 
 fn default_roomid_spacehierarchy_cache_capacity() -> u32 { parallelism_scaled_u32(1000) }
 
+fn default_device_last_seen_cache_capacity() -> u32 { parallelism_scaled_u32(1000) }
+
 fn default_dns_cache_entries() -> u32 { 32768 }
 
 fn default_dns_min_ttl() -> u64 { 60 * 180 }
@@ -2078,6 +2504,14 @@ fn default_sender_idle_timeout() -> u64 { 180 }
 
 fn default_sender_retry_backoff_limit() -> u64 { 86400 }
 
+fn default_federation_inbound_concurrency() -> usize { parallelism_scaled(4) }
+
+fn default_federation_inbound_edu_concurrency() -> usize { parallelism_scaled(4) }
+
+fn default_federation_inbound_per_origin() -> usize { 1 }
+
+fn default_federation_inbound_queue() -> usize { 8 }
+
 fn default_appservice_timeout() -> u64 { 35 }
 
 fn default_appservice_idle_timeout() -> u64 { 300 }
@@ -2086,6 +2520,10 @@ fn default_pusher_idle_timeout() -> u64 { 15 }
 
 fn default_max_fetch_prev_events() -> u16 { 192_u16 }
 
+fn default_federation_fetch_servers() -> usize { 8 }
+
+fn default_federation_fetch_concurrency() -> usize { 3 }
+
 fn default_tracing_flame_filter() -> String {
 	cfg!(debug_assertions)
 		.then_some("trace,h2=off")
@@ -2124,6 +2562,14 @@ fn default_openid_token_ttl() -> u64 { 60 * 60 }
 
 fn default_login_token_ttl() -> u64 { 2 * 60 * 1000 }
 
+fn default_rendezvous_ttl() -> u64 { 60 }
+
+fn default_rendezvous_max_content_length() -> usize { 4096 }
+
+fn default_rendezvous_max_sessions() -> usize { 1024 }
+
+fn default_device_last_seen_interval() -> u64 { 5 * 60 }
+
 fn default_turn_ttl() -> u64 { 60 * 60 * 24 }
 
 fn default_presence_idle_timeout_s() -> u64 { 5 * 60 }
@@ -2273,3 +2719,35 @@ pub(super) fn default_blurhash_x_component() -> u32 { 4 }
 pub(super) fn default_blurhash_y_component() -> u32 { 3 }
 
 // end recommended & blurhashing defaults
+
+impl Default for JobsConfig {
+	fn default() -> Self {
+		Self {
+			media_retention: None,
+			media_retention_max_age: default_media_retention_max_age(),
+			remote_media_eviction: None,
+			remote_media_max_age: default_remote_media_max_age(),
+			database_backup: None,
+			compaction: None,
+			compaction_columns: Vec::new(),
+			expired_tokens: default_expired_tokens_schedule(),
+			ip_retention: default_ip_retention_schedule(),
+			device_pruning: None,
+			device_max_inactivity: default_device_max_inactivity(),
+			device_max_per_user: 0,
+			history: default_job_history(),
+		}
+	}
+}
+
+fn default_media_retention_max_age() -> u64 { 60 * 60 * 24 * 90 }
+
+fn default_remote_media_max_age() -> u64 { 60 * 60 * 24 * 30 }
+
+fn default_expired_tokens_schedule() -> Option<Schedule> { "@hourly".parse().ok() }
+
+fn default_ip_retention_schedule() -> Option<Schedule> { "@daily".parse().ok() }
+
+fn default_device_max_inactivity() -> u64 { 60 * 60 * 24 * 90 }
+
+fn default_job_history() -> usize { 10 }
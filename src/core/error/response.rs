@@ -28,13 +28,24 @@ fn into_response(self) -> axum::response::Response {
 impl From<Error> for UiaaResponse {
 	#[inline]
 	fn from(error: Error) -> Self {
-		if let Error::Uiaa(uiaainfo) = error {
-			return Self::AuthResponse(uiaainfo);
-		}
+		let error = match error {
+			| Error::Uiaa(uiaainfo) => return Self::AuthResponse(uiaainfo),
+			// carries an errcode ErrorKind does not know, which would be lost below
+			| Error::Ruma(
+				error @ ruma::api::client::error::Error { body: ErrorBody::Json(_), .. },
+			) => return Self::MatrixError(error),
+			| error => error,
+		};
 
-		let body = ErrorBody::Standard {
-			kind: error.kind(),
-			message: error.message(),
+		let body = match error.kind() {
+			// M_USER_LOCKED always comes with soft_logout, for which ErrorKind has no
+			// field; clients keep their session to use once the account is unlocked.
+			| ErrorKind::UserLocked => ErrorBody::Json(serde_json::json!({
+				"errcode": "M_USER_LOCKED",
+				"error": error.message(),
+				"soft_logout": true,
+			})),
+			| kind => ErrorBody::Standard { kind, message: error.message() },
 		};
 
 		Self::MatrixError(ruma::api::client::error::Error {
@@ -72,12 +83,14 @@ pub(super) fn bad_request_code(kind: &ErrorKind) -> StatusCode {
 		| GuestAccessForbidden
 		| ThreepidAuthFailed
 		| UserDeactivated
+		| UserSuspended
 		| ThreepidDenied
 		| WrongRoomKeysVersion { .. }
 		| Forbidden { .. } => StatusCode::FORBIDDEN,
 
 		// 401
-		| UnknownToken { .. } | MissingToken | Unauthorized => StatusCode::UNAUTHORIZED,
+		| UnknownToken { .. } | MissingToken | Unauthorized | UserLocked =>
+			StatusCode::UNAUTHORIZED,
 
 		// 400
 		| _ => StatusCode::BAD_REQUEST,
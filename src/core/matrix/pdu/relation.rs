@@ -1,4 +1,4 @@
-use ruma::events::relation::RelationType;
+use ruma::{OwnedEventId, events::relation::RelationType};
 use serde::Deserialize;
 
 use crate::implement;
@@ -6,6 +6,7 @@
 #[derive(Clone, Debug, Deserialize)]
 struct ExtractRelType {
 	rel_type: RelationType,
+	event_id: Option<OwnedEventId>,
 }
 #[derive(Clone, Debug, Deserialize)]
 struct ExtractRelatesToEventId {
@@ -20,3 +21,14 @@ pub fn relation_type_equal(&self, rel_type: &RelationType) -> bool {
 		.map(|c: ExtractRelatesToEventId| c.relates_to.rel_type)
 		.is_ok_and(|r| r == *rel_type)
 }
+
+/// Returns the root event of the thread this event is in, if any.
+#[implement(super::Pdu)]
+#[must_use]
+pub fn thread_root(&self) -> Option<OwnedEventId> {
+	self.get_content()
+		.ok()
+		.map(|c: ExtractRelatesToEventId| c.relates_to)
+		.filter(|r| r.rel_type == RelationType::Thread)
+		.and_then(|r| r.event_id)
+}
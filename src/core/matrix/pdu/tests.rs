@@ -1,4 +1,23 @@
-use super::Count;
+use ruma::event_id;
+use serde_json::json;
+
+use super::{Count, Pdu};
+
+fn message(content: serde_json::Value) -> Pdu {
+	serde_json::from_value(json!({
+		"event_id": "$event:example.com",
+		"room_id": "!room:example.com",
+		"sender": "@alice:example.com",
+		"origin_server_ts": 1,
+		"type": "m.room.message",
+		"content": content,
+		"prev_events": [],
+		"depth": 1,
+		"auth_events": [],
+		"hashes": { "sha256": "" },
+	}))
+	.expect("valid pdu")
+}
 
 #[test]
 fn backfilled_parse() {
@@ -15,3 +34,46 @@ fn normal_parse() {
 
 	assert!(!backfilled, "backfilled variant");
 }
+
+#[test]
+fn thread_root_of_thread_reply() {
+	let pdu = message(json!({
+		"msgtype": "m.text",
+		"body": "reply",
+		"m.relates_to": {
+			"rel_type": "m.thread",
+			"event_id": "$root:example.com",
+			"is_falling_back": true,
+			"m.in_reply_to": { "event_id": "$root:example.com" },
+		},
+	}));
+
+	assert_eq!(pdu.thread_root().as_deref(), Some(event_id!("$root:example.com")));
+}
+
+#[test]
+fn thread_root_of_other_events() {
+	let plain = message(json!({ "msgtype": "m.text", "body": "hello" }));
+	assert_eq!(plain.thread_root(), None);
+
+	let reply = message(json!({
+		"msgtype": "m.text",
+		"body": "reply",
+		"m.relates_to": { "m.in_reply_to": { "event_id": "$root:example.com" } },
+	}));
+	assert_eq!(reply.thread_root(), None);
+
+	let edit = message(json!({
+		"msgtype": "m.text",
+		"body": "* edit",
+		"m.relates_to": { "rel_type": "m.replace", "event_id": "$root:example.com" },
+	}));
+	assert_eq!(edit.thread_root(), None);
+
+	let invalid = message(json!({
+		"msgtype": "m.text",
+		"body": "invalid",
+		"m.relates_to": { "rel_type": "m.thread" },
+	}));
+	assert_eq!(invalid.thread_root(), None);
+}
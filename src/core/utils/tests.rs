@@ -276,3 +276,39 @@ async fn set_intersection_sorted_stream2() {
 		.await;
 	assert!(r.eq(&["ccc", "ggg", "iii"]));
 }
+
+#[test]
+fn schedule_next_after() {
+	use std::time::{Duration, SystemTime};
+
+	use utils::time::Schedule;
+
+	// Monday 2024-01-01T00:00:00Z
+	let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+	let next = |schedule: &str| {
+		schedule
+			.parse::<Schedule>()
+			.expect("valid schedule")
+			.next_after(start)
+			.map(|next| next.duration_since(start).expect("after start").as_secs())
+	};
+
+	assert_eq!(next("30 4 * * *"), Some(16_200));
+	assert_eq!(next("* * * * *"), Some(60));
+	assert_eq!(next("@hourly"), Some(3_600));
+	assert_eq!(next("0 0 * * 7"), Some(6 * 86_400));
+	assert_eq!(next("0 0 13 * 5"), Some(4 * 86_400));
+	assert_eq!(next("0 */6 * 2 *"), Some(31 * 86_400));
+	assert_eq!(next("0 0 30 2 *"), None);
+}
+
+#[test]
+fn schedule_invalid() {
+	use utils::time::Schedule;
+
+	assert!("* * *".parse::<Schedule>().is_err());
+	assert!("60 * * * *".parse::<Schedule>().is_err());
+	assert!("0 0 0 * *".parse::<Schedule>().is_err());
+	assert!("*/0 * * * *".parse::<Schedule>().is_err());
+	assert!("5-1 * * * *".parse::<Schedule>().is_err());
+}
@@ -1,7 +1,10 @@
 pub mod exponential_backoff;
+pub mod schedule;
 
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+pub use self::schedule::Schedule;
+
 use crate::{Result, err};
 
 #[inline]
@@ -0,0 +1,166 @@
+//! Cron-like schedules.
+
+use std::{fmt, str::FromStr, time::SystemTime};
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Timelike, Utc};
+use serde::{Deserialize, Deserializer, de};
+
+use crate::{Err, Error, Result, err};
+
+/// Schedule in the five-field format of cron, `minute hour day-of-month month
+/// day-of-week`, evaluated in UTC. Fields take `*`, numbers, ranges `a-b`,
+/// steps `*/n` or `a-b/n`, and comma-separated lists of those. Sunday is
+/// either 0 or 7. The shorthands `@hourly`, `@daily`, `@weekly`, `@monthly`
+/// and `@yearly` are accepted as well.
+///
+/// As in cron, when both the day of the month and the day of the week are
+/// restricted, a day matching either of them matches.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+	source: String,
+	minutes: u64,
+	hours: u64,
+	days: u64,
+	months: u64,
+	weekdays: u64,
+	either_day: bool,
+}
+
+/// Schedules which never match are given up on after this many years.
+const MAX_YEARS: i32 = 5;
+
+impl Schedule {
+	/// The first time matching the schedule strictly after `time`, at the
+	/// start of its minute. None if no time within the next years matches.
+	#[must_use]
+	pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+		let time: DateTime<Utc> = time.into();
+		let mut time = time
+			.with_second(0)?
+			.with_nanosecond(0)?
+			.checked_add_signed(TimeDelta::minutes(1))?;
+
+		let limit = time.year().saturating_add(MAX_YEARS);
+		while time.year() <= limit {
+			if !matches(self.months, time.month()) {
+				let (year, month) = match time.month() {
+					| 12 => (time.year().saturating_add(1), 1),
+					| month => (time.year(), month.saturating_add(1)),
+				};
+
+				time = NaiveDate::from_ymd_opt(year, month, 1)?
+					.and_hms_opt(0, 0, 0)?
+					.and_utc();
+			} else if !self.day_matches(&time) {
+				time = time
+					.date_naive()
+					.succ_opt()?
+					.and_hms_opt(0, 0, 0)?
+					.and_utc();
+			} else if !matches(self.hours, time.hour()) {
+				time = time
+					.with_minute(0)?
+					.checked_add_signed(TimeDelta::hours(1))?;
+			} else if !matches(self.minutes, time.minute()) {
+				time = time.checked_add_signed(TimeDelta::minutes(1))?;
+			} else {
+				return Some(time.into());
+			}
+		}
+
+		None
+	}
+
+	fn day_matches(&self, time: &DateTime<Utc>) -> bool {
+		let day = matches(self.days, time.day());
+		let weekday = matches(self.weekdays, time.weekday().num_days_from_sunday());
+		if self.either_day {
+			day || weekday
+		} else {
+			day && weekday
+		}
+	}
+}
+
+impl FromStr for Schedule {
+	type Err = Error;
+
+	fn from_str(source: &str) -> Result<Self> {
+		let expanded = match source.trim() {
+			| "@yearly" | "@annually" => "0 0 1 1 *",
+			| "@monthly" => "0 0 1 * *",
+			| "@weekly" => "0 0 * * 0",
+			| "@daily" | "@midnight" => "0 0 * * *",
+			| "@hourly" => "0 * * * *",
+			| fields => fields,
+		};
+
+		let fields: Vec<&str> = expanded.split_whitespace().collect();
+		let [minutes, hours, days, months, weekdays] = fields.as_slice() else {
+			return Err!("Schedule {source:?} does not have five fields");
+		};
+
+		let sundays = field(weekdays, 0, 7)?;
+		Ok(Self {
+			source: source.trim().to_owned(),
+			minutes: field(minutes, 0, 59)?,
+			hours: field(hours, 0, 23)?,
+			days: field(days, 1, 31)?,
+			months: field(months, 1, 12)?,
+			weekdays: (sundays | (sundays >> 7)) & 0x7F,
+			either_day: !days.starts_with('*') && !weekdays.starts_with('*'),
+		})
+	}
+}
+
+impl fmt::Display for Schedule {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.source) }
+}
+
+impl<'de> Deserialize<'de> for Schedule {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}
+
+#[inline]
+fn matches(set: u64, value: u32) -> bool { set & bit(value) != 0 }
+
+#[inline]
+fn bit(value: u32) -> u64 { 1_u64.checked_shl(value).unwrap_or(0) }
+
+/// Parse a field into the set of the values it matches within `min..=max`.
+fn field(field: &str, min: u32, max: u32) -> Result<u64> {
+	let mut set = 0_u64;
+	for item in field.split(',') {
+		let (range, step) = match item.split_once('/') {
+			| Some((range, step)) => (range, Some(number(step)?)),
+			| None => (item, None),
+		};
+
+		let (start, end) = match range.split_once('-') {
+			| _ if range == "*" => (min, max),
+			| Some((start, end)) => (number(start)?, number(end)?),
+			| None if step.is_some() => (number(range)?, max),
+			| None => (number(range)?, number(range)?),
+		};
+
+		if start < min || end > max || start > end || step == Some(0) {
+			return Err!("Schedule field {field:?} is out of range {min}-{max}");
+		}
+
+		let step = step.unwrap_or(1);
+		for value in (start..=end).step_by(step.try_into()?) {
+			set |= bit(value);
+		}
+	}
+
+	Ok(set)
+}
+
+fn number(s: &str) -> Result<u32> {
+	s.parse()
+		.map_err(|e| err!("Schedule field value {s:?} is not a number: {e}"))
+}
@@ -0,0 +1,37 @@
+use conduwuit::{Result, debug_warn, implement};
+
+use crate::Database;
+
+/// Catch up a secondary instance with the primary. Returns the sequence number
+/// preceding the updates if there were any.
+#[implement(Database)]
+pub fn catch_up(&self) -> Result<Option<u64>> {
+	let engine = self.engine()?;
+	let since = engine.current_sequence();
+	engine.update()?;
+
+	Ok((engine.current_sequence() != since).then_some(since))
+}
+
+/// Wake the watchers of the keys updated since the sequence number.
+#[implement(Database)]
+pub fn wake_since(&self, since: u64) {
+	let keys = self.engine().and_then(|engine| engine.updated_keys(since));
+
+	// Which column a key belongs to is not recorded in the log, but waking a
+	// watcher spuriously is harmless: it only causes it to check again.
+	match keys {
+		| Ok(keys) if keys.is_empty() => {
+			debug_warn!("Waking every watcher; no updated keys found since {since}");
+			self.maps.values().for_each(|map| map.wake_all());
+		},
+		| Ok(keys) =>
+			for key in &keys {
+				self.maps.values().for_each(|map| map.wake(key));
+			},
+		| Err(e) => {
+			debug_warn!("Waking every watcher; failed to read updates since {since}: {e}");
+			self.maps.values().for_each(|map| map.wake_all());
+		},
+	}
+}
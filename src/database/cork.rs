@@ -3,7 +3,7 @@
 use crate::{Database, Engine};
 
 pub struct Cork {
-	db: Arc<Engine>,
+	db: Option<Arc<Engine>>,
 	flush: bool,
 	sync: bool,
 }
@@ -11,33 +11,40 @@ pub struct Cork {
 impl Database {
 	#[inline]
 	#[must_use]
-	pub fn cork(&self) -> Cork { Cork::new(&self.db, false, false) }
+	pub fn cork(&self) -> Cork { Cork::new(self.engine.as_ref(), false, false) }
 
 	#[inline]
 	#[must_use]
-	pub fn cork_and_flush(&self) -> Cork { Cork::new(&self.db, true, false) }
+	pub fn cork_and_flush(&self) -> Cork { Cork::new(self.engine.as_ref(), true, false) }
 
 	#[inline]
 	#[must_use]
-	pub fn cork_and_sync(&self) -> Cork { Cork::new(&self.db, true, true) }
+	pub fn cork_and_sync(&self) -> Cork { Cork::new(self.engine.as_ref(), true, true) }
 }
 
 impl Cork {
 	#[inline]
-	pub(super) fn new(db: &Arc<Engine>, flush: bool, sync: bool) -> Self {
-		db.cork();
-		Self { db: db.clone(), flush, sync }
+	pub(super) fn new(db: Option<&Arc<Engine>>, flush: bool, sync: bool) -> Self {
+		if let Some(db) = db {
+			db.cork();
+		}
+
+		Self { db: db.cloned(), flush, sync }
 	}
 }
 
 impl Drop for Cork {
 	fn drop(&mut self) {
-		self.db.uncork();
+		let Some(db) = &self.db else {
+			return;
+		};
+
+		db.uncork();
 		if self.flush {
-			self.db.flush().ok();
+			db.flush().ok();
 		}
 		if self.sync {
-			self.db.sync().ok();
+			db.sync().ok();
 		}
 	}
 }
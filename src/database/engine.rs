@@ -1,5 +1,6 @@
 mod backup;
 mod cf_opts;
+pub(crate) mod column;
 pub(crate) mod context;
 mod db_opts;
 pub(crate) mod descriptor;
@@ -7,7 +8,10 @@
 mod logger;
 mod memory_usage;
 mod open;
+mod options;
 mod repair;
+mod snapshot;
+mod updates;
 
 use std::{
 	ffi::CStr,
@@ -20,18 +24,16 @@
 use conduwuit::{Err, Result, debug, info, warn};
 use rocksdb::{
 	AsColumnFamilyRef, BoundColumnFamily, DBCommon, DBWithThreadMode, MultiThreaded,
-	WaitForCompactOptions,
+	WaitForCompactOptions, WriteBatchWithTransaction,
 };
 
 use crate::{
 	Context,
-	pool::Pool,
 	util::{map_err, result},
 };
 
 pub struct Engine {
 	pub(crate) db: Db,
-	pub(crate) pool: Arc<Pool>,
 	pub(crate) ctx: Arc<Context>,
 	pub(super) read_only: bool,
 	pub(super) secondary: bool,
@@ -118,6 +120,33 @@ pub(crate) fn cf(&self, name: &str) -> Arc<BoundColumnFamily<'_>> {
 			.expect("column must be described prior to database open")
 	}
 
+	/// Write to any number of columns atomically; a write without a value is
+	/// a deletion. Writes are flushed immediately unless the database is
+	/// corked.
+	pub(crate) fn write_batch<'a, I>(self: &Arc<Self>, writes: I) -> Result
+	where
+		I: IntoIterator<Item = (&'a str, &'a [u8], Option<&'a [u8]>)>,
+	{
+		let mut batch = WriteBatchWithTransaction::<false>::default();
+		for (column, key, val) in writes {
+			let cf = self.cf(column);
+			match val {
+				| Some(val) => batch.put_cf(&cf, key, val),
+				| None => batch.delete_cf(&cf, key),
+			}
+		}
+
+		self.db
+			.write_opt(batch, &options::write_options_default(self))
+			.map_err(map_err)?;
+
+		if !self.corked() {
+			self.flush()?;
+		}
+
+		Ok(())
+	}
+
 	#[inline]
 	#[must_use]
 	#[tracing::instrument(name = "sequence", level = "debug", skip_all, fields(sequence))]
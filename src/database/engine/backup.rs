@@ -1,9 +1,16 @@
-use std::fmt::Write;
+mod tests;
 
-use conduwuit::{Result, error, implement, info, utils::time::rfc2822_from_seconds, warn};
-use rocksdb::backup::{BackupEngine, BackupEngineOptions};
+use std::{fmt::Write, fs, path::Path, time::UNIX_EPOCH};
 
-use super::Engine;
+use conduwuit::{
+	Err, Result, err, error, implement, info, utils::time::rfc2822_from_seconds, warn,
+};
+use rocksdb::{
+	IteratorMode, Options, ReadOptions,
+	backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions},
+};
+
+use super::{Db, Engine};
 use crate::{or_else, util::map_err};
 
 #[implement(Engine)]
@@ -33,9 +40,13 @@ pub fn backup(&self) -> Result {
 		);
 	}
 
-	if config.database_backups_to_keep >= 0 {
-		let keep = u32::try_from(config.database_backups_to_keep)?;
-		if let Err(e) = engine.purge_old_backups(keep.try_into()?) {
+	let now = UNIX_EPOCH.elapsed().unwrap_or_default().as_secs();
+	let backups = engine.get_backup_info();
+	let retained =
+		retained(config.database_backups_to_keep, config.database_backup_max_age, now, &backups)?;
+
+	if let Some(keep) = retained {
+		if let Err(e) = engine.purge_old_backups(keep) {
 			error!("Failed to purge old backup: {e:?}");
 		}
 	}
@@ -43,6 +54,114 @@ pub fn backup(&self) -> Result {
 	Ok(())
 }
 
+/// Number of the newest backups to keep under "database_backups_to_keep" and
+/// "database_backup_max_age" at `now` (seconds since the epoch), or None to
+/// keep them all. The age limit never purges the newest backup.
+fn retained(
+	to_keep: i16,
+	max_age: u64,
+	now: u64,
+	backups: &[BackupEngineInfo],
+) -> Result<Option<usize>> {
+	let by_count = (to_keep >= 0)
+		.then(|| usize::try_from(to_keep))
+		.transpose()?;
+
+	let by_age = (max_age > 0).then(|| {
+		let now = i64::try_from(now).unwrap_or(i64::MAX);
+		let max_age = i64::try_from(max_age).unwrap_or(i64::MAX);
+		let cutoff = now.saturating_sub(max_age);
+		backups
+			.iter()
+			.filter(|info| info.timestamp >= cutoff)
+			.count()
+			.max(1)
+	});
+
+	Ok(by_count.into_iter().chain(by_age).min())
+}
+
+/// Restore a backup into a temporary directory, open it read-only and read
+/// every record of every column, failing on the first error. The latest
+/// backup is verified unless one is given.
+#[implement(Engine)]
+#[tracing::instrument(skip(self))]
+pub fn backup_verify(&self, backup_id: Option<u32>) -> Result<String> {
+	let server = &self.ctx.server;
+	let config = &server.config;
+	let Some(path) = config
+		.database_backup_path
+		.as_ref()
+		.filter(|path| !path.as_os_str().is_empty())
+	else {
+		return Err!(Config("database_backup_path", "No database backup path is configured."));
+	};
+
+	let options = BackupEngineOptions::new(path).map_err(map_err)?;
+	let mut engine = BackupEngine::open(&options, &*self.ctx.env.lock()?).map_err(map_err)?;
+	let backups = engine.get_backup_info();
+	let Some(info) = backups
+		.iter()
+		.rfind(|info| backup_id.is_none_or(|id| id == info.backup_id))
+	else {
+		return Err!("Backup not found.");
+	};
+
+	let backup_id = info.backup_id;
+	engine.verify_backup(backup_id).map_err(map_err)?;
+
+	let dir = config
+		.database_backup_restore_path
+		.clone()
+		.filter(|path| !path.as_os_str().is_empty())
+		.unwrap_or_else(std::env::temp_dir)
+		.join(format!("conduwuit-verify-backup-{backup_id}"));
+	if dir.exists() {
+		fs::remove_dir_all(&dir)?;
+	}
+
+	info!("Restoring database backup #{backup_id} into {dir:?} for verification");
+	let result = engine
+		.restore_from_backup(&dir, &dir, &RestoreOptions::default(), backup_id)
+		.map_err(map_err)
+		.and_then(|()| verify_columns(&dir));
+
+	if let Err(e) = fs::remove_dir_all(&dir) {
+		warn!("Failed to remove {dir:?} after verification: {e}");
+	}
+
+	let (columns, records) = result?;
+	info!(columns, records, "Verified database backup #{backup_id}");
+
+	Ok(format!(
+		"Backup #{backup_id} from {} is intact: read {records} records from {columns} columns.",
+		rfc2822_from_seconds(info.timestamp),
+	))
+}
+
+fn verify_columns(dir: &Path) -> Result<(usize, u64)> {
+	let opts = Options::default();
+	let names = Db::list_cf(&opts, dir).map_err(map_err)?;
+	let db = Db::open_cf_for_read_only(&opts, dir, &names, false).map_err(map_err)?;
+
+	let mut records: u64 = 0;
+	for name in &names {
+		let cf = db
+			.cf_handle(name)
+			.ok_or_else(|| err!(Database("Column {name:?} missing from restored backup.")))?;
+
+		let mut read_options = ReadOptions::default();
+		read_options.set_verify_checksums(true);
+		read_options.fill_cache(false);
+		for item in db.iterator_cf_opt(&cf, read_options, IteratorMode::Start) {
+			item.map_err(|e| err!(Database("Column {name:?} failed verification: {e}")))?;
+			records = records.saturating_add(1);
+		}
+	}
+
+	Ok((names.len(), records))
+}
+
 #[implement(Engine)]
 pub fn backup_list(&self) -> Result<String> {
 	let server = &self.ctx.server;
@@ -0,0 +1,68 @@
+#![cfg(test)]
+
+use rocksdb::backup::BackupEngineInfo;
+
+use super::retained;
+
+const DAY: u64 = 24 * 60 * 60;
+const NOW: u64 = days(100);
+
+const fn days(days: u64) -> u64 { days.saturating_mul(DAY) }
+
+/// Backups made the given number of days ago, oldest first.
+fn backups(days_ago: &[u64]) -> Vec<BackupEngineInfo> {
+	days_ago
+		.iter()
+		.zip(1..)
+		.map(|(days_ago, backup_id)| BackupEngineInfo {
+			timestamp: NOW
+				.saturating_sub(days(*days_ago))
+				.try_into()
+				.expect("valid timestamp"),
+			backup_id,
+			size: 0,
+			num_files: 0,
+		})
+		.collect()
+}
+
+#[test]
+fn retained_by_count() {
+	let backups = backups(&[3, 2, 1]);
+
+	assert_eq!(retained(2, 0, NOW, &backups).unwrap(), Some(2));
+	assert_eq!(retained(5, 0, NOW, &backups).unwrap(), Some(5));
+	assert_eq!(retained(0, 0, NOW, &backups).unwrap(), Some(0));
+}
+
+#[test]
+fn retained_unlimited() {
+	let backups = backups(&[3, 2, 1]);
+
+	assert_eq!(retained(-1, 0, NOW, &backups).unwrap(), None);
+}
+
+#[test]
+fn retained_by_age() {
+	let backups = backups(&[10, 5, 2, 1]);
+
+	assert_eq!(retained(-1, days(3), NOW, &backups).unwrap(), Some(2));
+	assert_eq!(retained(-1, days(5), NOW, &backups).unwrap(), Some(3));
+	assert_eq!(retained(-1, days(30), NOW, &backups).unwrap(), Some(4));
+}
+
+#[test]
+fn retained_keeps_newest_by_age() {
+	let backups = backups(&[10, 5]);
+
+	assert_eq!(retained(-1, DAY, NOW, &backups).unwrap(), Some(1));
+	assert_eq!(retained(-1, DAY, NOW, &[]).unwrap(), Some(1));
+}
+
+#[test]
+fn retained_stricter_limit() {
+	let backups = backups(&[10, 5, 2, 1]);
+
+	assert_eq!(retained(3, days(3), NOW, &backups).unwrap(), Some(2));
+	assert_eq!(retained(1, days(30), NOW, &backups).unwrap(), Some(1));
+}
@@ -0,0 +1,266 @@
+//! RocksDB implementation of `Storage`.
+
+use std::{ffi::CStr, sync::Arc};
+
+use conduwuit::{Err, Error, Result, implement};
+use rocksdb::{
+	AsColumnFamilyRef, BottommostLevelCompaction, ColumnFamily, CompactOptions, DBPinnableSlice,
+	DBRawIteratorWithThreadMode, ReadOptions, WriteBatchWithTransaction, WriteOptions,
+};
+
+use super::{
+	Db, Engine,
+	options::{
+		cache_iter_options_default, cache_read_options_default, iter_options_default,
+		read_options_default, write_options_default,
+	},
+};
+use crate::{
+	Handle,
+	map::compact,
+	storage::{Cached, Cursor, Storage},
+	util::{is_incomplete, map_err},
+};
+
+/// Column family of the RocksDB engine.
+pub(crate) struct Column {
+	cf: Arc<ColumnFamily>,
+	engine: Arc<Engine>,
+	read_options: ReadOptions,
+	cache_read_options: ReadOptions,
+	write_options: WriteOptions,
+}
+
+struct Iter<'a>(DBRawIteratorWithThreadMode<'a, Db>);
+
+// Batched multi-get can be optimized if the keys are pre-sorted **by the
+// column comparator**.
+const SORTED: bool = false;
+
+impl Column {
+	pub(crate) fn open(engine: &Arc<Engine>, name: &str) -> Self {
+		Self {
+			cf: open_cf(engine, name),
+			engine: engine.clone(),
+			read_options: read_options_default(engine),
+			cache_read_options: cache_read_options_default(engine),
+			write_options: write_options_default(engine),
+		}
+	}
+
+	#[inline]
+	fn cf(&self) -> impl AsColumnFamilyRef + '_ { &*self.cf }
+
+	/// Writes are flushed immediately unless the database is corked.
+	fn flush(&self) -> Result {
+		if !self.engine.corked() {
+			self.engine.flush()?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Storage for Column {
+	fn get(&self, key: &[u8]) -> Result<Option<Handle<'_>>> {
+		self.engine
+			.db
+			.get_pinned_cf_opt(&self.cf(), key, &self.read_options)
+			.map(|val| val.map(Handle::from))
+			.map_err(map_err)
+	}
+
+	fn get_cached(&self, key: &[u8]) -> Result<Cached<Option<Handle<'_>>>> {
+		cached(
+			self.engine
+				.db
+				.get_pinned_cf_opt(&self.cf(), key, &self.cache_read_options),
+		)
+	}
+
+	fn get_batch<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Option<Handle<'a>>>> {
+		self.engine
+			.db
+			.batched_multi_get_cf_opt(&self.cf(), keys, SORTED, &self.read_options)
+			.into_iter()
+			.map(|res| res.map(|val| val.map(Handle::from)).map_err(map_err))
+			.collect()
+	}
+
+	fn get_batch_cached<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Cached<Option<Handle<'a>>>>> {
+		self.engine
+			.db
+			.batched_multi_get_cf_opt(&self.cf(), keys, SORTED, &self.cache_read_options)
+			.into_iter()
+			.map(cached)
+			.collect()
+	}
+
+	/// Rocksdb limits this to kBlockCacheTier internally so this is not
+	/// actually a blocking call; in case that changes we set this as well in
+	/// our read_options.
+	fn may_exist(&self, key: &[u8]) -> bool {
+		self.engine
+			.db
+			.key_may_exist_cf_opt(&self.cf(), key, &self.cache_read_options)
+	}
+
+	fn put(&self, key: &[u8], val: &[u8]) -> Result {
+		self.engine
+			.db
+			.put_cf_opt(&self.cf(), key, val, &self.write_options)
+			.map_err(map_err)?;
+
+		self.flush()
+	}
+
+	fn put_batch(&self, batch: &[(&[u8], &[u8])]) -> Result {
+		let mut write = WriteBatchWithTransaction::<false>::default();
+		for (key, val) in batch {
+			write.put_cf(&self.cf(), key, val);
+		}
+
+		self.engine
+			.db
+			.write_opt(write, &self.write_options)
+			.map_err(map_err)?;
+
+		self.flush()
+	}
+
+	fn delete(&self, key: &[u8]) -> Result {
+		self.engine
+			.db
+			.delete_cf_opt(&self.cf(), key, &self.write_options)
+			.map_err(map_err)?;
+
+		self.flush()
+	}
+
+	fn cursor(&self, cached: bool) -> Box<dyn Cursor + '_> {
+		let opts = if cached {
+			cache_iter_options_default(&self.engine)
+		} else {
+			iter_options_default(&self.engine)
+		};
+
+		Box::new(Iter(self.engine.db.raw_iterator_cf_opt(&self.cf(), opts)))
+	}
+
+	fn compact(&self, opts: &compact::Options) -> Result { self.compact_range(opts) }
+
+	fn property(&self, name: &str) -> Result<String> { self.engine.property(&self.cf(), name) }
+
+	fn property_integer(&self, name: &CStr) -> Result<u64> {
+		self.engine.property_integer(&self.cf(), name)
+	}
+}
+
+#[implement(Column)]
+fn compact_range(&self, opts: &compact::Options) -> Result {
+	let mut co = CompactOptions::default();
+	co.set_exclusive_manual_compaction(opts.exclusive);
+	co.set_bottommost_level_compaction(match opts.exhaustive {
+		| true => BottommostLevelCompaction::Force,
+		| false => BottommostLevelCompaction::ForceOptimized,
+	});
+
+	match opts.level {
+		| (None, None) => {
+			co.set_change_level(true);
+			co.set_target_level(-1);
+		},
+		| (None, Some(level)) => {
+			co.set_change_level(true);
+			co.set_target_level(level.try_into()?);
+		},
+		| (Some(level), None) => {
+			co.set_change_level(false);
+			co.set_target_level(level.try_into()?);
+		},
+		| (Some(_), Some(_)) => return Err!("compacting between specific levels not supported"),
+	}
+
+	let (start, end) = &opts.range;
+	self.engine
+		.db
+		.compact_range_cf_opt(&self.cf(), start.as_deref(), end.as_deref(), &co);
+
+	Ok(())
+}
+
+impl Cursor for Iter<'_> {
+	#[inline]
+	fn seek(&mut self, key: &[u8]) { self.0.seek(key); }
+
+	#[inline]
+	fn seek_for_prev(&mut self, key: &[u8]) { self.0.seek_for_prev(key); }
+
+	#[inline]
+	fn seek_to_first(&mut self) { self.0.seek_to_first(); }
+
+	#[inline]
+	fn seek_to_last(&mut self) { self.0.seek_to_last(); }
+
+	#[inline]
+	fn next(&mut self) { self.0.next(); }
+
+	#[inline]
+	fn prev(&mut self) { self.0.prev(); }
+
+	#[inline]
+	fn valid(&self) -> bool { self.0.valid() }
+
+	#[inline]
+	fn key(&self) -> Option<&[u8]> { self.0.key() }
+
+	#[inline]
+	fn value(&self) -> Option<&[u8]> { self.0.value() }
+
+	#[inline]
+	fn item(&self) -> Option<(&[u8], &[u8])> { self.0.item() }
+
+	#[inline]
+	fn status(&self) -> Option<Error> { self.0.status().err().map(map_err) }
+
+	fn is_incomplete(&self) -> bool { matches!(self.0.status(), Err(e) if is_incomplete(&e)) }
+}
+
+fn cached(
+	result: Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>,
+) -> Result<Cached<Option<Handle<'_>>>> {
+	match result {
+		| Ok(val) => Ok(Cached::Hit(val.map(Handle::from))),
+		| Err(error) if is_incomplete(&error) => Ok(Cached::Miss),
+		| Err(error) => Err(map_err(error)),
+	}
+}
+
+fn open_cf(db: &Arc<Engine>, name: &str) -> Arc<ColumnFamily> {
+	let bounded_arc = db.cf(name);
+	let bounded_ptr = Arc::into_raw(bounded_arc);
+	let cf_ptr = bounded_ptr.cast::<ColumnFamily>();
+
+	// SAFETY: Column family handles out of RocksDB are basic pointers and can
+	// be invalidated: 1. when the database closes. 2. when the column is dropped or
+	// closed. rust_rocksdb wraps this for us by storing handles in their own
+	// `RwLock<BTreeMap>` map and returning an Arc<BoundColumnFamily<'_>>` to
+	// provide expected safety. Similarly in "single-threaded mode" we would
+	// receive `&'_ ColumnFamily`.
+	//
+	// PROBLEM: We need to hold these handles in a field, otherwise we have to take
+	// a lock and get them by name from this map for every query, which is what
+	// conduit was doing, but we're not going to make a query for every query so we
+	// need to be holding it right. The lifetime parameter on these references makes
+	// that complicated. If this can be done without polluting the userspace
+	// with lifetimes on every instance of `Map` then this `unsafe` might not be
+	// necessary.
+	//
+	// SOLUTION: After investigating the underlying types it appears valid to
+	// Arc-swap `BoundColumnFamily<'_>` for `ColumnFamily`. They have the
+	// same inner data, the same Drop behavior, Deref, etc. We're just losing the
+	// lifetime parameter. We should not hold this handle, even in its Arc, after
+	// closing the database (dropping `Engine`). Since `Arc<Engine>` is a sibling
+	// member along with this handle in `Column`, that is prevented.
+	unsafe { Arc::from_raw(cf_ptr) }
+}
@@ -42,7 +42,8 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 	let db = if config.rocksdb_read_only {
 		Db::open_cf_descriptors_read_only(&db_opts, path, cfds, false)
 	} else if config.rocksdb_secondary {
-		Db::open_cf_descriptors_as_secondary(&db_opts, path, path, cfds)
+		let secondary_path = config.rocksdb_secondary_path.as_ref().unwrap_or(path);
+		Db::open_cf_descriptors_as_secondary(&db_opts, path, secondary_path, cfds)
 	} else {
 		Db::open_cf_descriptors(&db_opts, path, cfds)
 	}
@@ -57,7 +58,6 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 
 	Ok(Arc::new(Self {
 		db,
-		pool: ctx.pool.clone(),
 		ctx: ctx.clone(),
 		read_only: config.rocksdb_read_only,
 		secondary: config.rocksdb_secondary,
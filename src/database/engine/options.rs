@@ -2,7 +2,7 @@
 
 use rocksdb::{ReadOptions, ReadTier, WriteOptions};
 
-use crate::Engine;
+use super::Engine;
 
 #[inline]
 pub(crate) fn cache_iter_options_default(db: &Arc<Engine>) -> ReadOptions {
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use conduwuit::Result;
+use futures::{Stream, stream};
+use rocksdb::{IteratorMode, SnapshotWithThreadMode};
+
+use super::{Db, Engine, options::iter_options_default};
+use crate::util::map_err;
+
+/// Consistent view of every column as of the moment it was taken.
+pub(crate) type Snapshot<'a> = SnapshotWithThreadMode<'a, Db>;
+
+impl Engine {
+	#[inline]
+	pub(crate) fn snapshot(&self) -> Snapshot<'_> { self.db.snapshot() }
+
+	/// Every record of a column as of the snapshot, in key order.
+	pub(crate) fn snapshot_records<'a>(
+		self: &'a Arc<Self>,
+		snapshot: &'a Snapshot<'a>,
+		column: &str,
+	) -> impl Stream<Item = Result<(Box<[u8]>, Box<[u8]>)>> + Send + 'a {
+		let iter = snapshot.iterator_cf_opt(
+			&self.cf(column),
+			iter_options_default(self),
+			IteratorMode::Start,
+		);
+
+		stream::iter(iter.map(|record| record.map_err(map_err)))
+	}
+}
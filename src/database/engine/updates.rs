@@ -0,0 +1,39 @@
+mod tests;
+
+use conduwuit::{Result, implement};
+use rocksdb::WriteBatchIteratorCf;
+
+use super::{Db, Engine};
+use crate::util::map_err;
+
+/// Keys written or deleted, in any column, by updates to the database
+/// following the sequence number.
+#[implement(Engine)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub fn updated_keys(&self, since: u64) -> Result<Vec<Box<[u8]>>> { keys_since(&self.db, since) }
+
+pub(super) fn keys_since(db: &Db, since: u64) -> Result<Vec<Box<[u8]>>> {
+	let mut keys = Keys::default();
+	for update in db
+		.get_updates_since(since.saturating_add(1))
+		.map_err(map_err)?
+	{
+		let (_sequence, batch) = update.map_err(map_err)?;
+		batch.iterate_cf(&mut keys);
+	}
+
+	Ok(keys.0)
+}
+
+/// Collects the keys of a write batch. The column family handler is used
+/// because the plain one only reports writes to the default column.
+#[derive(Default)]
+struct Keys(Vec<Box<[u8]>>);
+
+impl WriteBatchIteratorCf for Keys {
+	fn put_cf(&mut self, _cf_id: u32, key: &[u8], _val: &[u8]) { self.0.push(key.into()); }
+
+	fn delete_cf(&mut self, _cf_id: u32, key: &[u8]) { self.0.push(key.into()); }
+
+	fn merge_cf(&mut self, _cf_id: u32, key: &[u8], _val: &[u8]) { self.0.push(key.into()); }
+}
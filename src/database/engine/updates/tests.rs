@@ -0,0 +1,54 @@
+#![cfg(test)]
+
+use futures::FutureExt;
+use rocksdb::{ColumnFamilyDescriptor, Options};
+
+use super::keys_since;
+use crate::{engine::Db, watchers::Watchers};
+
+const COLUMN: &str = "userid_password";
+
+fn open(name: &str) -> Db {
+	let path =
+		std::env::temp_dir().join(format!("conduwuit-updates-{}-{name}", std::process::id()));
+	_ = std::fs::remove_dir_all(&path);
+
+	let mut opts = Options::default();
+	opts.create_if_missing(true);
+	opts.create_missing_column_families(true);
+	let column = ColumnFamilyDescriptor::new(COLUMN, Options::default());
+
+	Db::open_cf_descriptors(&opts, &path, [column]).expect("database opened")
+}
+
+#[test]
+fn updated_keys_of_named_column() {
+	let db = open("named_column");
+	let since = db.latest_sequence_number();
+
+	let cf = db.cf_handle(COLUMN).expect("column exists");
+	db.put_cf(&cf, b"@alice:example.com", b"hash").unwrap();
+	db.delete_cf(&cf, b"@bob:example.com").unwrap();
+
+	let expected: [Box<[u8]>; 2] =
+		[&b"@alice:example.com"[..], &b"@bob:example.com"[..]].map(Into::into);
+	assert_eq!(keys_since(&db, since).expect("updates read"), expected);
+}
+
+#[test]
+fn updated_keys_wake_watcher() {
+	let db = open("wake_watcher");
+	let since = db.latest_sequence_number();
+
+	let watchers = Watchers::default();
+	let watch = watchers.watch(b"@alice:example.com");
+
+	let cf = db.cf_handle(COLUMN).expect("column exists");
+	db.put_cf(&cf, b"@alice:example.com", b"hash").unwrap();
+
+	for key in keys_since(&db, since).expect("updates read") {
+		watchers.wake(&key);
+	}
+
+	assert!(watch.now_or_never().is_some(), "watcher woken");
+}
@@ -0,0 +1,425 @@
+//! Portable export and import of the database.
+//!
+//! An export holds the raw records of every column independent of the storage
+//! engine's on-disk format. Integers are big-endian u32:
+//!
+//! - `MAGIC` followed by the format version
+//! - for each column: its name, then every record as a key and a value, then
+//!   `END` in place of a key
+//! - the manifest as JSON
+//! - the length of the manifest
+//! - SHA-256 of everything preceding it
+//!
+//! Names, keys, values and the manifest are each prefixed by their length.
+
+mod tests;
+
+use std::{path::Path, pin::pin};
+
+use conduwuit::{Err, Result, debug, error, implement, info, utils::time::now_millis, warn};
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{
+	fs::{File, OpenOptions},
+	io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter, SeekFrom},
+};
+
+use crate::Database;
+
+/// Summary of an export, stored at its end.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Manifest {
+	/// Format version of the export.
+	pub version: u32,
+
+	/// Server the database belonged to.
+	pub server_name: String,
+
+	/// Time the export was started (milliseconds since the unix epoch).
+	pub created: u64,
+
+	/// Columns in the order they appear in the export.
+	pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Column {
+	pub name: String,
+	pub records: u64,
+}
+
+struct Writer {
+	inner: BufWriter<File>,
+	hasher: Sha256,
+}
+
+const MAGIC: &[u8; 16] = b"conduwuit-export";
+const VERSION: u32 = 1;
+const END: u32 = u32::MAX;
+const CHECKSUM_LEN: u64 = 32;
+
+/// Number of records written to the database at once during import.
+const BATCH_SIZE: usize = 1024;
+
+/// Keys in the `global` column tracking an import. The pending marker is set
+/// before the first record is loaded; the done marker holds the manifest of
+/// the completed import.
+const IMPORT_PENDING: &[u8] = b"database_import_pending";
+const IMPORT_DONE: &[u8] = b"database_import_done";
+
+/// Export every column to a new file at `path`. The database remains online;
+/// every column is read from one snapshot, so the export is consistent even
+/// while the server writes to it.
+#[implement(Database)]
+#[tracing::instrument(skip(self))]
+pub async fn export(&self, path: &Path) -> Result<Manifest> {
+	let server_name = self.ctx.server.config.server_name.as_str();
+	let engine = self.engine()?;
+	let snapshot = engine.snapshot();
+	let columns = self
+		.iter()
+		.map(|(name, _)| (*name, engine.snapshot_records(&snapshot, name)));
+
+	let manifest = write(path, server_name, columns).await?;
+	info!(
+		columns = manifest.columns.len(),
+		records = manifest.columns.iter().map(|c| c.records).sum::<u64>(),
+		"Exported database to {path:?}"
+	);
+
+	Ok(manifest)
+}
+
+/// Load an export into this database, which must be empty. This is done
+/// right after the database is opened, before any service reads from it, so
+/// that migrations then run on the imported data as usual.
+///
+/// A completed import is recorded in the database and not repeated on later
+/// startups. If loading fails, the records loaded so far are removed again;
+/// should the server stop before that, they are removed on the next attempt.
+#[implement(Database)]
+#[tracing::instrument(skip(self))]
+pub async fn import(&self, path: &Path) -> Result<Manifest> {
+	let global = self.get("global")?;
+	if let Ok(manifest) = global.get(IMPORT_DONE).await {
+		let manifest: Manifest = serde_json::from_slice(&manifest)?;
+		info!(
+			created = manifest.created,
+			"Database was already imported; ignoring database_import_path."
+		);
+
+		return Ok(manifest);
+	}
+
+	if self.is_read_only() {
+		return Err!(Database("Cannot import into a read-only database."));
+	}
+
+	if global.get(IMPORT_PENDING).await.is_ok() {
+		warn!("A previous import did not complete; removing the records it loaded.");
+		self.clear().await;
+	}
+
+	for (name, map) in self.iter() {
+		if map.raw_keys().boxed().next().await.is_some() {
+			return Err!(Database(error!(
+				"Refusing to import into a database which is not empty; column {name:?} has \
+				 records."
+			)));
+		}
+	}
+
+	let manifest = verify(path).await?;
+	let server_name = self.ctx.server.config.server_name.as_str();
+	if manifest.server_name != server_name {
+		return Err!(Database(error!(
+			"Refusing to import an export of {:?} into the database of {server_name:?}.",
+			manifest.server_name
+		)));
+	}
+
+	if let Some(column) = manifest
+		.columns
+		.iter()
+		.find(|column| self.get(&column.name).is_err())
+	{
+		return Err!(Database(error!(
+			"Export contains column {:?} which is unknown to this version.",
+			column.name
+		)));
+	}
+
+	info!(
+		server_name = manifest.server_name,
+		created = manifest.created,
+		columns = manifest.columns.len(),
+		"Importing database from {path:?}"
+	);
+
+	global.insert(IMPORT_PENDING, b"");
+	let loaded = {
+		let _cork = self.cork_and_sync();
+		read(path, &manifest, |name, batch| {
+			self.get(name)?.insert_batch(batch.into_iter());
+			Ok(())
+		})
+		.await
+	};
+
+	if let Err(e) = loaded {
+		error!("Import failed; removing the records loaded so far.");
+		self.clear().await;
+		return Err(e);
+	}
+
+	global.insert(IMPORT_DONE, serde_json::to_vec(&manifest)?);
+	global.remove(IMPORT_PENDING);
+
+	Ok(manifest)
+}
+
+/// Remove every record from every column.
+#[implement(Database)]
+async fn clear(&self) {
+	for (_, map) in self.iter() {
+		map.clear().await;
+	}
+}
+
+/// Write an export of `columns` to a new file at `path`.
+async fn write<'a, I, S, K, V>(path: &Path, server_name: &str, columns: I) -> Result<Manifest>
+where
+	I: Iterator<Item = (&'a str, S)> + Send,
+	S: Stream<Item = Result<(K, V)>> + Send + 'a,
+	K: AsRef<[u8]> + Send,
+	V: AsRef<[u8]> + Send,
+{
+	let file = OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(path)
+		.await?;
+
+	let mut out = Writer {
+		inner: BufWriter::new(file),
+		hasher: Sha256::new(),
+	};
+
+	let mut manifest = Manifest {
+		version: VERSION,
+		server_name: server_name.to_owned(),
+		created: now_millis(),
+		columns: Vec::new(),
+	};
+
+	out.write(MAGIC).await?;
+	out.write_u32(VERSION).await?;
+	for (name, stream) in columns {
+		out.write_bytes(name.as_bytes()).await?;
+
+		let mut records: u64 = 0;
+		let mut stream = pin!(stream);
+		while let Some((key, val)) = stream.try_next().await? {
+			out.write_bytes(key.as_ref()).await?;
+			out.write_bytes(val.as_ref()).await?;
+			records = records.saturating_add(1);
+		}
+
+		out.write_u32(END).await?;
+		debug!(column = name, records, "Exported column");
+		manifest
+			.columns
+			.push(Column { name: name.to_owned(), records });
+	}
+
+	let encoded = serde_json::to_vec(&manifest)?;
+	out.write(&encoded).await?;
+	out.write_u32(encoded.len().try_into()?).await?;
+	out.finish().await?;
+
+	Ok(manifest)
+}
+
+/// Read the records of a verified export, passing them to `insert` in batches
+/// along with the name of their column.
+async fn read<F>(path: &Path, manifest: &Manifest, mut insert: F) -> Result
+where
+	F: FnMut(&str, Vec<(Vec<u8>, Vec<u8>)>) -> Result + Send,
+{
+	let mut input = BufReader::new(File::open(path).await?);
+	read_header(&mut input).await?;
+
+	for column in &manifest.columns {
+		let name = read_bytes(&mut input).await?;
+		if name != column.name.as_bytes() {
+			return Err!(Database(error!(
+				"Export is corrupt; expected column {:?} next.",
+				column.name
+			)));
+		}
+
+		let mut batch = Vec::with_capacity(BATCH_SIZE);
+		let mut records: u64 = 0;
+		while let Some(key) = read_record(&mut input).await? {
+			let val = read_bytes(&mut input).await?;
+			batch.push((key, val));
+			if batch.len() >= BATCH_SIZE {
+				insert(
+					&column.name,
+					std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE)),
+				)?;
+			}
+
+			records = records.saturating_add(1);
+		}
+
+		if !batch.is_empty() {
+			insert(&column.name, batch)?;
+		}
+
+		if records != column.records {
+			return Err!(Database(error!(
+				"Export is corrupt; column {:?} has {records} records, expected {}.",
+				column.name, column.records
+			)));
+		}
+
+		debug!(column = column.name, records, "Imported column");
+	}
+
+	Ok(())
+}
+
+/// Check the checksum, header and manifest of an export.
+async fn verify(path: &Path) -> Result<Manifest> {
+	let mut file = File::open(path).await?;
+	let len = file.metadata().await?.len();
+	let Some(body_len) = len.checked_sub(CHECKSUM_LEN) else {
+		return Err!(Database("Export is truncated."));
+	};
+
+	let mut hasher = Sha256::new();
+	let mut body = (&mut file).take(body_len);
+	let mut buf = vec![0_u8; 64 * 1024];
+	loop {
+		let read = body.read(&mut buf).await?;
+		if read == 0 {
+			break;
+		}
+
+		hasher.update(&buf[..read]);
+	}
+
+	let mut checksum = [0_u8; 32];
+	file.seek(SeekFrom::Start(body_len)).await?;
+	file.read_exact(&mut checksum).await?;
+	if hasher.finalize().as_slice() != checksum {
+		return Err!(Database("Export checksum mismatch; the file is corrupt or incomplete."));
+	}
+
+	file.seek(SeekFrom::Start(0)).await?;
+	read_header(&mut file).await?;
+
+	let Some(manifest_end) = body_len.checked_sub(4) else {
+		return Err!(Database("Export is truncated."));
+	};
+
+	file.seek(SeekFrom::Start(manifest_end)).await?;
+	let manifest_len = file.read_u32().await?;
+	let Some(manifest_start) = manifest_end.checked_sub(manifest_len.into()) else {
+		return Err!(Database("Export is truncated."));
+	};
+
+	file.seek(SeekFrom::Start(manifest_start)).await?;
+	let mut manifest = vec![0_u8; manifest_len.try_into()?];
+	file.read_exact(&mut manifest).await?;
+	let manifest: Manifest = serde_json::from_slice(&manifest)?;
+	if manifest.version != VERSION {
+		return Err!(Database(error!(
+			"Export manifest has format version {}, expected {VERSION}.",
+			manifest.version
+		)));
+	}
+
+	Ok(manifest)
+}
+
+async fn read_header<R>(input: &mut R) -> Result
+where
+	R: AsyncRead + Unpin + Send,
+{
+	let mut magic = [0_u8; MAGIC.len()];
+	input.read_exact(&mut magic).await?;
+	if &magic != MAGIC {
+		return Err!(Database("Not a database export."));
+	}
+
+	let version = input.read_u32().await?;
+	if version != VERSION {
+		return Err!(Database(error!(
+			"Unsupported export format version {version}, expected {VERSION}."
+		)));
+	}
+
+	Ok(())
+}
+
+/// Read the key of the next record in a column, or None at its end.
+async fn read_record<R>(input: &mut R) -> Result<Option<Vec<u8>>>
+where
+	R: AsyncRead + Unpin + Send,
+{
+	match input.read_u32().await? {
+		| END => Ok(None),
+		| len => read_exact(input, len).await.map(Some),
+	}
+}
+
+async fn read_bytes<R>(input: &mut R) -> Result<Vec<u8>>
+where
+	R: AsyncRead + Unpin + Send,
+{
+	let len = input.read_u32().await?;
+	read_exact(input, len).await
+}
+
+async fn read_exact<R>(input: &mut R, len: u32) -> Result<Vec<u8>>
+where
+	R: AsyncRead + Unpin + Send,
+{
+	let mut buf = vec![0_u8; len.try_into()?];
+	input.read_exact(&mut buf).await?;
+
+	Ok(buf)
+}
+
+impl Writer {
+	async fn write(&mut self, buf: &[u8]) -> Result {
+		self.hasher.update(buf);
+		self.inner.write_all(buf).await?;
+
+		Ok(())
+	}
+
+	async fn write_u32(&mut self, val: u32) -> Result { self.write(&val.to_be_bytes()).await }
+
+	async fn write_bytes(&mut self, buf: &[u8]) -> Result {
+		let len: u32 = buf.len().try_into()?;
+		if len == END {
+			return Err!(Database("Record is too large to export."));
+		}
+
+		self.write_u32(len).await?;
+		self.write(buf).await
+	}
+
+	async fn finish(mut self) -> Result {
+		let checksum = self.hasher.finalize();
+		self.inner.write_all(&checksum).await?;
+		self.inner.flush().await?;
+		self.inner.into_inner().sync_all().await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,112 @@
+#![cfg(test)]
+
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+use futures::stream;
+
+use super::{Manifest, read, verify, write};
+
+type Records = BTreeMap<(String, Vec<u8>), Vec<u8>>;
+
+fn export_path(name: &str) -> PathBuf {
+	let path =
+		std::env::temp_dir().join(format!("conduwuit-export-{}-{name}", std::process::id()));
+	_ = std::fs::remove_file(&path);
+	path
+}
+
+async fn export(
+	path: &Path,
+	columns: &[(&'static str, Vec<(&'static [u8], &'static [u8])>)],
+) -> Manifest {
+	let columns = columns
+		.iter()
+		.map(|(name, records)| (*name, stream::iter(records.iter().copied().map(Ok))));
+
+	write(path, "example.com", columns)
+		.await
+		.expect("export written")
+}
+
+async fn import(path: &Path, manifest: &Manifest) -> conduwuit::Result<Records> {
+	let mut records = Records::new();
+	read(path, manifest, |name, batch| {
+		for (key, val) in batch {
+			records.insert((name.to_owned(), key), val);
+		}
+
+		Ok(())
+	})
+	.await?;
+
+	Ok(records)
+}
+
+#[tokio::test]
+async fn round_trip() {
+	let path = export_path("round_trip");
+	let columns = [
+		("global", vec![
+			(&b"version"[..], &b"\x00\x00\x00\x12"[..]),
+			(&b"empty"[..], &b""[..]),
+		]),
+		("userid_password", vec![]),
+		("userid_displayname", vec![(&b"@alice:example.com"[..], &b"Alice"[..])]),
+	];
+
+	let written = export(&path, &columns).await;
+	let manifest = verify(&path).await.expect("export verified");
+	assert_eq!(manifest.server_name, "example.com");
+	assert_eq!(manifest.created, written.created);
+	let counts: Vec<_> = manifest
+		.columns
+		.iter()
+		.map(|column| (column.name.as_str(), column.records))
+		.collect();
+	assert_eq!(counts, [("global", 2), ("userid_password", 0), ("userid_displayname", 1)]);
+
+	let records = import(&path, &manifest).await.expect("export read");
+	let expected: Records = columns
+		.iter()
+		.flat_map(|(name, records)| {
+			records
+				.iter()
+				.map(|(key, val)| (((*name).to_owned(), key.to_vec()), val.to_vec()))
+		})
+		.collect();
+	assert_eq!(records, expected);
+
+	_ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn corrupt() {
+	let path = export_path("corrupt");
+	export(&path, &[("global", vec![(&b"key"[..], &b"val"[..])])]).await;
+
+	let mut bytes = std::fs::read(&path).unwrap();
+	let last = bytes.len().saturating_sub(40);
+	bytes[last] ^= 0xFF;
+	std::fs::write(&path, &bytes).unwrap();
+	assert!(verify(&path).await.is_err());
+
+	bytes.truncate(20);
+	std::fs::write(&path, &bytes).unwrap();
+	assert!(verify(&path).await.is_err());
+
+	_ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn record_count_mismatch() {
+	let path = export_path("record_count_mismatch");
+	let mut manifest = export(&path, &[("global", vec![(&b"key"[..], &b"val"[..])])]).await;
+
+	manifest.columns[0].records = 2;
+	assert!(import(&path, &manifest).await.is_err());
+
+	_ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,199 @@
+//! Writes on a read replica.
+//!
+//! A RocksDB secondary instance cannot write to the database. Its columns are
+//! wrapped so that writes are queued instead, to be shipped to and applied by
+//! the primary. They become visible to the replica once it catches up with
+//! the primary.
+
+use std::{
+	ffi::CStr,
+	mem::take,
+	sync::{Arc, Mutex},
+};
+
+use conduwuit::{Result, implement};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::{
+	Database, Handle,
+	map::compact,
+	storage::{Cached, Cursor, Storage},
+};
+
+/// Queue of writes made on a read replica.
+#[derive(Default)]
+pub(crate) struct Forward {
+	queue: Mutex<Vec<Write>>,
+	notify: Notify,
+}
+
+/// Insertion, or removal if there is no value, of a key in a column. Keys and
+/// values are serialized in base64.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Write {
+	pub column: String,
+	#[serde(with = "encoded")]
+	pub key: Vec<u8>,
+	#[serde(default, skip_serializing_if = "Option::is_none", with = "encoded::option")]
+	pub val: Option<Vec<u8>>,
+}
+
+/// Column whose reads are served by the engine and whose writes are queued.
+pub(crate) struct Column {
+	name: &'static str,
+	inner: Box<dyn Storage>,
+	forward: Arc<Forward>,
+}
+
+impl Forward {
+	fn push<I>(&self, writes: I)
+	where
+		I: IntoIterator<Item = Write>,
+	{
+		self.queue.lock().expect("locked").extend(writes);
+		self.notify.notify_one();
+	}
+}
+
+impl Column {
+	pub(crate) fn new(
+		name: &'static str,
+		inner: Box<dyn Storage>,
+		forward: &Arc<Forward>,
+	) -> Self {
+		Self { name, inner, forward: forward.clone() }
+	}
+
+	fn write(&self, key: &[u8], val: Option<&[u8]>) -> Write {
+		Write {
+			column: self.name.to_owned(),
+			key: key.to_vec(),
+			val: val.map(<[u8]>::to_vec),
+		}
+	}
+}
+
+impl Storage for Column {
+	fn get(&self, key: &[u8]) -> Result<Option<Handle<'_>>> { self.inner.get(key) }
+
+	fn get_cached(&self, key: &[u8]) -> Result<Cached<Option<Handle<'_>>>> {
+		self.inner.get_cached(key)
+	}
+
+	fn get_batch<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Option<Handle<'a>>>> {
+		self.inner.get_batch(keys)
+	}
+
+	fn get_batch_cached<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Cached<Option<Handle<'a>>>>> {
+		self.inner.get_batch_cached(keys)
+	}
+
+	fn may_exist(&self, key: &[u8]) -> bool { self.inner.may_exist(key) }
+
+	fn put(&self, key: &[u8], val: &[u8]) -> Result {
+		self.forward.push([self.write(key, Some(val))]);
+		Ok(())
+	}
+
+	fn put_batch(&self, batch: &[(&[u8], &[u8])]) -> Result {
+		self.forward
+			.push(batch.iter().map(|(key, val)| self.write(key, Some(val))));
+		Ok(())
+	}
+
+	fn delete(&self, key: &[u8]) -> Result {
+		self.forward.push([self.write(key, None)]);
+		Ok(())
+	}
+
+	fn cursor(&self, cached: bool) -> Box<dyn Cursor + '_> { self.inner.cursor(cached) }
+
+	fn compact(&self, opts: &compact::Options) -> Result { self.inner.compact(opts) }
+
+	fn property(&self, name: &str) -> Result<String> { self.inner.property(name) }
+
+	fn property_integer(&self, name: &CStr) -> Result<u64> { self.inner.property_integer(name) }
+}
+
+/// Take the writes queued on this read replica, waiting until there are any.
+#[implement(Database)]
+pub async fn forwarded(&self) -> Vec<Write> {
+	loop {
+		let notified = self.forward.notify.notified();
+		let writes = take(&mut *self.forward.queue.lock().expect("locked"));
+		if !writes.is_empty() {
+			return writes;
+		}
+
+		notified.await;
+	}
+}
+
+/// Queue writes again which could not be shipped, ahead of any queued since.
+#[implement(Database)]
+pub fn requeue(&self, mut writes: Vec<Write>) {
+	let mut queue = self.forward.queue.lock().expect("locked");
+	writes.append(&mut queue);
+	*queue = writes;
+}
+
+/// Apply writes forwarded by a read replica atomically, then wake the
+/// watchers of the keys written.
+#[implement(Database)]
+pub fn apply(&self, writes: &[Write]) -> Result {
+	let maps = writes
+		.iter()
+		.map(|write| self.get(&write.column))
+		.collect::<Result<Vec<_>>>()?;
+
+	self.engine()?.write_batch(
+		writes
+			.iter()
+			.map(|write| (write.column.as_str(), write.key.as_slice(), write.val.as_deref())),
+	)?;
+
+	for (map, write) in maps.iter().zip(writes) {
+		map.wake(&write.key);
+	}
+
+	Ok(())
+}
+
+mod encoded {
+	use base64::{Engine as _, engine::general_purpose::STANDARD};
+	use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+	pub(super) fn serialize<S: Serializer>(data: &[u8], s: S) -> Result<S::Ok, S::Error> {
+		s.serialize_str(&STANDARD.encode(data))
+	}
+
+	pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+		let data = String::deserialize(d)?;
+		STANDARD.decode(data).map_err(Error::custom)
+	}
+
+	pub(super) mod option {
+		use serde::{Deserialize, Deserializer, Serializer};
+
+		#[allow(clippy::ref_option)]
+		pub(in super::super) fn serialize<S: Serializer>(
+			data: &Option<Vec<u8>>,
+			s: S,
+		) -> Result<S::Ok, S::Error> {
+			match data {
+				| Some(data) => super::serialize(data, s),
+				| None => s.serialize_none(),
+			}
+		}
+
+		pub(in super::super) fn deserialize<'de, D: Deserializer<'de>>(
+			d: D,
+		) -> Result<Option<Vec<u8>>, D::Error> {
+			#[derive(Deserialize)]
+			struct Wrap(#[serde(with = "super")] Vec<u8>);
+
+			Ok(Option::<Wrap>::deserialize(d)?.map(|Wrap(data)| data))
+		}
+	}
+}
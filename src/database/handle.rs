@@ -1,4 +1,4 @@
-use std::{fmt, fmt::Debug, ops::Deref};
+use std::{fmt, fmt::Debug, ops::Deref, sync::Arc};
 
 use conduwuit::Result;
 use rocksdb::DBPinnableSlice;
@@ -7,11 +7,21 @@
 use crate::{Deserialized, Slice, keyval::deserialize_val};
 
 pub struct Handle<'a> {
-	val: DBPinnableSlice<'a>,
+	val: Val<'a>,
+}
+
+/// Value as held by the storage engine.
+enum Val<'a> {
+	Pinned(DBPinnableSlice<'a>),
+	Shared(Arc<[u8]>),
 }
 
 impl<'a> From<DBPinnableSlice<'a>> for Handle<'a> {
-	fn from(val: DBPinnableSlice<'a>) -> Self { Self { val } }
+	fn from(val: DBPinnableSlice<'a>) -> Self { Self { val: Val::Pinned(val) } }
+}
+
+impl From<Arc<[u8]>> for Handle<'_> {
+	fn from(val: Arc<[u8]>) -> Self { Self { val: Val::Shared(val) } }
 }
 
 impl Debug for Handle<'_> {
@@ -72,10 +82,15 @@ impl Deref for Handle<'_> {
 	type Target = Slice;
 
 	#[inline]
-	fn deref(&self) -> &Self::Target { &self.val }
+	fn deref(&self) -> &Self::Target {
+		match &self.val {
+			| Val::Pinned(val) => val,
+			| Val::Shared(val) => val,
+		}
+	}
 }
 
 impl AsRef<Slice> for Handle<'_> {
 	#[inline]
-	fn as_ref(&self) -> &Slice { &self.val }
+	fn as_ref(&self) -> &Slice { self }
 }
@@ -8,8 +8,6 @@
 mod keys;
 mod keys_from;
 mod keys_prefix;
-mod open;
-mod options;
 mod qry;
 mod qry_batch;
 mod remove;
@@ -34,35 +32,28 @@
 };
 
 use conduwuit::Result;
-use rocksdb::{AsColumnFamilyRef, ColumnFamily, ReadOptions, WriteOptions};
 
-pub(crate) use self::options::{
-	cache_iter_options_default, cache_read_options_default, iter_options_default,
-	read_options_default, write_options_default,
-};
 pub use self::{get_batch::Get, qry_batch::Qry};
-use crate::{Engine, watchers::Watchers};
+use crate::{pool::Pool, storage::Storage, watchers::Watchers};
 
 pub struct Map {
 	name: &'static str,
 	watchers: Watchers,
-	cf: Arc<ColumnFamily>,
-	db: Arc<Engine>,
-	read_options: ReadOptions,
-	cache_read_options: ReadOptions,
-	write_options: WriteOptions,
+	storage: Box<dyn Storage>,
+	pool: Arc<Pool>,
 }
 
 impl Map {
-	pub(crate) fn open(db: &Arc<Engine>, name: &'static str) -> Result<Arc<Self>> {
+	pub(crate) fn open(
+		name: &'static str,
+		storage: Box<dyn Storage>,
+		pool: &Arc<Pool>,
+	) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			name,
 			watchers: Watchers::default(),
-			cf: open::open(db, name),
-			db: db.clone(),
-			read_options: read_options_default(db),
-			cache_read_options: cache_read_options_default(db),
-			write_options: write_options_default(db),
+			storage,
+			pool: pool.clone(),
 		}))
 	}
 
@@ -77,22 +68,27 @@ pub fn watch_prefix<'a, K>(
 		self.watchers.watch(prefix.as_ref())
 	}
 
+	/// Wake the watchers of every prefix of the key.
+	#[inline]
+	pub(crate) fn wake(&self, key: &[u8]) { self.watchers.wake(key); }
+
+	/// Wake every watcher.
+	#[inline]
+	pub(crate) fn wake_all(&self) { self.watchers.wake_all(); }
+
 	#[inline]
 	pub fn property_integer(&self, name: &CStr) -> Result<u64> {
-		self.db.property_integer(&self.cf(), name)
+		self.storage.property_integer(name)
 	}
 
 	#[inline]
-	pub fn property(&self, name: &str) -> Result<String> { self.db.property(&self.cf(), name) }
+	pub fn property(&self, name: &str) -> Result<String> { self.storage.property(name) }
 
 	#[inline]
 	pub fn name(&self) -> &str { self.name }
 
 	#[inline]
-	pub(crate) fn db(&self) -> &Arc<Engine> { &self.db }
-
-	#[inline]
-	pub(crate) fn cf(&self) -> impl AsColumnFamilyRef + '_ { &*self.cf }
+	pub(crate) fn storage(&self) -> &dyn Storage { &*self.storage }
 }
 
 impl Debug for Map {
@@ -1,5 +1,4 @@
-use conduwuit::{Err, Result, implement};
-use rocksdb::{BottommostLevelCompaction, CompactOptions};
+use conduwuit::{Result, implement};
 
 use crate::keyval::KeyBuf;
 
@@ -30,33 +29,4 @@ pub struct Options {
 	skip(self),
 	fields(%self),
 )]
-pub fn compact_blocking(&self, opts: Options) -> Result {
-	let mut co = CompactOptions::default();
-	co.set_exclusive_manual_compaction(opts.exclusive);
-	co.set_bottommost_level_compaction(match opts.exhaustive {
-		| true => BottommostLevelCompaction::Force,
-		| false => BottommostLevelCompaction::ForceOptimized,
-	});
-
-	match opts.level {
-		| (None, None) => {
-			co.set_change_level(true);
-			co.set_target_level(-1);
-		},
-		| (None, Some(level)) => {
-			co.set_change_level(true);
-			co.set_target_level(level.try_into()?);
-		},
-		| (Some(level), None) => {
-			co.set_change_level(false);
-			co.set_target_level(level.try_into()?);
-		},
-		| (Some(_), Some(_)) => return Err!("compacting between specific levels not supported"),
-	}
-
-	self.db
-		.db
-		.compact_range_cf_opt(&self.cf(), opts.range.0, opts.range.1, &co);
-
-	Ok(())
-}
+pub fn compact_blocking(&self, opts: Options) -> Result { self.storage.compact(&opts) }
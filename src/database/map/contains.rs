@@ -90,14 +90,12 @@ pub fn exists_blocking<K>(&self, key: &K) -> Result
 		.ok_or_else(|| err!(Request(NotFound("Not found in database"))))
 }
 
-/// Rocksdb limits this to kBlockCacheTier internally so this is not actually a
-/// blocking call; in case that changes we set this as well in our read_options.
+/// Returns false if the key certainly does not exist. This is not a blocking
+/// call.
 #[implement(super::Map)]
 pub(crate) fn maybe_exists<K>(&self, key: &K) -> bool
 where
 	K: AsRef<[u8]> + ?Sized,
 {
-	self.db
-		.db
-		.key_may_exist_cf_opt(&self.cf(), key, &self.cache_read_options)
+	self.storage.may_exist(key.as_ref())
 }
@@ -2,13 +2,9 @@
 
 use conduwuit::{Err, Result, err, implement, utils::result::MapExpect};
 use futures::{Future, FutureExt, TryFutureExt, future::ready};
-use rocksdb::{DBPinnableSlice, ReadOptions};
 use tokio::task;
 
-use crate::{
-	Handle,
-	util::{is_incomplete, map_err, or_else},
-};
+use crate::{Handle, storage::Cached};
 
 /// Fetch a value from the database into cache, returning a reference-handle
 /// asynchronously. The key is referenced directly to perform the query.
@@ -37,8 +33,7 @@ pub fn get<K>(
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_get(cmd)
 		.and_then(|mut res| ready(res.remove(0)))
 		.boxed()
@@ -51,7 +46,7 @@ pub(crate) fn get_cached<K>(&self, key: &K) -> Result<Option<Handle<'_>>>
 where
 	K: AsRef<[u8]> + Debug + ?Sized,
 {
-	let res = self.get_blocking_opts(key, &self.cache_read_options);
+	let res = self.storage.get_cached(key.as_ref());
 	cached_handle_from(res)
 }
 
@@ -64,47 +59,27 @@ pub fn get_blocking<K>(&self, key: &K) -> Result<Handle<'_>>
 where
 	K: AsRef<[u8]> + ?Sized,
 {
-	let res = self.get_blocking_opts(key, &self.read_options);
+	let res = self.storage.get(key.as_ref());
 	handle_from(res)
 }
 
-#[implement(super::Map)]
-fn get_blocking_opts<K>(
-	&self,
-	key: &K,
-	read_options: &ReadOptions,
-) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>
-where
-	K: AsRef<[u8]> + ?Sized,
-{
-	self.db.db.get_pinned_cf_opt(&self.cf(), key, read_options)
-}
-
 #[inline]
-pub(super) fn handle_from(
-	result: Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>,
-) -> Result<Handle<'_>> {
-	result
-		.map_err(map_err)?
-		.map(Handle::from)
-		.ok_or(err!(Request(NotFound("Not found in database"))))
+pub(super) fn handle_from(result: Result<Option<Handle<'_>>>) -> Result<Handle<'_>> {
+	result?.ok_or(err!(Request(NotFound("Not found in database"))))
 }
 
 #[inline]
 pub(super) fn cached_handle_from(
-	result: Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>,
+	result: Result<Cached<Option<Handle<'_>>>>,
 ) -> Result<Option<Handle<'_>>> {
-	match result {
+	match result? {
 		// cache hit; not found
-		| Ok(None) => Err!(Request(NotFound("Not found in database"))),
+		| Cached::Hit(None) => Err!(Request(NotFound("Not found in database"))),
 
 		// cache hit; value found
-		| Ok(Some(result)) => Ok(Some(Handle::from(result))),
+		| Cached::Hit(Some(handle)) => Ok(Some(handle)),
 
 		// cache miss; unknown
-		| Err(error) if is_incomplete(&error) => Ok(None),
-
-		// some other error occurred
-		| Err(error) => or_else(error),
+		| Cached::Miss => Ok(None),
 	}
 }
@@ -8,7 +8,6 @@
 	},
 };
 use futures::{Stream, StreamExt, TryStreamExt};
-use rocksdb::{DBPinnableSlice, ReadOptions};
 
 use super::get::{cached_handle_from, handle_from};
 use crate::Handle;
@@ -48,7 +47,7 @@ pub(crate) fn get_batch<'a, S, K>(
 
 	keys.ready_chunks(automatic_amplification())
 		.widen_then(automatic_width(), |chunk| {
-			self.db.pool.execute_get(Get {
+			self.pool.execute_get(Get {
 				map: self.clone(),
 				key: chunk.iter().map(AsRef::as_ref).map(Into::into).collect(),
 				res: None,
@@ -68,7 +67,10 @@ pub(crate) fn get_batch_cached<'a, I, K>(
 	I: Iterator<Item = &'a K> + ExactSizeIterator + Send,
 	K: AsRef<[u8]> + Send + ?Sized + Sync + 'a,
 {
-	self.get_batch_blocking_opts(keys, &self.cache_read_options)
+	let keys: Vec<&[u8]> = keys.map(AsRef::as_ref).collect();
+	self.storage
+		.get_batch_cached(&keys)
+		.into_iter()
 		.map(cached_handle_from)
 }
 
@@ -82,26 +84,6 @@ pub(crate) fn get_batch_blocking<'a, I, K>(
 	I: Iterator<Item = &'a K> + ExactSizeIterator + Send,
 	K: AsRef<[u8]> + Send + ?Sized + Sync + 'a,
 {
-	self.get_batch_blocking_opts(keys, &self.read_options)
-		.map(handle_from)
-}
-
-#[implement(super::Map)]
-fn get_batch_blocking_opts<'a, I, K>(
-	&self,
-	keys: I,
-	read_options: &ReadOptions,
-) -> impl Iterator<Item = Result<Option<DBPinnableSlice<'_>>, rocksdb::Error>> + Send + use<'_, I, K>
-where
-	I: Iterator<Item = &'a K> + ExactSizeIterator + Send,
-	K: AsRef<[u8]> + Send + ?Sized + Sync + 'a,
-{
-	// Optimization can be `true` if key vector is pre-sorted **by the column
-	// comparator**.
-	const SORTED: bool = false;
-
-	self.db
-		.db
-		.batched_multi_get_cf_opt(&self.cf(), keys, SORTED, read_options)
-		.into_iter()
+	let keys: Vec<&[u8]> = keys.map(AsRef::as_ref).collect();
+	self.storage.get_batch(&keys).into_iter().map(handle_from)
 }
@@ -6,13 +6,11 @@
 use std::{convert::AsRef, fmt::Debug, io::Write};
 
 use conduwuit::{arrayvec::ArrayVec, implement};
-use rocksdb::WriteBatchWithTransaction;
 use serde::Serialize;
 
 use crate::{
 	keyval::{KeyBuf, ValBuf},
 	ser,
-	util::or_else,
 };
 
 /// Insert Key/Value
@@ -197,17 +195,10 @@ pub fn insert<K, V>(&self, key: &K, val: V)
 	K: AsRef<[u8]> + ?Sized,
 	V: AsRef<[u8]>,
 {
-	let write_options = &self.write_options;
-	self.db
-		.db
-		.put_cf_opt(&self.cf(), key, val, write_options)
-		.or_else(or_else)
+	self.storage
+		.put(key.as_ref(), val.as_ref())
 		.expect("database insert error");
 
-	if !self.db.corked() {
-		self.db.flush().expect("database flush error");
-	}
-
 	self.watchers.wake(key.as_ref());
 }
 
@@ -219,19 +210,13 @@ pub fn insert_batch<'a, I, K, V>(&'a self, iter: I)
 	K: AsRef<[u8]> + Sized + Debug + 'a,
 	V: AsRef<[u8]> + Sized + 'a,
 {
-	let mut batch = WriteBatchWithTransaction::<false>::default();
-	for (key, val) in iter {
-		batch.put_cf(&self.cf(), key.as_ref(), val.as_ref());
-	}
-
-	let write_options = &self.write_options;
-	self.db
-		.db
-		.write_opt(batch, write_options)
-		.or_else(or_else)
+	let entries: Vec<_> = iter.collect();
+	let batch: Vec<_> = entries
+		.iter()
+		.map(|(key, val)| (key.as_ref(), val.as_ref()))
+		.collect();
+
+	self.storage
+		.put_batch(&batch)
 		.expect("database insert batch error");
-
-	if !self.db.corked() {
-		self.db.flush().expect("database flush error");
-	}
 }
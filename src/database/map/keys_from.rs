@@ -59,8 +59,7 @@ pub fn raw_keys_from<P>(
 {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self, from) {
 		return stream::Keys::<'_>::from(state.init_fwd(from.as_ref().into())).boxed();
 	}
@@ -73,8 +72,7 @@ pub fn raw_keys_from<P>(
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::Keys<'_>>()
 		.into_stream()
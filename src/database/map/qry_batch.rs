@@ -52,8 +52,7 @@ pub(crate) fn qry_batch<'a, S, K>(
 				.map(|result| result.expect("failed to serialize query key"))
 				.collect();
 
-			self.db
-				.pool
+			self.pool
 				.execute_get(Get { map: self.clone(), key: keys, res: None })
 		})
 		.map_ok(|results| results.into_iter().stream())
@@ -3,7 +3,7 @@
 use conduwuit::{arrayvec::ArrayVec, implement};
 use serde::Serialize;
 
-use crate::{keyval::KeyBuf, ser, util::or_else};
+use crate::{keyval::KeyBuf, ser};
 
 #[implement(super::Map)]
 #[inline]
@@ -42,14 +42,7 @@ pub fn remove<K>(&self, key: &K)
 where
 	K: AsRef<[u8]> + ?Sized + Debug,
 {
-	let write_options = &self.write_options;
-	self.db
-		.db
-		.delete_cf_opt(&self.cf(), key, write_options)
-		.or_else(or_else)
+	self.storage
+		.delete(key.as_ref())
 		.expect("database remove error");
-
-	if !self.db.corked() {
-		self.db.flush().expect("database flush error");
-	}
 }
@@ -22,8 +22,7 @@ pub fn rev_keys<'a, K>(self: &'a Arc<Self>) -> impl Stream<Item = Result<Key<'_,
 pub fn rev_raw_keys(self: &Arc<Self>) -> impl Stream<Item = Result<Key<'_>>> + Send {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self) {
 		let state = state.init_rev(None);
 		return task::consume_budget()
@@ -41,8 +40,7 @@ pub fn rev_raw_keys(self: &Arc<Self>) -> impl Stream<Item = Result<Key<'_>>> + S
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::KeysRev<'_>>()
 		.into_stream()
@@ -61,8 +61,7 @@ pub fn rev_raw_keys_from<P>(
 {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self, from) {
 		return stream::KeysRev::<'_>::from(state.init_rev(from.as_ref().into())).boxed();
 	}
@@ -75,8 +74,7 @@ pub fn rev_raw_keys_from<P>(
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::KeysRev<'_>>()
 		.into_stream()
@@ -31,8 +31,7 @@ pub fn rev_stream<'a, K, V>(
 pub fn rev_raw_stream(self: &Arc<Self>) -> impl Stream<Item = Result<KeyVal<'_>>> + Send {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self) {
 		let state = state.init_rev(None);
 		return task::consume_budget()
@@ -50,8 +49,7 @@ pub fn rev_raw_stream(self: &Arc<Self>) -> impl Stream<Item = Result<KeyVal<'_>>
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::ItemsRev<'_>>()
 		.into_stream()
@@ -66,8 +64,7 @@ pub fn rev_raw_stream(self: &Arc<Self>) -> impl Stream<Item = Result<KeyVal<'_>>
     fields(%map),
 )]
 pub(super) fn is_cached(map: &Arc<super::Map>) -> bool {
-	let opts = super::cache_iter_options_default(&map.db);
-	let state = stream::State::new(map, opts).init_rev(None);
+	let state = stream::State::new_cached(map).init_rev(None);
 
 	!state.is_incomplete()
 }
@@ -9,7 +9,6 @@
 use crate::{
 	keyval::{KeyVal, result_deserialize, serialize_key},
 	stream,
-	util::is_incomplete,
 };
 
 /// Iterate key-value entries in the map starting from upper-bound.
@@ -80,8 +79,7 @@ pub fn rev_raw_stream_from<P>(
 {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self, from) {
 		let state = state.init_rev(from.as_ref().into());
 		return task::consume_budget()
@@ -99,8 +97,7 @@ pub fn rev_raw_stream_from<P>(
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::ItemsRev<'_>>()
 		.into_stream()
@@ -118,10 +115,7 @@ pub(super) fn is_cached<P>(map: &Arc<super::Map>, from: &P) -> bool
 where
 	P: AsRef<[u8]> + ?Sized,
 {
-	let cache_opts = super::cache_iter_options_default(&map.db);
-	let cache_status = stream::State::new(map, cache_opts)
-		.init_rev(from.as_ref().into())
-		.status();
+	let state = stream::State::new_cached(map).init_rev(from.as_ref().into());
 
-	!matches!(cache_status, Some(e) if is_incomplete(&e))
+	!state.is_incomplete()
 }
@@ -30,8 +30,7 @@ pub fn stream<'a, K, V>(
 pub fn raw_stream(self: &Arc<Self>) -> impl Stream<Item = Result<KeyVal<'_>>> + Send {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self) {
 		let state = state.init_fwd(None);
 		return task::consume_budget()
@@ -49,8 +48,7 @@ pub fn raw_stream(self: &Arc<Self>) -> impl Stream<Item = Result<KeyVal<'_>>> +
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::Items<'_>>()
 		.into_stream()
@@ -65,8 +63,7 @@ pub fn raw_stream(self: &Arc<Self>) -> impl Stream<Item = Result<KeyVal<'_>>> +
     fields(%map),
 )]
 pub(super) fn is_cached(map: &Arc<super::Map>) -> bool {
-	let opts = super::cache_iter_options_default(&map.db);
-	let state = stream::State::new(map, opts).init_fwd(None);
+	let state = stream::State::new_cached(map).init_fwd(None);
 
 	!state.is_incomplete()
 }
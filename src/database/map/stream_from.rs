@@ -77,8 +77,7 @@ pub fn raw_stream_from<P>(
 {
 	use crate::pool::Seek;
 
-	let opts = super::iter_options_default(&self.db);
-	let state = stream::State::new(self, opts);
+	let state = stream::State::new(self);
 	if is_cached(self, from) {
 		let state = state.init_fwd(from.as_ref().into());
 		return task::consume_budget()
@@ -96,8 +95,7 @@ pub fn raw_stream_from<P>(
 		res: None,
 	};
 
-	self.db
-		.pool
+	self.pool
 		.execute_iter(seek)
 		.ok_into::<stream::Items<'_>>()
 		.into_stream()
@@ -115,8 +113,7 @@ pub(super) fn is_cached<P>(map: &Arc<super::Map>, from: &P) -> bool
 where
 	P: AsRef<[u8]> + ?Sized,
 {
-	let opts = super::cache_iter_options_default(&map.db);
-	let state = stream::State::new(map, opts).init_fwd(from.as_ref().into());
+	let state = stream::State::new_cached(map).init_fwd(from.as_ref().into());
 
 	!state.is_incomplete()
 }
@@ -4,19 +4,46 @@
 
 use crate::{
 	Engine, Map,
-	engine::descriptor::{self, CacheDisp, Descriptor},
+	engine::{
+		column::Column,
+		descriptor::{self, CacheDisp, Descriptor},
+	},
+	forward::{self, Forward},
+	memory,
+	pool::Pool,
+	storage::Storage,
 };
 
 pub(super) type Maps = BTreeMap<MapsKey, MapsVal>;
 pub(super) type MapsKey = &'static str;
 pub(super) type MapsVal = Arc<Map>;
 
-pub(super) fn open(db: &Arc<Engine>) -> Result<Maps> { open_list(db, MAPS) }
+pub(super) fn open(db: &Arc<Engine>, pool: &Arc<Pool>) -> Result<Maps> {
+	open_list(MAPS, pool, |name| Box::new(Column::open(db, name)))
+}
+
+/// Columns of a secondary instance, whose writes are forwarded to the primary.
+pub(super) fn open_forwarding(
+	db: &Arc<Engine>,
+	pool: &Arc<Pool>,
+	forward: &Arc<Forward>,
+) -> Result<Maps> {
+	open_list(MAPS, pool, |name| {
+		Box::new(forward::Column::new(name, Box::new(Column::open(db, name)), forward))
+	})
+}
+
+pub(super) fn open_memory(pool: &Arc<Pool>) -> Result<Maps> {
+	open_list(MAPS, pool, |_| Box::<memory::Column>::default())
+}
 
 #[tracing::instrument(name = "maps", level = "debug", skip_all)]
-pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
+fn open_list<F>(maps: &[Descriptor], pool: &Arc<Pool>, storage: F) -> Result<Maps>
+where
+	F: Fn(&'static str) -> Box<dyn Storage>,
+{
 	maps.iter()
-		.map(|desc| Ok((desc.name, Map::open(db, desc.name)?)))
+		.map(|desc| Ok((desc.name, Map::open(desc.name, storage(desc.name), pool)?)))
 		.collect()
 }
 
@@ -33,6 +60,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "aliasid_alias",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "auditid_entry",
+		..descriptor::SEQUENTIAL_SMALL
+	},
 	Descriptor {
 		name: "backupid_algorithm",
 		..descriptor::RANDOM_SMALL
@@ -121,6 +152,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		index_size: 512,
 		..descriptor::SEQUENTIAL
 	},
+	Descriptor {
+		name: "policyroomids",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "publicroomids",
 		..descriptor::RANDOM_SMALL
@@ -141,6 +176,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "referencedevents",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "registrationtoken_info",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_invitedcount",
 		..descriptor::RANDOM_SMALL
@@ -215,6 +254,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "roomuseroncejoinedids",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "roomuserthreadid_privateread",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomusertype_roomuserdataid",
 		..descriptor::RANDOM_SMALL
@@ -330,6 +373,22 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "url_previews",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userdeviceid_connections",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userdeviceid_fallbackkeys",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userdeviceid_impersonation",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userdeviceid_lastseen",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userdeviceid_metadata",
 		..descriptor::RANDOM_SMALL
@@ -358,6 +417,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_blurhash",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_dehydrateddevice",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_devicelistversion",
 		..descriptor::RANDOM_SMALL
@@ -370,6 +433,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_lastonetimekeyupdate",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_locked",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_masterkeyid",
 		..descriptor::RANDOM_SMALL
@@ -382,10 +449,22 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_presenceid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_registrationtoken",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_selfsigningkeyid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_shadowbanned",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_suspended",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_usersigningkeyid",
 		..descriptor::RANDOM_SMALL
@@ -426,4 +505,20 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userroomid_notificationcount",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userroomthreadid_highlightcount",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userroomthreadid_notificationcount",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userroomthreadid_subscription",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userroomthreadid_unsubscribed",
+		..descriptor::RANDOM_SMALL
+	},
 ];
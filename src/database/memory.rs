@@ -0,0 +1,153 @@
+//! In-memory storage engine. Nothing is persisted: the database is empty every
+//! time the server starts. Every query is served without I/O so the pool is
+//! only used for batched queries.
+
+mod tests;
+
+use std::{
+	collections::BTreeMap,
+	ops::Bound::{Excluded, Included, Unbounded},
+	sync::{Arc, RwLock, RwLockReadGuard},
+};
+
+use conduwuit::{Error, Result};
+
+use crate::{
+	Handle,
+	storage::{Cached, Cursor, Storage},
+};
+
+/// Column held in an ordered map.
+#[derive(Default)]
+pub(crate) struct Column {
+	data: RwLock<Data>,
+}
+
+struct Iter<'a> {
+	data: &'a RwLock<Data>,
+	item: Option<(Vec<u8>, Arc<[u8]>)>,
+}
+
+type Data = BTreeMap<Vec<u8>, Arc<[u8]>>;
+
+impl Column {
+	#[inline]
+	fn read(&self) -> RwLockReadGuard<'_, Data> { self.data.read().expect("locked") }
+}
+
+impl Storage for Column {
+	fn get(&self, key: &[u8]) -> Result<Option<Handle<'_>>> {
+		Ok(self.read().get(key).cloned().map(Handle::from))
+	}
+
+	fn get_cached(&self, key: &[u8]) -> Result<Cached<Option<Handle<'_>>>> {
+		self.get(key).map(Cached::Hit)
+	}
+
+	fn get_batch<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Option<Handle<'a>>>> {
+		keys.iter().map(|key| self.get(key)).collect()
+	}
+
+	fn get_batch_cached<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Cached<Option<Handle<'a>>>>> {
+		keys.iter().map(|key| self.get_cached(key)).collect()
+	}
+
+	fn may_exist(&self, key: &[u8]) -> bool { self.read().contains_key(key) }
+
+	fn put(&self, key: &[u8], val: &[u8]) -> Result {
+		self.data
+			.write()
+			.expect("locked")
+			.insert(key.to_vec(), val.into());
+
+		Ok(())
+	}
+
+	fn put_batch(&self, batch: &[(&[u8], &[u8])]) -> Result {
+		let mut data = self.data.write().expect("locked");
+		for (key, val) in batch {
+			data.insert(key.to_vec(), (*val).into());
+		}
+
+		Ok(())
+	}
+
+	fn delete(&self, key: &[u8]) -> Result {
+		self.data.write().expect("locked").remove(key);
+
+		Ok(())
+	}
+
+	fn cursor(&self, _cached: bool) -> Box<dyn Cursor + '_> {
+		Box::new(Iter { data: &self.data, item: None })
+	}
+}
+
+impl Iter<'_> {
+	/// Position the cursor at the entry selected from the column, or make it
+	/// invalid if there is none.
+	fn position<F>(&mut self, select: F)
+	where
+		F: for<'d> FnOnce(&'d Data) -> Option<(&'d Vec<u8>, &'d Arc<[u8]>)>,
+	{
+		let data = self.data.read().expect("locked");
+		self.item = select(&data).map(|(key, val)| (key.clone(), val.clone()));
+	}
+}
+
+impl Cursor for Iter<'_> {
+	fn seek(&mut self, key: &[u8]) {
+		self.position(|data| data.range::<[u8], _>((Included(key), Unbounded)).next());
+	}
+
+	fn seek_for_prev(&mut self, key: &[u8]) {
+		self.position(|data| {
+			data.range::<[u8], _>((Unbounded, Included(key)))
+				.next_back()
+		});
+	}
+
+	fn seek_to_first(&mut self) { self.position(Data::first_key_value); }
+
+	fn seek_to_last(&mut self) { self.position(Data::last_key_value); }
+
+	fn next(&mut self) {
+		if let Some((key, _)) = self.item.take() {
+			self.position(|data| {
+				data.range::<[u8], _>((Excluded(key.as_slice()), Unbounded))
+					.next()
+			});
+		}
+	}
+
+	fn prev(&mut self) {
+		if let Some((key, _)) = self.item.take() {
+			self.position(|data| {
+				data.range::<[u8], _>((Unbounded, Excluded(key.as_slice())))
+					.next_back()
+			});
+		}
+	}
+
+	#[inline]
+	fn valid(&self) -> bool { self.item.is_some() }
+
+	#[inline]
+	fn key(&self) -> Option<&[u8]> { self.item.as_ref().map(|(key, _)| key.as_slice()) }
+
+	#[inline]
+	fn value(&self) -> Option<&[u8]> { self.item.as_ref().map(|(_, val)| &**val) }
+
+	#[inline]
+	fn item(&self) -> Option<(&[u8], &[u8])> {
+		self.item
+			.as_ref()
+			.map(|(key, val)| (key.as_slice(), &**val))
+	}
+
+	#[inline]
+	fn status(&self) -> Option<Error> { None }
+
+	#[inline]
+	fn is_incomplete(&self) -> bool { false }
+}
@@ -0,0 +1,127 @@
+#![cfg(test)]
+
+use super::Column;
+use crate::storage::{Cached, Cursor, Storage};
+
+fn column(keys: &[&str]) -> Column {
+	let column = Column::default();
+	for key in keys {
+		column.put(key.as_bytes(), key.as_bytes()).expect("put");
+	}
+
+	column
+}
+
+fn get(column: &Column, key: &[u8]) -> Option<Vec<u8>> {
+	column.get(key).expect("get").map(|handle| handle.to_vec())
+}
+
+fn keys(cursor: &mut dyn Cursor, forward: bool) -> Vec<Vec<u8>> {
+	let mut keys = Vec::new();
+	while let Some(key) = cursor.key() {
+		keys.push(key.to_vec());
+		if forward {
+			cursor.next();
+		} else {
+			cursor.prev();
+		}
+	}
+
+	keys
+}
+
+#[test]
+fn put_get_delete() {
+	let column = Column::default();
+	assert_eq!(get(&column, b"a"), None);
+	assert!(!column.may_exist(b"a"));
+
+	column.put(b"a", b"1").expect("put");
+	assert_eq!(get(&column, b"a").as_deref(), Some(&b"1"[..]));
+	assert!(column.may_exist(b"a"));
+
+	column.put(b"a", b"2").expect("overwrite");
+	assert_eq!(get(&column, b"a").as_deref(), Some(&b"2"[..]));
+
+	column.delete(b"a").expect("delete");
+	assert_eq!(get(&column, b"a"), None);
+	column.delete(b"a").expect("deleting a missing key");
+}
+
+#[test]
+fn batches() {
+	let column = Column::default();
+	let batch: [(&[u8], &[u8]); 2] = [(b"a", b"1"), (b"b", b"2")];
+	column.put_batch(&batch).expect("put_batch");
+
+	let keys: [&[u8]; 3] = [b"b", b"c", b"a"];
+	let values: Vec<_> = column
+		.get_batch(&keys)
+		.into_iter()
+		.map(|res| res.expect("get").map(|handle| handle.to_vec()))
+		.collect();
+
+	assert_eq!(values, [Some(b"2".to_vec()), None, Some(b"1".to_vec())]);
+}
+
+#[test]
+fn cached_queries_hit() {
+	let column = column(&["a"]);
+	assert!(matches!(column.get_cached(b"a"), Ok(Cached::Hit(Some(_)))));
+	assert!(matches!(column.get_cached(b"b"), Ok(Cached::Hit(None))));
+
+	let mut cursor = column.cursor(true);
+	cursor.seek_to_first();
+	assert!(cursor.valid());
+	assert!(!cursor.is_incomplete());
+	assert!(cursor.status().is_none());
+}
+
+#[test]
+fn cursor_forward() {
+	let column = column(&["c", "a", "b"]);
+	let mut cursor = column.cursor(false);
+	assert!(!cursor.valid());
+
+	cursor.seek_to_first();
+	assert_eq!(keys(&mut *cursor, true), [b"a", b"b", b"c"]);
+	assert!(!cursor.valid());
+
+	cursor.seek(b"aa");
+	assert_eq!(keys(&mut *cursor, true), [b"b", b"c"]);
+
+	cursor.seek(b"d");
+	assert!(!cursor.valid());
+}
+
+#[test]
+fn cursor_reverse() {
+	let column = column(&["c", "a", "b"]);
+	let mut cursor = column.cursor(false);
+
+	cursor.seek_to_last();
+	assert_eq!(keys(&mut *cursor, false), [b"c", b"b", b"a"]);
+
+	cursor.seek_for_prev(b"bb");
+	assert_eq!(keys(&mut *cursor, false), [b"b", b"a"]);
+
+	cursor.seek_for_prev(b"b");
+	assert_eq!(cursor.item(), Some((&b"b"[..], &b"b"[..])));
+
+	cursor.seek_for_prev(b"0");
+	assert!(!cursor.valid());
+}
+
+#[test]
+fn cursor_sees_writes_after_positioning() {
+	let column = column(&["a", "c"]);
+	let mut cursor = column.cursor(false);
+	cursor.seek_to_first();
+
+	column.put(b"b", b"b").expect("put");
+	column.delete(b"c").expect("delete");
+	cursor.next();
+	assert_eq!(cursor.key(), Some(&b"b"[..]));
+	cursor.next();
+	assert!(!cursor.valid());
+}
@@ -9,16 +9,21 @@
 
 #[cfg(test)]
 mod benches;
+mod catch_up;
 mod cork;
 mod de;
 mod deserialized;
 mod engine;
+pub mod export;
+pub mod forward;
 mod handle;
 pub mod keyval;
 mod map;
 pub mod maps;
+mod memory;
 mod pool;
 mod ser;
+mod storage;
 mod stream;
 #[cfg(test)]
 mod tests;
@@ -27,7 +32,7 @@
 
 use std::{ops::Index, sync::Arc};
 
-use conduwuit::{Result, Server, err};
+use conduwuit::{Err, Result, Server, err, warn};
 
 pub use self::{
 	de::{Ignore, IgnoreAll},
@@ -41,26 +46,62 @@
 	engine::{Engine, context::Context},
 	util::or_else,
 };
-use crate::maps::{Maps, MapsKey, MapsVal};
+use crate::{
+	forward::Forward,
+	maps::{Maps, MapsKey, MapsVal},
+};
 
 pub struct Database {
 	maps: Maps,
-	pub db: Arc<Engine>,
-	pub(crate) _ctx: Arc<Context>,
+	engine: Option<Arc<Engine>>,
+	forward: Arc<Forward>,
+	pub(crate) ctx: Arc<Context>,
 }
 
 impl Database {
 	/// Load an existing database or create a new one.
 	pub async fn open(server: &Arc<Server>) -> Result<Arc<Self>> {
 		let ctx = Context::new(server)?;
-		let db = Engine::open(ctx.clone(), maps::MAPS).await?;
-		Ok(Arc::new(Self {
-			maps: maps::open(&db)?,
-			db: db.clone(),
-			_ctx: ctx,
-		}))
+		let forward = Arc::<Forward>::default();
+		let (engine, maps) = match server.config.database_backend.as_str() {
+			| "rocksdb" => {
+				let engine = Engine::open(ctx.clone(), maps::MAPS).await?;
+				let maps = if engine.is_secondary() {
+					maps::open_forwarding(&engine, &ctx.pool, &forward)?
+				} else {
+					maps::open(&engine, &ctx.pool)?
+				};
+
+				(Some(engine), maps)
+			},
+			| "memory" => {
+				warn!("Using the in-memory storage engine; nothing will be persisted.");
+				(None, maps::open_memory(&ctx.pool)?)
+			},
+			| backend => {
+				return Err!(Config(
+					"database_backend",
+					"Unknown storage engine {backend:?}; expected \"rocksdb\" or \"memory\"."
+				));
+			},
+		};
+
+		Ok(Arc::new(Self { maps, engine, forward, ctx }))
+	}
+
+	/// The RocksDB engine, for operations beyond the storage interface such as
+	/// backups and statistics.
+	#[inline]
+	pub fn engine(&self) -> Result<&Arc<Engine>> {
+		self.engine
+			.as_ref()
+			.ok_or_else(|| err!(Database("Not supported by the in-memory storage engine.")))
 	}
 
+	/// Flush memory to persistent storage.
+	#[inline]
+	pub fn sort(&self) -> Result { self.engine.as_ref().map_or(Ok(()), |engine| engine.sort()) }
+
 	#[inline]
 	pub fn get(&self, name: &str) -> Result<&Arc<Map>> {
 		self.maps
@@ -78,11 +119,11 @@ pub fn keys(&self) -> impl Iterator<Item = &MapsKey> + Send + '_ { self.maps.key
 
 	#[inline]
 	#[must_use]
-	pub fn is_read_only(&self) -> bool { self.db.is_read_only() }
+	pub fn is_read_only(&self) -> bool { self.engine.as_ref().is_some_and(|e| e.is_read_only()) }
 
 	#[inline]
 	#[must_use]
-	pub fn is_secondary(&self) -> bool { self.db.is_secondary() }
+	pub fn is_secondary(&self) -> bool { self.engine.as_ref().is_some_and(|e| e.is_secondary()) }
 }
 
 impl Index<&str> for Database {
@@ -0,0 +1,92 @@
+//! Interface between `Map` and the storage engine.
+//!
+//! A storage engine provides each column with point queries, writes and a raw
+//! cursor. Everything else `Map` offers (serialization, prefix and range
+//! iteration, watchers, offloading to the pool) is built on top of these and
+//! shared by all engines.
+//!
+//! - RocksDB (`engine::Column`) is the default and the only persistent engine.
+//! - Memory (`memory::Column`) keeps every column in an ordered map which is
+//!   lost at shutdown; it is intended for tests and ephemeral deployments.
+
+use std::ffi::CStr;
+
+use conduwuit::{Err, Error, Result};
+
+use crate::{Handle, map::compact};
+
+/// Result of a query restricted to data the engine can serve without I/O.
+pub(crate) enum Cached<T> {
+	Hit(T),
+	Miss,
+}
+
+/// A column of the storage engine.
+pub(crate) trait Storage: Send + Sync {
+	/// Point query; None when the key is not found.
+	fn get(&self, key: &[u8]) -> Result<Option<Handle<'_>>>;
+
+	/// Point query which performs no I/O.
+	fn get_cached(&self, key: &[u8]) -> Result<Cached<Option<Handle<'_>>>>;
+
+	/// Multi-point query; results are in the order of the keys.
+	fn get_batch<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Option<Handle<'a>>>>;
+
+	/// Multi-point query which performs no I/O.
+	fn get_batch_cached<'a>(&'a self, keys: &[&[u8]]) -> Vec<Result<Cached<Option<Handle<'a>>>>>;
+
+	/// False when the key certainly does not exist. Performs no I/O.
+	fn may_exist(&self, key: &[u8]) -> bool;
+
+	fn put(&self, key: &[u8], val: &[u8]) -> Result;
+
+	/// Write all entries atomically.
+	fn put_batch(&self, batch: &[(&[u8], &[u8])]) -> Result;
+
+	fn delete(&self, key: &[u8]) -> Result;
+
+	/// Unpositioned cursor over the column. A cached cursor performs no I/O;
+	/// it becomes invalid and reports `is_incomplete()` where it would have to.
+	fn cursor(&self, cached: bool) -> Box<dyn Cursor + '_>;
+
+	fn compact(&self, _opts: &compact::Options) -> Result {
+		Err!("Compaction is not supported by this storage engine.")
+	}
+
+	fn property(&self, name: &str) -> Result<String> {
+		Err!("Property {name:?} is not supported by this storage engine.")
+	}
+
+	fn property_integer(&self, name: &CStr) -> Result<u64> {
+		Err!("Property {name:?} is not supported by this storage engine.")
+	}
+}
+
+/// Raw cursor over a column. Slices it returns are valid until it is moved.
+pub(crate) trait Cursor: Send {
+	fn seek(&mut self, key: &[u8]);
+
+	fn seek_for_prev(&mut self, key: &[u8]);
+
+	fn seek_to_first(&mut self);
+
+	fn seek_to_last(&mut self);
+
+	fn next(&mut self);
+
+	fn prev(&mut self);
+
+	fn valid(&self) -> bool;
+
+	fn key(&self) -> Option<&[u8]>;
+
+	fn value(&self) -> Option<&[u8]>;
+
+	fn item(&self) -> Option<(&[u8], &[u8])>;
+
+	/// Error which stopped the cursor, if any.
+	fn status(&self) -> Option<Error>;
+
+	/// True if a cached cursor stopped where it would have performed I/O.
+	fn is_incomplete(&self) -> bool;
+}
@@ -5,15 +5,13 @@
 
 use std::sync::Arc;
 
-use conduwuit::{Result, utils::exchange};
-use rocksdb::{DBRawIteratorWithThreadMode, ReadOptions};
+use conduwuit::{Error, Result, utils::exchange};
 
 pub(crate) use self::{items::Items, items_rev::ItemsRev, keys::Keys, keys_rev::KeysRev};
 use crate::{
 	Map, Slice,
-	engine::Db,
 	keyval::{Key, KeyVal, Val},
-	util::{is_incomplete, map_err},
+	storage,
 };
 
 pub(crate) struct State<'a> {
@@ -33,7 +31,7 @@ pub(crate) trait Cursor<'a, T> {
 	fn get(&self) -> Option<Result<T>> {
 		self.fetch()
 			.map(Ok)
-			.or_else(|| self.state().status().map(map_err).map(Err))
+			.or_else(|| self.state().status().map(Err))
 	}
 
 	#[inline]
@@ -43,19 +41,24 @@ fn seek_and_get(&mut self) -> Option<Result<T>> {
 	}
 }
 
-type Inner<'a> = DBRawIteratorWithThreadMode<'a, Db>;
+type Inner<'a> = Box<dyn storage::Cursor + 'a>;
 type From<'a> = Option<Key<'a>>;
 
 impl<'a> State<'a> {
 	#[inline]
-	pub(super) fn new(map: &'a Arc<Map>, opts: ReadOptions) -> Self {
-		Self {
-			inner: map.db().db.raw_iterator_cf_opt(&map.cf(), opts),
-			init: true,
-			seek: false,
-		}
+	pub(super) fn new(map: &'a Arc<Map>) -> Self {
+		Self::with_cursor(map.storage().cursor(false))
+	}
+
+	/// State of a cursor which performs no I/O.
+	#[inline]
+	pub(super) fn new_cached(map: &'a Arc<Map>) -> Self {
+		Self::with_cursor(map.storage().cursor(true))
 	}
 
+	#[inline]
+	fn with_cursor(inner: Inner<'a>) -> Self { Self { inner, init: true, seek: false } }
+
 	#[inline]
 	#[tracing::instrument(level = "trace", skip_all)]
 	pub(super) fn init_fwd(mut self, from: From<'_>) -> Self {
@@ -108,9 +111,7 @@ pub(super) fn seek_rev(&mut self) {
 		}
 	}
 
-	pub(super) fn is_incomplete(&self) -> bool {
-		matches!(self.status(), Some(e) if is_incomplete(&e))
-	}
+	pub(super) fn is_incomplete(&self) -> bool { self.inner.is_incomplete() }
 
 	#[inline]
 	fn fetch_key(&self) -> Option<Key<'_>> { self.inner.key() }
@@ -122,7 +123,7 @@ fn _fetch_val(&self) -> Option<Val<'_>> { self.inner.value() }
 	fn fetch(&self) -> Option<KeyVal<'_>> { self.inner.item() }
 
 	#[inline]
-	pub(super) fn status(&self) -> Option<rocksdb::Error> { self.inner.status().err() }
+	pub(super) fn status(&self) -> Option<Error> { self.inner.status() }
 
 	#[inline]
 	pub(super) fn valid(&self) -> bool { self.inner.valid() }
@@ -133,7 +134,7 @@ fn keyval_longevity<'a, 'b: 'a>(item: KeyVal<'a>) -> KeyVal<'b> {
 }
 
 fn slice_longevity<'a, 'b: 'a>(item: &'a Slice) -> &'b Slice {
-	// SAFETY: The lifetime of the data returned by the rocksdb cursor is only valid
+	// SAFETY: The lifetime of the data returned by the storage cursor is only valid
 	// between each movement of the cursor. It is hereby unsafely extended to match
 	// the lifetime of the cursor itself. This is due to the limitation of the
 	// Stream trait where the Item is incapable of conveying a lifetime; this is due
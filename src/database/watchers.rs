@@ -55,4 +55,10 @@ pub(crate) fn wake(&self, key: &[u8]) {
 			}
 		}
 	}
+
+	pub(crate) fn wake_all(&self) {
+		for (_, tx) in self.watchers.write().unwrap().drain() {
+			tx.0.send(()).expect("channel should still be open");
+		}
+	}
 }
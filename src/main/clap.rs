@@ -44,6 +44,14 @@ pub(crate) struct Args {
 	#[arg(long)]
 	pub(crate) execute: Vec<String>,
 
+	/// Print the output of --execute commands as JSON.
+	#[arg(long)]
+	pub(crate) json: bool,
+
+	/// Import a database export into a new, empty database at startup.
+	#[arg(long)]
+	pub(crate) import: Option<PathBuf>,
+
 	/// Set functional testing modes if available. Ex '--test=smoke'
 	#[arg(long, hide(true))]
 	pub(crate) test: Vec<String>,
@@ -145,6 +153,14 @@ pub(crate) fn update(mut config: Figment, args: &Args) -> Result<Figment> {
 		config = config.join(("admin_console_automatic", true));
 	}
 
+	if let Some(path) = &args.import {
+		config = config.join(("database_import_path", path));
+	}
+
+	if args.json {
+		config = config.join(("admin_execute_json", true));
+	}
+
 	// Execute commands after any commands listed in configuration file
 	config = config.adjoin(("admin_execute", &args.execute));
 
@@ -23,7 +23,7 @@
 };
 use tracing::Level;
 
-use crate::{request, router};
+use crate::{replica, request, router};
 
 const CONDUWUIT_CSP: &[&str; 5] = &[
 	"default-src 'none'",
@@ -60,6 +60,7 @@ pub(crate) fn build(services: &Arc<Services>) -> Result<(Router, Guard)> {
 				.on_response(DefaultOnResponse::new().level(Level::DEBUG)),
 		)
 		.layer(axum::middleware::from_fn_with_state(Arc::clone(services), request::handle))
+		.layer(axum::middleware::from_fn_with_state(Arc::clone(services), replica::handle))
 		.layer(SecureClientIpSource::ConnectInfo.into_extension())
 		.layer(ResponseBodyTimeoutLayer::new(Duration::from_secs(
 			server.config.client_response_timeout,
@@ -100,6 +101,25 @@ pub(crate) fn build(services: &Arc<Services>) -> Result<(Router, Guard)> {
 	Ok((router.layer(layers), guard))
 }
 
+/// Layers of the primary's internal listener for its read replicas.
+pub(crate) fn build_replica(services: &Arc<Services>) -> (Router, Guard) {
+	let services_ = services.clone();
+	let layers = ServiceBuilder::new()
+		.layer(
+			TraceLayer::new_for_http()
+				.make_span_with(tracing_span::<_>)
+				.on_failure(DefaultOnFailure::new().level(Level::ERROR))
+				.on_request(DefaultOnRequest::new().level(Level::TRACE))
+				.on_response(DefaultOnResponse::new().level(Level::DEBUG)),
+		)
+		.layer(axum::middleware::from_fn_with_state(Arc::clone(services), request::handle))
+		.layer(body_limit_layer(&services.server))
+		.layer(CatchPanicLayer::custom(move |panic| catch_panic(panic, services_.clone())));
+
+	let (router, guard) = router::build_replica(services);
+	(router.layer(layers), guard)
+}
+
 #[cfg(any(
 	feature = "zstd_compression",
 	feature = "gzip_compression",
@@ -149,18 +169,24 @@ fn cors_layer(_server: &Server) -> CorsLayer {
 		Method::OPTIONS,
 	];
 
-	let headers: [HeaderName; 5] = [
+	let headers: [HeaderName; 7] = [
 		header::ORIGIN,
 		HeaderName::from_lowercase(b"x-requested-with").unwrap(),
 		header::CONTENT_TYPE,
 		header::ACCEPT,
 		header::AUTHORIZATION,
+		header::IF_MATCH,
+		header::IF_NONE_MATCH,
 	];
 
+	// read by browser clients of rendezvous sessions (MSC4108)
+	let exposed: [HeaderName; 3] = [header::ETAG, header::EXPIRES, header::LAST_MODIFIED];
+
 	CorsLayer::new()
 		.allow_origin(cors::Any)
 		.allow_methods(METHODS)
 		.allow_headers(headers)
+		.expose_headers(exposed)
 		.max_age(Duration::from_secs(86400))
 }
 
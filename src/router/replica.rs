@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::{
+	RequestPartsExt,
+	extract::State,
+	response::{IntoResponse, Response},
+};
+use axum_client_ip::InsecureClientIp;
+use conduwuit::{Result, debug, err};
+use conduwuit_service::{Services, replica};
+
+/// Forward the requests a read replica does not serve itself to the primary.
+#[tracing::instrument(name = "replica", level = "debug", skip_all)]
+pub(crate) async fn handle(
+	State(services): State<Arc<Services>>,
+	req: http::Request<axum::body::Body>,
+	next: axum::middleware::Next,
+) -> Response {
+	if !services.replica.is_replica() || replica::serves(req.method(), req.uri().path()) {
+		return next.run(req).await;
+	}
+
+	forward(&services, req)
+		.await
+		.unwrap_or_else(IntoResponse::into_response)
+}
+
+async fn forward(services: &Services, req: http::Request<axum::body::Body>) -> Result<Response> {
+	let (mut parts, body) = req.into_parts();
+	let client = parts
+		.extract::<InsecureClientIp>()
+		.await
+		.ok()
+		.map(|InsecureClientIp(ip)| ip);
+
+	let path_and_query = parts
+		.uri
+		.path_and_query()
+		.map_or("/", |path_and_query| path_and_query.as_str());
+
+	debug!(method = %parts.method, uri = %parts.uri, "forwarding to primary");
+	let body = axum::body::to_bytes(body, services.server.config.max_request_size)
+		.await
+		.map_err(|e| err!(Request(TooLarge("Failed to read request body: {e}"))))?;
+
+	services
+		.replica
+		.forward(parts.method, path_and_query, parts.headers, body, client)
+		.await
+		.map(IntoResponse::into_response)
+}
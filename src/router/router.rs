@@ -18,6 +18,16 @@ pub(crate) fn build(services: &Arc<Services>) -> (Router, Guard) {
 	(router, guard)
 }
 
+pub(crate) fn build_replica(services: &Arc<Services>) -> (Router, Guard) {
+	let router = Router::<state::State>::new();
+	let (state, guard) = state::create(services.clone());
+	let router = conduwuit_api::router::build_replica(router)
+		.fallback(not_found)
+		.with_state(state);
+
+	(router, guard)
+}
+
 async fn not_found(_uri: Uri) -> impl IntoResponse {
 	Error::Request(ErrorKind::Unrecognized, "Not Found".into(), StatusCode::NOT_FOUND)
 }
@@ -12,10 +12,21 @@
 
 use super::layers;
 
-/// Serve clients
+/// Serve clients, and the read replicas of this primary if configured
 pub(super) async fn serve(
 	services: Arc<Services>,
 	handle: ServerHandle,
+	shutdown: broadcast::Receiver<()>,
+) -> Result {
+	let replica = serve_replica(&services, handle.clone());
+	let clients = serve_clients(&services, handle, shutdown);
+	tokio::try_join!(clients, replica).map(|((), ())| ())
+}
+
+/// Serve clients
+async fn serve_clients(
+	services: &Arc<Services>,
+	handle: ServerHandle,
 	mut shutdown: broadcast::Receiver<()>,
 ) -> Result {
 	let server = &services.server;
@@ -28,7 +39,7 @@ pub(super) async fn serve(
 	}
 
 	let addrs = config.get_bind_addrs();
-	let (app, _guard) = layers::build(&services)?;
+	let (app, _guard) = layers::build(services)?;
 	if cfg!(unix) && config.unix_socket_path.is_some() {
 		unix::serve(server, app, shutdown).await
 	} else if config.tls.certs.is_some() {
@@ -44,3 +55,19 @@ pub(super) async fn serve(
 		plain::serve(server, app, handle, addrs).await
 	}
 }
+
+/// Serve the writes forwarded by read replicas on the internal listener,
+/// separately from clients.
+async fn serve_replica(services: &Arc<Services>, handle: ServerHandle) -> Result {
+	let server = &services.server;
+	let Some(addr) = server.config.replica_listen else {
+		return Ok(());
+	};
+
+	if services.replica.is_replica() {
+		return Ok(());
+	}
+
+	let (app, _guard) = layers::build_replica(services);
+	plain::serve(server, app, handle, vec![addr]).await
+}
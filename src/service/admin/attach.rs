@@ -0,0 +1,57 @@
+use conduwuit::{
+	Result, implement,
+	utils::{self, content_disposition::make_content_disposition},
+};
+use ruma::{
+	EventId, Mxc, UInt,
+	events::{
+		relation::InReplyTo,
+		room::message::{
+			FileInfo, FileMessageEventContent, MessageType, Relation::Reply,
+			RoomMessageEventContent,
+		},
+	},
+};
+use serde_json::Value as JsonValue;
+
+use crate::media::MXC_LENGTH;
+
+const CONTENT_TYPE: &str = "application/json";
+
+const FILENAME: &str = "output.json";
+
+/// Uploads the JSON result of a command and returns a file message for it in
+/// reply to the command.
+#[implement(super::Service)]
+pub(super) async fn json_attachment(
+	&self,
+	json: &JsonValue,
+	reply_id: &EventId,
+) -> Result<RoomMessageEventContent> {
+	let file = serde_json::to_vec_pretty(json)?;
+	let content_disposition = make_content_disposition(None, Some(CONTENT_TYPE), Some(FILENAME));
+	let mxc = Mxc {
+		server_name: self.services.globals.server_name(),
+		media_id: &utils::random_string(MXC_LENGTH),
+	};
+
+	self.services
+		.media
+		.create(&mxc, None, Some(&content_disposition), Some(CONTENT_TYPE), &file)
+		.await?;
+
+	let mut info = FileInfo::new();
+	info.mimetype = Some(CONTENT_TYPE.to_owned());
+	info.size = UInt::try_from(file.len()).ok();
+
+	let mut message = FileMessageEventContent::plain(FILENAME.to_owned(), mxc.to_string().into());
+	message.filename = Some(FILENAME.to_owned());
+	message.info = Some(Box::new(info));
+
+	let mut content = RoomMessageEventContent::new(MessageType::File(message));
+	content.relates_to = Some(Reply {
+		in_reply_to: InReplyTo { event_id: reply_id.to_owned() },
+	});
+
+	Ok(content)
+}
@@ -7,12 +7,11 @@
 
 use conduwuit::{Server, debug, defer, error, log, log::is_systemd_mode};
 use futures::future::{AbortHandle, Abortable};
-use ruma::events::room::message::RoomMessageEventContent;
 use rustyline_async::{Readline, ReadlineError, ReadlineEvent};
 use termimad::MadSkin;
 use tokio::task::JoinHandle;
 
-use crate::{Dep, admin};
+use crate::{Dep, admin, admin::CommandOutput};
 
 pub struct Console {
 	server: Arc<Server>,
@@ -174,13 +173,13 @@ async fn process(self: Arc<Self>, line: String) {
 		}
 	}
 
-	fn output_err(self: Arc<Self>, output_content: &RoomMessageEventContent) {
+	fn output_err(self: Arc<Self>, output_content: &CommandOutput) {
 		let output = configure_output_err(self.output.clone());
-		output.print_text(output_content.body());
+		output.print_text(&output_text(output_content));
 	}
 
-	fn output(self: Arc<Self>, output_content: &RoomMessageEventContent) {
-		self.output.print_text(output_content.body());
+	fn output(self: Arc<Self>, output_content: &CommandOutput) {
+		self.output.print_text(&output_text(output_content));
 	}
 
 	fn set_history(&self, readline: &mut Readline) {
@@ -209,6 +208,18 @@ fn tab_complete(&self, line: &str) -> String {
 	}
 }
 
+/// The markdown to print for a command; the JSON result in a code block when
+/// there is one.
+fn output_text(output: &CommandOutput) -> String {
+	match &output.json {
+		| Some(json) => {
+			let json = serde_json::to_string_pretty(json).expect("JSON value serializes");
+			format!("```json\n{json}\n```")
+		},
+		| None => output.content.body().to_owned(),
+	}
+}
+
 /// Standalone/static markdown printer for errors.
 pub fn print_err(markdown: &str) {
 	let output = configure_output_err(MadSkin::default_dark());
@@ -1,7 +1,9 @@
 use conduwuit::{Err, Result, debug, debug_info, error, implement, info};
-use ruma::events::room::message::RoomMessageEventContent;
+use serde_json::Value as JsonValue;
 use tokio::time::{Duration, sleep};
 
+use super::{CommandInput, CommandOutput};
+
 pub(super) const SIGNAL: &str = "SIGUSR2";
 
 /// Possibly spawn the terminal console at startup if configured.
@@ -88,7 +90,16 @@ pub(super) async fn signal_execute(&self) -> Result {
 async fn execute_command(&self, i: usize, command: String) -> Result {
 	debug!("Execute command #{i}: executing {command:?}");
 
-	match self.command_in_place(command, None).await {
+	let json = self.services.server.config.admin_execute_json;
+	match self
+		.process_command(CommandInput {
+			command,
+			reply_id: None,
+			json,
+			sender: None,
+		})
+		.await
+	{
 		| Ok(Some(output)) => Self::execute_command_output(i, &output),
 		| Err(output) => Self::execute_command_error(i, &output),
 		| Ok(None) => {
@@ -98,30 +109,51 @@ async fn execute_command(&self, i: usize, command: String) -> Result {
 	}
 }
 
+/// Prints the JSON result of a command alone on stdout for scripts.
+fn print_json(json: &JsonValue) {
+	println!("{json}");
+}
+
 #[cfg(feature = "console")]
 #[implement(super::Service)]
-fn execute_command_output(i: usize, content: &RoomMessageEventContent) -> Result {
+fn execute_command_output(i: usize, output: &CommandOutput) -> Result {
 	debug_info!("Execute command #{i} completed:");
-	super::console::print(content.body());
+	match &output.json {
+		| Some(json) => print_json(json),
+		| None => super::console::print(output.content.body()),
+	}
+
 	Ok(())
 }
 
 #[cfg(feature = "console")]
 #[implement(super::Service)]
-fn execute_command_error(i: usize, content: &RoomMessageEventContent) -> Result {
-	super::console::print_err(content.body());
+fn execute_command_error(i: usize, output: &CommandOutput) -> Result {
+	match &output.json {
+		| Some(json) => print_json(json),
+		| None => super::console::print_err(output.content.body()),
+	}
+
 	Err!(debug_error!("Execute command #{i} failed."))
 }
 
 #[cfg(not(feature = "console"))]
 #[implement(super::Service)]
-fn execute_command_output(i: usize, content: &RoomMessageEventContent) -> Result {
-	info!("Execute command #{i} completed:\n{:#}", content.body());
+fn execute_command_output(i: usize, output: &CommandOutput) -> Result {
+	match &output.json {
+		| Some(json) => print_json(json),
+		| None => info!("Execute command #{i} completed:\n{:#}", output.content.body()),
+	}
+
 	Ok(())
 }
 
 #[cfg(not(feature = "console"))]
 #[implement(super::Service)]
-fn execute_command_error(i: usize, content: &RoomMessageEventContent) -> Result {
-	Err!(error!("Execute command #{i} failed:\n{:#}", content.body()))
+fn execute_command_error(i: usize, output: &CommandOutput) -> Result {
+	if let Some(json) = &output.json {
+		print_json(json);
+	}
+
+	Err!(error!("Execute command #{i} failed:\n{:#}", output.content.body()))
 }
@@ -1,3 +1,4 @@
+mod attach;
 pub mod console;
 mod create;
 mod execute;
@@ -17,12 +18,13 @@
 use futures::{FutureExt, TryFutureExt};
 use loole::{Receiver, Sender};
 use ruma::{
-	OwnedEventId, OwnedRoomId, RoomId, UserId,
+	OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
 	events::room::message::{Relation, RoomMessageEventContent},
 };
+use serde_json::Value as JsonValue;
 use tokio::sync::RwLock;
 
-use crate::{Dep, account_data, globals, rooms, rooms::state::RoomMutexGuard};
+use crate::{Dep, account_data, globals, media, rooms, rooms::state::RoomMutexGuard};
 
 pub struct Service {
 	services: Services,
@@ -42,14 +44,20 @@ struct Services {
 	state_cache: Dep<rooms::state_cache::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	account_data: Dep<account_data::Service>,
+	media: Dep<media::Service>,
 	services: StdRwLock<Option<Weak<crate::Services>>>,
 }
 
-/// Inputs to a command are a multi-line string and optional reply_id.
+/// Inputs to a command are a multi-line string and optional reply_id. When
+/// json is set the result is also returned as JSON, as with `--json`. The
+/// sender is the user who sent the command to the admin room; None for the
+/// console and `--execute`.
 #[derive(Debug)]
 pub struct CommandInput {
 	pub command: String,
 	pub reply_id: Option<OwnedEventId>,
+	pub json: bool,
+	pub sender: Option<OwnedUserId>,
 }
 
 /// Prototype of the tab-completer. The input is buffered text when tab
@@ -69,8 +77,14 @@ pub struct CommandInput {
 /// dropped to produce no response.
 pub type ProcessorResult = Result<Option<CommandOutput>, CommandOutput>;
 
-/// Alias for the output structure.
-pub type CommandOutput = RoomMessageEventContent;
+/// Output of a command. The content is the markdown reply; the result is also
+/// carried as JSON when requested with `--json`, which is attached to the
+/// reply in rooms and printed in place of it by `--execute`.
+#[derive(Clone, Debug)]
+pub struct CommandOutput {
+	pub content: RoomMessageEventContent,
+	pub json: Option<JsonValue>,
+}
 
 /// Maximum number of commands which can be queued for dispatch.
 const COMMAND_QUEUE_LIMIT: usize = 512;
@@ -89,6 +103,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				account_data: args.depend::<account_data::Service>("account_data"),
+				media: args.depend::<media::Service>("media"),
 				services: None.into(),
 			},
 			channel: loole::bounded(COMMAND_QUEUE_LIMIT),
@@ -159,10 +174,20 @@ pub async fn send_message(&self, message_content: RoomMessageEventContent) -> Re
 	/// Posts a command to the command processor queue and returns. Processing
 	/// will take place on the service worker's task asynchronously. Errors if
 	/// the queue is full.
-	pub fn command(&self, command: String, reply_id: Option<OwnedEventId>) -> Result<()> {
+	pub fn command(
+		&self,
+		command: String,
+		reply_id: Option<OwnedEventId>,
+		sender: OwnedUserId,
+	) -> Result<()> {
 		self.channel
 			.0
-			.send(CommandInput { command, reply_id })
+			.send(CommandInput {
+				command,
+				reply_id,
+				json: false,
+				sender: Some(sender),
+			})
 			.map_err(|e| err!("Failed to enqueue admin command: {e:?}"))
 	}
 
@@ -173,8 +198,13 @@ pub async fn command_in_place(
 		command: String,
 		reply_id: Option<OwnedEventId>,
 	) -> ProcessorResult {
-		self.process_command(CommandInput { command, reply_id })
-			.await
+		self.process_command(CommandInput {
+			command,
+			reply_id,
+			json: false,
+			sender: None,
+		})
+		.await
 	}
 
 	/// Invokes the tab-completer to complete the command. When unavailable,
@@ -255,7 +285,8 @@ pub async fn get_admin_room(&self) -> Result<OwnedRoomId> {
 			.ok_or_else(|| err!(Request(NotFound("Admin user not joined to admin room"))))
 	}
 
-	async fn handle_response(&self, content: RoomMessageEventContent) -> Result<()> {
+	async fn handle_response(&self, output: CommandOutput) -> Result<()> {
+		let CommandOutput { content, json } = output;
 		let Some(Relation::Reply { in_reply_to }) = content.relates_to.as_ref() else {
 			return Ok(());
 		};
@@ -274,9 +305,22 @@ async fn handle_response(&self, content: RoomMessageEventContent) -> Result<()>
 			&pdu.sender
 		};
 
+		let attachment = match json {
+			| Some(json) => Some(self.json_attachment(&json, &in_reply_to.event_id).await?),
+			| None => None,
+		};
+
 		self.respond_to_room(content, &pdu.room_id, response_sender)
 			.boxed()
-			.await
+			.await?;
+
+		if let Some(attachment) = attachment {
+			self.respond_to_room(attachment, &pdu.room_id, response_sender)
+				.boxed()
+				.await?;
+		}
+
+		Ok(())
 	}
 
 	async fn respond_to_room(
@@ -0,0 +1,195 @@
+mod tests;
+
+use std::{path::Path, sync::Arc};
+
+use conduwuit::{
+	Result, Server, debug_warn,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use database::{Ignore, Json, Map};
+use futures::{Stream, StreamExt};
+use ruma::{MilliSecondsSinceUnixEpoch, OwnedUserId, UserId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tokio::io::AsyncWriteExt;
+
+use crate::{Dep, globals};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	globals: Dep<globals::Service>,
+}
+
+struct Data {
+	/// Keyed by the timestamp of the entry followed by a count, both
+	/// big-endian, so time ranges are sought rather than scanned.
+	auditid_entry: Arc<Map>,
+}
+
+/// An administrative or security-sensitive action, as recorded in the audit
+/// log.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+	pub ts: MilliSecondsSinceUnixEpoch,
+
+	/// Who took the action; the server user for the admin console and
+	/// `--execute`.
+	pub actor: OwnedUserId,
+
+	/// What was done, e.g. `admin.users.deactivate` or `user.password_change`.
+	pub action: String,
+
+	/// The user, room or other thing it was done to, if any.
+	pub target: Option<String>,
+
+	/// Further parameters of the action.
+	pub params: JsonValue,
+}
+
+/// Criteria for querying the audit log. Entries match when they meet all the
+/// criteria given.
+#[derive(Debug, Default)]
+pub struct Filter<'a> {
+	pub actor: Option<&'a UserId>,
+	pub action: Option<&'a str>,
+	pub target: Option<&'a str>,
+	pub since: Option<MilliSecondsSinceUnixEpoch>,
+	pub until: Option<MilliSecondsSinceUnixEpoch>,
+}
+
+/// Syslog priority of entries: facility authpriv (10), severity notice (5).
+#[cfg(unix)]
+const SYSLOG_PRIORITY: u8 = 85;
+
+#[cfg(unix)]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				auditid_entry: args.db["auditid_entry"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				globals: args.depend::<globals::Service>("globals"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Appends an entry to the audit log, and exports it to the file and
+	/// syslog configured. Entries cannot be changed or removed.
+	pub async fn record(
+		&self,
+		actor: &UserId,
+		action: &str,
+		target: Option<&str>,
+		params: JsonValue,
+	) {
+		let entry = Entry {
+			ts: MilliSecondsSinceUnixEpoch::now(),
+			actor: actor.to_owned(),
+			action: action.to_owned(),
+			target: target.map(ToOwned::to_owned),
+			params,
+		};
+
+		let Ok(count) = self.services.globals.next_count() else {
+			debug_warn!(?entry, "Failed to record audit log entry");
+			return;
+		};
+
+		let key = entry_key(entry.ts, count);
+		self.db.auditid_entry.raw_put(key, Json(&entry));
+
+		self.export(&entry).await;
+	}
+
+	/// Returns the entries matching the filter, most recent first.
+	pub fn entries<'a>(
+		&'a self,
+		filter: &'a Filter<'a>,
+	) -> impl Stream<Item = Entry> + Send + 'a {
+		let from = range_start(filter.until);
+		self.db
+			.auditid_entry
+			.rev_stream_raw_from(&from)
+			.ignore_err()
+			.map(|(_, entry): (Ignore, Entry)| entry)
+			.ready_take_while(move |entry| filter.since.is_none_or(|since| entry.ts >= since))
+			.ready_filter(move |entry| filter.matches(entry))
+	}
+
+	async fn export(&self, entry: &Entry) {
+		let config = &self.services.server.config;
+		let Ok(line) = serde_json::to_string(entry) else {
+			return;
+		};
+
+		if let Some(path) = config.audit_log_file.as_deref() {
+			if let Err(e) = append_line(path, &line).await {
+				debug_warn!(?path, "Failed to export audit log entry: {e}");
+			}
+		}
+
+		#[cfg(unix)]
+		if config.audit_log_syslog {
+			if let Err(e) = send_syslog(&line).await {
+				debug_warn!("Failed to send audit log entry to syslog: {e}");
+			}
+		}
+	}
+}
+
+impl Filter<'_> {
+	#[must_use]
+	pub fn matches(&self, entry: &Entry) -> bool {
+		self.actor.is_none_or(|actor| *entry.actor == *actor)
+			&& self
+				.action
+				.is_none_or(|action| entry.action.starts_with(action))
+			&& self
+				.target
+				.is_none_or(|target| entry.target.as_deref() == Some(target))
+			&& self.since.is_none_or(|since| entry.ts >= since)
+			&& self.until.is_none_or(|until| entry.ts < until)
+	}
+}
+
+fn entry_key(ts: MilliSecondsSinceUnixEpoch, count: u64) -> Vec<u8> {
+	[u64::from(ts.get()).to_be_bytes(), count.to_be_bytes()].concat()
+}
+
+/// The key from which entries before `until` are iterated in reverse.
+fn range_start(until: Option<MilliSecondsSinceUnixEpoch>) -> Vec<u8> {
+	let last_ts = until.map_or(u64::MAX, |until| u64::from(until.get()).saturating_sub(1));
+	[last_ts.to_be_bytes(), u64::MAX.to_be_bytes()].concat()
+}
+
+async fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+	let mut file = tokio::fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)
+		.await?;
+
+	file.write_all(format!("{line}\n").as_bytes()).await
+}
+
+#[cfg(unix)]
+async fn send_syslog(line: &str) -> std::io::Result<()> {
+	let socket = tokio::net::UnixDatagram::unbound()?;
+	let message = format!("<{SYSLOG_PRIORITY}>conduwuit-audit: {line}");
+	socket.send_to(message.as_bytes(), SYSLOG_SOCKET).await?;
+
+	Ok(())
+}
@@ -0,0 +1,85 @@
+#![cfg(test)]
+
+use ruma::{MilliSecondsSinceUnixEpoch, UInt, user_id};
+
+use super::{Entry, Filter, entry_key, range_start};
+
+fn entry(ts: u32) -> Entry {
+	Entry {
+		ts: MilliSecondsSinceUnixEpoch(UInt::from(ts)),
+		actor: user_id!("@admin:example.com").to_owned(),
+		action: "admin.users.deactivate".to_owned(),
+		target: Some("@spammer:example.com".to_owned()),
+		params: serde_json::json!({}),
+	}
+}
+
+#[test]
+fn filter_fields() {
+	let entry = entry(1000);
+	assert!(Filter::default().matches(&entry));
+
+	let actor = Filter {
+		actor: Some(user_id!("@admin:example.com")),
+		..Default::default()
+	};
+	assert!(actor.matches(&entry));
+
+	let actor = Filter {
+		actor: Some(user_id!("@other:example.com")),
+		..Default::default()
+	};
+	assert!(!actor.matches(&entry));
+
+	let target = Filter {
+		target: Some("@spammer:example.com"),
+		..Default::default()
+	};
+	assert!(target.matches(&entry));
+
+	let action = Filter {
+		action: Some("admin.users"),
+		..Default::default()
+	};
+	assert!(action.matches(&entry));
+
+	let action = Filter {
+		action: Some("user."),
+		..Default::default()
+	};
+	assert!(!action.matches(&entry));
+}
+
+#[test]
+fn filter_time_range() {
+	let range = Filter {
+		since: Some(MilliSecondsSinceUnixEpoch(UInt::from(1000_u32))),
+		until: Some(MilliSecondsSinceUnixEpoch(UInt::from(2000_u32))),
+		..Default::default()
+	};
+
+	assert!(!range.matches(&entry(999)));
+	assert!(range.matches(&entry(1000)));
+	assert!(range.matches(&entry(1999)));
+	assert!(!range.matches(&entry(2000)));
+}
+
+fn ts(ms: u32) -> MilliSecondsSinceUnixEpoch { MilliSecondsSinceUnixEpoch(UInt::from(ms)) }
+
+#[test]
+fn keys_sort_by_time() {
+	assert!(entry_key(ts(1000), 5) < entry_key(ts(1000), 6));
+	assert!(entry_key(ts(1000), 6) < entry_key(ts(1001), 1));
+	assert!(entry_key(ts(999), u64::MAX) < entry_key(ts(1000), 0));
+}
+
+#[test]
+fn range_starts_before_until() {
+	let from = range_start(Some(ts(2000)));
+	assert!(entry_key(ts(1999), u64::MAX) <= from);
+	assert!(entry_key(ts(1999), 0) <= from);
+	assert!(entry_key(ts(2000), 0) > from);
+
+	let from = range_start(None);
+	assert!(entry_key(MilliSecondsSinceUnixEpoch(UInt::MAX), u64::MAX) <= from);
+}
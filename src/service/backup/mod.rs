@@ -0,0 +1,144 @@
+mod remote;
+mod s3;
+mod tests;
+
+use std::{collections::BTreeMap, fmt::Write, fs, path::Path, sync::Arc};
+
+use async_trait::async_trait;
+use conduwuit::{Err, Result, Server, implement};
+use database::Database;
+use tokio::sync::Mutex;
+
+use self::remote::Remote;
+use crate::{Dep, client};
+
+/// Database backups and their shipping to a remote target. Backups are
+/// scheduled by the database backup job.
+pub struct Service {
+	running: Mutex<()>,
+	remote: Option<Remote>,
+	db: Arc<Database>,
+	services: Services,
+}
+
+struct Services {
+	client: Dep<client::Service>,
+	server: Arc<Server>,
+}
+
+/// Files of the local backup directory by their path relative to it, with
+/// their size.
+type Files = BTreeMap<String, u64>;
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			running: Mutex::new(()),
+			remote: Remote::from_config(&args.server.config)?,
+			db: args.db.clone(),
+			services: Services {
+				client: args.depend::<client::Service>("client"),
+				server: args.server.clone(),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Create a backup in "database_backup_path", applying the retention limits,
+/// then mirror the backup directory to the remote target if one is
+/// configured. Returns a summary of the backups.
+#[implement(Service)]
+pub async fn backup(&self) -> Result<String> {
+	let _running = self.running.lock().await;
+	let Some(path) = self.backup_path() else {
+		return Err!(Config("database_backup_path", "No database backup path is configured."));
+	};
+
+	let db = self.db.clone();
+	let local = path.to_owned();
+	let (before, after) = self
+		.services
+		.server
+		.runtime()
+		.spawn_blocking(move || -> Result<(Files, Files)> {
+			let before = list_files(&local)?;
+			db.engine()?.backup()?;
+			let after = list_files(&local)?;
+			Ok((before, after))
+		})
+		.await??;
+
+	let mut summary = self.db.engine()?.backup_list()?;
+	if let Some(remote) = &self.remote {
+		let client = &self.services.client.default;
+		let (uploaded, removed) = remote.mirror(client, path, &before, &after).await?;
+		writeln!(summary, "\nUploaded {uploaded} and removed {removed} files at {remote}.")?;
+	}
+
+	Ok(summary)
+}
+
+/// Verify a backup by restoring it into a temporary directory and reading
+/// every column. The latest backup is verified unless one is given.
+#[implement(Service)]
+pub async fn verify(&self, backup_id: Option<u32>) -> Result<String> {
+	let db = self.db.clone();
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || db.engine()?.backup_verify(backup_id))
+		.await?
+}
+
+#[implement(Service)]
+fn backup_path(&self) -> Option<&Path> {
+	self.services
+		.server
+		.config
+		.database_backup_path
+		.as_deref()
+		.filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Files in the backup directory, excluding the temporary files of a backup
+/// in progress.
+fn list_files(root: &Path) -> Result<Files> {
+	let mut files = Files::new();
+	if !root.exists() {
+		return Ok(files);
+	}
+
+	let mut dirs = vec![root.to_path_buf()];
+	while let Some(dir) = dirs.pop() {
+		for entry in fs::read_dir(&dir)? {
+			let entry = entry?;
+			let name = entry.file_name();
+			if name.to_string_lossy().starts_with('.') || name.to_string_lossy().ends_with(".tmp")
+			{
+				continue;
+			}
+
+			let metadata = entry.metadata()?;
+			if metadata.is_dir() {
+				dirs.push(entry.path());
+				continue;
+			}
+
+			let path = entry.path();
+			let relative = path
+				.strip_prefix(root)
+				.expect("entry is within the backup directory")
+				.components()
+				.map(|component| component.as_os_str().to_string_lossy())
+				.collect::<Vec<_>>()
+				.join("/");
+
+			files.insert(relative, metadata.len());
+		}
+	}
+
+	Ok(files)
+}
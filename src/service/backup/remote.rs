@@ -0,0 +1,126 @@
+use std::{
+	fmt,
+	io::ErrorKind,
+	path::{Path, PathBuf},
+};
+
+use conduwuit::{Config, Err, Result, debug};
+use tokio::fs;
+
+use super::{Files, s3::S3};
+
+/// Where backup files are shipped after each backup.
+pub(super) enum Remote {
+	Directory(PathBuf),
+	S3(S3),
+}
+
+impl Remote {
+	pub(super) fn from_config(config: &Config) -> Result<Option<Self>> {
+		let directory = config
+			.database_backup_remote_path
+			.clone()
+			.filter(|path| !path.as_os_str().is_empty());
+
+		match (directory, &config.database_backup_s3_endpoint) {
+			| (Some(_), Some(_)) => Err!(Config(
+				"database_backup_remote_path",
+				"Only one of database_backup_remote_path and database_backup_s3_endpoint may be \
+				 set."
+			)),
+			| (Some(path), None) => Ok(Some(Self::Directory(path))),
+			| (None, Some(_)) => Ok(Some(Self::S3(S3::from_config(config)?))),
+			| (None, None) => Ok(None),
+		}
+	}
+
+	/// Make the remote match the local backup directory. Backup files are
+	/// immutable, so files already present with the same size are skipped.
+	/// Metadata is uploaded last and removed first, so the remote never lists a
+	/// backup with missing files. Returns the number of files uploaded and
+	/// removed.
+	pub(super) async fn mirror(
+		&self,
+		client: &reqwest::Client,
+		local: &Path,
+		before: &Files,
+		after: &Files,
+	) -> Result<(usize, usize)> {
+		let mut removed: usize = 0;
+		for name in ordered(before.keys().filter(|name| !after.contains_key(*name)), true) {
+			self.remove(client, name).await?;
+			removed = removed.saturating_add(1);
+		}
+
+		let mut uploaded: usize = 0;
+		for name in ordered(after.keys(), false) {
+			let size = after[name];
+			if self.size(client, name).await? == Some(size) {
+				continue;
+			}
+
+			debug!(%name, size, "Uploading backup file");
+			self.upload(client, &local.join(name), name).await?;
+			uploaded = uploaded.saturating_add(1);
+		}
+
+		Ok((uploaded, removed))
+	}
+
+	async fn size(&self, client: &reqwest::Client, name: &str) -> Result<Option<u64>> {
+		match self {
+			| Self::Directory(dir) => match fs::metadata(dir.join(name)).await {
+				| Ok(metadata) => Ok(Some(metadata.len())),
+				| Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+				| Err(e) => Err(e.into()),
+			},
+			| Self::S3(s3) => s3.head(client, name).await,
+		}
+	}
+
+	async fn upload(&self, client: &reqwest::Client, file: &Path, name: &str) -> Result {
+		match self {
+			| Self::Directory(dir) => {
+				let dest = dir.join(name);
+				let partial = dir.join(format!("{name}.tmp"));
+				if let Some(parent) = dest.parent() {
+					fs::create_dir_all(parent).await?;
+				}
+
+				fs::copy(file, &partial).await?;
+				fs::rename(&partial, &dest).await?;
+				Ok(())
+			},
+			| Self::S3(s3) => s3.upload(client, name, file).await,
+		}
+	}
+
+	async fn remove(&self, client: &reqwest::Client, name: &str) -> Result {
+		match self {
+			| Self::Directory(dir) => match fs::remove_file(dir.join(name)).await {
+				| Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+				| _ => Ok(()),
+			},
+			| Self::S3(s3) => s3.delete(client, name).await,
+		}
+	}
+}
+
+impl fmt::Display for Remote {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			| Self::Directory(dir) => write!(f, "{}", dir.display()),
+			| Self::S3(s3) => write!(f, "{s3}"),
+		}
+	}
+}
+
+/// Order files so the backup metadata under `meta/` comes first or last.
+fn ordered<'a, I>(names: I, meta_first: bool) -> Vec<&'a String>
+where
+	I: Iterator<Item = &'a String>,
+{
+	let mut names: Vec<_> = names.collect();
+	names.sort_by_key(|name| name.starts_with("meta/") != meta_first);
+	names
+}
@@ -0,0 +1,381 @@
+use std::{fmt, fmt::Write, path::Path, time::SystemTime};
+
+use conduwuit::{Config, Err, Result, err, utils::time, warn};
+use hmac::{Hmac, Mac};
+use reqwest::{
+	Client, Method, RequestBuilder, StatusCode,
+	header::{AUTHORIZATION, CONTENT_LENGTH, ETAG},
+};
+use sha2::{Digest, Sha256};
+use tokio::{fs::File, io::AsyncReadExt};
+use url::Url;
+
+/// An S3-compatible bucket, addressed path-style with requests signed using
+/// AWS Signature Version 4.
+pub(super) struct S3 {
+	endpoint: Url,
+	bucket: String,
+	prefix: String,
+	credentials: Credentials,
+}
+
+pub(super) struct Credentials {
+	pub(super) region: String,
+	pub(super) access_key: String,
+	pub(super) secret_key: String,
+}
+
+/// Headers authenticating a request.
+pub(super) struct Signature {
+	pub(super) timestamp: String,
+	pub(super) payload_hash: String,
+	pub(super) authorization: String,
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Files larger than this are uploaded in parts of this size, so that no more
+/// than a part is held in memory. S3 requires at least 5 MiB per part.
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+impl S3 {
+	pub(super) fn from_config(config: &Config) -> Result<Self> {
+		let Some(endpoint) = config.database_backup_s3_endpoint.clone() else {
+			return Err!(Config("database_backup_s3_endpoint", "No S3 endpoint is configured."));
+		};
+
+		if config.database_backup_s3_bucket.is_empty() {
+			return Err!(Config(
+				"database_backup_s3_bucket",
+				"A bucket is required to upload backups to S3."
+			));
+		}
+
+		Ok(Self {
+			endpoint,
+			bucket: config.database_backup_s3_bucket.clone(),
+			prefix: config.database_backup_s3_prefix.clone(),
+			credentials: Credentials {
+				region: config.database_backup_s3_region.clone(),
+				access_key: config.database_backup_s3_access_key.clone(),
+				secret_key: config.database_backup_s3_secret_key.clone(),
+			},
+		})
+	}
+
+	/// Size of an object, or None if it does not exist.
+	pub(super) async fn head(&self, client: &Client, name: &str) -> Result<Option<u64>> {
+		let response = self
+			.request(client, Method::HEAD, name, &[], &[])
+			.send()
+			.await?;
+
+		match response.status() {
+			| StatusCode::NOT_FOUND => Ok(None),
+			| status if status.is_success() => Ok(response
+				.headers()
+				.get(CONTENT_LENGTH)
+				.and_then(|len| len.to_str().ok())
+				.and_then(|len| len.parse().ok())),
+			| status => Err!("S3 request for {name:?} failed: {status}"),
+		}
+	}
+
+	/// Upload a file, with a multipart upload when it is larger than a part.
+	pub(super) async fn upload(&self, client: &Client, name: &str, file: &Path) -> Result {
+		let mut file = File::open(file).await?;
+		let first = read_part(&mut file).await?;
+		if first.len() < PART_SIZE {
+			return self.put(client, name, first).await;
+		}
+
+		let upload_id = self.create_multipart(client, name).await?;
+		let result = self
+			.upload_parts(client, name, &upload_id, first, &mut file)
+			.await;
+
+		if result.is_err() {
+			if let Err(e) = self.abort_multipart(client, name, &upload_id).await {
+				warn!("Failed to abort S3 upload of {name:?}: {e}");
+			}
+		}
+
+		result
+	}
+
+	async fn put(&self, client: &Client, name: &str, body: Vec<u8>) -> Result {
+		let status = self
+			.request(client, Method::PUT, name, &[], &body)
+			.body(body)
+			.send()
+			.await?
+			.status();
+
+		if !status.is_success() {
+			return Err!("S3 upload of {name:?} failed: {status}");
+		}
+
+		Ok(())
+	}
+
+	async fn create_multipart(&self, client: &Client, name: &str) -> Result<String> {
+		let response = self
+			.request(client, Method::POST, name, &[("uploads", "")], &[])
+			.send()
+			.await?;
+
+		let status = response.status();
+		if !status.is_success() {
+			return Err!("S3 upload of {name:?} failed to start: {status}");
+		}
+
+		let body = response.text().await?;
+		xml_value(&body, "UploadId")
+			.map(ToOwned::to_owned)
+			.ok_or_else(|| err!("S3 upload of {name:?} started without an upload ID."))
+	}
+
+	/// Upload the parts of the file starting with `part`, then complete the
+	/// upload.
+	async fn upload_parts(
+		&self,
+		client: &Client,
+		name: &str,
+		upload_id: &str,
+		mut part: Vec<u8>,
+		file: &mut File,
+	) -> Result {
+		let mut etags = Vec::new();
+		while !part.is_empty() {
+			let number = etags.len().saturating_add(1).to_string();
+			let query = [("partNumber", number.as_str()), ("uploadId", upload_id)];
+			let response = self
+				.request(client, Method::PUT, name, &query, &part)
+				.body(part)
+				.send()
+				.await?;
+
+			let status = response.status();
+			if !status.is_success() {
+				return Err!("S3 upload of part {number} of {name:?} failed: {status}");
+			}
+
+			let etag = response
+				.headers()
+				.get(ETAG)
+				.and_then(|etag| etag.to_str().ok())
+				.ok_or_else(|| err!("S3 upload of part {number} of {name:?} has no ETag."))?;
+
+			etags.push(etag.to_owned());
+			part = read_part(file).await?;
+		}
+
+		let mut body = String::from("<CompleteMultipartUpload>");
+		for (number, etag) in etags.iter().enumerate() {
+			let number = number.saturating_add(1);
+			write!(body, "<Part><PartNumber>{number}</PartNumber><ETag>{etag}</ETag></Part>")?;
+		}
+		body.push_str("</CompleteMultipartUpload>");
+
+		let response = self
+			.request(client, Method::POST, name, &[("uploadId", upload_id)], body.as_bytes())
+			.body(body)
+			.send()
+			.await?;
+
+		// completion may fail after the response started, with an error in the body
+		let status = response.status();
+		if !status.is_success() || response.text().await?.contains("<Error>") {
+			return Err!("S3 upload of {name:?} failed to complete: {status}");
+		}
+
+		Ok(())
+	}
+
+	async fn abort_multipart(&self, client: &Client, name: &str, upload_id: &str) -> Result {
+		let status = self
+			.request(client, Method::DELETE, name, &[("uploadId", upload_id)], &[])
+			.send()
+			.await?
+			.status();
+
+		if !status.is_success() && status != StatusCode::NOT_FOUND {
+			return Err!("{status}");
+		}
+
+		Ok(())
+	}
+
+	pub(super) async fn delete(&self, client: &Client, name: &str) -> Result {
+		let status = self
+			.request(client, Method::DELETE, name, &[], &[])
+			.send()
+			.await?
+			.status();
+
+		if !status.is_success() && status != StatusCode::NOT_FOUND {
+			return Err!("S3 removal of {name:?} failed: {status}");
+		}
+
+		Ok(())
+	}
+
+	fn request(
+		&self,
+		client: &Client,
+		method: Method,
+		name: &str,
+		query: &[(&str, &str)],
+		payload: &[u8],
+	) -> RequestBuilder {
+		let url = self.url(name, query);
+		let signature = self
+			.credentials
+			.sign(&method, &url, payload, SystemTime::now());
+
+		client
+			.request(method, url)
+			.header("x-amz-content-sha256", signature.payload_hash)
+			.header("x-amz-date", signature.timestamp)
+			.header(AUTHORIZATION, signature.authorization)
+	}
+
+	fn url(&self, name: &str, query: &[(&str, &str)]) -> Url {
+		let key = format!("{}{name}", self.prefix);
+		let mut url = self.endpoint.clone();
+		url.path_segments_mut()
+			.expect("S3 endpoint can be a base URL")
+			.pop_if_empty()
+			.push(&self.bucket)
+			.extend(key.split('/'));
+
+		let query = canonical_query(query);
+		url.set_query((!query.is_empty()).then_some(query.as_str()));
+		url
+	}
+}
+
+impl Credentials {
+	/// Sign a request to `url` made at `now`. The query of `url` must already
+	/// be canonical, as made by `canonical_query()`.
+	pub(super) fn sign(
+		&self,
+		method: &Method,
+		url: &Url,
+		payload: &[u8],
+		now: SystemTime,
+	) -> Signature {
+		let host = url.host_str().unwrap_or_default();
+		let host = match url.port() {
+			| Some(port) => format!("{host}:{port}"),
+			| None => host.to_owned(),
+		};
+
+		let timestamp = time::format(now, "%Y%m%dT%H%M%SZ");
+		let date = time::format(now, "%Y%m%d");
+		let payload_hash = hex(&Sha256::digest(payload));
+
+		let headers =
+			format!("host:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{timestamp}\n");
+		let canonical_request = format!(
+			"{method}\n{path}\n{query}\n{headers}\n{SIGNED_HEADERS}\n{payload_hash}",
+			path = url.path(),
+			query = url.query().unwrap_or_default(),
+		);
+
+		let scope = format!("{date}/{}/s3/aws4_request", self.region);
+		let string_to_sign = format!(
+			"AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{}",
+			hex(&Sha256::digest(canonical_request.as_bytes())),
+		);
+
+		let signing_key = signing_key(&self.secret_key, &date, &self.region, "s3");
+		let authorization = format!(
+			"AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, \
+			 Signature={}",
+			self.access_key,
+			hex(&hmac(&signing_key, string_to_sign.as_bytes())),
+		);
+
+		Signature { timestamp, payload_hash, authorization }
+	}
+}
+
+impl fmt::Display for S3 {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let endpoint = self.endpoint.as_str().trim_end_matches('/');
+		write!(f, "{endpoint}/{}/{}", self.bucket, self.prefix)
+	}
+}
+
+/// Read up to a part of the file; a part shorter than `PART_SIZE` is the last.
+async fn read_part(file: &mut File) -> Result<Vec<u8>> {
+	let mut part = vec![0; PART_SIZE];
+	let mut len: usize = 0;
+	while len < part.len() {
+		match file.read(&mut part[len..]).await? {
+			| 0 => break,
+			| read => len = len.saturating_add(read),
+		}
+	}
+
+	part.truncate(len);
+	Ok(part)
+}
+
+/// Query string with the parameters sorted and encoded as required by the
+/// signature.
+pub(super) fn canonical_query(query: &[(&str, &str)]) -> String {
+	let mut query: Vec<_> = query
+		.iter()
+		.map(|(key, value)| format!("{}={}", uri_encode(key), uri_encode(value)))
+		.collect();
+
+	query.sort();
+	query.join("&")
+}
+
+fn uri_encode(value: &str) -> String {
+	value.bytes().fold(String::new(), |mut out, byte| {
+		if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+			out.push(char::from(byte));
+		} else {
+			write!(out, "%{byte:02X}").expect("writing to a string");
+		}
+
+		out
+	})
+}
+
+pub(super) fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+	[date, region, service, "aws4_request"]
+		.into_iter()
+		.fold(format!("AWS4{secret_key}").into_bytes(), |key, part| {
+			hmac(&key, part.as_bytes())
+		})
+}
+
+/// Text of the first `tag` element of an XML response.
+fn xml_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+	let (_, rest) = xml.split_once(&format!("<{tag}>"))?;
+	let (value, _) = rest.split_once(&format!("</{tag}>"))?;
+
+	Some(value)
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+	let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+	mac.update(data);
+	mac.finalize().into_bytes().to_vec()
+}
+
+pub(super) fn hex(bytes: &[u8]) -> String {
+	bytes
+		.iter()
+		.fold(String::with_capacity(bytes.len().saturating_mul(2)), |mut out, byte| {
+			write!(out, "{byte:02x}").expect("writing to a string");
+			out
+		})
+}
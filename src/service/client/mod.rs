@@ -17,6 +17,7 @@ pub struct Service {
 	pub sender: reqwest::Client,
 	pub appservice: reqwest::Client,
 	pub pusher: reqwest::Client,
+	pub replica: reqwest::Client,
 
 	pub cidr_range_denylist: Vec<IPAddress>,
 }
@@ -105,6 +106,11 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				.redirect(redirect::Policy::limited(2))
 				.build()?,
 
+			replica: base(config)?
+				.no_proxy()
+				.redirect(redirect::Policy::none())
+				.build()?,
+
 			cidr_range_denylist: config
 				.ip_range_denylist
 				.iter()
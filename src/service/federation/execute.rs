@@ -74,10 +74,18 @@ pub async fn execute_on<T>(
 		return Err!(Request(Forbidden(debug_warn!("Federation with {dest} is not allowed."))));
 	}
 
-	let actual = self.services.resolver.get_actual_dest(dest).await?;
+	let actual = self
+		.services
+		.resolver
+		.get_actual_dest(dest)
+		.await
+		.inspect_err(|_| self.record_failure(dest))?;
 	let request = into_http_request::<T>(&actual, request)?;
 	let request = self.prepare(dest, request)?;
-	self.perform::<T>(dest, &actual, request, client).await
+	let result = self.perform::<T>(dest, &actual, request, client).await;
+	self.record_result(dest, &result);
+
+	result
 }
 
 #[implement(super::Service)]
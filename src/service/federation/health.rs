@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use conduwuit::{Error, implement, utils::continue_exponential_backoff_secs};
+use ruma::{OwnedServerName, ServerName};
+
+/// Observed health of a remote destination based on the outcome of our own
+/// outgoing federation requests.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Health {
+	/// Number of consecutive failures since the last success.
+	pub failures: u32,
+
+	/// Time of the last failed request.
+	pub last_failure: Option<Instant>,
+
+	/// Time of the last successful request.
+	pub last_success: Option<Instant>,
+}
+
+const BACKOFF_MIN_SECS: u64 = 30;
+const BACKOFF_MAX_SECS: u64 = 60 * 60;
+
+impl Health {
+	/// True when the destination failed recently enough that it should only
+	/// be asked as a last resort.
+	#[must_use]
+	pub fn is_backing_off(&self) -> bool {
+		self.last_failure.is_some_and(|time| {
+			continue_exponential_backoff_secs(
+				BACKOFF_MIN_SECS,
+				BACKOFF_MAX_SECS,
+				time.elapsed(),
+				self.failures,
+			)
+		})
+	}
+
+	fn rank(&self) -> (bool, u32, bool) {
+		(self.is_backing_off(), self.failures, self.last_success.is_none())
+	}
+}
+
+/// Returns the recorded health of a destination, if any request was made.
+#[implement(super::Service)]
+#[must_use]
+pub fn health(&self, dest: &ServerName) -> Option<Health> {
+	self.health
+		.read()
+		.expect("locked for reading")
+		.get(dest)
+		.copied()
+}
+
+/// Sort servers from healthiest to least healthy, keeping at most `limit`.
+/// The `pinned` server (e.g. the origin) stays first when it is listed. The
+/// sort is stable so the caller's order is kept among equally healthy servers.
+#[implement(super::Service)]
+pub fn rank_servers<I>(
+	&self,
+	servers: I,
+	pinned: Option<&ServerName>,
+	limit: usize,
+) -> Vec<OwnedServerName>
+where
+	I: IntoIterator<Item = OwnedServerName>,
+{
+	let health = self.health.read().expect("locked for reading");
+	rank(servers.into_iter().collect(), pinned, limit, |server| {
+		health.get(server).copied().unwrap_or_default()
+	})
+}
+
+pub(super) fn rank<F>(
+	mut servers: Vec<OwnedServerName>,
+	pinned: Option<&ServerName>,
+	limit: usize,
+	health: F,
+) -> Vec<OwnedServerName>
+where
+	F: Fn(&ServerName) -> Health,
+{
+	let position = pinned.and_then(|pinned| servers.iter().position(|server| server == pinned));
+	if let Some(position) = position {
+		let server = servers.remove(position);
+		servers.insert(0, server);
+	}
+
+	if let Some(unpinned) = servers.get_mut(usize::from(position.is_some())..) {
+		unpinned.sort_by_cached_key(|server| health(server).rank());
+	}
+
+	servers.truncate(limit.max(1));
+	servers
+}
+
+#[implement(super::Service)]
+pub(super) fn record_result<T>(&self, dest: &ServerName, result: &Result<T, Error>) {
+	// Error responses from the remote still indicate it is up and answering.
+	match result {
+		| Ok(_) => self.record_success(dest),
+		| Err(e @ Error::Federation(..)) if !e.status_code().is_server_error() =>
+			self.record_success(dest),
+		| Err(_) => self.record_failure(dest),
+	}
+}
+
+#[implement(super::Service)]
+pub(super) fn record_success(&self, dest: &ServerName) {
+	let mut health = self.health.write().expect("locked for writing");
+	let entry = health.entry(dest.to_owned()).or_default();
+	entry.failures = 0;
+	entry.last_success = Some(Instant::now());
+}
+
+#[implement(super::Service)]
+pub(super) fn record_failure(&self, dest: &ServerName) {
+	let mut health = self.health.write().expect("locked for writing");
+	let entry = health.entry(dest.to_owned()).or_default();
+	entry.failures = entry.failures.saturating_add(1);
+	entry.last_failure = Some(Instant::now());
+}
@@ -0,0 +1,325 @@
+//! Scheduling of inbound federation transactions.
+//!
+//! Each origin has a bounded queue and a concurrency limit of its own; permits
+//! for the shared PDU lane are then granted in arrival order, so an origin
+//! flooding us only ever competes with its own backlog. EDUs are granted from
+//! a separate lane and are not held up behind PDU processing of other
+//! transactions; within a transaction they are still handled after its PDUs,
+//! which they may refer to (e.g. read receipts).
+//!
+//! Origins which have been idle for a while are forgotten, along with their
+//! statistics, unless processing from them is paused.
+
+use std::{
+	sync::{
+		Arc,
+		atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+	},
+	time::{Duration, Instant},
+};
+
+use conduwuit::{Error, Result, implement, utils::time::now_millis};
+use http::StatusCode;
+use ruma::{
+	OwnedServerName, ServerName,
+	api::client::error::{ErrorKind, RetryAfter},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Scheduling state and statistics for a single origin.
+#[derive(Debug)]
+pub struct Origin {
+	permits: Arc<Semaphore>,
+	paused: AtomicBool,
+
+	/// Transactions waiting for or holding a permit.
+	pub queued: AtomicUsize,
+
+	/// Transactions currently being processed.
+	pub active: AtomicUsize,
+
+	/// Transactions admitted.
+	pub transactions: AtomicU64,
+
+	/// Transactions refused because the origin was paused or its queue full.
+	pub rejected: AtomicU64,
+
+	/// PDUs received in admitted transactions.
+	pub pdus: AtomicU64,
+
+	/// EDUs received in admitted transactions.
+	pub edus: AtomicU64,
+
+	/// Total time spent processing (microseconds).
+	pub busy: AtomicU64,
+
+	/// Total time spent waiting for a permit (microseconds).
+	pub waited: AtomicU64,
+
+	/// When a transaction was last admitted (milliseconds since the epoch).
+	pub last_admitted: AtomicU64,
+}
+
+/// An admitted transaction. Permits for each lane are acquired from this.
+pub struct Ticket {
+	pdus: usize,
+	edus: usize,
+	origin: Arc<Origin>,
+	pdu_lane: Arc<Semaphore>,
+	edu_lane: Arc<Semaphore>,
+}
+
+/// Held while part of a transaction is processed.
+pub struct Permit {
+	origin: Arc<Origin>,
+	started: Instant,
+	_permits: Vec<OwnedSemaphorePermit>,
+}
+
+/// Time remote servers are asked to wait before retrying a refused
+/// transaction.
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Idle origins are forgotten once they sent no transaction for this long.
+const IDLE_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// Admit a transaction from `origin`. Fails without waiting when processing
+/// from the origin is paused or too many of its transactions are queued.
+#[implement(super::Service)]
+pub fn admit(&self, origin: &ServerName, pdus: usize, edus: usize) -> Result<Ticket> {
+	let queue = self.services.server.config.federation_inbound_queue;
+	let entry = self.inbound_origin(origin);
+	entry.enqueue(origin, queue, pdus, edus)?;
+
+	Ok(Ticket::new(
+		entry,
+		pdus,
+		edus,
+		self.inbound_pdu_lane.clone(),
+		self.inbound_edu_lane.clone(),
+	))
+}
+
+/// Stop admitting transactions from `origin`; they are refused with a
+/// retryable error until resumed. Returns false if it was already paused.
+#[implement(super::Service)]
+pub fn pause_origin(&self, origin: &ServerName) -> bool { self.inbound_origin(origin).pause() }
+
+/// Resume admitting transactions from `origin`. Returns false if it was not
+/// paused.
+#[implement(super::Service)]
+pub fn resume_origin(&self, origin: &ServerName) -> bool { self.inbound_origin(origin).resume() }
+
+/// Snapshot of the inbound state of every origin we recently received
+/// transactions from, or paused.
+#[implement(super::Service)]
+pub fn inbound_origins(&self) -> Vec<(OwnedServerName, Arc<Origin>)> {
+	self.inbound
+		.read()
+		.expect("locked for reading")
+		.iter()
+		.map(|(origin, entry)| (origin.clone(), entry.clone()))
+		.collect()
+}
+
+#[implement(super::Service)]
+fn inbound_origin(&self, origin: &ServerName) -> Arc<Origin> {
+	if let Some(entry) = self.inbound.read().expect("locked for reading").get(origin) {
+		return entry.clone();
+	}
+
+	let per_origin = self
+		.services
+		.server
+		.config
+		.federation_inbound_per_origin
+		.max(1);
+
+	// new origins are rare compared to transactions; forgetting expired ones
+	// here keeps the map from growing with every server which ever sent us one.
+	let now = now_millis();
+	let mut inbound = self.inbound.write().expect("locked for writing");
+	inbound.retain(|_, entry| !entry.is_expired(now));
+	inbound
+		.entry(origin.to_owned())
+		.or_insert_with(|| Arc::new(Origin::new(per_origin)))
+		.clone()
+}
+
+impl Ticket {
+	pub(super) fn new(
+		origin: Arc<Origin>,
+		pdus: usize,
+		edus: usize,
+		pdu_lane: Arc<Semaphore>,
+		edu_lane: Arc<Semaphore>,
+	) -> Self {
+		Self { pdus, edus, origin, pdu_lane, edu_lane }
+	}
+
+	/// Wait for permission to process the PDUs of the transaction. The
+	/// origin's own limit is acquired first so its transactions queue behind
+	/// each other rather than in front of other origins. Returns None without
+	/// waiting when the transaction has no PDUs.
+	pub async fn pdus(&self) -> Option<Permit> {
+		if self.pdus == 0 {
+			return None;
+		}
+
+		let started = Instant::now();
+		let origin = self
+			.origin
+			.permits
+			.clone()
+			.acquire_owned()
+			.await
+			.expect("origin semaphore is never closed");
+
+		let lane = self
+			.pdu_lane
+			.clone()
+			.acquire_owned()
+			.await
+			.expect("lane semaphore is never closed");
+
+		Some(self.permit(started, vec![origin, lane]))
+	}
+
+	/// Wait for permission to process the EDUs of the transaction. Returns
+	/// None without waiting when the transaction has no EDUs.
+	pub async fn edus(&self) -> Option<Permit> {
+		if self.edus == 0 {
+			return None;
+		}
+
+		let started = Instant::now();
+		let lane = self
+			.edu_lane
+			.clone()
+			.acquire_owned()
+			.await
+			.expect("lane semaphore is never closed");
+
+		Some(self.permit(started, vec![lane]))
+	}
+
+	fn permit(&self, started: Instant, permits: Vec<OwnedSemaphorePermit>) -> Permit {
+		let waited = started.elapsed().as_micros().try_into().unwrap_or(u64::MAX);
+		self.origin.waited.fetch_add(waited, Ordering::Relaxed);
+		self.origin.active.fetch_add(1, Ordering::AcqRel);
+
+		Permit {
+			origin: self.origin.clone(),
+			started: Instant::now(),
+			_permits: permits,
+		}
+	}
+}
+
+impl Drop for Ticket {
+	fn drop(&mut self) { self.origin.queued.fetch_sub(1, Ordering::AcqRel); }
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		let busy = self
+			.started
+			.elapsed()
+			.as_micros()
+			.try_into()
+			.unwrap_or(u64::MAX);
+
+		self.origin.busy.fetch_add(busy, Ordering::Relaxed);
+		self.origin.active.fetch_sub(1, Ordering::AcqRel);
+	}
+}
+
+impl Origin {
+	pub(super) fn new(permits: usize) -> Self {
+		Self {
+			permits: Arc::new(Semaphore::new(permits)),
+			paused: AtomicBool::new(false),
+			queued: AtomicUsize::new(0),
+			active: AtomicUsize::new(0),
+			transactions: AtomicU64::new(0),
+			rejected: AtomicU64::new(0),
+			pdus: AtomicU64::new(0),
+			edus: AtomicU64::new(0),
+			busy: AtomicU64::new(0),
+			waited: AtomicU64::new(0),
+			last_admitted: AtomicU64::new(now_millis()),
+		}
+	}
+
+	/// Count a transaction as queued, unless processing from the origin is
+	/// paused or `queue` of its transactions are queued already. The caller
+	/// makes a Ticket of it, which takes it off the queue when dropped.
+	pub(super) fn enqueue(
+		&self,
+		origin: &ServerName,
+		queue: usize,
+		pdus: usize,
+		edus: usize,
+	) -> Result {
+		if self.is_paused() {
+			self.rejected.fetch_add(1, Ordering::Relaxed);
+			return Err(limit_exceeded(format!(
+				"Processing of transactions from {origin} is paused."
+			)));
+		}
+
+		if self.queued.fetch_add(1, Ordering::AcqRel) >= queue {
+			self.queued.fetch_sub(1, Ordering::AcqRel);
+			self.rejected.fetch_add(1, Ordering::Relaxed);
+			return Err(limit_exceeded(format!(
+				"Too many transactions from {origin} are queued."
+			)));
+		}
+
+		self.transactions.fetch_add(1, Ordering::Relaxed);
+		self.pdus
+			.fetch_add(pdus.try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+		self.edus
+			.fetch_add(edus.try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+		self.last_admitted.store(now_millis(), Ordering::Relaxed);
+
+		Ok(())
+	}
+
+	/// Returns false if it was already paused.
+	pub(super) fn pause(&self) -> bool { !self.paused.swap(true, Ordering::AcqRel) }
+
+	/// Returns false if it was not paused.
+	pub(super) fn resume(&self) -> bool { self.paused.swap(false, Ordering::AcqRel) }
+
+	/// Whether the origin is idle and sent no transaction for `IDLE_EXPIRY`
+	/// before `now` (milliseconds since the epoch).
+	pub(super) fn is_expired(&self, now: u64) -> bool {
+		let expiry = IDLE_EXPIRY.as_millis().try_into().unwrap_or(u64::MAX);
+		let last = self.last_admitted.load(Ordering::Relaxed);
+
+		self.is_idle()
+			&& self.active.load(Ordering::Acquire) == 0
+			&& now.saturating_sub(last) >= expiry
+	}
+
+	#[inline]
+	#[must_use]
+	pub fn is_paused(&self) -> bool { self.paused.load(Ordering::Acquire) }
+
+	#[inline]
+	#[must_use]
+	pub fn is_idle(&self) -> bool {
+		!self.is_paused() && self.queued.load(Ordering::Acquire) == 0
+	}
+}
+
+fn limit_exceeded(message: String) -> Error {
+	Error::Request(
+		ErrorKind::LimitExceeded {
+			retry_after: Some(RetryAfter::Delay(RETRY_AFTER)),
+		},
+		message.into(),
+		StatusCode::TOO_MANY_REQUESTS,
+	)
+}
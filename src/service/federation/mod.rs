@@ -1,15 +1,36 @@
 mod execute;
+mod health;
+mod inbound;
+mod tests;
 
-use std::sync::Arc;
+use std::{
+	collections::HashMap,
+	fmt::Write,
+	sync::{Arc, RwLock},
+};
 
+use async_trait::async_trait;
 use conduwuit::{Result, Server};
+use ruma::OwnedServerName;
+use tokio::sync::Semaphore;
 
+pub use self::{
+	health::Health,
+	inbound::{Origin, Permit, Ticket},
+};
 use crate::{Dep, client, resolver, server_keys};
 
 pub struct Service {
+	health: RwLock<HealthMap>,
+	inbound: RwLock<InboundMap>,
+	inbound_pdu_lane: Arc<Semaphore>,
+	inbound_edu_lane: Arc<Semaphore>,
 	services: Services,
 }
 
+type HealthMap = HashMap<OwnedServerName, Health>;
+type InboundMap = HashMap<OwnedServerName, Arc<Origin>>;
+
 struct Services {
 	server: Arc<Server>,
 	client: Dep<client::Service>,
@@ -17,9 +38,19 @@ struct Services {
 	server_keys: Dep<server_keys::Service>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
 		Ok(Arc::new(Self {
+			health: RwLock::new(HealthMap::new()),
+			inbound: RwLock::new(InboundMap::new()),
+			inbound_pdu_lane: Arc::new(Semaphore::new(
+				config.federation_inbound_concurrency.max(1),
+			)),
+			inbound_edu_lane: Arc::new(Semaphore::new(
+				config.federation_inbound_edu_concurrency.max(1),
+			)),
 			services: Services {
 				server: args.server.clone(),
 				client: args.depend::<client::Service>("client"),
@@ -29,5 +60,23 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let health = self.health.read().expect("locked for reading").len();
+		writeln!(out, "destination_health: {health}")?;
+
+		let inbound = self.inbound.read().expect("locked for reading").len();
+		writeln!(out, "inbound_origins: {inbound}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.health.write().expect("locked for writing").clear();
+		self.inbound
+			.write()
+			.expect("locked for writing")
+			.retain(|_, origin| !origin.is_idle());
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
@@ -0,0 +1,200 @@
+#![cfg(test)]
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, atomic::Ordering},
+	time::{Duration, Instant},
+};
+
+use conduwuit::Result;
+use ruma::{OwnedServerName, ServerName, api::client::error::ErrorKind, server_name};
+use tokio::sync::Semaphore;
+
+use super::{Health, Origin, Ticket, health::rank};
+
+const HOUR: u64 = 60 * 60 * 1000;
+
+/// As `Service::admit` with a queue of `queue` transactions.
+fn admit(entry: &Arc<Origin>, queue: usize, pdus: usize, edus: usize) -> Result<Ticket> {
+	entry.enqueue(server_name!("example.com"), queue, pdus, edus)?;
+
+	let lane = Arc::new(Semaphore::new(8));
+	Ok(Ticket::new(entry.clone(), pdus, edus, lane.clone(), lane))
+}
+
+#[test]
+fn admit_counts_transactions() {
+	let entry = Arc::new(Origin::new(1));
+
+	let ticket = admit(&entry, 2, 3, 4).expect("admitted");
+	assert_eq!(entry.queued.load(Ordering::Relaxed), 1);
+	assert_eq!(entry.transactions.load(Ordering::Relaxed), 1);
+	assert_eq!(entry.pdus.load(Ordering::Relaxed), 3);
+	assert_eq!(entry.edus.load(Ordering::Relaxed), 4);
+	assert!(!entry.is_idle());
+
+	drop(ticket);
+	assert_eq!(entry.queued.load(Ordering::Relaxed), 0);
+	assert!(entry.is_idle());
+}
+
+#[test]
+fn admit_refuses_full_queue() {
+	let entry = Arc::new(Origin::new(1));
+	let first = admit(&entry, 2, 1, 0).expect("admitted");
+	let _second = admit(&entry, 2, 1, 0).expect("admitted");
+
+	let error = admit(&entry, 2, 1, 0).err().expect("refused");
+	assert!(matches!(error.kind(), ErrorKind::LimitExceeded { .. }));
+	assert_eq!(error.status_code(), http::StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(entry.queued.load(Ordering::Relaxed), 2);
+	assert_eq!(entry.rejected.load(Ordering::Relaxed), 1);
+	assert_eq!(entry.transactions.load(Ordering::Relaxed), 2);
+
+	drop(first);
+	admit(&entry, 2, 1, 0).expect("admitted");
+}
+
+#[test]
+fn pause_and_resume() {
+	let entry = Arc::new(Origin::new(1));
+
+	assert!(entry.pause());
+	assert!(!entry.pause(), "already paused");
+	assert!(entry.is_paused());
+	assert!(!entry.is_idle());
+
+	let error = admit(&entry, 8, 1, 0).err().expect("refused");
+	assert!(matches!(error.kind(), ErrorKind::LimitExceeded { .. }));
+	assert_eq!(entry.queued.load(Ordering::Relaxed), 0);
+	assert_eq!(entry.rejected.load(Ordering::Relaxed), 1);
+
+	assert!(entry.resume());
+	assert!(!entry.resume(), "not paused");
+	admit(&entry, 8, 1, 0).expect("admitted");
+}
+
+#[test]
+fn idle_origins_expire() {
+	let entry = Arc::new(Origin::new(1));
+	let created = entry.last_admitted.load(Ordering::Relaxed);
+	assert!(!entry.is_expired(created));
+	assert!(entry.is_expired(created.saturating_add(HOUR)));
+
+	let ticket = admit(&entry, 8, 1, 0).expect("admitted");
+	let admitted = entry.last_admitted.load(Ordering::Relaxed);
+	assert!(!entry.is_expired(admitted.saturating_add(HOUR)), "queued transaction");
+
+	drop(ticket);
+	assert!(entry.is_expired(admitted.saturating_add(HOUR)));
+
+	entry.pause();
+	assert!(!entry.is_expired(admitted.saturating_add(HOUR)), "paused");
+}
+
+#[tokio::test]
+async fn pdus_wait_for_origin() {
+	let entry = Arc::new(Origin::new(1));
+	let first = admit(&entry, 8, 1, 1).expect("admitted");
+	let second = admit(&entry, 8, 1, 1).expect("admitted");
+
+	let permit = first.pdus().await.expect("permit");
+	assert_eq!(entry.active.load(Ordering::Relaxed), 1);
+	let waiting = tokio::time::timeout(Duration::from_millis(10), second.pdus()).await;
+	assert!(waiting.is_err(), "pdus of an origin are processed one transaction at a time");
+
+	// edus do not wait for the origin
+	drop(second.edus().await.expect("permit"));
+
+	drop(permit);
+	assert_eq!(entry.active.load(Ordering::Relaxed), 0);
+	assert!(second.pdus().await.is_some());
+
+	let empty = admit(&entry, 8, 0, 0).expect("admitted");
+	assert!(empty.pdus().await.is_none());
+	assert!(empty.edus().await.is_none());
+}
+
+fn servers(names: &[&str]) -> Vec<OwnedServerName> {
+	names
+		.iter()
+		.map(|name| ServerName::parse(name).expect("valid server name"))
+		.collect()
+}
+
+fn healthy() -> Health {
+	Health {
+		last_success: Some(Instant::now()),
+		..Health::default()
+	}
+}
+
+fn failing(failures: u32) -> Health {
+	Health {
+		failures,
+		last_failure: Some(Instant::now()),
+		..Health::default()
+	}
+}
+
+fn rank_with(
+	names: &[&str],
+	pinned: Option<&ServerName>,
+	limit: usize,
+	health: &HashMap<&str, Health>,
+) -> Vec<OwnedServerName> {
+	rank(servers(names), pinned, limit, |server| {
+		health.get(server.as_str()).copied().unwrap_or_default()
+	})
+}
+
+#[test]
+fn rank_by_health() {
+	let health = HashMap::from([
+		("a.example", failing(3)),
+		("b.example", failing(1)),
+		("c.example", healthy()),
+	]);
+
+	let ranked =
+		rank_with(&["a.example", "b.example", "c.example", "d.example"], None, 8, &health);
+	assert_eq!(ranked, servers(&["c.example", "d.example", "b.example", "a.example"]));
+}
+
+#[test]
+fn rank_is_stable() {
+	let ranked = rank_with(&["c.example", "a.example", "b.example"], None, 8, &HashMap::new());
+	assert_eq!(ranked, servers(&["c.example", "a.example", "b.example"]));
+}
+
+#[test]
+fn rank_pins_origin_first() {
+	let health = HashMap::from([("origin.example", failing(5)), ("b.example", healthy())]);
+	let origin = server_name!("origin.example");
+
+	let ranked =
+		rank_with(&["origin.example", "a.example", "b.example"], Some(origin), 8, &health);
+	assert_eq!(ranked, servers(&["origin.example", "b.example", "a.example"]));
+
+	// pinned wherever it was listed, and kept when truncating
+	let ranked =
+		rank_with(&["b.example", "a.example", "origin.example"], Some(origin), 1, &health);
+	assert_eq!(ranked, servers(&["origin.example"]));
+
+	// not listed, e.g. filtered out by the caller
+	let ranked = rank_with(&["a.example", "b.example"], Some(origin), 8, &health);
+	assert_eq!(ranked, servers(&["b.example", "a.example"]));
+}
+
+#[test]
+fn rank_limit() {
+	let names = ["a.example", "b.example", "c.example"];
+	let health = HashMap::new();
+
+	assert_eq!(rank_with(&names, None, 2, &health), servers(&["a.example", "b.example"]));
+	assert_eq!(rank_with(&names, None, 8, &health).len(), 3);
+
+	// at least one server is asked
+	assert_eq!(rank_with(&names, None, 0, &health), servers(&["a.example"]));
+	assert!(rank_with(&[], None, 8, &health).is_empty());
+}
@@ -0,0 +1,86 @@
+use std::pin::pin;
+
+use conduwuit::{Result, implement};
+use futures::TryStreamExt;
+use ruma::OwnedEventId;
+use serde::Deserialize;
+
+use super::Report;
+
+#[derive(Deserialize)]
+struct ExtractEventId {
+	event_id: OwnedEventId,
+}
+
+/// Every `eventid_pduid` row points at an existing `pduid_pdu` row. Dangling
+/// rows are removed; the event is still found if it is also an outlier.
+#[implement(super::Service)]
+pub async fn check_event_pdus(&self, fix: bool) -> Result<Report> {
+	let mut report = Report::new("event_pdus");
+	let mut dangling = Vec::new();
+	let mut rows = pin!(self.db.eventid_pduid.raw_stream());
+	while let Some((event_id, pdu_id)) = rows.try_next().await? {
+		report.checked = report.checked.saturating_add(1);
+		if self.db.pduid_pdu.exists(pdu_id).await.is_ok() {
+			continue;
+		}
+
+		let outlier = self.db.eventid_outlierpdu.exists(event_id).await.is_ok();
+		report.problem(|| {
+			format!(
+				"{} points at missing PDU {pdu_id:?}{}",
+				String::from_utf8_lossy(event_id),
+				if outlier { " (also an outlier)" } else { "" },
+			)
+		});
+
+		dangling.push(event_id.to_vec());
+	}
+
+	if fix {
+		for event_id in &dangling {
+			self.db.eventid_pduid.remove(event_id);
+		}
+
+		report.fixed(dangling.len());
+	}
+
+	Ok(report)
+}
+
+/// Every `pduid_pdu` row is pointed at by the `eventid_pduid` row of its event.
+/// Missing rows are restored; rows pointing at another PDU are only reported.
+#[implement(super::Service)]
+pub async fn check_pdu_events(&self, fix: bool) -> Result<Report> {
+	let mut report = Report::new("pdu_events");
+	let mut missing = Vec::new();
+	let mut rows = pin!(self.db.pduid_pdu.raw_stream());
+	while let Some((pdu_id, pdu)) = rows.try_next().await? {
+		report.checked = report.checked.saturating_add(1);
+		let Ok(ExtractEventId { event_id }) = serde_json::from_slice(pdu) else {
+			report.problem(|| format!("PDU {pdu_id:?} has no valid event_id"));
+			continue;
+		};
+
+		match self.db.eventid_pduid.get(event_id.as_str()).await {
+			| Ok(found) if *found == *pdu_id => (),
+			| Ok(found) => report.problem(|| {
+				format!("{event_id} of PDU {pdu_id:?} points at another PDU {:?}", &*found)
+			}),
+			| Err(_) => {
+				report.problem(|| format!("{event_id} of PDU {pdu_id:?} is not indexed"));
+				missing.push((event_id, pdu_id.to_vec()));
+			},
+		}
+	}
+
+	if fix {
+		for (event_id, pdu_id) in &missing {
+			self.db.eventid_pduid.insert(event_id.as_str(), pdu_id);
+		}
+
+		report.fixed(missing.len());
+	}
+
+	Ok(report)
+}
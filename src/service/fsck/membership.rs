@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+
+use conduwuit::{
+	Result, implement,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use futures::StreamExt;
+use ruma::{
+	OwnedRoomId, OwnedUserId, UserId,
+	events::{
+		TimelineEventType,
+		room::member::{MembershipState, RoomMemberEventContent},
+	},
+};
+
+use super::Report;
+
+/// The users joined to each room according to `roomuserid_joined` and
+/// `userroomid_joined` are those whose membership is join in the room's
+/// current state. The membership caches of rooms with disagreeing users are
+/// recomputed from the state.
+#[implement(super::Service)]
+pub async fn check_room_memberships(&self, fix: bool) -> Result<Report> {
+	let mut report = Report::new("room_memberships");
+	let mut fixes: Vec<OwnedRoomId> = Vec::new();
+	for room_id in self.rooms().await {
+		if self
+			.db
+			.roomid_shortstatehash
+			.exists(room_id.as_str())
+			.await
+			.is_err()
+		{
+			continue;
+		}
+
+		report.checked = report.checked.saturating_add(1);
+		let joined: HashSet<OwnedUserId> = self
+			.services
+			.state_accessor
+			.room_state_full_pdus(&room_id)
+			.ignore_err()
+			.ready_filter(|pdu| pdu.kind == TimelineEventType::RoomMember)
+			.ready_filter_map(|pdu| {
+				let content: RoomMemberEventContent = pdu.get_content().ok()?;
+				let user_id = UserId::parse(pdu.state_key.as_deref()?).ok()?;
+				(content.membership == MembershipState::Join).then_some(user_id)
+			})
+			.collect()
+			.await;
+
+		let members: HashSet<OwnedUserId> = self
+			.services
+			.state_cache
+			.room_members(&room_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		let (missing, not_joined) = compare_members(&joined, &members);
+		for user_id in missing {
+			report.problem(|| {
+				format!("{user_id} is joined to {room_id} but missing from its members")
+			});
+			fixes.push(room_id.clone());
+		}
+
+		for user_id in joined.intersection(&members) {
+			if !self.services.state_cache.is_joined(user_id, &room_id).await {
+				report
+					.problem(|| format!("{user_id} is joined to {room_id} but missing its room"));
+				fixes.push(room_id.clone());
+			}
+		}
+
+		for user_id in not_joined {
+			report.problem(|| format!("{user_id} is a member of {room_id} but not joined to it"));
+			fixes.push(room_id.clone());
+		}
+	}
+
+	// Members which are not joined may be invited or knocking rather than gone,
+	// so rooms are recomputed from their state instead of marking them as left.
+	if fix {
+		let rooms: HashSet<_> = fixes.iter().collect();
+		for room_id in rooms {
+			self.services
+				.state_cache
+				.recompute_memberships(room_id)
+				.await?;
+		}
+
+		report.fixed(fixes.len());
+	}
+
+	Ok(report)
+}
+
+/// `roomid_joinedcount` and `roomid_invitedcount` agree with the members of
+/// each room. Incorrect counts are recomputed.
+#[implement(super::Service)]
+pub async fn check_joined_counts(&self, fix: bool) -> Result<Report> {
+	let mut report = Report::new("joined_counts");
+	let mut incorrect = Vec::new();
+	let state_cache = &self.services.state_cache;
+	for room_id in self.rooms().await {
+		report.checked = report.checked.saturating_add(1);
+		let joined = state_cache.room_members(&room_id).count().await;
+		let invited = state_cache.room_members_invited(&room_id).count().await;
+		let joined_count = state_cache.room_joined_count(&room_id).await.unwrap_or(0);
+		let invited_count = state_cache.room_invited_count(&room_id).await.unwrap_or(0);
+		if joined_count == joined.try_into()? && invited_count == invited.try_into()? {
+			continue;
+		}
+
+		report.problem(|| {
+			format!(
+				"{room_id} counts {joined_count} joined and {invited_count} invited but has \
+				 {joined} and {invited}"
+			)
+		});
+		incorrect.push(room_id);
+	}
+
+	if fix {
+		for room_id in &incorrect {
+			state_cache.update_joined_count(room_id).await;
+		}
+
+		report.fixed(incorrect.len());
+	}
+
+	Ok(report)
+}
+
+/// Users joined in a room's state but missing from its indexed members, and
+/// indexed members not joined in its state.
+pub(super) fn compare_members<'a>(
+	joined: &'a HashSet<OwnedUserId>,
+	members: &'a HashSet<OwnedUserId>,
+) -> (Vec<&'a OwnedUserId>, Vec<&'a OwnedUserId>) {
+	let mut missing: Vec<_> = joined.difference(members).collect();
+	let mut not_joined: Vec<_> = members.difference(joined).collect();
+	missing.sort_unstable();
+	not_joined.sort_unstable();
+
+	(missing, not_joined)
+}
+
+#[implement(super::Service)]
+async fn rooms(&self) -> Vec<OwnedRoomId> {
+	self.services
+		.metadata
+		.iter_ids()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await
+}
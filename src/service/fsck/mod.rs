@@ -0,0 +1,147 @@
+//! Consistency checks of the invariants between columns of the database.
+//!
+//! Each check scans the columns it covers and reports the rows which violate
+//! its invariant. When asked to fix them, it repairs those it can once the
+//! scan is complete; the others are only reported. Orphaned rows are typically
+//! left behind by a crash between the writes of a single operation.
+
+mod events;
+mod membership;
+mod short;
+mod state;
+mod tests;
+
+use std::{
+	fmt::{self, Display},
+	sync::Arc,
+};
+
+use conduwuit::Result;
+use database::Map;
+
+use crate::{Dep, rooms};
+
+pub struct Service {
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	eventid_outlierpdu: Arc<Map>,
+	eventid_pduid: Arc<Map>,
+	eventid_shorteventid: Arc<Map>,
+	pduid_pdu: Arc<Map>,
+	roomid_shortstatehash: Arc<Map>,
+	shorteventid_eventid: Arc<Map>,
+	shortstatehash_statediff: Arc<Map>,
+}
+
+struct Services {
+	metadata: Dep<rooms::metadata::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+}
+
+/// Outcome of a check.
+#[derive(Debug)]
+pub struct Report {
+	pub name: &'static str,
+
+	/// Number of rows or rooms checked.
+	pub checked: usize,
+
+	/// Number of problems found.
+	pub found: usize,
+
+	/// Number of problems fixed.
+	pub fixed: usize,
+
+	/// Descriptions of the first problems found.
+	pub problems: Vec<String>,
+}
+
+/// Number of problems described in a report.
+const MAX_PROBLEMS: usize = 32;
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				eventid_outlierpdu: args.db["eventid_outlierpdu"].clone(),
+				eventid_pduid: args.db["eventid_pduid"].clone(),
+				eventid_shorteventid: args.db["eventid_shorteventid"].clone(),
+				pduid_pdu: args.db["pduid_pdu"].clone(),
+				roomid_shortstatehash: args.db["roomid_shortstatehash"].clone(),
+				shorteventid_eventid: args.db["shorteventid_eventid"].clone(),
+				shortstatehash_statediff: args.db["shortstatehash_statediff"].clone(),
+			},
+			services: Services {
+				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Run every check in an order where the fixes of one don't invalidate
+	/// the results of a later one.
+	pub async fn check_all(&self, fix: bool) -> Result<Vec<Report>> {
+		Ok(vec![
+			self.check_event_pdus(fix).await?,
+			self.check_pdu_events(fix).await?,
+			self.check_short_event_ids(fix).await?,
+			self.check_state_diffs().await?,
+			self.check_room_memberships(fix).await?,
+			self.check_joined_counts(fix).await?,
+		])
+	}
+}
+
+impl Report {
+	fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			checked: 0,
+			found: 0,
+			fixed: 0,
+			problems: Vec::new(),
+		}
+	}
+
+	fn problem<F>(&mut self, describe: F)
+	where
+		F: FnOnce() -> String,
+	{
+		self.found = self.found.saturating_add(1);
+		if self.problems.len() < MAX_PROBLEMS {
+			self.problems.push(describe());
+		}
+	}
+
+	fn fixed(&mut self, count: usize) { self.fixed = self.fixed.saturating_add(count); }
+}
+
+impl Display for Report {
+	fn fmt(&self, out: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			out,
+			"{}: checked {}, found {} problems, fixed {}",
+			self.name, self.checked, self.found, self.fixed
+		)?;
+
+		for problem in &self.problems {
+			writeln!(out, "- {problem}")?;
+		}
+
+		if self.found > self.problems.len() {
+			writeln!(out, "- ... and {} more", self.found.saturating_sub(self.problems.len()))?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,77 @@
+use std::pin::pin;
+
+use conduwuit::{Result, implement};
+use futures::TryStreamExt;
+
+use super::Report;
+
+/// `shorteventid_eventid` and `eventid_shorteventid` are inverses of each
+/// other. A row missing from either side is restored from the other; two
+/// rows which disagree are only reported since state may refer to either.
+#[implement(super::Service)]
+pub async fn check_short_event_ids(&self, fix: bool) -> Result<Report> {
+	let mut report = Report::new("short_event_ids");
+	let mut missing_reverse = Vec::new();
+	let mut rows = pin!(self.db.shorteventid_eventid.raw_stream());
+	while let Some((short, event_id)) = rows.try_next().await? {
+		report.checked = report.checked.saturating_add(1);
+		match self.db.eventid_shorteventid.get(event_id).await {
+			| Ok(found) if *found == *short => (),
+			| Ok(found) => report.problem(|| {
+				format!(
+					"short event id {short:?} maps to {} which maps to {:?}",
+					String::from_utf8_lossy(event_id),
+					&*found,
+				)
+			}),
+			| Err(_) => {
+				report.problem(|| {
+					format!(
+						"{} is missing the inverse of short event id {short:?}",
+						String::from_utf8_lossy(event_id)
+					)
+				});
+				missing_reverse.push((event_id.to_vec(), short.to_vec()));
+			},
+		}
+	}
+
+	let mut missing_forward = Vec::new();
+	let mut rows = pin!(self.db.eventid_shorteventid.raw_stream());
+	while let Some((event_id, short)) = rows.try_next().await? {
+		report.checked = report.checked.saturating_add(1);
+		match self.db.shorteventid_eventid.get(short).await {
+			| Ok(found) if *found == *event_id => (),
+			| Ok(found) => report.problem(|| {
+				format!(
+					"{} maps to short event id {short:?} which maps to {}",
+					String::from_utf8_lossy(event_id),
+					String::from_utf8_lossy(&found),
+				)
+			}),
+			| Err(_) => {
+				report.problem(|| {
+					format!(
+						"short event id {short:?} is missing the inverse of {}",
+						String::from_utf8_lossy(event_id)
+					)
+				});
+				missing_forward.push((short.to_vec(), event_id.to_vec()));
+			},
+		}
+	}
+
+	if fix {
+		for (event_id, short) in &missing_reverse {
+			self.db.eventid_shorteventid.insert(event_id, short);
+		}
+
+		for (short, event_id) in &missing_forward {
+			self.db.shorteventid_eventid.insert(short, event_id);
+		}
+
+		report.fixed(missing_reverse.len().saturating_add(missing_forward.len()));
+	}
+
+	Ok(report)
+}
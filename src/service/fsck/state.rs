@@ -0,0 +1,60 @@
+use std::pin::pin;
+
+use conduwuit::{Result, implement, utils};
+use futures::TryStreamExt;
+
+use super::Report;
+
+/// The parent of every `shortstatehash_statediff` row exists, as does the
+/// current state of every room. These can't be fixed since the state they
+/// refer to is lost; the affected rooms must be rejoined or purged.
+#[implement(super::Service)]
+pub async fn check_state_diffs(&self) -> Result<Report> {
+	const PARENT: usize = size_of::<u64>();
+
+	let mut report = Report::new("state_diffs");
+	let mut rows = pin!(self.db.shortstatehash_statediff.raw_stream());
+	while let Some((shortstatehash, diff)) = rows.try_next().await? {
+		report.checked = report.checked.saturating_add(1);
+		let Some(parent) = diff
+			.get(..PARENT)
+			.and_then(|parent| utils::u64_from_bytes(parent).ok())
+		else {
+			report.problem(|| format!("state diff {shortstatehash:?} is truncated"));
+			continue;
+		};
+
+		if parent != 0
+			&& self
+				.db
+				.shortstatehash_statediff
+				.exists(&parent.to_be_bytes())
+				.await
+				.is_err()
+		{
+			report
+				.problem(|| format!("state diff {shortstatehash:?} has missing parent {parent}"));
+		}
+	}
+
+	let mut rows = pin!(self.db.roomid_shortstatehash.raw_stream());
+	while let Some((room_id, shortstatehash)) = rows.try_next().await? {
+		report.checked = report.checked.saturating_add(1);
+		if self
+			.db
+			.shortstatehash_statediff
+			.exists(shortstatehash)
+			.await
+			.is_err()
+		{
+			report.problem(|| {
+				format!(
+					"current state {shortstatehash:?} of {} is missing",
+					String::from_utf8_lossy(room_id)
+				)
+			});
+		}
+	}
+
+	Ok(report)
+}
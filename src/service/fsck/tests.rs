@@ -0,0 +1,62 @@
+#![cfg(test)]
+
+use std::collections::HashSet;
+
+use ruma::{OwnedUserId, user_id};
+
+use super::{MAX_PROBLEMS, Report, membership::compare_members};
+
+#[test]
+fn report_caps_problems() {
+	let mut report = Report::new("test");
+	let found = MAX_PROBLEMS.saturating_add(3);
+	for i in 0..found {
+		report.problem(|| format!("problem {i}"));
+	}
+
+	report.fixed(2);
+	assert_eq!(report.found, found);
+	assert_eq!(report.fixed, 2);
+	assert_eq!(report.problems.len(), MAX_PROBLEMS);
+
+	let out = report.to_string();
+	assert!(out.starts_with(&format!("test: checked 0, found {found} problems, fixed 2\n")));
+	assert!(out.ends_with("- ... and 3 more\n"));
+}
+
+#[test]
+fn report_without_problems() {
+	let mut report = Report::new("test");
+	report.checked = 5;
+	assert_eq!(report.to_string(), "test: checked 5, found 0 problems, fixed 0\n");
+}
+
+#[test]
+fn compare_members_agrees() {
+	let users: HashSet<OwnedUserId> = [
+		user_id!("@alice:example.com").to_owned(),
+		user_id!("@bob:example.com").to_owned(),
+	]
+	.into();
+
+	let (missing, not_joined) = compare_members(&users, &users);
+	assert!(missing.is_empty());
+	assert!(not_joined.is_empty());
+}
+
+#[test]
+fn compare_members_disagrees() {
+	let alice = user_id!("@alice:example.com").to_owned();
+	let bob = user_id!("@bob:example.com").to_owned();
+	let carol = user_id!("@carol:example.com").to_owned();
+	let dave = user_id!("@dave:example.com").to_owned();
+
+	// bob is joined in the state but not indexed; carol and dave are indexed as
+	// members but e.g. invited or knocking in the state
+	let joined: HashSet<_> = [alice.clone(), bob.clone()].into();
+	let members: HashSet<_> = [alice, dave.clone(), carol.clone()].into();
+
+	let (missing, not_joined) = compare_members(&joined, &members);
+	assert_eq!(missing, [&bob]);
+	assert_eq!(not_joined, [&carol, &dave]);
+}
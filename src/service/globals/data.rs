@@ -1,6 +1,6 @@
 use std::sync::{Arc, RwLock};
 
-use conduwuit::{Result, utils};
+use conduwuit::{Err, Result, utils};
 use database::{Database, Deserialized, Map};
 
 pub struct Data {
@@ -24,6 +24,10 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 	}
 
 	pub fn next_count(&self) -> Result<u64> {
+		if self.db.is_secondary() {
+			return Err!(Database("The counter cannot be advanced on a read replica."));
+		}
+
 		let _cork = self.db.cork();
 		let mut lock = self.counter.write().expect("locked");
 		let counter: &mut u64 = &mut lock;
@@ -46,13 +50,22 @@ pub fn current_count(&self) -> u64 {
 		let lock = self.counter.read().expect("locked");
 		let counter: &u64 = &lock;
 		debug_assert!(
-			*counter == Self::stored_count(&self.global).expect("database failure"),
+			self.db.is_secondary()
+				|| *counter == Self::stored_count(&self.global).expect("database failure"),
 			"counter mismatch"
 		);
 
 		*counter
 	}
 
+	/// Load the counter again after a read replica has caught up with the
+	/// primary.
+	pub fn reload_count(&self) -> Result {
+		*self.counter.write().expect("locked") = Self::stored_count(&self.global)?;
+
+		Ok(())
+	}
+
 	fn stored_count(global: &Arc<Map>) -> Result<u64> {
 		global
 			.get_blocking(COUNTER)
@@ -74,8 +87,8 @@ pub fn bump_database_version(&self, new_version: u64) {
 	}
 
 	#[inline]
-	pub fn backup(&self) -> Result { self.db.db.backup() }
+	pub fn backup(&self) -> Result { self.db.engine()?.backup() }
 
 	#[inline]
-	pub fn backup_list(&self) -> Result<String> { self.db.db.backup_list() }
+	pub fn backup_list(&self) -> Result<String> { self.db.engine()?.backup_list() }
 }
@@ -0,0 +1,330 @@
+mod run;
+
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fmt,
+	str::FromStr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant, SystemTime},
+};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, Error, Result, Server, config::JobsConfig, debug, error, implement, info,
+	utils::time::Schedule,
+};
+use database::Database;
+use futures::{FutureExt, StreamExt, stream::FuturesUnordered};
+use tokio::{sync::Notify, time::sleep};
+
+use crate::{Dep, backup, media, users};
+
+/// Maintenance jobs run on the schedules of the `[global.jobs]` section.
+pub struct Service {
+	interrupt: Notify,
+	changed: Notify,
+	jobs: BTreeMap<Job, Entry>,
+	history: usize,
+	db: Arc<Database>,
+	services: Services,
+}
+
+struct Services {
+	backup: Dep<backup::Service>,
+	media: Dep<media::Service>,
+	users: Dep<users::Service>,
+	server: Arc<Server>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Job {
+	MediaRetention,
+	RemoteMediaEviction,
+	DatabaseBackup,
+	Compaction,
+	ExpiredTokens,
+	IpRetention,
+	DevicePruning,
+}
+
+/// A run of a job.
+#[derive(Clone, Debug)]
+pub struct Run {
+	pub started: SystemTime,
+	pub elapsed: Duration,
+	pub manual: bool,
+
+	/// Summary of the run, or the error it failed with.
+	pub result: Result<String, String>,
+}
+
+/// State of a job, most recent run first.
+#[derive(Debug)]
+pub struct Status {
+	pub job: Job,
+	pub schedule: Option<Schedule>,
+	pub paused: bool,
+	pub running: bool,
+	pub next: Option<SystemTime>,
+	pub history: Vec<Run>,
+}
+
+struct Entry {
+	schedule: Option<Schedule>,
+	state: Mutex<State>,
+	running: tokio::sync::Mutex<()>,
+}
+
+#[derive(Default)]
+struct State {
+	paused: bool,
+	next: Option<SystemTime>,
+	history: VecDeque<Run>,
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config.jobs;
+		for column in &config.compaction_columns {
+			if args.db.get(column).is_err() {
+				return Err!(Config(
+					"compaction_columns",
+					"No database column named {column:?}."
+				));
+			}
+		}
+
+		let jobs = Job::ALL
+			.into_iter()
+			.map(|job| {
+				let entry = Entry {
+					schedule: job.schedule(config).cloned(),
+					state: Mutex::default(),
+					running: tokio::sync::Mutex::new(()),
+				};
+
+				(job, entry)
+			})
+			.collect();
+
+		Ok(Arc::new(Self {
+			interrupt: Notify::new(),
+			changed: Notify::new(),
+			jobs,
+			history: config.history,
+			db: args.db.clone(),
+			services: Services {
+				backup: args.depend::<backup::Service>("backup"),
+				media: args.depend::<media::Service>("media"),
+				users: args.depend::<users::Service>("users"),
+				server: args.server.clone(),
+			},
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "jobs", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.db.is_read_only() {
+			debug!("Scheduled jobs are disabled on a read-only database");
+			return Ok(());
+		}
+
+		let now = SystemTime::now();
+		for entry in self.jobs.values() {
+			entry.state.lock().expect("locked").next = entry.next_after(now);
+		}
+
+		// Runs are polled alongside the schedule, so a long job neither delays the
+		// others nor shutdown; runs still in progress are cancelled on interrupt.
+		let mut runs = FuturesUnordered::new();
+		loop {
+			let due = self.next_due();
+			let wait = due.map_or(Duration::MAX, |(_, at)| {
+				at.duration_since(SystemTime::now()).unwrap_or_default()
+			});
+
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				() = self.changed.notified() => continue,
+				Some((job, result)) = runs.next() => {
+					if let Err(e) = result {
+						info!("Skipped scheduled run of job {job}: {e}");
+					}
+
+					continue;
+				},
+				() = sleep(wait), if due.is_some() => (),
+			}
+
+			let Some((job, at)) = due else {
+				continue;
+			};
+
+			self.jobs[&job].state.lock().expect("locked").next = self.jobs[&job].next_after(at);
+			runs.push(self.execute(job, false).map(move |result| (job, result)));
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_one(); }
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Run a job now, waiting for it to complete. Fails if it is already running.
+#[implement(Service)]
+pub async fn trigger(&self, job: Job) -> Result<Run> { self.execute(job, true).await }
+
+/// Stop running a job on its schedule until it is resumed or the server
+/// restarts.
+#[implement(Service)]
+pub fn pause(&self, job: Job) -> Result {
+	let mut state = self.jobs[&job].state.lock().expect("locked");
+	if state.paused {
+		return Err!("Job {job} is already paused.");
+	}
+
+	state.paused = true;
+	self.changed.notify_one();
+	Ok(())
+}
+
+/// Run a paused job on its schedule again, starting from its next scheduled
+/// time from now.
+#[implement(Service)]
+pub fn resume(&self, job: Job) -> Result {
+	let entry = &self.jobs[&job];
+	let mut state = entry.state.lock().expect("locked");
+	if !state.paused {
+		return Err!("Job {job} is not paused.");
+	}
+
+	state.paused = false;
+	state.next = entry.next_after(SystemTime::now());
+	self.changed.notify_one();
+	Ok(())
+}
+
+/// State of every job.
+#[implement(Service)]
+#[must_use]
+pub fn status(&self) -> Vec<Status> {
+	self.jobs
+		.iter()
+		.map(|(&job, entry)| {
+			let state = entry.state.lock().expect("locked");
+			Status {
+				job,
+				schedule: entry.schedule.clone(),
+				paused: state.paused,
+				running: entry.running.try_lock().is_err(),
+				next: state.next.filter(|_| !state.paused),
+				history: state.history.iter().cloned().collect(),
+			}
+		})
+		.collect()
+}
+
+#[implement(Service)]
+async fn execute(&self, job: Job, manual: bool) -> Result<Run> {
+	let entry = &self.jobs[&job];
+	let Ok(_running) = entry.running.try_lock() else {
+		return Err!("Job {job} is already running.");
+	};
+
+	let started = SystemTime::now();
+	let timer = Instant::now();
+	let result = self.perform(job).await.map_err(|e| e.to_string());
+	let run = Run {
+		started,
+		elapsed: timer.elapsed(),
+		manual,
+		result,
+	};
+
+	match &run.result {
+		| Ok(summary) => info!("Job {job} completed in {:?}: {summary}", run.elapsed),
+		| Err(e) => error!("Job {job} failed after {:?}: {e}", run.elapsed),
+	}
+
+	let mut state = entry.state.lock().expect("locked");
+	state.history.push_front(run.clone());
+	state.history.truncate(self.history);
+
+	Ok(run)
+}
+
+/// The unpaused job due first and when it is due.
+#[implement(Service)]
+fn next_due(&self) -> Option<(Job, SystemTime)> {
+	self.jobs
+		.iter()
+		.filter_map(|(&job, entry)| {
+			let state = entry.state.lock().expect("locked");
+			let next = state.next.filter(|_| !state.paused)?;
+			Some((job, next))
+		})
+		.min_by_key(|&(_, next)| next)
+}
+
+impl Entry {
+	fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+		self.schedule.as_ref()?.next_after(time)
+	}
+}
+
+impl Job {
+	pub const ALL: [Self; 7] = [
+		Self::MediaRetention,
+		Self::RemoteMediaEviction,
+		Self::DatabaseBackup,
+		Self::Compaction,
+		Self::ExpiredTokens,
+		Self::IpRetention,
+		Self::DevicePruning,
+	];
+
+	/// Name of the job, which is also the name of its schedule in the config.
+	#[must_use]
+	pub fn name(self) -> &'static str {
+		match self {
+			| Self::MediaRetention => "media_retention",
+			| Self::RemoteMediaEviction => "remote_media_eviction",
+			| Self::DatabaseBackup => "database_backup",
+			| Self::Compaction => "compaction",
+			| Self::ExpiredTokens => "expired_tokens",
+			| Self::IpRetention => "ip_retention",
+			| Self::DevicePruning => "device_pruning",
+		}
+	}
+
+	fn schedule(self, config: &JobsConfig) -> Option<&Schedule> {
+		match self {
+			| Self::MediaRetention => config.media_retention.as_ref(),
+			| Self::RemoteMediaEviction => config.remote_media_eviction.as_ref(),
+			| Self::DatabaseBackup => config.database_backup.as_ref(),
+			| Self::Compaction => config.compaction.as_ref(),
+			| Self::ExpiredTokens => config.expired_tokens.as_ref(),
+			| Self::IpRetention => config.ip_retention.as_ref(),
+			| Self::DevicePruning => config.device_pruning.as_ref(),
+		}
+	}
+}
+
+impl FromStr for Job {
+	type Err = Error;
+
+	fn from_str(name: &str) -> Result<Self> {
+		let name = name.replace('-', "_");
+		match Self::ALL.into_iter().find(|job| job.name() == name) {
+			| Some(job) => Ok(job),
+			| None => Err!("No job named {name:?}."),
+		}
+	}
+}
+
+impl fmt::Display for Job {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.name()) }
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use conduwuit::{Error, Result, implement, utils::time::timepoint_ago};
+use database::compact::Options;
+
+use super::Job;
+use crate::{media::NO_ELIGIBLE_MXCS, users::DevicePolicy};
+
+#[implement(super::Service)]
+pub(super) async fn perform(&self, job: Job) -> Result<String> {
+	let config = &self.services.server.config.jobs;
+	match job {
+		| Job::MediaRetention =>
+			self.delete_media(config.media_retention_max_age, true)
+				.await,
+		| Job::RemoteMediaEviction => self.delete_media(config.remote_media_max_age, false).await,
+		| Job::DatabaseBackup => self.services.backup.backup().await,
+		| Job::Compaction => self.compact(config.compaction_columns.clone()).await,
+		| Job::ExpiredTokens => {
+			let removed = self.services.users.remove_expired_tokens().await?;
+			Ok(format!("Removed {removed} expired tokens."))
+		},
+		| Job::IpRetention => self.expire_connections().await,
+		| Job::DevicePruning =>
+			self.prune_devices(&DevicePolicy::from_config(config)?)
+				.await,
+	}
+}
+
+/// Delete media older than `max_age` seconds; remote media only unless
+/// `local` is set.
+#[implement(super::Service)]
+async fn delete_media(&self, max_age: u64, local: bool) -> Result<String> {
+	let time = timepoint_ago(Duration::from_secs(max_age))?;
+	match self
+		.services
+		.media
+		.delete_all_remote_media_at_after_time(time, false, true, local)
+		.await
+	{
+		| Ok(deleted) => Ok(format!("Deleted {deleted} media files.")),
+		| Err(Error::Database(message)) if message == NO_ELIGIBLE_MXCS =>
+			Ok("No media old enough to delete.".to_owned()),
+		| Err(e) => Err(e),
+	}
+}
+
+/// Compact the given columns one after another; all columns if none are
+/// given.
+#[implement(super::Service)]
+async fn compact(&self, columns: Vec<String>) -> Result<String> {
+	let db = self.db.clone();
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || -> Result<String> {
+			let maps: Vec<_> = if columns.is_empty() {
+				db.iter().map(|(_, map)| map.clone()).collect()
+			} else {
+				columns
+					.iter()
+					.map(|column| db.get(column).cloned())
+					.collect::<Result<Vec<_>>>()?
+			};
+
+			for map in &maps {
+				map.compact_blocking(Options::default())?;
+			}
+
+			Ok(format!("Compacted {} columns.", maps.len()))
+		})
+		.await?
+}
+
+/// Remove the addresses and user agents devices were seen with longer than
+/// "device_ip_retention" ago.
+#[implement(super::Service)]
+async fn expire_connections(&self) -> Result<String> {
+	let retention = self.services.server.config.device_ip_retention;
+	if retention == 0 {
+		return Ok("Device addresses are kept.".to_owned());
+	}
+
+	let cutoff = DevicePolicy::cutoff(Duration::from_secs(retention))?;
+	let removed = self.services.users.expire_connections(cutoff).await?;
+
+	Ok(format!("Removed {removed} device connections."))
+}
+
+/// Remove the devices of local users which are stale under the policy.
+#[implement(super::Service)]
+async fn prune_devices(&self, policy: &DevicePolicy) -> Result<String> {
+	let removed = self.services.users.prune_all_devices(policy).await?;
+
+	Ok(format!("Removed {removed} stale devices."))
+}
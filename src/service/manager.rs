@@ -15,6 +15,7 @@ pub(crate) struct Manager {
 	workers: Mutex<Workers>,
 	server: Arc<Server>,
 	service: Arc<service::Map>,
+	secondary: bool,
 }
 
 type Workers = JoinSet<WorkerResult>;
@@ -30,6 +31,7 @@ pub(super) fn new(services: &Services) -> Arc<Self> {
 			workers: Mutex::new(JoinSet::new()),
 			server: services.server.clone(),
 			service: services.service.clone(),
+			secondary: services.db.is_secondary(),
 		})
 	}
 
@@ -161,6 +163,11 @@ async fn start_worker(
 			);
 		}
 
+		if self.secondary && !service.on_replica() {
+			debug!("Service {:?} worker not starting on a read replica.", service.name());
+			return Ok(());
+		}
+
 		debug!("Service {:?} worker starting...", service.name());
 		workers.spawn_on(worker(service.clone()), self.server.runtime());
 
@@ -53,6 +53,9 @@ struct Services {
 /// Default cross-origin resource policy.
 pub const CORP_CROSS_ORIGIN: &str = "cross-origin";
 
+/// Error message when there is no media to delete in the given time range.
+pub const NO_ELIGIBLE_MXCS: &str = "Did not found any eligible MXCs to delete.";
+
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
@@ -308,7 +311,7 @@ pub async fn delete_all_remote_media_at_after_time(
 		}
 
 		if remote_mxcs.is_empty() {
-			return Err!(Database("Did not found any eligible MXCs to delete."));
+			return Err!(Database("{NO_ELIGIBLE_MXCS}"));
 		}
 
 		debug_info!("Deleting media now in the past {time:?}");
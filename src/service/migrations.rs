@@ -30,6 +30,19 @@
 pub(crate) const DATABASE_VERSION: u64 = 17;
 
 pub(crate) async fn migrations(services: &Services) -> Result<()> {
+	// A read replica cannot write to the database; the primary migrates it.
+	if services.db.is_secondary() {
+		let version = services.globals.db.database_version().await;
+		if version != DATABASE_VERSION {
+			return Err!(Database(
+				"Database schema version {version} does not match {DATABASE_VERSION}; the \
+				 primary must be started with this version first.",
+			));
+		}
+
+		return Ok(());
+	}
+
 	let users_count = services.users.count().await;
 
 	// Matrix resource ownership is based on the server name; changing it
@@ -371,7 +384,7 @@ async fn fix_bad_double_separator_in_state_cache(services: &Services) -> Result<
 		})
 		.await;
 
-	db.db.sort()?;
+	db.sort()?;
 	db["global"].insert(b"fix_bad_double_separator_in_state_cache", []);
 
 	info!("Finished fixing");
@@ -440,7 +453,7 @@ async fn retroactively_fix_bad_data_from_roomuserid_joined(services: &Services)
 
 		for user_id in &non_joined_members {
 			debug_info!("User is left or banned, marking as left");
-			services.rooms.state_cache.mark_as_left(user_id, room_id);
+			services.rooms.state_cache.mark_as_left(user_id, room_id)?;
 		}
 	}
 
@@ -457,7 +470,7 @@ async fn retroactively_fix_bad_data_from_roomuserid_joined(services: &Services)
 			.await;
 	}
 
-	db.db.sort()?;
+	db.sort()?;
 	db["global"].insert(b"retroactively_fix_bad_data_from_roomuserid_joined", []);
 
 	info!("Finished fixing");
@@ -503,7 +516,7 @@ async fn fix_referencedevents_missing_sep(services: &Services) -> Result {
 	info!(?total, ?fixed, "Fixed missing record separators in 'referencedevents'.");
 
 	db["global"].insert(b"fix_referencedevents_missing_sep", []);
-	db.db.sort()
+	db.sort()
 }
 
 async fn fix_readreceiptid_readreceipt_duplicates(services: &Services) -> Result {
@@ -555,5 +568,5 @@ async fn fix_readreceiptid_readreceipt_duplicates(services: &Services) -> Result
 	info!(?total, ?fixed, "Fixed undeleted entries in readreceiptid_readreceipt.");
 
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
-	db.db.sort()
+	db.sort()
 }
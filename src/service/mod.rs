@@ -9,19 +9,28 @@
 pub mod account_data;
 pub mod admin;
 pub mod appservice;
+pub mod audit;
+pub mod backup;
 pub mod client;
 pub mod config;
 pub mod emergency;
 pub mod federation;
+pub mod fsck;
 pub mod globals;
+pub mod jobs;
 pub mod key_backups;
 pub mod media;
+pub mod policy;
 pub mod presence;
 pub mod pusher;
+pub mod registration_tokens;
+pub mod rendezvous;
+pub mod replica;
 pub mod resolver;
 pub mod rooms;
 pub mod sending;
 pub mod server_keys;
+pub mod spam_checker;
 pub mod sync;
 pub mod transaction_ids;
 pub mod uiaa;
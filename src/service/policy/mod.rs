@@ -0,0 +1,138 @@
+mod redact;
+mod rules;
+mod tests;
+
+use std::{
+	fmt::Write,
+	sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use conduwuit::{Result, Server, debug_warn, implement, info, utils::stream::TryIgnore};
+use database::Map;
+use futures::{Stream, StreamExt};
+use loole::{Receiver, Sender};
+use ruma::{OwnedEventId, OwnedRoomId, RoomId};
+
+use self::rules::Rules;
+pub use self::rules::{Kind, Rule};
+use crate::{Dep, globals, rooms};
+
+/// Enforcement of moderation policy lists (MSC2313). Rules with the `m.ban`
+/// recommendation are read from the `m.policy.rule.*` state of the subscribed
+/// policy rooms and kept in memory.
+pub struct Service {
+	rules: RwLock<Rules>,
+	redactions: (Sender<Redaction>, Receiver<Redaction>),
+	db: Data,
+	services: Services,
+}
+
+struct Data {
+	policyroomids: Arc<Map>,
+}
+
+struct Services {
+	server: Arc<Server>,
+	globals: Dep<globals::Service>,
+	state: Dep<rooms::state::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+}
+
+type Redaction = (OwnedRoomId, OwnedEventId);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			rules: RwLock::new(Rules::default()),
+			redactions: loole::unbounded(),
+			db: Data {
+				policyroomids: args.db["policyroomids"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				globals: args.depend::<globals::Service>("globals"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+			},
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let rooms: Vec<OwnedRoomId> = self.subscriptions().map(ToOwned::to_owned).collect().await;
+		for room_id in &rooms {
+			self.load_rules(room_id).await;
+		}
+
+		if !rooms.is_empty() {
+			info!("Loaded {} policy rules from {} policy rooms", self.rule_count(), rooms.len());
+		}
+
+		let receiver = self.redactions.1.clone();
+		while let Ok((room_id, event_id)) = receiver.recv_async().await {
+			if let Err(e) = self.redact(&room_id, &event_id).await {
+				debug_warn!(%room_id, %event_id, "Failed to redact event from banned user: {e}");
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) {
+		let (sender, _) = &self.redactions;
+		if !sender.is_closed() {
+			sender.close();
+		}
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let rules = self.rule_count();
+		writeln!(out, "policy_rules: {rules}")?;
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Subscribe to a policy room and load its rules. Our server must already be
+/// joined to the room. Returns the number of rules loaded from it.
+#[implement(Service)]
+pub async fn subscribe(&self, room_id: &RoomId) -> usize {
+	self.db.policyroomids.insert(room_id, []);
+	self.load_rules(room_id).await
+}
+
+/// Unsubscribe from a policy room; its rules stop being enforced. Returns
+/// false if the room was not subscribed.
+#[implement(Service)]
+pub async fn unsubscribe(&self, room_id: &RoomId) -> bool {
+	if !self.is_subscribed(room_id).await {
+		return false;
+	}
+
+	self.db.policyroomids.remove(room_id);
+	self.rules
+		.write()
+		.expect("locked for writing")
+		.remove_room(room_id);
+
+	true
+}
+
+#[implement(Service)]
+pub fn subscriptions(&self) -> impl Stream<Item = &RoomId> + Send + '_ {
+	self.db.policyroomids.keys().ignore_err()
+}
+
+#[implement(Service)]
+#[inline]
+pub async fn is_subscribed(&self, room_id: &RoomId) -> bool {
+	self.db.policyroomids.get(room_id).await.is_ok()
+}
@@ -0,0 +1,78 @@
+use conduwuit::{PduEvent, Result, debug, implement, matrix::PduBuilder};
+use ruma::{
+	EventId, RoomId,
+	events::{TimelineEventType, room::redaction::RoomRedactionEventContent},
+};
+
+/// Queue a timeline event for redaction when `policy_list_auto_redact` is
+/// enabled and its sender is banned by a policy room. State events are left
+/// alone.
+#[implement(super::Service)]
+pub fn redact_if_banned(&self, pdu: &PduEvent) {
+	if !self.services.server.config.policy_list_auto_redact
+		|| pdu.state_key.is_some()
+		|| pdu.kind == TimelineEventType::RoomRedaction
+		|| pdu.sender == self.services.globals.server_user
+	{
+		return;
+	}
+
+	if !self.is_user_banned(&pdu.sender) {
+		return;
+	}
+
+	let (sender, _) = &self.redactions;
+	sender
+		.send((pdu.room_id.clone(), pdu.event_id.clone()))
+		.ok();
+}
+
+/// Redact the event as the server user, provided it is joined to the room
+/// with enough power to do so.
+#[implement(super::Service)]
+pub(super) async fn redact(&self, room_id: &RoomId, event_id: &EventId) -> Result {
+	let server_user = &self.services.globals.server_user;
+	if !self
+		.services
+		.state_cache
+		.is_joined(server_user, room_id)
+		.await
+	{
+		debug!("Not redacting {event_id}; the server user is not in {room_id}");
+		return Ok(());
+	}
+
+	if !self
+		.services
+		.state_accessor
+		.user_can_redact(event_id, server_user, room_id, false)
+		.await?
+	{
+		debug!("Not redacting {event_id}; the server user lacks power in {room_id}");
+		return Ok(());
+	}
+
+	let reason = format!(
+		"The sender is banned by a policy list followed by {}.",
+		self.services.globals.server_name()
+	);
+
+	let state_lock = self.services.state.mutex.lock(room_id).await;
+	self.services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder {
+				redacts: Some(event_id.to_owned()),
+				..PduBuilder::timeline(&RoomRedactionEventContent {
+					redacts: Some(event_id.to_owned()),
+					reason: Some(reason),
+				})
+			},
+			server_user,
+			room_id,
+			&state_lock,
+		)
+		.await?;
+
+	Ok(())
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, HashSet};
+
+use conduwuit::{
+	PduEvent, implement,
+	matrix::StateKey,
+	utils::stream::{ReadyExt, TryIgnore},
+	warn,
+};
+use futures::StreamExt;
+use regex::Regex;
+use ruma::{
+	OwnedEventId, OwnedRoomId, RoomId, ServerName, UserId,
+	events::{
+		TimelineEventType,
+		policy::rule::{PolicyRuleEventContent, Recommendation},
+	},
+};
+
+/// The kind of entity a rule applies to.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Kind {
+	User,
+	Room,
+	Server,
+}
+
+/// A ban rule from a policy room.
+#[derive(Clone, Debug)]
+pub struct Rule {
+	pub kind: Kind,
+
+	/// The entity, possibly containing `*` and `?` glob wildcards.
+	pub entity: String,
+
+	pub reason: String,
+
+	/// The policy room the rule was published in.
+	pub room_id: OwnedRoomId,
+
+	pub event_id: OwnedEventId,
+
+	/// The entity compiled when the rule was loaded, if it has wildcards.
+	glob: Option<Regex>,
+}
+
+/// Rules from every subscribed policy room, keyed by their state key, along
+/// with an index for matching entities.
+#[derive(Default)]
+pub(super) struct Rules {
+	rules: BTreeMap<RuleKey, Rule>,
+	users: Matcher,
+	rooms: Matcher,
+	servers: Matcher,
+}
+
+type RuleKey = (OwnedRoomId, Kind, StateKey);
+
+#[derive(Default)]
+pub(super) struct Matcher {
+	exact: HashSet<String>,
+	globs: Vec<Regex>,
+}
+
+/// Read the current rules of a subscribed policy room, replacing any rules
+/// previously loaded from it. Returns the number of rules in the room.
+#[implement(super::Service)]
+pub(super) async fn load_rules(&self, room_id: &RoomId) -> usize {
+	let pdus: Vec<PduEvent> = self
+		.services
+		.state_accessor
+		.room_state_full_pdus(room_id)
+		.ignore_err()
+		.ready_filter(|pdu| Kind::from_event_type(&pdu.kind).is_some())
+		.collect()
+		.await;
+
+	let mut rules = self.rules.write().expect("locked for writing");
+	rules
+		.rules
+		.retain(|(rule_room_id, ..), _| rule_room_id != room_id);
+	for pdu in &pdus {
+		rules.insert(pdu);
+	}
+
+	rules.reindex();
+	rules.count_in(room_id)
+}
+
+/// Update the rules with a policy rule state event appended to the timeline.
+/// Events in rooms which are not subscribed are ignored.
+#[implement(super::Service)]
+pub async fn update_rule(&self, pdu: &PduEvent) {
+	if pdu.state_key.is_none() || Kind::from_event_type(&pdu.kind).is_none() {
+		return;
+	}
+
+	if !self.is_subscribed(&pdu.room_id).await {
+		return;
+	}
+
+	let mut rules = self.rules.write().expect("locked for writing");
+	rules.insert(pdu);
+	rules.reindex();
+}
+
+/// True if the user or their server is banned by a policy room.
+#[implement(super::Service)]
+#[must_use]
+pub fn is_user_banned(&self, user_id: &UserId) -> bool {
+	let rules = self.rules.read().expect("locked for reading");
+	rules.users.is_match(user_id.as_str()) || rules.servers.is_match(user_id.server_name().host())
+}
+
+/// True if the room is banned by a policy room.
+#[implement(super::Service)]
+#[must_use]
+pub fn is_room_banned(&self, room_id: &RoomId) -> bool {
+	self.rules
+		.read()
+		.expect("locked for reading")
+		.rooms
+		.is_match(room_id.as_str())
+}
+
+/// True if the server is banned by a policy room.
+#[implement(super::Service)]
+#[must_use]
+pub fn is_server_banned(&self, server_name: &ServerName) -> bool {
+	self.rules
+		.read()
+		.expect("locked for reading")
+		.servers
+		.is_match(server_name.host())
+}
+
+/// Every rule whose entity matches `entity`, for any kind.
+#[implement(super::Service)]
+pub fn matching_rules(&self, entity: &str) -> Vec<Rule> {
+	self.rules
+		.read()
+		.expect("locked for reading")
+		.rules
+		.values()
+		.filter(|rule| rule.matches(entity))
+		.cloned()
+		.collect()
+}
+
+/// Number of rules loaded from a policy room.
+#[implement(super::Service)]
+#[must_use]
+pub fn room_rule_count(&self, room_id: &RoomId) -> usize {
+	self.rules
+		.read()
+		.expect("locked for reading")
+		.count_in(room_id)
+}
+
+#[implement(super::Service)]
+pub(super) fn rule_count(&self) -> usize {
+	self.rules.read().expect("locked for reading").rules.len()
+}
+
+impl Rules {
+	/// Insert the rule from a policy rule state event. Events without a ban
+	/// recommendation, including those with the empty content of a removed or
+	/// redacted rule, remove the rule previously at that state key.
+	fn insert(&mut self, pdu: &PduEvent) {
+		let (Some(kind), Some(state_key)) = (Kind::from_event_type(&pdu.kind), &pdu.state_key)
+		else {
+			return;
+		};
+
+		let key = (pdu.room_id.clone(), kind, state_key.clone());
+		match pdu.get_content::<PolicyRuleEventContent>() {
+			| Ok(content) if matches!(content.recommendation, Recommendation::Ban) => {
+				self.rules.insert(key, Rule {
+					kind,
+					glob: compile_glob(&content.entity),
+					entity: content.entity,
+					reason: content.reason,
+					room_id: pdu.room_id.clone(),
+					event_id: pdu.event_id.clone(),
+				});
+			},
+			| _ => {
+				self.rules.remove(&key);
+			},
+		}
+	}
+
+	pub(super) fn remove_room(&mut self, room_id: &RoomId) {
+		self.rules
+			.retain(|(rule_room_id, ..), _| rule_room_id != room_id);
+		self.reindex();
+	}
+
+	fn count_in(&self, room_id: &RoomId) -> usize {
+		self.rules
+			.keys()
+			.filter(|(rule_room_id, ..)| rule_room_id == room_id)
+			.count()
+	}
+
+	fn reindex(&mut self) {
+		self.users = Matcher::new(self.entities(Kind::User));
+		self.rooms = Matcher::new(self.entities(Kind::Room));
+		self.servers = Matcher::new(self.entities(Kind::Server));
+	}
+
+	fn entities(&self, kind: Kind) -> impl Iterator<Item = &str> {
+		self.rules
+			.values()
+			.filter(move |rule| rule.kind == kind)
+			.map(|rule| rule.entity.as_str())
+	}
+}
+
+impl Rule {
+	/// True if the rule applies to `entity`.
+	#[must_use]
+	pub fn matches(&self, entity: &str) -> bool {
+		match &self.glob {
+			| Some(glob) => glob.is_match(entity),
+			| None => self.entity == entity,
+		}
+	}
+}
+
+impl Matcher {
+	pub(super) fn new<'a, I>(entities: I) -> Self
+	where
+		I: Iterator<Item = &'a str>,
+	{
+		let (globs, exact): (Vec<_>, HashSet<_>) = entities
+			.map(ToOwned::to_owned)
+			.partition(|entity| is_glob(entity));
+
+		// each glob is compiled on its own so that one which fails to compile
+		// does not disable the others
+		let globs = globs.iter().filter_map(|glob| compile_glob(glob)).collect();
+
+		Self { exact, globs }
+	}
+
+	pub(super) fn is_match(&self, entity: &str) -> bool {
+		self.exact.contains(entity) || self.globs.iter().any(|glob| glob.is_match(entity))
+	}
+}
+
+impl Kind {
+	fn from_event_type(event_type: &TimelineEventType) -> Option<Self> {
+		match event_type {
+			| TimelineEventType::PolicyRuleUser => Some(Self::User),
+			| TimelineEventType::PolicyRuleRoom => Some(Self::Room),
+			| TimelineEventType::PolicyRuleServer => Some(Self::Server),
+			| _ => None,
+		}
+	}
+}
+
+fn is_glob(entity: &str) -> bool { entity.contains(['*', '?']) }
+
+fn glob_to_regex(glob: &str) -> String {
+	let pattern = regex::escape(glob).replace("\\*", ".*").replace("\\?", ".");
+
+	format!("^{pattern}$")
+}
+
+pub(super) fn compile_glob(entity: &str) -> Option<Regex> {
+	if !is_glob(entity) {
+		return None;
+	}
+
+	Regex::new(&glob_to_regex(entity))
+		.inspect_err(|e| warn!("Failed to compile policy rule glob {entity:?}: {e}"))
+		.ok()
+}
@@ -0,0 +1,68 @@
+#![cfg(test)]
+
+use super::rules::{Matcher, compile_glob};
+
+fn matches(glob: &str, entity: &str) -> bool {
+	compile_glob(glob).expect("compiled").is_match(entity)
+}
+
+#[test]
+fn exact_entities_are_not_globs() {
+	assert!(compile_glob("@spam:example.com").is_none());
+	assert!(compile_glob("example.com").is_none());
+}
+
+#[test]
+fn glob_star() {
+	assert!(matches("*.example.com", "evil.example.com"));
+	assert!(matches("*.example.com", "a.b.example.com"));
+	assert!(!matches("*.example.com", "example.com"));
+	assert!(matches("@*:example.com", "@spam:example.com"));
+	assert!(matches("*", ""));
+}
+
+#[test]
+fn glob_question_mark() {
+	assert!(matches("@spam?:example.com", "@spam1:example.com"));
+	assert!(!matches("@spam?:example.com", "@spam:example.com"));
+	assert!(!matches("@spam?:example.com", "@spam12:example.com"));
+}
+
+#[test]
+fn glob_is_anchored() {
+	assert!(!matches("*.example.com", "evil.example.com.org"));
+	assert!(!matches("spam*", "nospam"));
+}
+
+#[test]
+fn glob_escapes_regex() {
+	assert!(!matches("*.example.com", "evil.exampleXcom"));
+	assert!(matches("[a-z]+*", "[a-z]+spam"));
+	assert!(!matches("[a-z]+*", "abc"));
+}
+
+#[test]
+fn matcher() {
+	let matcher = Matcher::new(["example.com", "*.evil.org", "spam?.net"].into_iter());
+
+	assert!(matcher.is_match("example.com"));
+	assert!(!matcher.is_match("sub.example.com"));
+	assert!(matcher.is_match("a.evil.org"));
+	assert!(!matcher.is_match("evil.org"));
+	assert!(matcher.is_match("spam1.net"));
+	assert!(!matcher.is_match("other.net"));
+
+	assert!(!Matcher::default().is_match("example.com"));
+	assert!(!Matcher::new(std::iter::empty()).is_match(""));
+}
+
+#[test]
+fn matcher_skips_globs_failing_to_compile() {
+	// larger than the regex size limit once compiled
+	let huge = "?".repeat(1_000_000);
+	assert!(compile_glob(&huge).is_none());
+
+	let matcher = Matcher::new([huge.as_str(), "*.evil.org"].into_iter());
+	assert!(matcher.is_match("a.evil.org"));
+	assert!(!matcher.is_match("example.com"));
+}
@@ -14,7 +14,7 @@
 use tokio::time::sleep;
 
 use self::{data::Data, presence::Presence};
-use crate::{Dep, globals, users};
+use crate::{Dep, globals, replica, users};
 
 pub struct Service {
 	timer_channel: (Sender<TimerType>, Receiver<TimerType>),
@@ -29,6 +29,7 @@ struct Services {
 	server: Arc<Server>,
 	db: Arc<Database>,
 	globals: Dep<globals::Service>,
+	replica: Dep<replica::Service>,
 	users: Dep<users::Service>,
 }
 
@@ -50,6 +51,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				server: args.server.clone(),
 				db: args.db.clone(),
 				globals: args.depend::<globals::Service>("globals"),
+				replica: args.depend::<replica::Service>("replica"),
 				users: args.depend::<users::Service>("users"),
 			},
 		}))
@@ -118,6 +120,14 @@ pub async fn ping_presence(&self, user_id: &UserId, new_state: &PresenceState) -
 			return Ok(());
 		}
 
+		if self.services.replica.is_replica() {
+			return self
+				.services
+				.replica
+				.ping_presence(user_id, new_state)
+				.await;
+		}
+
 		let status_msg = match last_presence {
 			| Ok((_, ref presence)) => presence.content.status_msg.clone(),
 			| Err(_) => Some(String::new()),
@@ -2,7 +2,7 @@
 
 use bytes::BytesMut;
 use conduwuit::{
-	Err, PduEvent, Result, debug_warn, err, trace,
+	Err, PduEvent, Result, Server, debug_warn, err, trace,
 	utils::{stream::TryIgnore, string_from_bytes},
 	warn,
 };
@@ -10,7 +10,7 @@
 use futures::{Stream, StreamExt};
 use ipaddress::IPAddress;
 use ruma::{
-	DeviceId, OwnedDeviceId, RoomId, UInt, UserId,
+	DeviceId, EventId, OwnedDeviceId, RoomId, UInt, UserId,
 	api::{
 		IncomingResponse, MatrixVersion, OutgoingRequest, SendAccessToken,
 		client::push::{Pusher, PusherKind, set_pusher},
@@ -38,10 +38,12 @@ pub struct Service {
 }
 
 struct Services {
+	server: Arc<Server>,
 	globals: Dep<globals::Service>,
 	client: Dep<client::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
+	threads: Dep<rooms::threads::Service>,
 	users: Dep<users::Service>,
 	sending: Dep<sending::Service>,
 }
@@ -59,11 +61,13 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				pushkey_deviceid: args.db["pushkey_deviceid"].clone(),
 			},
 			services: Services {
+				server: args.server.clone(),
 				globals: args.depend::<globals::Service>("globals"),
 				client: args.depend::<client::Service>("client"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				threads: args.depend::<rooms::threads::Service>("rooms::threads"),
 				users: args.depend::<users::Service>("users"),
 				sending: args.depend::<sending::Service>("sending"),
 			},
@@ -297,7 +301,14 @@ pub async fn send_push_notice(
 			.unwrap_or_default();
 
 		for action in self
-			.get_actions(user, &ruleset, &power_levels, &pdu.to_sync_room_event(), &pdu.room_id)
+			.get_actions(
+				user,
+				&ruleset,
+				&power_levels,
+				&pdu.to_sync_room_event(),
+				&pdu.room_id,
+				pdu.thread_root().as_deref(),
+			)
 			.await
 		{
 			let n = match action {
@@ -326,6 +337,9 @@ pub async fn send_push_notice(
 		Ok(())
 	}
 
+	/// Evaluates the push rules of the user for the event. With thread
+	/// subscriptions, events in threads the user is not subscribed to only
+	/// notify when they highlight (e.g. mentions).
 	#[tracing::instrument(skip(self, user, ruleset, pdu), level = "debug")]
 	pub async fn get_actions<'a>(
 		&self,
@@ -334,6 +348,7 @@ pub async fn get_actions<'a>(
 		power_levels: &RoomPowerLevelsEventContent,
 		pdu: &Raw<AnySyncTimelineEvent>,
 		room_id: &RoomId,
+		thread_root: Option<&EventId>,
 	) -> &'a [Action] {
 		let power_levels = PushConditionPowerLevelsCtx {
 			users: power_levels.users.clone(),
@@ -365,7 +380,26 @@ pub async fn get_actions<'a>(
 			power_levels: Some(power_levels),
 		};
 
-		ruleset.get_actions(pdu, &ctx)
+		let actions = ruleset.get_actions(pdu, &ctx);
+
+		let highlight = actions
+			.iter()
+			.any(|action| matches!(action, Action::SetTweak(Tweak::Highlight(true))));
+
+		if let Some(thread_root) = thread_root {
+			if !highlight
+				&& self.services.server.config.allow_thread_subscriptions
+				&& !self
+					.services
+					.threads
+					.is_subscribed(user, room_id, thread_root)
+					.await
+			{
+				return &[];
+			}
+		}
+
+		actions
 	}
 
 	#[tracing::instrument(skip(self, unread, pusher, tweaks, event))]
@@ -0,0 +1,349 @@
+mod tests;
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex as StdMutex},
+	time::{Duration, Instant},
+};
+
+use conduwuit::{
+	Err, Result, Server, err, implement, utils,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Ignore, Json, Map};
+use futures::{Stream, StreamExt};
+use ruma::{MilliSecondsSinceUnixEpoch, OwnedUserId, UserId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{Dep, globals};
+
+pub struct Service {
+	/// Serializes changes to the use counts of tokens.
+	update: Mutex<()>,
+	reservations: StdMutex<Reservations>,
+	db: Data,
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	globals: Dep<globals::Service>,
+}
+
+struct Data {
+	registrationtoken_info: Arc<Map>,
+	userid_registrationtoken: Arc<Map>,
+}
+
+/// A registration token (MSC3231) created by an admin, with its limits and
+/// how often it was used.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RegistrationToken {
+	pub token: String,
+
+	/// Number of registrations the token may be used for; unlimited when
+	/// None.
+	pub uses_allowed: Option<u64>,
+
+	/// Registrations in progress holding a use of the token, counted from the
+	/// UIAA sessions which reserved one.
+	pub pending: u64,
+
+	/// Registrations completed with the token.
+	pub completed: u64,
+
+	/// The token can no longer be used from this time; never when None.
+	pub expiry_time: Option<MilliSecondsSinceUnixEpoch>,
+}
+
+/// Uses of tokens reserved by registrations in progress, by UIAA session.
+#[derive(Debug, Default)]
+struct Reservations(HashMap<String, Reservation>);
+
+#[derive(Debug)]
+struct Reservation {
+	token: String,
+	expires: Instant,
+}
+
+/// Time a registration may hold a use of a token before it is released.
+const RESERVATION_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Length of generated tokens.
+pub const TOKEN_LENGTH: usize = 16;
+
+/// Maximum length of a token allowed by the specification.
+const TOKEN_MAX_LENGTH: usize = 64;
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			update: Mutex::new(()),
+			reservations: StdMutex::default(),
+			db: Data {
+				registrationtoken_info: args.db["registrationtoken_info"].clone(),
+				userid_registrationtoken: args.db["userid_registrationtoken"].clone(),
+			},
+			services: Services {
+				server: args.server.clone(),
+				globals: args.depend::<globals::Service>("globals"),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl RegistrationToken {
+	/// Whether the token can be used for another registration now.
+	#[must_use]
+	pub fn is_valid(&self) -> bool {
+		let expired = self
+			.expiry_time
+			.is_some_and(|expiry_time| expiry_time <= MilliSecondsSinceUnixEpoch::now());
+
+		let used_up = self.uses_allowed.is_some_and(|uses_allowed| {
+			self.pending.saturating_add(self.completed) >= uses_allowed
+		});
+
+		!expired && !used_up
+	}
+}
+
+impl Reservations {
+	/// Releases the uses held by registrations which did not complete in time.
+	fn prune(&mut self, now: Instant) {
+		self.0.retain(|_, reservation| reservation.expires > now);
+	}
+
+	/// Number of uses of the token held by registrations in progress.
+	fn pending(&self, token: &str) -> u64 {
+		self.0
+			.values()
+			.filter(|reservation| reservation.token == token)
+			.count()
+			.try_into()
+			.unwrap_or(u64::MAX)
+	}
+
+	fn holds(&self, session: &str, token: &str) -> bool {
+		self.0
+			.get(session)
+			.is_some_and(|reservation| reservation.token == token)
+	}
+
+	fn reserve(&mut self, session: &str, token: &str, expires: Instant) {
+		let reservation = Reservation { token: token.to_owned(), expires };
+		self.0.insert(session.to_owned(), reservation);
+	}
+
+	fn release(&mut self, session: &str) { self.0.remove(session); }
+}
+
+/// Whether registration requires a token, either from the database or the
+/// legacy `registration_token` config options.
+#[implement(Service)]
+#[must_use]
+pub fn required(&self) -> bool {
+	self.services.server.config.registration_requires_token
+		|| self.services.globals.registration_token.is_some()
+}
+
+/// Creates a token, generating one when none is given.
+#[implement(Service)]
+pub async fn create(
+	&self,
+	token: Option<&str>,
+	uses_allowed: Option<u64>,
+	expiry_time: Option<MilliSecondsSinceUnixEpoch>,
+) -> Result<RegistrationToken> {
+	let token = token.map_or_else(|| utils::random_string(TOKEN_LENGTH), ToOwned::to_owned);
+	if !is_valid_token(&token) {
+		return Err!(
+			"Registration token {token:?} must be at most {TOKEN_MAX_LENGTH} of the characters \
+			 A-Z, a-z, 0-9, '.', '_', '~' and '-'."
+		);
+	}
+
+	let _update = self.update.lock().await;
+	if self.get(&token).await.is_ok() {
+		return Err!("Registration token {token} already exists.");
+	}
+
+	let token = RegistrationToken {
+		token,
+		uses_allowed,
+		pending: 0,
+		completed: 0,
+		expiry_time,
+	};
+
+	self.put(&token);
+	Ok(token)
+}
+
+/// Changes the limits of a token. Limits which are None are left as they are.
+#[implement(Service)]
+pub async fn update(
+	&self,
+	token: &str,
+	uses_allowed: Option<Option<u64>>,
+	expiry_time: Option<Option<MilliSecondsSinceUnixEpoch>>,
+) -> Result<RegistrationToken> {
+	let _update = self.update.lock().await;
+	let mut token = self.get(token).await?;
+	if let Some(uses_allowed) = uses_allowed {
+		token.uses_allowed = uses_allowed;
+	}
+
+	if let Some(expiry_time) = expiry_time {
+		token.expiry_time = expiry_time;
+	}
+
+	self.put(&token);
+	Ok(token)
+}
+
+/// Removes a token so it can no longer be used. The users who registered with
+/// it are still recorded.
+#[implement(Service)]
+pub async fn revoke(&self, token: &str) -> Result {
+	let _update = self.update.lock().await;
+	self.get(token).await?;
+	self.db.registrationtoken_info.remove(token);
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn get(&self, token: &str) -> Result<RegistrationToken> {
+	self.db
+		.registrationtoken_info
+		.get(token)
+		.await
+		.deserialized()
+		.map(|token| self.with_pending(token))
+		.map_err(|_| err!(Request(NotFound("Registration token {token} not found."))))
+}
+
+/// Returns every token in the database.
+#[implement(Service)]
+pub fn tokens(&self) -> impl Stream<Item = RegistrationToken> + Send + '_ {
+	self.db
+		.registrationtoken_info
+		.stream()
+		.ignore_err()
+		.map(|(_, token): (Ignore, RegistrationToken)| self.with_pending(token))
+}
+
+/// Whether a token is valid for registration right now: a database token
+/// which is not expired or used up.
+#[implement(Service)]
+pub async fn is_valid(&self, token: &str) -> bool {
+	self.get(token)
+		.await
+		.as_ref()
+		.is_ok_and(RegistrationToken::is_valid)
+}
+
+/// Reserves a use of a database token for the registration of the UIAA
+/// session, counted as pending until the registration completes, fails or
+/// times out. A session holds at most one use, so retrying it does not use the
+/// token again. Returns false if the token is not a valid database token.
+#[implement(Service)]
+pub async fn begin(&self, token: &str, session: &str) -> bool {
+	let _update = self.update.lock().await;
+	let Ok(mut token) = self.get(token).await else {
+		return false;
+	};
+
+	let now = Instant::now();
+	let mut reservations = self.reservations.lock().expect("locked");
+	reservations.prune(now);
+	if reservations.holds(session, &token.token) {
+		return true;
+	}
+
+	reservations.release(session);
+	token.pending = reservations.pending(&token.token);
+	if !token.is_valid() {
+		return false;
+	}
+
+	let expires = now.checked_add(RESERVATION_TTL).unwrap_or(now);
+	reservations.reserve(session, &token.token, expires);
+
+	true
+}
+
+/// Releases the use of a token held by the registration of the UIAA session,
+/// when the registration failed.
+#[implement(Service)]
+pub fn release(&self, session: &str) {
+	self.reservations.lock().expect("locked").release(session);
+}
+
+/// Records that the user registered with the token. The use held by the
+/// registration's UIAA session counts as completed rather than pending.
+#[implement(Service)]
+pub async fn complete(&self, token: &str, session: &str, user_id: &UserId) {
+	{
+		let _update = self.update.lock().await;
+		self.release(session);
+		if let Ok(mut token) = self.get(token).await {
+			token.completed = token.completed.saturating_add(1);
+			self.put(&token);
+		}
+	}
+
+	self.db.userid_registrationtoken.insert(user_id, token);
+}
+
+/// Returns the token the user registered with, if they used one.
+#[implement(Service)]
+pub async fn user_token(&self, user_id: &UserId) -> Option<String> {
+	self.db
+		.userid_registrationtoken
+		.get(user_id)
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// Returns the users who registered with the token.
+#[implement(Service)]
+pub fn users(&self, token: &str) -> impl Stream<Item = OwnedUserId> + Send + '_ {
+	let token = token.to_owned();
+	self.db
+		.userid_registrationtoken
+		.stream()
+		.ignore_err()
+		.ready_filter_map(move |(user_id, user_token): (&UserId, &str)| {
+			(user_token == token).then(|| user_id.to_owned())
+		})
+}
+
+/// Counts the uses of the token held by registrations in progress.
+#[implement(Service)]
+fn with_pending(&self, mut token: RegistrationToken) -> RegistrationToken {
+	let mut reservations = self.reservations.lock().expect("locked");
+	reservations.prune(Instant::now());
+	token.pending = reservations.pending(&token.token);
+	token
+}
+
+#[implement(Service)]
+fn put(&self, token: &RegistrationToken) {
+	self.db
+		.registrationtoken_info
+		.raw_put(&token.token, Json(token));
+}
+
+fn is_valid_token(token: &str) -> bool {
+	!token.is_empty()
+		&& token.len() <= TOKEN_MAX_LENGTH
+		&& token
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '~' | '-'))
+}
@@ -0,0 +1,91 @@
+#![cfg(test)]
+
+use std::time::{Duration, Instant, SystemTime};
+
+use ruma::MilliSecondsSinceUnixEpoch;
+
+use super::{RESERVATION_TTL, RegistrationToken, Reservations, is_valid_token};
+
+fn token(uses_allowed: Option<u64>, expiry_time: Option<SystemTime>) -> RegistrationToken {
+	RegistrationToken {
+		token: "abc".to_owned(),
+		uses_allowed,
+		pending: 1,
+		completed: 1,
+		expiry_time: expiry_time.and_then(MilliSecondsSinceUnixEpoch::from_system_time),
+	}
+}
+
+#[test]
+fn token_uses() {
+	assert!(token(None, None).is_valid());
+	assert!(token(Some(3), None).is_valid());
+	assert!(!token(Some(2), None).is_valid());
+	assert!(!token(Some(0), None).is_valid());
+}
+
+#[test]
+fn token_expiry() {
+	let hour = Duration::from_secs(3600);
+	assert!(token(None, SystemTime::now().checked_add(hour)).is_valid());
+	assert!(!token(None, SystemTime::now().checked_sub(hour)).is_valid());
+}
+
+#[test]
+fn token_characters() {
+	assert!(is_valid_token("Ab0._~-"));
+	assert!(!is_valid_token(""));
+	assert!(!is_valid_token("with space"));
+	assert!(!is_valid_token(&"a".repeat(65)));
+}
+
+fn after(now: Instant, duration: Duration) -> Instant {
+	now.checked_add(duration).expect("instant in range")
+}
+
+#[test]
+fn retried_session_holds_one_use() {
+	let now = Instant::now();
+	let mut reservations = Reservations::default();
+	reservations.reserve("session", "abc", after(now, RESERVATION_TTL));
+
+	assert!(reservations.holds("session", "abc"));
+	assert!(!reservations.holds("other", "abc"));
+
+	reservations.reserve("session", "abc", after(now, RESERVATION_TTL));
+	assert_eq!(reservations.pending("abc"), 1);
+
+	reservations.reserve("other", "abc", after(now, RESERVATION_TTL));
+	assert_eq!(reservations.pending("abc"), 2);
+}
+
+#[test]
+fn abandoned_session_releases_use() {
+	let now = Instant::now();
+	let mut reservations = Reservations::default();
+	reservations.reserve("abandoned", "abc", after(now, Duration::from_secs(1)));
+	reservations.reserve("active", "abc", after(now, RESERVATION_TTL));
+	assert_eq!(reservations.pending("abc"), 2);
+
+	reservations.prune(after(now, Duration::from_secs(2)));
+	assert_eq!(reservations.pending("abc"), 1);
+	assert!(!reservations.holds("abandoned", "abc"));
+
+	reservations.release("active");
+	assert_eq!(reservations.pending("abc"), 0);
+}
+
+#[test]
+fn pending_uses_limit_token() {
+	let now = Instant::now();
+	let mut reservations = Reservations::default();
+	reservations.reserve("session", "abc", after(now, RESERVATION_TTL));
+
+	let mut token = token(Some(2), None);
+	token.pending = reservations.pending("abc");
+	assert!(!token.is_valid());
+
+	reservations.release("session");
+	token.pending = reservations.pending("abc");
+	assert!(token.is_valid());
+}
@@ -0,0 +1,178 @@
+mod tests;
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use conduwuit::{Err, Error, Result, Server, err, utils};
+use ruma::api::client::error::{ErrorKind, RetryAfter};
+
+/// Rendezvous sessions (MSC4108), through which a device signs in a new device
+/// of the same user, e.g. by scanning a QR code. A session is a small mailbox
+/// the two devices take turns writing, versioned by ETag. Sessions are only
+/// held in memory and expire after a short time.
+pub struct Service {
+	server: Arc<Server>,
+	sessions: Mutex<Sessions>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+	pub content: Bytes,
+	pub content_type: String,
+	pub etag: String,
+	pub last_modified: SystemTime,
+	pub expires: SystemTime,
+}
+
+#[derive(Default)]
+struct Sessions(HashMap<String, Session>);
+
+const SESSION_ID_LENGTH: usize = 32;
+const ETAG_LENGTH: usize = 16;
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			server: args.server.clone(),
+			sessions: Mutex::default(),
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Creates a session holding the content, returning its ID.
+	pub fn create(&self, content: Bytes, content_type: String) -> Result<(String, Session)> {
+		let config = &self.server.config;
+		if !config.allow_rendezvous {
+			return Err!(Request(NotFound("Rendezvous is disabled on this server.")));
+		}
+
+		check_size(&content, config.rendezvous_max_content_length)?;
+		self.sessions.lock().expect("locked").create(
+			content,
+			content_type,
+			SystemTime::now(),
+			Duration::from_secs(config.rendezvous_ttl),
+			config.rendezvous_max_sessions,
+		)
+	}
+
+	/// Returns a session, unless it expired.
+	pub fn get(&self, id: &str) -> Result<Session> {
+		self.sessions
+			.lock()
+			.expect("locked")
+			.get(id, SystemTime::now())
+	}
+
+	/// Replaces the content of a session, if its ETag is still `if_match`.
+	/// Returns the updated session, or `None` when the session was written
+	/// since. The session keeps its expiry.
+	pub fn update(
+		&self,
+		id: &str,
+		if_match: &str,
+		content: Bytes,
+		content_type: String,
+	) -> Result<Option<Session>> {
+		check_size(&content, self.server.config.rendezvous_max_content_length)?;
+		self.sessions.lock().expect("locked").update(
+			id,
+			if_match,
+			content,
+			content_type,
+			SystemTime::now(),
+		)
+	}
+
+	pub fn delete(&self, id: &str) -> Result { self.sessions.lock().expect("locked").delete(id) }
+}
+
+impl Sessions {
+	fn create(
+		&mut self,
+		content: Bytes,
+		content_type: String,
+		now: SystemTime,
+		ttl: Duration,
+		max_sessions: usize,
+	) -> Result<(String, Session)> {
+		self.0.retain(|_, session| session.expires > now);
+		if self.0.len() >= max_sessions {
+			return Err(Error::Request(
+				ErrorKind::LimitExceeded {
+					retry_after: Some(RetryAfter::Delay(ttl)),
+				},
+				"Too many rendezvous sessions, try again later.".into(),
+				http::StatusCode::TOO_MANY_REQUESTS,
+			));
+		}
+
+		let id = utils::random_string(SESSION_ID_LENGTH);
+		let session = Session {
+			content,
+			content_type,
+			etag: utils::random_string(ETAG_LENGTH),
+			last_modified: now,
+			expires: now.checked_add(ttl).unwrap_or(now),
+		};
+
+		self.0.insert(id.clone(), session.clone());
+
+		Ok((id, session))
+	}
+
+	fn get(&self, id: &str, now: SystemTime) -> Result<Session> {
+		self.0
+			.get(id)
+			.filter(|session| session.expires > now)
+			.cloned()
+			.ok_or_else(|| not_found(id))
+	}
+
+	fn update(
+		&mut self,
+		id: &str,
+		if_match: &str,
+		content: Bytes,
+		content_type: String,
+		now: SystemTime,
+	) -> Result<Option<Session>> {
+		let Some(session) = self.0.get_mut(id).filter(|session| session.expires > now) else {
+			return Err(not_found(id));
+		};
+
+		if session.etag != if_match {
+			return Ok(None);
+		}
+
+		session.content = content;
+		session.content_type = content_type;
+		session.etag = utils::random_string(ETAG_LENGTH);
+		session.last_modified = now;
+
+		Ok(Some(session.clone()))
+	}
+
+	fn delete(&mut self, id: &str) -> Result {
+		self.0.remove(id).map(|_| ()).ok_or_else(|| not_found(id))
+	}
+}
+
+fn check_size(content: &Bytes, max: usize) -> Result {
+	if content.len() > max {
+		return Err!(Request(TooLarge("Rendezvous content is larger than {max} bytes.")));
+	}
+
+	Ok(())
+}
+
+fn not_found(id: &str) -> Error {
+	err!(Request(NotFound("Rendezvous session {id} not found or expired.")))
+}
@@ -0,0 +1,110 @@
+#![cfg(test)]
+
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use ruma::api::client::error::ErrorKind;
+
+use super::{Sessions, check_size};
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn at(secs: u64) -> SystemTime {
+	SystemTime::UNIX_EPOCH
+		.checked_add(Duration::from_secs(secs))
+		.expect("valid time")
+}
+
+fn create(sessions: &mut Sessions, now: SystemTime) -> (String, super::Session) {
+	sessions
+		.create(Bytes::from_static(b"offer"), "text/plain".into(), now, TTL, 2)
+		.expect("session created")
+}
+
+#[test]
+fn update_etag_mismatch() {
+	let now = at(0);
+	let mut sessions = Sessions::default();
+	let (id, session) = create(&mut sessions, now);
+
+	let stale = format!("{}-stale", session.etag);
+	let updated = sessions
+		.update(&id, &stale, Bytes::from_static(b"answer"), "text/plain".into(), now)
+		.expect("session found");
+	assert!(updated.is_none());
+	assert_eq!(sessions.get(&id, now).unwrap().content, "offer");
+
+	let updated = sessions
+		.update(&id, &session.etag, Bytes::from_static(b"answer"), "text/plain".into(), now)
+		.expect("session found")
+		.expect("etag matched");
+	assert_eq!(updated.content, "answer");
+	assert_ne!(updated.etag, session.etag);
+	assert_eq!(updated.expires, session.expires);
+
+	// the previous etag no longer matches
+	let updated = sessions
+		.update(&id, &session.etag, Bytes::from_static(b"again"), "text/plain".into(), now)
+		.expect("session found");
+	assert!(updated.is_none());
+}
+
+#[test]
+fn expiry() {
+	let now = at(0);
+	let mut sessions = Sessions::default();
+	let (id, session) = create(&mut sessions, now);
+	assert_eq!(session.expires, at(60));
+
+	let before = at(59);
+	assert!(sessions.get(&id, before).is_ok());
+
+	let after = at(60);
+	let error = sessions.get(&id, after).unwrap_err();
+	assert!(matches!(error.kind(), ErrorKind::NotFound));
+
+	let error = sessions
+		.update(&id, &session.etag, Bytes::new(), "text/plain".into(), after)
+		.unwrap_err();
+	assert!(matches!(error.kind(), ErrorKind::NotFound));
+}
+
+#[test]
+fn size_limit() {
+	assert!(check_size(&Bytes::from_static(b"1234"), 4).is_ok());
+	assert!(check_size(&Bytes::new(), 0).is_ok());
+
+	let error = check_size(&Bytes::from_static(b"12345"), 4).unwrap_err();
+	assert!(matches!(error.kind(), ErrorKind::TooLarge));
+}
+
+#[test]
+fn session_cap() {
+	let now = at(0);
+	let mut sessions = Sessions::default();
+	create(&mut sessions, now);
+	create(&mut sessions, now);
+
+	let error = sessions
+		.create(Bytes::new(), "text/plain".into(), now, TTL, 2)
+		.unwrap_err();
+	assert!(matches!(error.kind(), ErrorKind::LimitExceeded { .. }));
+	assert_eq!(error.status_code(), http::StatusCode::TOO_MANY_REQUESTS);
+
+	// expired sessions are pruned to make room
+	let later = at(60);
+	let (id, _) = create(&mut sessions, later);
+	assert_eq!(sessions.0.len(), 1);
+	assert!(sessions.get(&id, later).is_ok());
+}
+
+#[test]
+fn delete() {
+	let now = at(0);
+	let mut sessions = Sessions::default();
+	let (id, _) = create(&mut sessions, now);
+
+	assert!(sessions.delete(&id).is_ok());
+	assert!(matches!(sessions.delete(&id).unwrap_err().kind(), ErrorKind::NotFound));
+	assert!(sessions.get(&id, now).is_err());
+}
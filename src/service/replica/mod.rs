@@ -0,0 +1,323 @@
+//! Read replicas.
+//!
+//! A process whose database is a RocksDB secondary instance follows the
+//! database of another process, the primary, by catching up with it
+//! periodically. It serves the read-heavy client endpoints itself and forwards
+//! every other request to the primary. Writes made while serving a request are
+//! forwarded to the primary as well, either as raw column writes or, where the
+//! primary must allocate from the global counter, as the operation itself.
+
+mod tests;
+
+use std::{net::IpAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use conduwuit::{
+	Err, Result, Server, debug, err, error, implement, matrix::pdu::PduCount, result::LogErr,
+};
+use database::{Database, forward::Write};
+use http::{
+	HeaderMap, HeaderValue, Method, Response,
+	header::{self, Entry, X_FORWARDED_FOR},
+};
+use ruma::{OwnedRoomId, OwnedUserId, RoomId, UserId, presence::PresenceState};
+use serde::{Deserialize, Serialize};
+use tokio::{
+	sync::Notify,
+	time::{MissedTickBehavior, interval, sleep},
+};
+use url::Url;
+
+use crate::{Dep, client, globals};
+
+pub struct Service {
+	interrupt: Notify,
+	primary: Option<Url>,
+	db: Arc<Database>,
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	client: Dep<client::Service>,
+	globals: Dep<globals::Service>,
+}
+
+/// Operation forwarded by a read replica to the primary.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Forwarded {
+	/// Raw writes to the columns of the database.
+	Writes {
+		writes: Vec<Write>,
+	},
+
+	/// `presence::Service::ping_presence()`
+	Presence {
+		user_id: OwnedUserId,
+		presence: PresenceState,
+	},
+
+	/// `rooms::timeline::Service::backfill_if_required()`
+	Backfill {
+		room_id: OwnedRoomId,
+		from: i64,
+	},
+}
+
+/// Path on the primary's internal listener receiving forwarded operations.
+pub const FORWARD_PATH: &str = "/_conduwuit/replica";
+
+/// Delay before shipping writes again after the primary could not be reached.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			interrupt: Notify::new(),
+			primary: args.server.config.replica_primary_url.clone(),
+			db: args.db.clone(),
+			services: Services {
+				server: args.server.clone(),
+				client: args.depend::<client::Service>("client"),
+				globals: args.depend::<globals::Service>("globals"),
+			},
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "replica", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if !self.is_replica() {
+			return Ok(());
+		}
+
+		let period =
+			Duration::from_millis(self.services.server.config.replica_catchup_interval_ms);
+		let mut i = interval(period);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		loop {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => _ = self.catch_up().await.log_err(),
+				writes = self.db.forwarded() => self.ship(writes).await,
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+
+	fn on_replica(&self) -> bool { true }
+}
+
+/// True if this process is a read replica.
+#[implement(Service)]
+#[inline]
+#[must_use]
+pub fn is_replica(&self) -> bool { self.db.is_secondary() }
+
+/// True if a read replica serves the request itself rather than forwarding it
+/// to the primary.
+#[must_use]
+pub fn serves(method: &Method, path: &str) -> bool {
+	if *method != Method::GET {
+		return false;
+	}
+
+	let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+	match segments.as_slice() {
+		| ["_matrix", "client", "r0" | "v3", "sync"]
+		| ["_matrix", "client", "r0" | "v3", "profile", ..]
+		| ["_matrix", "media", "r0" | "v3", "download" | "thumbnail", ..]
+		| ["_matrix", "client", "v1", "media", "download" | "thumbnail", ..] => true,
+		| ["_matrix", "client", "r0" | "v3", "rooms", _, endpoint @ ..] => matches!(
+			endpoint,
+			["messages" | "members" | "joined_members"]
+				| ["context" | "event", _]
+				| ["state", ..]
+		),
+		| _ => false,
+	}
+}
+
+/// Catch up with the primary. The counter is reloaded before any watcher is
+/// woken so requests woken by new data don't observe a stale counter.
+#[implement(Service)]
+pub async fn catch_up(&self) -> Result {
+	let db = self.db.clone();
+	let globals = self.services.globals.clone();
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || -> Result {
+			if let Some(since) = db.catch_up()? {
+				globals.db.reload_count()?;
+				db.wake_since(since);
+			}
+
+			Ok(())
+		})
+		.await?
+}
+
+/// Forward a client request to the primary and return its response. The
+/// client's address, when known, is passed on in `X-Forwarded-For` unless the
+/// request already carries it.
+#[implement(Service)]
+pub async fn forward(
+	&self,
+	method: Method,
+	path_and_query: &str,
+	mut headers: HeaderMap,
+	body: Bytes,
+	client: Option<IpAddr>,
+) -> Result<Response<Bytes>> {
+	let url = self.primary_url(path_and_query)?;
+	if let (Some(client), Entry::Vacant(entry)) = (client, headers.entry(X_FORWARDED_FOR)) {
+		entry.insert(HeaderValue::from_str(&client.to_string())?);
+	}
+
+	for name in [
+		header::HOST,
+		header::CONNECTION,
+		header::CONTENT_LENGTH,
+		header::TRANSFER_ENCODING,
+		header::ACCEPT_ENCODING,
+	] {
+		headers.remove(name);
+	}
+
+	let response = self
+		.services
+		.client
+		.replica
+		.request(method, url)
+		.headers(headers)
+		.body(body)
+		.send()
+		.await?;
+
+	let mut builder = Response::builder().status(response.status());
+	for (name, value) in response.headers() {
+		if *name != header::CONTENT_LENGTH && *name != header::TRANSFER_ENCODING {
+			builder = builder.header(name, value);
+		}
+	}
+
+	Ok(builder.body(response.bytes().await?)?)
+}
+
+/// Forward a presence ping to the primary.
+#[implement(Service)]
+pub async fn ping_presence(&self, user_id: &UserId, presence: &PresenceState) -> Result {
+	self.send(&Forwarded::Presence {
+		user_id: user_id.to_owned(),
+		presence: presence.clone(),
+	})
+	.await
+}
+
+/// Have the primary backfill the room, then catch up with it so the request
+/// finds the backfilled events.
+#[implement(Service)]
+pub async fn backfill(&self, room_id: &RoomId, from: PduCount) -> Result {
+	self.send(&Forwarded::Backfill {
+		room_id: room_id.to_owned(),
+		from: from.into_signed(),
+	})
+	.await?;
+
+	self.catch_up().await
+}
+
+/// Check the secret presented by a read replica.
+#[implement(Service)]
+pub fn authorize(&self, secret: &str) -> Result {
+	match self.services.server.config.replica_secret.as_deref() {
+		| Some(expected)
+			if !expected.is_empty() && secret_eq(expected.as_bytes(), secret.as_bytes()) =>
+			Ok(()),
+		| _ => Err!(Request(Forbidden("Invalid replica secret."))),
+	}
+}
+
+#[implement(Service)]
+async fn ship(&self, writes: Vec<Write>) {
+	let count = writes.len();
+	let forwarded = Forwarded::Writes { writes };
+	match self.send(&forwarded).await {
+		| Ok(()) => debug!("Forwarded {count} writes to the primary"),
+		| Err(e) => {
+			error!("Failed to forward {count} writes to the primary: {e}");
+			if let Forwarded::Writes { writes } = forwarded {
+				self.db.requeue(writes);
+			}
+
+			sleep(RETRY_DELAY).await;
+		},
+	}
+}
+
+#[implement(Service)]
+async fn send(&self, forwarded: &Forwarded) -> Result {
+	let secret = self
+		.services
+		.server
+		.config
+		.replica_secret
+		.as_deref()
+		.unwrap_or_default();
+
+	let response = self
+		.services
+		.client
+		.replica
+		.post(self.forward_url()?)
+		.bearer_auth(secret)
+		.json(forwarded)
+		.send()
+		.await?;
+
+	if !response.status().is_success() {
+		let status = response.status();
+		let body = response.text().await.unwrap_or_default();
+		return Err!(Database("Primary refused forwarded operation with {status}: {body}"));
+	}
+
+	Ok(())
+}
+
+/// The primary's internal listener, which only accepts forwarded operations.
+#[implement(Service)]
+fn forward_url(&self) -> Result<Url> {
+	let addr =
+		self.services.server.config.replica_listen.ok_or_else(|| {
+			err!(Config("replica_listen", "No internal listener is configured."))
+		})?;
+
+	Url::parse(&format!("http://{addr}{FORWARD_PATH}"))
+		.map_err(|e| err!(Config("replica_listen", "Invalid internal listener address: {e}")))
+}
+
+#[implement(Service)]
+fn primary_url(&self, path_and_query: &str) -> Result<Url> {
+	let primary = self
+		.primary
+		.as_ref()
+		.ok_or_else(|| err!(Config("replica_primary_url", "No primary is configured.")))?;
+
+	primary
+		.join(path_and_query)
+		.map_err(|e| err!(Config("replica_primary_url", "Invalid primary address: {e}")))
+}
+
+/// Compare secrets in time independent of where they differ.
+fn secret_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len()
+		&& std::hint::black_box(a.iter().zip(b).fold(0_u8, |acc, (a, b)| acc | (a ^ b))) == 0
+}
@@ -0,0 +1,69 @@
+#![cfg(test)]
+
+use http::Method;
+
+use super::{secret_eq, serves};
+
+#[test]
+fn serves_read_endpoints() {
+	for path in [
+		"/_matrix/client/v3/sync",
+		"/_matrix/client/r0/sync",
+		"/_matrix/client/v3/profile/@alice:example.com",
+		"/_matrix/client/v3/profile/@alice:example.com/displayname",
+		"/_matrix/media/v3/download/example.com/abc",
+		"/_matrix/media/r0/thumbnail/example.com/abc",
+		"/_matrix/client/v1/media/download/example.com/abc",
+		"/_matrix/client/v1/media/thumbnail/example.com/abc",
+		"/_matrix/client/v3/rooms/!room:example.com/messages",
+		"/_matrix/client/v3/rooms/!room:example.com/members",
+		"/_matrix/client/v3/rooms/!room:example.com/joined_members",
+		"/_matrix/client/v3/rooms/!room:example.com/context/$event",
+		"/_matrix/client/v3/rooms/!room:example.com/event/$event",
+		"/_matrix/client/v3/rooms/!room:example.com/state",
+		"/_matrix/client/v3/rooms/!room:example.com/state/m.room.name/",
+	] {
+		assert!(serves(&Method::GET, path), "{path} should be served");
+	}
+}
+
+#[test]
+fn forwards_writes() {
+	for method in [Method::POST, Method::PUT, Method::DELETE] {
+		assert!(!serves(&method, "/_matrix/client/v3/sync"));
+		assert!(!serves(&method, "/_matrix/client/v3/profile/@alice:example.com/displayname"));
+		assert!(!serves(
+			&method,
+			"/_matrix/client/v3/rooms/!room:example.com/state/m.room.name/"
+		));
+	}
+}
+
+#[test]
+fn forwards_other_endpoints() {
+	for path in [
+		"/",
+		"/_matrix/client/versions",
+		"/_matrix/client/v3/account/whoami",
+		"/_matrix/client/v3/rooms/!room:example.com/aliases",
+		"/_matrix/client/v3/rooms/!room:example.com/context",
+		"/_matrix/client/v3/rooms/!room:example.com/event/$event/extra",
+		"/_matrix/client/v3/rooms/!room:example.com/messages/extra",
+		"/_matrix/client/v1/rooms/!room:example.com/messages",
+		"/_matrix/client/v3/sync/extra",
+		"/_matrix/media/v3/upload",
+		"/_matrix/media/v3/config",
+		"/_conduwuit/replica",
+	] {
+		assert!(!serves(&Method::GET, path), "{path} should be forwarded");
+	}
+}
+
+#[test]
+fn secret_comparison() {
+	assert!(secret_eq(b"secret", b"secret"));
+	assert!(!secret_eq(b"secret", b"secreT"));
+	assert!(!secret_eq(b"secret", b"secrets"));
+	assert!(!secret_eq(b"secret", b""));
+	assert!(secret_eq(b"", b""));
+}
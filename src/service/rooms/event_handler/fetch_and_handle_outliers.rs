@@ -1,15 +1,17 @@
 use std::{
 	collections::{BTreeMap, HashSet, VecDeque, hash_map},
+	iter::once,
 	time::Instant,
 };
 
 use conduwuit::{
-	PduEvent, debug, debug_error, debug_warn, implement, pdu, trace,
+	Err, PduEvent, debug, debug_error, debug_warn, implement, pdu, trace,
 	utils::continue_exponential_backoff_secs, warn,
 };
 use ruma::{
 	CanonicalJsonValue, OwnedEventId, RoomId, ServerName, api::federation::event::get_event,
 };
+use tokio::sync::OnceCell;
 
 use super::get_room_version_id;
 
@@ -21,7 +23,7 @@
 /// a. Look in the main timeline (pduid_pdu tree)
 /// b. Look at outlier pdu tree
 /// c. Ask origin server over federation
-/// d. TODO: Ask other servers over federation?
+/// d. Ask other servers in the room over federation
 #[implement(super::Service)]
 pub(super) async fn fetch_and_handle_outliers<'a>(
 	&self,
@@ -46,6 +48,7 @@ pub(super) async fn fetch_and_handle_outliers<'a>(
 		},
 	};
 
+	let servers = OnceCell::new();
 	let mut events_with_auth_events = Vec::with_capacity(events.len());
 	for id in events {
 		// a. Look in the main timeline (pduid_pdu tree)
@@ -58,6 +61,7 @@ pub(super) async fn fetch_and_handle_outliers<'a>(
 		}
 
 		// c. Ask origin server over federation
+		// d. Ask other servers in the room over federation
 		// We also handle its auth chain here so we don't get a stack overflow in
 		// handle_outlier_pdu.
 		let mut todo_auth_events: VecDeque<_> = [id.clone()].into();
@@ -99,38 +103,39 @@ pub(super) async fn fetch_and_handle_outliers<'a>(
 				continue;
 			}
 
+			let Ok(room_version_id) = get_room_version_id(create_event) else {
+				back_off((*next_id).to_owned());
+				continue;
+			};
+
+			let servers = servers
+				.get_or_init(|| self.fetch_servers(room_id, once(origin.to_owned())))
+				.await;
+
 			debug!("Fetching {next_id} over federation.");
-			match self
-				.services
-				.sending
-				.send_federation_request(origin, get_event::v1::Request {
-					event_id: (*next_id).to_owned(),
-					include_unredacted_content: None,
-				})
-				.await
-			{
-				| Ok(res) => {
-					debug!("Got {next_id} over federation");
-					let Ok(room_version_id) = get_room_version_id(create_event) else {
-						back_off((*next_id).to_owned());
-						continue;
-					};
-
-					let Ok((calculated_event_id, value)) =
-						pdu::gen_event_id_canonical_json(&res.pdu, &room_version_id)
-					else {
-						back_off((*next_id).to_owned());
-						continue;
-					};
-
-					if calculated_event_id != *next_id {
-						warn!(
-							"Server didn't return event id we requested: requested: {next_id}, \
-							 we got {calculated_event_id}. Event: {:?}",
-							&res.pdu
-						);
-					}
+			let request = get_event::v1::Request {
+				event_id: (*next_id).to_owned(),
+				include_unredacted_content: None,
+			};
+
+			let accept = |server: &ServerName, res: get_event::v1::Response| {
+				let (calculated_event_id, value) =
+					pdu::gen_event_id_canonical_json(&res.pdu, &room_version_id)?;
+
+				if calculated_event_id != *next_id {
+					return Err!(BadServerResponse(warn!(
+						"Server {server} didn't return event id we requested: requested: \
+						 {next_id}, we got {calculated_event_id}. Event: {:?}",
+						&res.pdu
+					)));
+				}
+
+				Ok(value)
+			};
 
+			match self.fetch_any(servers, request, accept).await {
+				| Ok((server, value)) => {
+					debug!("Got {next_id} over federation from {server}");
 					if let Some(auth_events) = value
 						.get("auth_events")
 						.and_then(CanonicalJsonValue::as_array)
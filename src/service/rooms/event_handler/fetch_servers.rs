@@ -0,0 +1,116 @@
+use std::{collections::HashSet, fmt::Debug};
+
+use conduwuit::{Result, debug, debug_warn, err, implement, utils::stream::ReadyExt};
+use futures::{StreamExt, stream::FuturesUnordered};
+use ruma::{OwnedServerName, RoomId, ServerName, api::OutgoingRequest};
+
+/// Servers which may be asked for events, state or backfill in a room. The
+/// preferred servers (e.g. the origin) are listed first followed by the other
+/// servers joined to the room; the list is then ranked by destination health
+/// and truncated to the configured limit. The first preferred server is kept
+/// first regardless of its health.
+#[implement(super::Service)]
+#[tracing::instrument(level = "debug", skip(self, preferred))]
+pub async fn fetch_servers<I>(&self, room_id: &RoomId, preferred: I) -> Vec<OwnedServerName>
+where
+	I: IntoIterator<Item = OwnedServerName> + Send,
+{
+	let config = &self.services.server.config;
+	let mut servers: Vec<_> = preferred.into_iter().collect();
+	let pinned = servers.first().cloned();
+	self.services
+		.state_cache
+		.room_servers(room_id)
+		.map(ToOwned::to_owned)
+		.ready_for_each(|server| servers.push(server))
+		.await;
+
+	let mut seen = HashSet::with_capacity(servers.len());
+	servers.retain(|server| {
+		seen.insert(server.clone())
+			&& !self.services.globals.server_is_ours(server)
+			&& !config.forbidden_remote_server_names.is_match(server.host())
+	});
+
+	self.services.federation.rank_servers(
+		servers,
+		pinned.as_deref(),
+		config.federation_fetch_servers,
+	)
+}
+
+/// Send the request to each of the servers in order, with up to the
+/// configured number of requests in flight. Each response is passed through
+/// `accept` which may reject it (e.g. when a server returns the wrong event);
+/// the first accepted result is returned along with the server which
+/// provided it. Every server is asked at most once.
+#[implement(super::Service)]
+#[tracing::instrument(level = "debug", skip_all, fields(servers = servers.len()))]
+pub async fn fetch_any<T, R, F>(
+	&self,
+	servers: &[OwnedServerName],
+	request: T,
+	accept: F,
+) -> Result<(OwnedServerName, R)>
+where
+	T: OutgoingRequest + Clone + Debug + Send,
+	F: Fn(&ServerName, T::IncomingResponse) -> Result<R> + Send + Sync,
+{
+	let concurrency = self.services.server.config.federation_fetch_concurrency;
+
+	fetch_first(servers, concurrency, |server| {
+		let request = request.clone();
+		let accept = &accept;
+		async move {
+			self.services
+				.sending
+				.send_federation_request(server, request)
+				.await
+				.and_then(|response| accept(server, response))
+		}
+	})
+	.await
+}
+
+/// Make `request` of each of the servers in order with up to `concurrency` in
+/// flight, returning the first to succeed.
+pub(super) async fn fetch_first<'a, R, F, Fut>(
+	servers: &'a [OwnedServerName],
+	concurrency: usize,
+	request: F,
+) -> Result<(OwnedServerName, R)>
+where
+	F: Fn(&'a ServerName) -> Fut,
+	Fut: Future<Output = Result<R>>,
+{
+	let mut servers = servers.iter();
+	let mut requests = FuturesUnordered::new();
+	let mut last_error = None;
+	loop {
+		while requests.len() < concurrency.max(1) {
+			let Some(server) = servers.next() else {
+				break;
+			};
+
+			let response = request(server);
+			requests.push(async move { (server, response.await) });
+		}
+
+		let Some((server, response)) = requests.next().await else {
+			break;
+		};
+
+		match response {
+			| Ok(result) => {
+				debug!(%server, "Request succeeded");
+				return Ok((server.clone(), result));
+			},
+			| Err(e) => {
+				debug_warn!(%server, "Request failed: {e}");
+				last_error = Some(e);
+			},
+		}
+	}
+
+	Err(last_error.unwrap_or_else(|| err!(Request(NotFound("No servers available to ask.")))))
+}
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, hash_map};
+use std::{
+	collections::{HashMap, hash_map},
+	iter::once,
+};
 
 use conduwuit::{Err, Error, PduEvent, Result, debug, debug_warn, implement};
 use futures::FutureExt;
@@ -9,7 +12,8 @@
 
 use crate::rooms::short::ShortStateKey;
 
-/// Call /state_ids to find out what the state at this pdu is. We trust the
+/// Call /state_ids to find out what the state at this pdu is. The origin is
+/// asked first, falling back to other servers in the room. We trust the
 /// server's response to some extend (sic), but we still do a lot of checks
 /// on the events
 #[implement(super::Service)]
@@ -25,19 +29,40 @@ pub(super) async fn fetch_state(
 	room_id: &RoomId,
 	event_id: &EventId,
 ) -> Result<Option<HashMap<u64, OwnedEventId>>> {
-	let res = self
-		.services
-		.sending
-		.send_federation_request(origin, get_room_state_ids::v1::Request {
-			room_id: room_id.to_owned(),
-			event_id: event_id.to_owned(),
-		})
+	let servers = self.fetch_servers(room_id, once(origin.to_owned())).await;
+	let request = get_room_state_ids::v1::Request {
+		room_id: room_id.to_owned(),
+		event_id: event_id.to_owned(),
+	};
+
+	let (server, res) = self
+		.fetch_any(&servers, request, |_, res| Ok(res))
 		.await
 		.inspect_err(|e| debug_warn!("Fetching state for event failed: {e}"))?;
 
-	debug!("Fetching state events");
+	debug!("Fetching state events from {server}");
+	self.state_from_ids(origin, create_event, room_id, &res.pdu_ids)
+		.await
+		.map(Some)
+}
+
+/// Fetch and validate the state events listed by a /state_ids response,
+/// building the state map from them.
+#[implement(super::Service)]
+#[tracing::instrument(
+	level = "debug",
+	skip_all,
+	fields(%origin, count = pdu_ids.len()),
+)]
+pub(super) async fn state_from_ids(
+	&self,
+	origin: &ServerName,
+	create_event: &PduEvent,
+	room_id: &RoomId,
+	pdu_ids: &[OwnedEventId],
+) -> Result<HashMap<u64, OwnedEventId>> {
 	let state_vec = self
-		.fetch_and_handle_outliers(origin, &res.pdu_ids, create_event, room_id)
+		.fetch_and_handle_outliers(origin, pdu_ids, create_event, room_id)
 		.boxed()
 		.await;
 
@@ -77,5 +102,5 @@ pub(super) async fn fetch_state(
 		return Err!(Database("Incoming event refers to wrong create event."));
 	}
 
-	Ok(Some(state))
+	Ok(state)
 }
@@ -1,19 +1,22 @@
 mod acl_check;
 mod fetch_and_handle_outliers;
 mod fetch_prev;
+mod fetch_servers;
 mod fetch_state;
 mod handle_incoming_pdu;
 mod handle_outlier_pdu;
 mod handle_prev_pdu;
 mod parse_incoming_pdu;
+mod repair_state;
 mod resolve_state;
 mod state_at_incoming;
+mod tests;
 mod upgrade_outlier_pdu;
 
 use std::{
 	collections::HashMap,
 	fmt::Write,
-	sync::{Arc, RwLock as StdRwLock},
+	sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock},
 	time::Instant,
 };
 
@@ -24,15 +27,18 @@
 	events::room::create::RoomCreateEventContent,
 };
 
-use crate::{Dep, globals, rooms, sending, server_keys};
+pub use self::repair_state::{MembershipChange, PowerLevelChange, StateChange, StateRepair};
+use crate::{Dep, federation, globals, rooms, sending, server_keys, spam_checker};
 
 pub struct Service {
 	pub mutex_federation: RoomMutexMap,
 	pub federation_handletime: StdRwLock<HandleTimeMap>,
+	state_repairs: StdMutex<StateRepairMap>,
 	services: Services,
 }
 
 struct Services {
+	federation: Dep<federation::Service>,
 	globals: Dep<globals::Service>,
 	sending: Dep<sending::Service>,
 	auth_chain: Dep<rooms::auth_chain::Service>,
@@ -40,9 +46,11 @@ struct Services {
 	outlier: Dep<rooms::outlier::Service>,
 	pdu_metadata: Dep<rooms::pdu_metadata::Service>,
 	server_keys: Dep<server_keys::Service>,
+	spam_checker: Dep<spam_checker::Service>,
 	short: Dep<rooms::short::Service>,
 	state: Dep<rooms::state::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
 	state_compressor: Dep<rooms::state_compressor::Service>,
 	timeline: Dep<rooms::timeline::Service>,
 	server: Arc<Server>,
@@ -50,6 +58,7 @@ struct Services {
 
 type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
 type HandleTimeMap = HashMap<OwnedRoomId, (OwnedEventId, Instant)>;
+type StateRepairMap = HashMap<OwnedRoomId, Arc<StateRepair>>;
 
 #[async_trait]
 impl crate::Service for Service {
@@ -57,18 +66,22 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			mutex_federation: RoomMutexMap::new(),
 			federation_handletime: HandleTimeMap::new().into(),
+			state_repairs: StateRepairMap::new().into(),
 			services: Services {
+				federation: args.depend::<federation::Service>("federation"),
 				globals: args.depend::<globals::Service>("globals"),
 				sending: args.depend::<sending::Service>("sending"),
 				auth_chain: args.depend::<rooms::auth_chain::Service>("rooms::auth_chain"),
 				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
 				outlier: args.depend::<rooms::outlier::Service>("rooms::outlier"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
+				spam_checker: args.depend::<spam_checker::Service>("spam_checker"),
 				pdu_metadata: args.depend::<rooms::pdu_metadata::Service>("rooms::pdu_metadata"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
 				state_compressor: args
 					.depend::<rooms::state_compressor::Service>("rooms::state_compressor"),
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
@@ -88,6 +101,9 @@ async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 			.len();
 		writeln!(out, "federation_handletime: {federation_handletime}")?;
 
+		let state_repairs = self.state_repairs.lock().expect("locked").len();
+		writeln!(out, "state_repairs: {state_repairs}")?;
+
 		Ok(())
 	}
 
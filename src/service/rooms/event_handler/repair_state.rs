@@ -0,0 +1,426 @@
+use std::{
+	borrow::Borrow,
+	collections::{BTreeSet, HashMap},
+	iter::empty,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use conduwuit::{
+	Err, PduEvent, Result, debug_warn, err, implement, info,
+	matrix::{StateKey, StateMap},
+	utils::stream::{IterStream, ReadyExt},
+	warn,
+};
+use futures::{FutureExt, StreamExt, future::join_all};
+use ruma::{
+	Int, OwnedEventId, OwnedServerName, OwnedUserId, RoomId, ServerName, UserId,
+	api::federation::event::get_room_state_ids,
+	events::{
+		StateEventType,
+		room::{
+			member::{MembershipState, RoomMemberEventContent},
+			power_levels::RoomPowerLevelsEventContent,
+		},
+	},
+};
+
+use super::get_room_version_id;
+use crate::rooms::state_compressor::HashSetCompressStateEvent;
+
+/// State repair for a room computed by resolving our state against the state
+/// of several remote servers. It is held until confirmed by
+/// `apply_state_repair` or until it expires.
+#[derive(Debug)]
+pub struct StateRepair {
+	/// The latest event when the repair was prepared, after which the remote
+	/// state was computed. The repair only applies while it is still the
+	/// latest; it is kept among the forward extremities.
+	pub event_id: OwnedEventId,
+
+	/// Every server asked, with the size of the state it returned or the
+	/// reason it could not be used.
+	pub servers: Vec<(OwnedServerName, Result<usize, String>)>,
+
+	/// The resolved state.
+	pub resolved: StateMap<OwnedEventId>,
+
+	/// State entries differing from our current state, ordered by type and
+	/// state key.
+	pub changes: Vec<StateChange>,
+
+	/// Membership differences from our current state.
+	pub membership: Vec<MembershipChange>,
+
+	/// Power level differences from our current state.
+	pub power_levels: Vec<PowerLevelChange>,
+
+	created: Instant,
+}
+
+/// A state entry replaced, added or removed by a repair.
+#[derive(Debug, Eq, PartialEq)]
+pub struct StateChange {
+	pub event_type: StateEventType,
+	pub state_key: StateKey,
+
+	/// Our current event, if the entry is in our state.
+	pub before: Option<OwnedEventId>,
+
+	/// The resolved event; None when the entry is removed from our state.
+	pub after: Option<OwnedEventId>,
+}
+
+#[derive(Debug)]
+pub struct MembershipChange {
+	pub user_id: OwnedUserId,
+	pub before: Option<MembershipState>,
+	pub after: Option<MembershipState>,
+}
+
+#[derive(Debug)]
+pub struct PowerLevelChange {
+	pub user_id: OwnedUserId,
+	pub before: Int,
+	pub after: Int,
+}
+
+/// Time after which a prepared repair must be computed again.
+const REPAIR_EXPIRES: Duration = Duration::from_secs(15 * 60);
+
+/// Fetch the room state from several joined servers (or those given) after
+/// the latest event we have, resolve it against our current state and compute
+/// the differences. Nothing is changed until the repair is applied.
+#[implement(super::Service)]
+#[tracing::instrument(level = "info", skip(self))]
+pub async fn prepare_state_repair(
+	&self,
+	room_id: &RoomId,
+	servers: Vec<OwnedServerName>,
+) -> Result<Arc<StateRepair>> {
+	let latest_pdu = self
+		.services
+		.timeline
+		.latest_pdu_in_room(room_id)
+		.await
+		.map_err(|e| err!(Database("Failed to find the latest PDU in room: {e}")))?;
+
+	let create_event = self
+		.services
+		.state_accessor
+		.room_state_get(room_id, &StateEventType::RoomCreate, "")
+		.await?;
+
+	let room_version_id = get_room_version_id(&create_event)?;
+	let servers = if servers.is_empty() {
+		self.fetch_servers(room_id, empty()).await
+	} else {
+		servers
+	};
+
+	if servers.is_empty() {
+		return Err!(Request(NotFound("There are no remote servers to ask for the room state.")));
+	}
+
+	info!("Fetching state at {} from {} servers", latest_pdu.event_id, servers.len());
+	let forks = servers
+		.iter()
+		.map(|server| self.fetch_state_fork(server, &create_event, room_id, &latest_pdu));
+
+	let mut fork_states = Vec::with_capacity(servers.len());
+	let mut report = Vec::with_capacity(servers.len());
+	for (server, fork) in servers.into_iter().zip(join_all(forks).await) {
+		match fork {
+			| Ok(state) => {
+				report.push((server, Ok(state.len())));
+				fork_states.push(state);
+			},
+			| Err(e) => {
+				debug_warn!(%server, "Failed to fetch state fork: {e}");
+				report.push((server, Err(e.to_string())));
+			},
+		}
+	}
+
+	if fork_states.is_empty() {
+		return Err!("None of the servers provided the room state.");
+	}
+
+	let resolved = self
+		.resolve_state_forks(room_id, &room_version_id, fork_states)
+		.boxed()
+		.await?;
+
+	let mut repair = StateRepair {
+		event_id: latest_pdu.event_id,
+		servers: report,
+		resolved,
+		changes: Vec::new(),
+		membership: Vec::new(),
+		power_levels: Vec::new(),
+		created: Instant::now(),
+	};
+
+	self.state_repair_diff(room_id, &mut repair).await?;
+
+	let repair = Arc::new(repair);
+	self.state_repairs
+		.lock()
+		.expect("locked")
+		.insert(room_id.to_owned(), repair.clone());
+
+	Ok(repair)
+}
+
+/// Apply a prepared repair: the resolved state replaces the current state of
+/// the room. The repair is refused when events arrived since it was prepared,
+/// as the resolved state does not account for them; it must be prepared again.
+/// The event it was computed after is added to the forward extremities.
+///
+/// No event is sent for this; the resolved state consists of events already
+/// in the room, so our copy of the state is overwritten in place as when
+/// resolving the state of incoming events, and other servers are not told.
+/// Each overwritten entry is logged with our event and the resolved one.
+#[implement(super::Service)]
+#[tracing::instrument(level = "info", skip(self))]
+pub async fn apply_state_repair(&self, room_id: &RoomId) -> Result<Arc<StateRepair>> {
+	let repair = self
+		.state_repairs
+		.lock()
+		.expect("locked")
+		.remove(room_id)
+		.filter(|repair| repair.created.elapsed() < REPAIR_EXPIRES)
+		.ok_or_else(|| err!(Request(NotFound("No pending state repair for this room."))))?;
+
+	let _federation_lock = self.mutex_federation.lock(room_id).await;
+	let state_lock = self.services.state.mutex.lock(room_id).await;
+	let latest_pdu = self
+		.services
+		.timeline
+		.latest_pdu_in_room(room_id)
+		.await
+		.map_err(|e| err!(Database("Failed to find the latest PDU in room: {e}")))?;
+
+	if latest_pdu.event_id != repair.event_id {
+		return Err!(Request(Unknown(
+			"The room received {} since the repair was prepared; prepare it again.",
+			latest_pdu.event_id
+		)));
+	}
+
+	let new_room_state = self.compress_resolved_state(&repair.resolved).await;
+
+	let HashSetCompressStateEvent { shortstatehash, added, removed } = self
+		.services
+		.state_compressor
+		.save_state(room_id, new_room_state)
+		.await?;
+
+	for change in &repair.changes {
+		warn!(
+			%room_id,
+			event_type = %change.event_type,
+			state_key = %change.state_key,
+			before = ?change.before,
+			after = ?change.after,
+			"Overwriting room state entry",
+		);
+	}
+
+	self.services
+		.state
+		.force_state(room_id, shortstatehash, added, removed, &state_lock)
+		.await?;
+
+	let mut extremities: Vec<OwnedEventId> = self
+		.services
+		.state
+		.get_forward_extremities(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	if !extremities.contains(&repair.event_id) {
+		extremities.push(repair.event_id.clone());
+	}
+
+	self.services
+		.state
+		.set_forward_extremities(room_id, extremities.iter().map(Borrow::borrow), &state_lock)
+		.await;
+
+	info!(
+		"Applied state repair at {} with {} changes",
+		repair.event_id,
+		repair.changes.len()
+	);
+	Ok(repair)
+}
+
+/// Discard a prepared repair without applying it.
+#[implement(super::Service)]
+pub fn cancel_state_repair(&self, room_id: &RoomId) -> bool {
+	self.state_repairs
+		.lock()
+		.expect("locked")
+		.remove(room_id)
+		.is_some()
+}
+
+#[implement(super::Service)]
+async fn fetch_state_fork(
+	&self,
+	server: &ServerName,
+	create_event: &PduEvent,
+	room_id: &RoomId,
+	pdu: &PduEvent,
+) -> Result<HashMap<u64, OwnedEventId>> {
+	let res = self
+		.services
+		.sending
+		.send_federation_request(server, get_room_state_ids::v1::Request {
+			room_id: room_id.to_owned(),
+			event_id: pdu.event_id.clone(),
+		})
+		.await?;
+
+	let mut state = self
+		.state_from_ids(server, create_event, room_id, &res.pdu_ids)
+		.await?;
+
+	// the state returned is the state before the event
+	if let Some(state_key) = &pdu.state_key {
+		let shortstatekey = self
+			.services
+			.short
+			.get_or_create_shortstatekey(&pdu.kind.to_string().into(), state_key)
+			.await;
+
+		state.insert(shortstatekey, pdu.event_id.clone());
+	}
+
+	Ok(state)
+}
+
+#[implement(super::Service)]
+async fn state_repair_diff(&self, room_id: &RoomId, repair: &mut StateRepair) -> Result {
+	let current_shortstatehash = self.services.state.get_room_shortstatehash(room_id).await?;
+
+	let (shortstatekeys, event_ids): (Vec<_>, Vec<OwnedEventId>) = self
+		.services
+		.state_accessor
+		.state_full_ids(current_shortstatehash)
+		.unzip()
+		.await;
+
+	let current: StateMap<OwnedEventId> = self
+		.services
+		.short
+		.multi_get_statekey_from_short(shortstatekeys.into_iter().stream())
+		.zip(event_ids.into_iter().stream())
+		.ready_filter_map(|(key, event_id)| Some((key.ok()?, event_id)))
+		.collect()
+		.await;
+
+	repair.changes = state_changes(&current, &repair.resolved);
+	for change in &repair.changes {
+		let before = match &change.before {
+			| Some(before) => self.services.timeline.get_pdu(before).await.ok(),
+			| None => None,
+		};
+
+		let after = match &change.after {
+			| Some(after) => Some(self.services.timeline.get_pdu(after).await?),
+			| None => None,
+		};
+
+		match change.event_type {
+			| StateEventType::RoomMember => {
+				let Ok(user_id) = UserId::parse(change.state_key.as_str()) else {
+					continue;
+				};
+
+				let membership = |pdu: &PduEvent| {
+					pdu.get_content::<RoomMemberEventContent>()
+						.map(|content| content.membership)
+						.ok()
+				};
+
+				let before = before.as_ref().and_then(membership);
+				let after = after.as_ref().and_then(membership);
+				if before != after {
+					repair
+						.membership
+						.push(MembershipChange { user_id, before, after });
+				}
+			},
+			| StateEventType::RoomPowerLevels => {
+				let content = |pdu: Option<PduEvent>| -> Result<RoomPowerLevelsEventContent> {
+					Ok(pdu
+						.map(|pdu| pdu.get_content())
+						.transpose()?
+						.unwrap_or_default())
+				};
+
+				let (before, after) = (content(before)?, content(after)?);
+				let level = |content: &RoomPowerLevelsEventContent, user_id| {
+					content
+						.users
+						.get(user_id)
+						.copied()
+						.unwrap_or(content.users_default)
+				};
+
+				let users: BTreeSet<_> = before.users.keys().chain(after.users.keys()).collect();
+				repair
+					.power_levels
+					.extend(users.into_iter().filter_map(|user_id| {
+						let (before, after) = (level(&before, user_id), level(&after, user_id));
+						(before != after).then(|| PowerLevelChange {
+							user_id: user_id.clone(),
+							before,
+							after,
+						})
+					}));
+			},
+			| _ => {},
+		}
+	}
+
+	Ok(())
+}
+
+/// The entries of the resolved state which differ from the current state,
+/// including those missing from it which are removed when applied.
+pub(super) fn state_changes(
+	current: &StateMap<OwnedEventId>,
+	resolved: &StateMap<OwnedEventId>,
+) -> Vec<StateChange> {
+	let changed = resolved
+		.iter()
+		.filter(|(key, after)| current.get(*key) != Some(*after))
+		.map(|(key, after)| {
+			let (event_type, state_key) = key;
+			StateChange {
+				event_type: event_type.clone(),
+				state_key: state_key.clone(),
+				before: current.get(key).cloned(),
+				after: Some(after.clone()),
+			}
+		});
+
+	let removed = current
+		.iter()
+		.filter(|(key, _)| !resolved.contains_key(*key))
+		.map(|((event_type, state_key), before)| StateChange {
+			event_type: event_type.clone(),
+			state_key: state_key.clone(),
+			before: Some(before.clone()),
+			after: None,
+		});
+
+	let mut changes: Vec<_> = changed.chain(removed).collect();
+	changes
+		.sort_by_cached_key(|change| (change.event_type.to_string(), change.state_key.clone()));
+	changes
+}
@@ -1,6 +1,7 @@
 use std::{
 	borrow::Borrow,
 	collections::{HashMap, HashSet},
+	iter::once,
 	sync::Arc,
 };
 
@@ -23,6 +24,26 @@ pub async fn resolve_state(
 	room_version_id: &RoomVersionId,
 	incoming_state: HashMap<u64, OwnedEventId>,
 ) -> Result<Arc<CompressedState>> {
+	let state = self
+		.resolve_state_forks(room_id, room_version_id, once(incoming_state))
+		.await?;
+
+	Ok(self.compress_resolved_state(&state).await)
+}
+
+/// Resolve the current state of the room against any number of other forks
+/// of its state, returning the resolved state.
+#[implement(super::Service)]
+#[tracing::instrument(name = "forks", level = "debug", skip_all)]
+pub async fn resolve_state_forks<I>(
+	&self,
+	room_id: &RoomId,
+	room_version_id: &RoomVersionId,
+	incoming_states: I,
+) -> Result<StateMap<OwnedEventId>>
+where
+	I: IntoIterator<Item = HashMap<u64, OwnedEventId>> + Send,
+{
 	trace!("Loading current room state ids");
 	let current_sstatehash = self
 		.services
@@ -39,7 +60,7 @@ pub async fn resolve_state(
 		.await;
 
 	trace!("Loading fork states");
-	let fork_states = [current_state_ids, incoming_state];
+	let fork_states: Vec<_> = once(current_state_ids).chain(incoming_states).collect();
 	let auth_chain_sets = fork_states
 		.iter()
 		.try_stream()
@@ -76,6 +97,16 @@ pub async fn resolve_state(
 		.await?;
 
 	trace!("State resolution done.");
+	Ok(state)
+}
+
+/// Compress the result of state resolution for saving.
+#[implement(super::Service)]
+#[tracing::instrument(name = "compress", level = "debug", skip_all)]
+pub async fn compress_resolved_state(
+	&self,
+	state: &StateMap<OwnedEventId>,
+) -> Arc<CompressedState> {
 	let state_events: Vec<_> = state
 		.iter()
 		.stream()
@@ -96,7 +127,7 @@ pub async fn resolve_state(
 		.collect()
 		.await;
 
-	Ok(Arc::new(new_room_state))
+	Arc::new(new_room_state)
 }
 
 #[implement(super::Service)]
@@ -0,0 +1,176 @@
+#![cfg(test)]
+
+use std::{
+	collections::HashMap,
+	sync::{
+		Mutex,
+		atomic::{AtomicUsize, Ordering},
+	},
+};
+
+use conduwuit::{Err, Result, matrix::StateMap};
+use ruma::{EventId, OwnedEventId, OwnedServerName, ServerName};
+
+use super::{StateChange, fetch_servers::fetch_first, repair_state::state_changes};
+
+fn servers(names: &[&str]) -> Vec<OwnedServerName> {
+	names
+		.iter()
+		.map(|name| ServerName::parse(name).expect("valid server name"))
+		.collect()
+}
+
+/// Records the servers asked and the most requests in flight at once. Servers
+/// named `ok*` answer with their name; the others fail.
+#[derive(Default)]
+struct Recorder {
+	asked: Mutex<Vec<OwnedServerName>>,
+	in_flight: AtomicUsize,
+	most_in_flight: AtomicUsize,
+}
+
+impl Recorder {
+	async fn request(&self, server: &ServerName) -> Result<String> {
+		self.asked.lock().unwrap().push(server.to_owned());
+		let in_flight = self
+			.in_flight
+			.fetch_add(1, Ordering::SeqCst)
+			.saturating_add(1);
+		self.most_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+		tokio::task::yield_now().await;
+		self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+		if server.as_str().starts_with("ok") {
+			Ok(server.to_string())
+		} else {
+			Err!(Request(NotFound("Not found on {server}.")))
+		}
+	}
+
+	fn asked(&self) -> Vec<OwnedServerName> { self.asked.lock().unwrap().clone() }
+}
+
+#[tokio::test]
+async fn fetch_concurrency_limit() {
+	let servers = servers(&["a.example", "b.example", "c.example", "d.example", "e.example"]);
+	let recorder = Recorder::default();
+
+	let result = fetch_first(&servers, 2, |server| recorder.request(server)).await;
+	assert!(result.is_err());
+	assert_eq!(recorder.most_in_flight.load(Ordering::SeqCst), 2);
+
+	// every server is asked once, in order
+	assert_eq!(recorder.asked(), servers);
+}
+
+#[tokio::test]
+async fn fetch_at_least_one_in_flight() {
+	let servers = servers(&["a.example", "ok.example"]);
+	let recorder = Recorder::default();
+
+	let (server, result) = fetch_first(&servers, 0, |server| recorder.request(server))
+		.await
+		.expect("fetched");
+	assert_eq!(server, "ok.example");
+	assert_eq!(result, "ok.example");
+	assert_eq!(recorder.most_in_flight.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn fetch_stops_at_first_success() {
+	let servers = servers(&["a.example", "ok1.example", "ok2.example", "b.example"]);
+	let recorder = Recorder::default();
+
+	let (server, _) = fetch_first(&servers, 1, |server| recorder.request(server))
+		.await
+		.expect("fetched");
+	assert_eq!(server, "ok1.example");
+	assert_eq!(recorder.asked(), servers[..2]);
+}
+
+#[tokio::test]
+async fn fetch_without_servers() {
+	let result: Result<(OwnedServerName, ())> = fetch_first(&[], 3, |_| async { Ok(()) }).await;
+
+	assert!(result.unwrap_err().is_not_found());
+}
+
+#[tokio::test]
+async fn fetch_returns_last_error() {
+	let servers = servers(&["a.example", "b.example"]);
+	let errors: HashMap<_, _> = [("a.example", "first"), ("b.example", "last")].into();
+
+	let result: Result<(OwnedServerName, ())> = fetch_first(&servers, 1, |server| {
+		let error = errors[server.as_str()];
+		async move { Err!(Request(Forbidden("{error}"))) }
+	})
+	.await;
+
+	assert!(result.unwrap_err().message().ends_with("last"));
+}
+
+fn state(entries: &[(&str, &str, &str)]) -> StateMap<OwnedEventId> {
+	entries
+		.iter()
+		.map(|(event_type, state_key, event_id)| {
+			let event_id = EventId::parse(event_id).expect("valid event ID");
+			(((*event_type).into(), (*state_key).into()), event_id)
+		})
+		.collect()
+}
+
+fn change(
+	event_type: &str,
+	state_key: &str,
+	before: Option<&str>,
+	after: Option<&str>,
+) -> StateChange {
+	let event_id = |event_id| EventId::parse(event_id).expect("valid event ID");
+	StateChange {
+		event_type: event_type.into(),
+		state_key: state_key.into(),
+		before: before.map(event_id),
+		after: after.map(event_id),
+	}
+}
+
+#[test]
+fn state_changes_unchanged() {
+	let current = state(&[("m.room.create", "", "$create"), ("m.room.name", "", "$name")]);
+
+	assert!(state_changes(&current, &current.clone()).is_empty());
+	assert!(state_changes(&StateMap::new(), &StateMap::new()).is_empty());
+}
+
+#[test]
+fn state_changes_replaced_added_and_removed() {
+	let current = state(&[
+		("m.room.create", "", "$create"),
+		("m.room.member", "@alice:example.com", "$alice_join"),
+		("m.room.member", "@bob:example.com", "$bob_join"),
+		("m.room.topic", "", "$topic"),
+	]);
+	let resolved = state(&[
+		("m.room.create", "", "$create"),
+		("m.room.member", "@alice:example.com", "$alice_leave"),
+		("m.room.member", "@bob:example.com", "$bob_join"),
+		("m.room.name", "", "$name"),
+	]);
+
+	assert_eq!(state_changes(&current, &resolved), [
+		change("m.room.member", "@alice:example.com", Some("$alice_join"), Some("$alice_leave")),
+		change("m.room.name", "", None, Some("$name")),
+		change("m.room.topic", "", Some("$topic"), None),
+	]);
+}
+
+#[test]
+fn state_changes_everything_removed() {
+	let current = state(&[("m.room.name", "", "$name"), ("m.room.create", "", "$create")]);
+
+	assert_eq!(state_changes(&current, &StateMap::new()), [
+		change("m.room.create", "", Some("$create"), None),
+		change("m.room.name", "", Some("$name"), None),
+	]);
+}
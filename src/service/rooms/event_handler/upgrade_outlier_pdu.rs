@@ -133,6 +133,15 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 				.await?,
 	};
 
+	// Events rejected by a spam checker are soft failed as well
+	let soft_fail = soft_fail
+		|| self
+			.services
+			.spam_checker
+			.check_event_for_spam(&incoming_pdu)
+			.await
+			.is_err();
+
 	// 13. Use state resolution to find new room state
 
 	// We start looking at current room state now, so lets lock the room
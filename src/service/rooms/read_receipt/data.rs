@@ -4,18 +4,23 @@
 	Result,
 	utils::{ReadyExt, stream::TryIgnore},
 };
-use database::{Deserialized, Json, Map};
+use database::{Deserialized, Interfix, Json, Map};
 use futures::{Stream, StreamExt};
 use ruma::{
-	CanonicalJsonObject, RoomId, UserId,
-	events::{AnySyncEphemeralRoomEvent, receipt::ReceiptEvent},
+	CanonicalJsonObject, EventId, MilliSecondsSinceUnixEpoch, RoomId, UserId,
+	events::{
+		AnySyncEphemeralRoomEvent,
+		receipt::{ReceiptEvent, ReceiptThread},
+	},
 	serde::Raw,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{Dep, globals};
 
 pub(super) struct Data {
 	roomuserid_privateread: Arc<Map>,
+	roomuserthreadid_privateread: Arc<Map>,
 	roomuserid_lastprivatereadupdate: Arc<Map>,
 	services: Services,
 	readreceiptid_readreceipt: Arc<Map>,
@@ -27,11 +32,19 @@ struct Services {
 
 pub(super) type ReceiptItem<'a> = (&'a UserId, u64, Raw<AnySyncEphemeralRoomEvent>);
 
+/// A private read receipt of a user in a thread of a room.
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct PrivateRead {
+	pub(super) count: u64,
+	pub(super) ts: MilliSecondsSinceUnixEpoch,
+}
+
 impl Data {
 	pub(super) fn new(args: &crate::Args<'_>) -> Self {
 		let db = &args.db;
 		Self {
 			roomuserid_privateread: db["roomuserid_privateread"].clone(),
+			roomuserthreadid_privateread: db["roomuserthreadid_privateread"].clone(),
 			roomuserid_lastprivatereadupdate: db["roomuserid_lastprivatereadupdate"].clone(),
 			readreceiptid_readreceipt: db["readreceiptid_readreceipt"].clone(),
 			services: Services {
@@ -46,14 +59,20 @@ pub(super) async fn readreceipt_update(
 		room_id: &RoomId,
 		event: &ReceiptEvent,
 	) {
-		// Remove old entry
+		// Remove the old entry of the same thread; receipts in other threads stay
+		let thread = receipt_thread(event);
 		let last_possible_key = (room_id, u64::MAX);
 		self.readreceiptid_readreceipt
-			.rev_keys_from_raw(&last_possible_key)
+			.rev_stream_from_raw(&last_possible_key)
 			.ignore_err()
-			.ready_take_while(|key| key.starts_with(room_id.as_bytes()))
-			.ready_filter_map(|key| key.ends_with(user_id.as_bytes()).then_some(key))
-			.ready_for_each(|key| self.readreceiptid_readreceipt.del(key))
+			.ready_take_while(|(key, _)| key.starts_with(room_id.as_bytes()))
+			.ready_filter(|(key, _)| key.ends_with(user_id.as_bytes()))
+			.ready_filter(|(_, val)| {
+				serde_json::from_slice::<ReceiptEvent>(val)
+					.ok()
+					.is_none_or(|old| receipt_thread(&old) == thread)
+			})
+			.ready_for_each(|(key, _)| self.readreceiptid_readreceipt.del(key))
 			.await;
 
 		let count = self.services.globals.next_count().unwrap();
@@ -86,14 +105,47 @@ pub(super) fn readreceipts_since<'a>(
 			.ignore_err()
 	}
 
-	pub(super) fn private_read_set(&self, room_id: &RoomId, user_id: &UserId, pdu_count: u64) {
+	pub(super) fn private_read_set(
+		&self,
+		room_id: &RoomId,
+		user_id: &UserId,
+		pdu_count: u64,
+		thread: &ReceiptThread,
+	) {
 		let key = (room_id, user_id);
 		let next_count = self.services.globals.next_count().unwrap();
 
-		self.roomuserid_privateread.put(key, pdu_count);
+		if *thread == ReceiptThread::Unthreaded {
+			self.roomuserid_privateread.put(key, pdu_count);
+		}
+
+		let private_read = PrivateRead {
+			count: pdu_count,
+			ts: MilliSecondsSinceUnixEpoch::now(),
+		};
+
+		self.roomuserthreadid_privateread
+			.put((room_id, user_id, thread_key(thread)), Json(private_read));
 		self.roomuserid_lastprivatereadupdate.put(key, next_count);
 	}
 
+	/// Returns the private read receipts of the user in the room, by thread.
+	pub(super) fn private_reads<'a>(
+		&'a self,
+		room_id: &'a RoomId,
+		user_id: &'a UserId,
+	) -> impl Stream<Item = (ReceiptThread, PrivateRead)> + Send + 'a {
+		type Key<'a> = (&'a RoomId, &'a UserId, &'a str);
+
+		let prefix = (room_id, user_id, Interfix);
+		self.roomuserthreadid_privateread
+			.stream_prefix(&prefix)
+			.ignore_err()
+			.ready_filter_map(|((_, _, thread), private_read): (Key<'_>, PrivateRead)| {
+				Some((thread_from_key(thread)?, private_read))
+			})
+	}
+
 	pub(super) async fn private_read_get_count(
 		&self,
 		room_id: &RoomId,
@@ -116,3 +168,30 @@ pub(super) async fn last_privateread_update(
 			.unwrap_or(0)
 	}
 }
+
+/// The thread of the (single) receipt of a receipt event.
+pub(super) fn receipt_thread(event: &ReceiptEvent) -> Option<&ReceiptThread> {
+	event
+		.content
+		.values()
+		.flat_map(|receipts| receipts.values())
+		.flat_map(|users| users.values())
+		.map(|receipt| &receipt.thread)
+		.next()
+}
+
+pub(super) fn thread_key(thread: &ReceiptThread) -> &str {
+	match thread {
+		| ReceiptThread::Main => "main",
+		| ReceiptThread::Thread(root_id) => root_id.as_str(),
+		| _ => "",
+	}
+}
+
+pub(super) fn thread_from_key(key: &str) -> Option<ReceiptThread> {
+	match key {
+		| "" => Some(ReceiptThread::Unthreaded),
+		| "main" => Some(ReceiptThread::Main),
+		| root_id => EventId::parse(root_id).ok().map(ReceiptThread::Thread),
+	}
+}
@@ -1,18 +1,21 @@
 mod data;
+mod tests;
 
 use std::{collections::BTreeMap, sync::Arc};
 
 use conduwuit::{
-	Result, debug, err,
+	Err, Result, debug, err,
 	matrix::pdu::{PduCount, PduId, RawPduId},
 	warn,
 };
-use futures::{Stream, TryFutureExt, try_join};
+use futures::{Stream, StreamExt};
 use ruma::{
-	OwnedEventId, OwnedUserId, RoomId, UserId,
+	OwnedEventId, RoomId, UserId,
 	events::{
 		AnySyncEphemeralRoomEvent, SyncEphemeralRoomEvent,
-		receipt::{ReceiptEvent, ReceiptEventContent, Receipts},
+		receipt::{
+			Receipt, ReceiptEvent, ReceiptEventContent, ReceiptThread, ReceiptType, Receipts,
+		},
 	},
 	serde::Raw,
 };
@@ -62,37 +65,63 @@ pub async fn readreceipt_update(
 			.expect("room flush failed");
 	}
 
-	/// Gets the latest private read receipt from the user in the room
+	/// Gets the latest private read receipts from the user in the room, one per
+	/// thread
 	pub async fn private_read_get(
 		&self,
 		room_id: &RoomId,
 		user_id: &UserId,
 	) -> Result<Raw<AnySyncEphemeralRoomEvent>> {
-		let pdu_count = self.private_read_get_count(room_id, user_id).map_err(|e| {
-			err!(Database(warn!("No private read receipt was set in {room_id}: {e}")))
-		});
-		let shortroomid = self.services.short.get_shortroomid(room_id).map_err(|e| {
-			err!(Database(warn!("Short room ID does not exist in database for {room_id}: {e}")))
-		});
-		let (pdu_count, shortroomid) = try_join!(pdu_count, shortroomid)?;
-
-		let shorteventid = PduCount::Normal(pdu_count);
-		let pdu_id: RawPduId = PduId { shortroomid, shorteventid }.into();
-
-		let pdu = self.services.timeline.get_pdu_from_id(&pdu_id).await?;
-
-		let event_id: OwnedEventId = pdu.event_id;
-		let user_id: OwnedUserId = user_id.to_owned();
-		let content: BTreeMap<OwnedEventId, Receipts> = BTreeMap::from_iter([(
-			event_id,
-			BTreeMap::from_iter([(
-				ruma::events::receipt::ReceiptType::ReadPrivate,
-				BTreeMap::from_iter([(user_id, ruma::events::receipt::Receipt {
-					ts: None, // TODO: start storing the timestamp so we can return one
-					thread: ruma::events::receipt::ReceiptThread::Unthreaded,
-				})]),
-			)]),
-		)]);
+		let shortroomid = self
+			.services
+			.short
+			.get_shortroomid(room_id)
+			.await
+			.map_err(|e| {
+				err!(Database(warn!(
+					"Short room ID does not exist in database for {room_id}: {e}"
+				)))
+			})?;
+
+		let mut private_reads: Vec<_> = self
+			.db
+			.private_reads(room_id, user_id)
+			.map(|(thread, private_read)| (thread, private_read.count, Some(private_read.ts)))
+			.collect()
+			.await;
+
+		// Receipts set before their thread and timestamp were stored
+		if private_reads.is_empty() {
+			let pdu_count = self
+				.private_read_get_count(room_id, user_id)
+				.await
+				.map_err(|e| {
+					err!(Database(warn!("No private read receipt was set in {room_id}: {e}")))
+				})?;
+
+			private_reads.push((ReceiptThread::Unthreaded, pdu_count, None));
+		}
+
+		let mut content: BTreeMap<OwnedEventId, Receipts> = BTreeMap::new();
+		for (thread, pdu_count, ts) in private_reads {
+			let shorteventid = PduCount::Normal(pdu_count);
+			let pdu_id: RawPduId = PduId { shortroomid, shorteventid }.into();
+			let Ok(pdu) = self.services.timeline.get_pdu_from_id(&pdu_id).await else {
+				continue;
+			};
+
+			content
+				.entry(pdu.event_id)
+				.or_default()
+				.entry(ReceiptType::ReadPrivate)
+				.or_default()
+				.insert(user_id.to_owned(), Receipt { ts, thread });
+		}
+
+		if content.is_empty() {
+			return Err!(Database("No event found for the private read receipts in {room_id}."));
+		}
+
 		let receipt_event_content = ReceiptEventContent(content);
 		let receipt_sync_event = SyncEphemeralRoomEvent { content: receipt_event_content };
 
@@ -114,14 +143,20 @@ pub fn readreceipts_since<'a>(
 		self.db.readreceipts_since(room_id, since)
 	}
 
-	/// Sets a private read marker at PDU `count`.
+	/// Sets a private read marker at PDU `count` in the thread.
 	#[inline]
 	#[tracing::instrument(skip(self), level = "debug")]
-	pub fn private_read_set(&self, room_id: &RoomId, user_id: &UserId, count: u64) {
-		self.db.private_read_set(room_id, user_id, count);
+	pub fn private_read_set(
+		&self,
+		room_id: &RoomId,
+		user_id: &UserId,
+		count: u64,
+		thread: &ReceiptThread,
+	) {
+		self.db.private_read_set(room_id, user_id, count, thread);
 	}
 
-	/// Returns the private read marker PDU count.
+	/// Returns the unthreaded private read marker PDU count.
 	#[inline]
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub async fn private_read_get_count(
@@ -151,8 +186,14 @@ pub fn pack_receipts<I>(receipts: I) -> Raw<SyncEphemeralRoomEvent<ReceiptEventC
 		);
 		match receipt {
 			| Ok(value) =>
-				for (event, receipt) in value.content {
-					json.insert(event, receipt);
+				for (event, receipts) in value.content {
+					let event_receipts: &mut Receipts = json.entry(event).or_default();
+					for (receipt_type, users) in receipts {
+						event_receipts
+							.entry(receipt_type)
+							.or_default()
+							.extend(users);
+					}
 				},
 			| _ => {
 				debug!("failed to parse receipt: {:?}", receipt);
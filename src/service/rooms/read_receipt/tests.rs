@@ -0,0 +1,120 @@
+#![cfg(test)]
+
+use ruma::{
+	event_id,
+	events::{
+		AnySyncEphemeralRoomEvent, SyncEphemeralRoomEvent,
+		receipt::{ReceiptEvent, ReceiptEventContent, ReceiptThread, ReceiptType},
+	},
+	owned_event_id, room_id,
+	serde::Raw,
+	user_id,
+};
+use serde_json::{json, value::to_raw_value};
+
+use super::{
+	data::{receipt_thread, thread_from_key, thread_key},
+	pack_receipts,
+};
+
+fn receipt(event_id: &str, user_id: &str, thread: Option<&str>) -> serde_json::Value {
+	let mut receipt = json!({ "ts": 1 });
+	if let Some(thread) = thread {
+		receipt["thread_id"] = thread.into();
+	}
+
+	json!({
+		"type": "m.receipt",
+		"content": {
+			event_id: {
+				"m.read": {
+					user_id: receipt,
+				},
+			},
+		},
+	})
+}
+
+fn raw(value: &serde_json::Value) -> Raw<AnySyncEphemeralRoomEvent> {
+	Raw::from_json(to_raw_value(value).expect("valid json"))
+}
+
+#[test]
+fn thread_keys_round_trip() {
+	for thread in [
+		ReceiptThread::Unthreaded,
+		ReceiptThread::Main,
+		ReceiptThread::Thread(owned_event_id!("$root:example.com")),
+	] {
+		assert_eq!(thread_from_key(thread_key(&thread)), Some(thread));
+	}
+}
+
+#[test]
+fn thread_keys() {
+	assert_eq!(thread_key(&ReceiptThread::Unthreaded), "");
+	assert_eq!(thread_key(&ReceiptThread::Main), "main");
+	assert_eq!(
+		thread_key(&ReceiptThread::Thread(owned_event_id!("$root:example.com"))),
+		"$root:example.com"
+	);
+	assert_eq!(thread_from_key("not an event id"), None);
+}
+
+#[test]
+fn thread_of_receipt() {
+	let mut value =
+		receipt("$event:example.com", "@alice:example.com", Some("$root:example.com"));
+	value["room_id"] = json!(room_id!("!room:example.com"));
+	let event: ReceiptEvent = serde_json::from_value(value).expect("valid receipt event");
+	assert_eq!(
+		receipt_thread(&event),
+		Some(&ReceiptThread::Thread(owned_event_id!("$root:example.com")))
+	);
+
+	let mut value = receipt("$event:example.com", "@alice:example.com", None);
+	value["room_id"] = json!(room_id!("!room:example.com"));
+	let event: ReceiptEvent = serde_json::from_value(value).expect("valid receipt event");
+	assert_eq!(receipt_thread(&event), Some(&ReceiptThread::Unthreaded));
+}
+
+#[test]
+fn packed_receipts_merge() {
+	let receipts = [
+		receipt("$a:example.com", "@alice:example.com", None),
+		receipt("$a:example.com", "@bob:example.com", Some("main")),
+		receipt("$b:example.com", "@alice:example.com", Some("$root:example.com")),
+	];
+
+	let packed = pack_receipts(receipts.iter().map(raw));
+	let packed: SyncEphemeralRoomEvent<ReceiptEventContent> =
+		packed.deserialize().expect("valid receipt event");
+
+	let content = packed.content;
+	assert_eq!(content.len(), 2);
+
+	let read_a = &content[event_id!("$a:example.com")][&ReceiptType::Read];
+	assert_eq!(read_a.len(), 2);
+	assert_eq!(read_a[user_id!("@alice:example.com")].thread, ReceiptThread::Unthreaded);
+	assert_eq!(read_a[user_id!("@bob:example.com")].thread, ReceiptThread::Main);
+
+	let read_b = &content[event_id!("$b:example.com")][&ReceiptType::Read];
+	assert_eq!(
+		read_b[user_id!("@alice:example.com")].thread,
+		ReceiptThread::Thread(owned_event_id!("$root:example.com"))
+	);
+}
+
+#[test]
+fn packed_receipts_skip_invalid() {
+	let receipts = [
+		json!({ "type": "m.receipt", "content": "invalid" }),
+		receipt("$a:example.com", "@alice:example.com", None),
+	];
+
+	let packed = pack_receipts(receipts.iter().map(raw));
+	let packed: SyncEphemeralRoomEvent<ReceiptEventContent> =
+		packed.deserialize().expect("valid receipt event");
+
+	assert_eq!(packed.content.len(), 1);
+}
@@ -101,6 +101,8 @@ async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 
 	async fn clear_cache(&self) { self.roomid_spacehierarchy_cache.lock().await.clear(); }
 
+	fn cached_columns(&self) -> &[&str] { &["roomid_shortstatehash"] }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
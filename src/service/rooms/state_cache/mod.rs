@@ -1,3 +1,6 @@
+mod recompute;
+mod tests;
+
 use std::{
 	collections::{HashMap, HashSet},
 	sync::{Arc, RwLock},
@@ -28,6 +31,7 @@
 	serde::Raw,
 };
 
+pub use self::recompute::Recomputed;
 use crate::{Dep, account_data, appservice::RegistrationInfo, config, globals, rooms, users};
 
 pub struct Service {
@@ -41,6 +45,7 @@ struct Services {
 	config: Dep<config::Service>,
 	globals: Dep<globals::Service>,
 	metadata: Dep<rooms::metadata::Service>,
+	state: Dep<rooms::state::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	users: Dep<users::Service>,
 }
@@ -75,6 +80,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				config: args.depend::<config::Service>("config"),
 				globals: args.depend::<globals::Service>("globals"),
 				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				users: args.depend::<users::Service>("users"),
@@ -267,10 +273,10 @@ pub async fn update_membership(
 				}
 
 				self.mark_as_invited(user_id, room_id, last_state, invite_via)
-					.await;
+					.await?;
 			},
 			| MembershipState::Leave | MembershipState::Ban => {
-				self.mark_as_left(user_id, room_id);
+				self.mark_as_left(user_id, room_id)?;
 
 				if self.services.globals.user_is_local(user_id)
 					&& (self.services.config.forget_forced_upon_leave
@@ -362,7 +368,9 @@ pub fn mark_as_joined(&self, user_id: &UserId, room_id: &RoomId) {
 	/// recommended to use this directly. You most likely should use
 	/// `update_membership` instead
 	#[tracing::instrument(skip(self), level = "debug")]
-	pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) {
+	pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) -> Result {
+		let count = self.services.globals.next_count()?;
+
 		let userroom_id = (user_id, room_id);
 		let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
 
@@ -377,7 +385,7 @@ pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) {
 			.raw_put(&userroom_id, Json(leftstate));
 		self.db
 			.roomuserid_leftcount
-			.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+			.raw_aput::<8, _, _>(&roomuser_id, count);
 
 		self.db.userroomid_joined.remove(&userroom_id);
 		self.db.roomuserid_joined.remove(&roomuser_id);
@@ -389,6 +397,8 @@ pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) {
 		self.db.roomuserid_knockedcount.remove(&roomuser_id);
 
 		self.db.roomid_inviteviaservers.remove(room_id);
+
+		Ok(())
 	}
 
 	/// Direct DB function to directly mark a user as knocked. It is not
@@ -400,7 +410,9 @@ pub fn mark_as_knocked(
 		user_id: &UserId,
 		room_id: &RoomId,
 		knocked_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
-	) {
+	) -> Result {
+		let count = self.services.globals.next_count()?;
+
 		let userroom_id = (user_id, room_id);
 		let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
 
@@ -412,7 +424,7 @@ pub fn mark_as_knocked(
 			.raw_put(&userroom_id, Json(knocked_state.unwrap_or_default()));
 		self.db
 			.roomuserid_knockedcount
-			.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+			.raw_aput::<8, _, _>(&roomuser_id, count);
 
 		self.db.userroomid_joined.remove(&userroom_id);
 		self.db.roomuserid_joined.remove(&roomuser_id);
@@ -424,6 +436,8 @@ pub fn mark_as_knocked(
 		self.db.roomuserid_leftcount.remove(&roomuser_id);
 
 		self.db.roomid_inviteviaservers.remove(room_id);
+
+		Ok(())
 	}
 
 	/// Makes a user forget a room.
@@ -948,7 +962,9 @@ pub async fn mark_as_invited(
 		room_id: &RoomId,
 		last_state: Option<Vec<Raw<AnyStrippedStateEvent>>>,
 		invite_via: Option<Vec<OwnedServerName>>,
-	) {
+	) -> Result {
+		let count = self.services.globals.next_count()?;
+
 		let roomuser_id = (room_id, user_id);
 		let roomuser_id = serialize_key(roomuser_id).expect("failed to serialize roomuser_id");
 
@@ -960,7 +976,7 @@ pub async fn mark_as_invited(
 			.raw_put(&userroom_id, Json(last_state.unwrap_or_default()));
 		self.db
 			.roomuserid_invitecount
-			.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+			.raw_aput::<8, _, _>(&roomuser_id, count);
 
 		self.db.userroomid_joined.remove(&userroom_id);
 		self.db.roomuserid_joined.remove(&roomuser_id);
@@ -974,6 +990,8 @@ pub async fn mark_as_invited(
 		if let Some(servers) = invite_via.filter(is_not_empty!()) {
 			self.add_servers_invite_via(room_id, servers).await;
 		}
+
+		Ok(())
 	}
 
 	#[tracing::instrument(level = "debug", skip(self, servers))]
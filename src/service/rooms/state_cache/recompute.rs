@@ -0,0 +1,221 @@
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt::{self, Display},
+};
+
+use conduwuit::{PduEvent, Result, implement, utils::stream::ReadyExt};
+use futures::{StreamExt, TryStreamExt};
+use ruma::{
+	OwnedRoomId, OwnedServerName, OwnedUserId, RoomId, UserId,
+	events::{
+		TimelineEventType,
+		room::member::{MembershipState, RoomMemberEventContent},
+	},
+};
+
+/// Differences between the membership indexes of a room and its current
+/// state, as found and corrected by `recompute_memberships()`.
+#[derive(Debug)]
+pub struct Recomputed {
+	pub room_id: OwnedRoomId,
+
+	/// Users whose indexed membership disagreed with the state, with their
+	/// indexed membership and their membership in the state.
+	pub users: Vec<(OwnedUserId, Option<MembershipState>, Option<MembershipState>)>,
+
+	/// Joined and invited counts before and after.
+	pub joined_count: (u64, u64),
+	pub invited_count: (u64, u64),
+
+	/// Servers added to and removed from the room's servers.
+	pub servers_added: Vec<OwnedServerName>,
+	pub servers_removed: Vec<OwnedServerName>,
+}
+
+/// Recompute every membership-derived index of the room from its current
+/// state: the joined, invited, knocked and left users, the joined and invited
+/// counts and the servers in the room. Returns None for a room without state,
+/// such as one only known from an invite over federation.
+///
+/// Invited and knocked users keep the stripped state they were shown; if they
+/// have none it is rebuilt from their member event.
+#[implement(super::Service)]
+#[tracing::instrument(skip(self), level = "info")]
+pub async fn recompute_memberships(&self, room_id: &RoomId) -> Result<Option<Recomputed>> {
+	if self
+		.services
+		.state
+		.get_room_shortstatehash(room_id)
+		.await
+		.is_err()
+	{
+		return Ok(None);
+	}
+
+	let state: BTreeMap<OwnedUserId, (MembershipState, PduEvent)> = self
+		.services
+		.state_accessor
+		.room_state_full_pdus(room_id)
+		.try_collect::<Vec<_>>()
+		.await?
+		.into_iter()
+		.filter(|pdu| pdu.kind == TimelineEventType::RoomMember)
+		.filter_map(|pdu| {
+			let content: RoomMemberEventContent = pdu.get_content().ok()?;
+			let user_id = UserId::parse(pdu.state_key.as_deref()?).ok()?;
+			Some((user_id, (content.membership, pdu)))
+		})
+		.collect();
+
+	let joined: BTreeSet<OwnedUserId> = self
+		.room_members(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut users = joined.clone();
+	users.extend(state.keys().cloned());
+	self.room_members_invited(room_id)
+		.chain(self.room_members_knocked(room_id))
+		.ready_for_each(|user_id| {
+			users.insert(user_id.to_owned());
+		})
+		.await;
+
+	let servers_before: BTreeSet<OwnedServerName> = self
+		.room_servers(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let joined_before = self.room_joined_count(room_id).await.unwrap_or(0);
+	let invited_before = self.room_invited_count(room_id).await.unwrap_or(0);
+
+	let mut changed = Vec::new();
+	for user_id in users {
+		let member = state.get(&user_id);
+		let expected = member.map(|(membership, _)| expected_membership(membership));
+		let indexed = self
+			.user_membership(&user_id, room_id)
+			.await
+			.filter(|membership| *membership != MembershipState::Ban);
+
+		if agrees(expected.as_ref(), indexed.as_ref(), joined.contains(&user_id)) {
+			continue;
+		}
+
+		match (&expected, member) {
+			| (Some(MembershipState::Join), _) => {
+				self.mark_as_once_joined(&user_id, room_id);
+				self.mark_as_joined(&user_id, room_id);
+			},
+			| (Some(MembershipState::Invite), Some((_, pdu))) => {
+				let invite_state = match self.invite_state(&user_id, room_id).await {
+					| Ok(invite_state) => invite_state,
+					| Err(_) => self.services.state.summary_stripped(pdu).await,
+				};
+
+				self.mark_as_invited(&user_id, room_id, Some(invite_state), None)
+					.await?;
+			},
+			| (Some(MembershipState::Knock), Some((_, pdu))) => {
+				let knock_state = match self.knock_state(&user_id, room_id).await {
+					| Ok(knock_state) => knock_state,
+					| Err(_) => self.services.state.summary_stripped(pdu).await,
+				};
+
+				self.mark_as_knocked(&user_id, room_id, Some(knock_state))?;
+			},
+			| _ => self.mark_as_left(&user_id, room_id)?,
+		}
+
+		changed.push((user_id, indexed, expected));
+	}
+
+	self.update_joined_count(room_id).await;
+
+	let servers_after: BTreeSet<OwnedServerName> = self
+		.room_servers(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	Ok(Some(Recomputed {
+		room_id: room_id.to_owned(),
+		users: changed,
+		joined_count: (joined_before, self.room_joined_count(room_id).await.unwrap_or(0)),
+		invited_count: (invited_before, self.room_invited_count(room_id).await.unwrap_or(0)),
+		servers_added: servers_after.difference(&servers_before).cloned().collect(),
+		servers_removed: servers_before.difference(&servers_after).cloned().collect(),
+	}))
+}
+
+/// The membership indexed for a membership in the state; anything but a join,
+/// invite or knock is indexed as left.
+pub(super) fn expected_membership(membership: &MembershipState) -> MembershipState {
+	match membership {
+		| MembershipState::Join | MembershipState::Invite | MembershipState::Knock =>
+			membership.clone(),
+		| _ => MembershipState::Leave,
+	}
+}
+
+/// Whether the indexed membership of a user agrees with the state, and with
+/// whether the user is among the joined members of the room. No membership
+/// and a left one are equivalent.
+pub(super) fn agrees(
+	expected: Option<&MembershipState>,
+	indexed: Option<&MembershipState>,
+	is_joined: bool,
+) -> bool {
+	let consistent = is_joined == (indexed == Some(&MembershipState::Join));
+	let agrees = match (expected, indexed) {
+		| (None | Some(MembershipState::Leave), None | Some(MembershipState::Leave)) => true,
+		| (expected, indexed) => expected == indexed,
+	};
+
+	consistent && agrees
+}
+
+impl Recomputed {
+	/// True if the indexes agreed with the state.
+	#[must_use]
+	pub fn is_unchanged(&self) -> bool {
+		self.users.is_empty()
+			&& self.joined_count.0 == self.joined_count.1
+			&& self.invited_count.0 == self.invited_count.1
+			&& self.servers_added.is_empty()
+			&& self.servers_removed.is_empty()
+	}
+}
+
+impl Display for Recomputed {
+	fn fmt(&self, out: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(out, "{}:", self.room_id)?;
+		for (user_id, indexed, state) in &self.users {
+			let indexed = indexed.as_ref().map_or("none", MembershipState::as_str);
+			let state = state.as_ref().map_or("none", MembershipState::as_str);
+			writeln!(out, "- {user_id}: {indexed} -> {state}")?;
+		}
+
+		let (joined_before, joined_after) = self.joined_count;
+		if joined_before != joined_after {
+			writeln!(out, "- joined count: {joined_before} -> {joined_after}")?;
+		}
+
+		let (invited_before, invited_after) = self.invited_count;
+		if invited_before != invited_after {
+			writeln!(out, "- invited count: {invited_before} -> {invited_after}")?;
+		}
+
+		for server in &self.servers_added {
+			writeln!(out, "- server added: {server}")?;
+		}
+
+		for server in &self.servers_removed {
+			writeln!(out, "- server removed: {server}")?;
+		}
+
+		Ok(())
+	}
+}
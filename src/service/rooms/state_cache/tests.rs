@@ -0,0 +1,55 @@
+#![cfg(test)]
+
+use ruma::events::room::member::MembershipState;
+
+use super::recompute::{agrees, expected_membership};
+
+#[test]
+fn expected_memberships() {
+	for membership in [MembershipState::Join, MembershipState::Invite, MembershipState::Knock] {
+		assert_eq!(expected_membership(&membership), membership);
+	}
+
+	for membership in [MembershipState::Leave, MembershipState::Ban] {
+		assert_eq!(expected_membership(&membership), MembershipState::Leave);
+	}
+}
+
+#[test]
+fn invite_agrees() {
+	let invite = MembershipState::Invite;
+	assert!(agrees(Some(&invite), Some(&invite), false));
+	assert!(!agrees(Some(&invite), None, false));
+	assert!(!agrees(Some(&invite), Some(&MembershipState::Knock), false));
+	assert!(!agrees(Some(&invite), Some(&MembershipState::Join), true));
+
+	// indexed as invited, but also among the joined members
+	assert!(!agrees(Some(&invite), Some(&invite), true));
+}
+
+#[test]
+fn knock_agrees() {
+	let knock = MembershipState::Knock;
+	assert!(agrees(Some(&knock), Some(&knock), false));
+	assert!(!agrees(Some(&knock), None, false));
+	assert!(!agrees(Some(&knock), Some(&MembershipState::Invite), false));
+	assert!(!agrees(Some(&knock), Some(&MembershipState::Leave), false));
+}
+
+#[test]
+fn join_agrees() {
+	let join = MembershipState::Join;
+	assert!(agrees(Some(&join), Some(&join), true));
+	assert!(!agrees(Some(&join), Some(&join), false));
+	assert!(!agrees(Some(&join), None, true));
+}
+
+#[test]
+fn leave_agrees_with_none() {
+	let leave = MembershipState::Leave;
+	assert!(agrees(None, None, false));
+	assert!(agrees(Some(&leave), None, false));
+	assert!(agrees(None, Some(&leave), false));
+	assert!(!agrees(None, Some(&leave), true));
+	assert!(!agrees(Some(&leave), Some(&MembershipState::Invite), false));
+}
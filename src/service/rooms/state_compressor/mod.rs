@@ -112,6 +112,8 @@ async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 
 	async fn clear_cache(&self) { self.stateinfo_cache.lock().expect("locked").clear(); }
 
+	fn cached_columns(&self) -> &[&str] { &["shortstatehash_statediff"] }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
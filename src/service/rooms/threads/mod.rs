@@ -1,3 +1,6 @@
+mod subscription;
+mod tests;
+
 use std::{collections::BTreeMap, sync::Arc};
 
 use conduwuit::{
@@ -16,7 +19,8 @@
 };
 use serde_json::json;
 
-use crate::{Dep, rooms, rooms::short::ShortRoomId};
+pub use self::subscription::ThreadSubscription;
+use crate::{Dep, globals, rooms, rooms::short::ShortRoomId};
 
 pub struct Service {
 	db: Data,
@@ -24,12 +28,15 @@ pub struct Service {
 }
 
 struct Services {
+	globals: Dep<globals::Service>,
 	short: Dep<rooms::short::Service>,
 	timeline: Dep<rooms::timeline::Service>,
 }
 
 pub(super) struct Data {
 	threadid_userids: Arc<Map>,
+	userroomthreadid_subscription: Arc<Map>,
+	userroomthreadid_unsubscribed: Arc<Map>,
 }
 
 impl crate::Service for Service {
@@ -37,8 +44,11 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			db: Data {
 				threadid_userids: args.db["threadid_userids"].clone(),
+				userroomthreadid_subscription: args.db["userroomthreadid_subscription"].clone(),
+				userroomthreadid_unsubscribed: args.db["userroomthreadid_unsubscribed"].clone(),
 			},
 			services: Services {
+				globals: args.depend::<globals::Service>("globals"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
 			},
@@ -0,0 +1,169 @@
+use conduwuit::{Error, Result, err, implement};
+use database::{Deserialized, Json};
+use http::StatusCode;
+use ruma::{
+	EventId, RoomId, UserId,
+	api::client::error::{Error as RumaError, ErrorBody},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// A subscription of a user to a thread (MSC4306).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ThreadSubscription {
+	/// Whether the client subscribed the user on their behalf, e.g. when they
+	/// replied in the thread, rather than the user subscribing themselves.
+	pub automatic: bool,
+}
+
+/// Subscribes the user to the thread. An automatic subscription is made on
+/// behalf of the user because of the `automatic` event; it does not replace
+/// an existing subscription, and conflicts with an unsubscription after the
+/// event.
+#[implement(super::Service)]
+pub async fn subscribe_thread(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	root_id: &EventId,
+	automatic: Option<&EventId>,
+) -> Result {
+	let key = (user_id, room_id, root_id);
+	if let Some(event_id) = automatic {
+		if self
+			.thread_subscription(user_id, room_id, root_id)
+			.await
+			.is_ok()
+		{
+			return Ok(());
+		}
+
+		let count = self
+			.services
+			.timeline
+			.get_pdu_count(event_id)
+			.await
+			.map_err(|_| err!(Request(NotFound("Event not found."))))?;
+
+		let unsubscribed: Option<u64> = self
+			.db
+			.userroomthreadid_unsubscribed
+			.qry(&key)
+			.await
+			.deserialized()
+			.ok();
+
+		if unsubscribed_since(count.into_unsigned(), unsubscribed) {
+			return Err(conflicting_unsubscription());
+		}
+	}
+
+	let subscription = ThreadSubscription { automatic: automatic.is_some() };
+
+	self.db.userroomthreadid_unsubscribed.del(key);
+	self.db
+		.userroomthreadid_subscription
+		.put(key, Json(subscription));
+
+	Ok(())
+}
+
+/// Whether the user unsubscribed from the thread, at the count
+/// `unsubscribed`, no earlier than the event at `count`.
+pub(super) fn unsubscribed_since(count: u64, unsubscribed: Option<u64>) -> bool {
+	unsubscribed.is_some_and(|unsubscribed| count <= unsubscribed)
+}
+
+fn conflicting_unsubscription() -> Error {
+	let body = ErrorBody::Json(json!({
+		"errcode": "IO.ELEMENT.MSC4306.M_CONFLICTING_UNSUBSCRIPTION",
+		"error": "The user unsubscribed from the thread after this event.",
+	}));
+
+	RumaError::new(StatusCode::CONFLICT, body).into()
+}
+
+/// Unsubscribes the user from the thread. They are no longer subscribed to it
+/// for participating in it, and automatic subscriptions for events up to now
+/// are refused.
+#[implement(super::Service)]
+pub fn unsubscribe_thread(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	root_id: &EventId,
+) -> Result {
+	let key = (user_id, room_id, root_id);
+	let count = self.services.globals.next_count()?;
+
+	self.db.userroomthreadid_subscription.del(key);
+	self.db.userroomthreadid_unsubscribed.put(key, count);
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+pub async fn thread_subscription(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	root_id: &EventId,
+) -> Result<ThreadSubscription> {
+	let key = (user_id, room_id, root_id);
+	self.db
+		.userroomthreadid_subscription
+		.qry(&key)
+		.await
+		.deserialized()
+}
+
+/// Whether events in the thread notify the user: they subscribed to it, or
+/// they participate in it and did not unsubscribe.
+#[implement(super::Service)]
+pub async fn is_subscribed(&self, user_id: &UserId, room_id: &RoomId, root_id: &EventId) -> bool {
+	let subscribed = self
+		.thread_subscription(user_id, room_id, root_id)
+		.await
+		.is_ok();
+
+	let key = (user_id, room_id, root_id);
+	let unsubscribed = self
+		.db
+		.userroomthreadid_unsubscribed
+		.qry(&key)
+		.await
+		.is_ok();
+
+	let participates = !subscribed && !unsubscribed && self.participates(user_id, root_id).await;
+
+	notifies(subscribed, unsubscribed, participates)
+}
+
+/// Whether a user subscribed to a thread, unsubscribed from it, or
+/// participating in it is notified of its events. A subscription is removed
+/// on unsubscribing and the other way around, so both are not expected;
+/// should they be, the subscription wins.
+pub(super) fn notifies(subscribed: bool, unsubscribed: bool, participates: bool) -> bool {
+	subscribed || (!unsubscribed && participates)
+}
+
+/// Whether the user replied in the thread, or sent its root.
+#[implement(super::Service)]
+async fn participates(&self, user_id: &UserId, root_id: &EventId) -> bool {
+	let Ok(root_pdu_id) = self.services.timeline.get_pdu_id(root_id).await else {
+		return false;
+	};
+
+	match self.get_participants(&root_pdu_id).await {
+		| Ok(participants) => participants
+			.iter()
+			.any(|participant| *participant == *user_id),
+		// The thread has no replies yet; the root's sender started it
+		| Err(_) => self
+			.services
+			.timeline
+			.get_pdu_from_id(&root_pdu_id)
+			.await
+			.is_ok_and(|root| *root.sender == *user_id),
+	}
+}
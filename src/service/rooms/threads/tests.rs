@@ -0,0 +1,34 @@
+#![cfg(test)]
+
+use super::subscription::{notifies, unsubscribed_since};
+
+#[test]
+fn automatic_subscription_after_unsubscription() {
+	// never unsubscribed
+	assert!(!unsubscribed_since(5, None));
+
+	// unsubscribed before the event which made the client subscribe
+	assert!(!unsubscribed_since(5, Some(4)));
+
+	// unsubscribed after the event, or at the same count
+	assert!(unsubscribed_since(5, Some(6)));
+	assert!(unsubscribed_since(5, Some(5)));
+}
+
+#[test]
+fn subscription_notifies() {
+	assert!(notifies(true, false, false));
+	assert!(notifies(true, false, true));
+}
+
+#[test]
+fn unsubscription_overrides_participation() {
+	assert!(!notifies(false, true, true));
+	assert!(!notifies(false, true, false));
+}
+
+#[test]
+fn participation_notifies() {
+	assert!(notifies(false, false, true));
+	assert!(!notifies(false, false, false));
+}
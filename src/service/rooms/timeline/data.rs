@@ -19,6 +19,8 @@ pub(super) struct Data {
 	pduid_pdu: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
 	userroomid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
 	pub(super) db: Arc<Database>,
 	services: Services,
 }
@@ -38,6 +40,8 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			pduid_pdu: db["pduid_pdu"].clone(),
 			userroomid_highlightcount: db["userroomid_highlightcount"].clone(),
 			userroomid_notificationcount: db["userroomid_notificationcount"].clone(),
+			userroomthreadid_highlightcount: db["userroomthreadid_highlightcount"].clone(),
+			userroomthreadid_notificationcount: db["userroomthreadid_notificationcount"].clone(),
 			db: args.db.clone(),
 			services: Services {
 				short: args.depend::<rooms::short::Service>("rooms::short"),
@@ -269,26 +273,43 @@ fn each_pdu((pdu_id, pdu): KeyVal<'_>, user_id: Option<&UserId>) -> Result<PdusI
 		Ok((pdu_id.pdu_count(), pdu))
 	}
 
+	/// Increments the notification counts of the users in the room, and in the
+	/// thread of the event, if any.
 	pub(super) fn increment_notification_counts(
 		&self,
 		room_id: &RoomId,
+		thread_root: Option<&EventId>,
 		notifies: Vec<OwnedUserId>,
 		highlights: Vec<OwnedUserId>,
 	) {
 		let _cork = self.db.cork();
 
-		for user in notifies {
-			let mut userroom_id = user.as_bytes().to_vec();
-			userroom_id.push(0xFF);
-			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_notificationcount, &userroom_id);
-		}
-
-		for user in highlights {
-			let mut userroom_id = user.as_bytes().to_vec();
-			userroom_id.push(0xFF);
-			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_highlightcount, &userroom_id);
+		let counts = [
+			(
+				notifies,
+				&self.userroomid_notificationcount,
+				&self.userroomthreadid_notificationcount,
+			),
+			(
+				highlights,
+				&self.userroomid_highlightcount,
+				&self.userroomthreadid_highlightcount,
+			),
+		];
+
+		for (users, room_counts, thread_counts) in counts {
+			for user in users {
+				let mut userroom_id = user.as_bytes().to_vec();
+				userroom_id.push(0xFF);
+				userroom_id.extend_from_slice(room_id.as_bytes());
+				increment(room_counts, &userroom_id);
+
+				if let Some(thread_root) = thread_root {
+					userroom_id.push(0xFF);
+					userroom_id.extend_from_slice(thread_root.as_bytes());
+					increment(thread_counts, &userroom_id);
+				}
+			}
 		}
 	}
 
@@ -34,6 +34,7 @@
 	events::{
 		GlobalAccountDataEventType, StateEventType, TimelineEventType,
 		push_rules::PushRulesEvent,
+		receipt::ReceiptThread,
 		room::{
 			create::RoomCreateEventContent,
 			encrypted::Relation,
@@ -53,9 +54,9 @@
 use crate::{
 	Dep, account_data, admin, appservice,
 	appservice::NamespaceRegex,
-	globals, pusher, rooms,
+	globals, policy, pusher, replica, rooms,
 	rooms::{short::ShortRoomId, state_compressor::CompressedState},
-	sending, server_keys, users,
+	sending, server_keys, spam_checker, users,
 };
 
 // Update Relationships
@@ -93,14 +94,17 @@ struct Services {
 	admin: Dep<admin::Service>,
 	alias: Dep<rooms::alias::Service>,
 	globals: Dep<globals::Service>,
+	policy: Dep<policy::Service>,
 	short: Dep<rooms::short::Service>,
 	state: Dep<rooms::state::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	pdu_metadata: Dep<rooms::pdu_metadata::Service>,
 	read_receipt: Dep<rooms::read_receipt::Service>,
+	replica: Dep<replica::Service>,
 	sending: Dep<sending::Service>,
 	server_keys: Dep<server_keys::Service>,
+	spam_checker: Dep<spam_checker::Service>,
 	user: Dep<rooms::user::Service>,
 	users: Dep<users::Service>,
 	pusher: Dep<pusher::Service>,
@@ -124,6 +128,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				admin: args.depend::<admin::Service>("admin"),
 				alias: args.depend::<rooms::alias::Service>("rooms::alias"),
 				globals: args.depend::<globals::Service>("globals"),
+				policy: args.depend::<policy::Service>("policy"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
@@ -131,8 +136,10 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				pdu_metadata: args.depend::<rooms::pdu_metadata::Service>("rooms::pdu_metadata"),
 				read_receipt: args.depend::<rooms::read_receipt::Service>("rooms::read_receipt"),
+				replica: args.depend::<replica::Service>("replica"),
 				sending: args.depend::<sending::Service>("sending"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
+				spam_checker: args.depend::<spam_checker::Service>("spam_checker"),
 				user: args.depend::<rooms::user::Service>("rooms::user"),
 				users: args.depend::<users::Service>("users"),
 				pusher: args.depend::<pusher::Service>("pusher"),
@@ -349,15 +356,27 @@ pub async fn append_pdu<'a, Leafs>(
 
 		let insert_lock = self.mutex_insert.lock(&pdu.room_id).await;
 
+		let thread_root = pdu.thread_root();
+
 		let count1 = self.services.globals.next_count().unwrap();
 		// Mark as read first so the sending client doesn't get a notification even if
 		// appending fails
-		self.services
-			.read_receipt
-			.private_read_set(&pdu.room_id, &pdu.sender, count1);
+		self.services.read_receipt.private_read_set(
+			&pdu.room_id,
+			&pdu.sender,
+			count1,
+			&ReceiptThread::Unthreaded,
+		);
 		self.services
 			.user
-			.reset_notification_counts(&pdu.sender, &pdu.room_id);
+			.reset_notification_counts(
+				&pdu.sender,
+				&pdu.room_id,
+				&thread_root
+					.clone()
+					.map_or(ReceiptThread::Main, ReceiptThread::Thread),
+			)
+			.await?;
 
 		let count2 = PduCount::Normal(self.services.globals.next_count().unwrap());
 		let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count2 }.into();
@@ -418,7 +437,14 @@ pub async fn append_pdu<'a, Leafs>(
 			for action in self
 				.services
 				.pusher
-				.get_actions(user, &rules_for_user, &power_levels, &sync_pdu, &pdu.room_id)
+				.get_actions(
+					user,
+					&rules_for_user,
+					&power_levels,
+					&sync_pdu,
+					&pdu.room_id,
+					thread_root.as_deref(),
+				)
 				.await
 			{
 				match action {
@@ -455,8 +481,14 @@ pub async fn append_pdu<'a, Leafs>(
 				.await;
 		}
 
-		self.db
-			.increment_notification_counts(&pdu.room_id, notifies, highlights);
+		let counts_lock = self.services.user.mutex_counts.lock(&pdu.room_id).await;
+		self.db.increment_notification_counts(
+			&pdu.room_id,
+			thread_root.as_deref(),
+			notifies,
+			highlights,
+		);
+		drop(counts_lock);
 
 		match pdu.kind {
 			| TimelineEventType::RoomRedaction => {
@@ -491,6 +523,11 @@ pub async fn append_pdu<'a, Leafs>(
 					},
 				}
 			},
+			| TimelineEventType::PolicyRuleUser
+			| TimelineEventType::PolicyRuleRoom
+			| TimelineEventType::PolicyRuleServer => {
+				self.services.policy.update_rule(pdu).await;
+			},
 			| TimelineEventType::SpaceChild =>
 				if let Some(_state_key) = &pdu.state_key {
 					self.services
@@ -536,15 +573,19 @@ pub async fn append_pdu<'a, Leafs>(
 					self.services.search.index_pdu(shortroomid, &pdu_id, &body);
 
 					if self.services.admin.is_admin_command(pdu, &body).await {
-						self.services
-							.admin
-							.command(body, Some((*pdu.event_id).into()))?;
+						self.services.admin.command(
+							body,
+							Some((*pdu.event_id).into()),
+							pdu.sender.clone(),
+						)?;
 					}
 				}
 			},
 			| _ => {},
 		}
 
+		self.services.policy.redact_if_banned(pdu);
+
 		if let Ok(content) = pdu.get_content::<ExtractRelatesToEventId>() {
 			if let Ok(related_pducount) = self.get_pdu_count(&content.relates_to.event_id).await {
 				self.services
@@ -834,6 +875,13 @@ pub async fn build_and_append_pdu(
 			.create_hash_and_sign_event(pdu_builder, sender, room_id, state_lock)
 			.await?;
 
+		if sender != &*self.services.globals.server_user {
+			self.services
+				.spam_checker
+				.check_event_for_spam(&pdu)
+				.await?;
+		}
+
 		if self.services.admin.is_admin_room(&pdu.room_id).await {
 			self.check_pdu_for_admin_room(&pdu, sender).boxed().await?;
 		}
@@ -1091,6 +1139,10 @@ pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Re
 			return Ok(());
 		}
 
+		if self.services.replica.is_replica() {
+			return self.services.replica.backfill(room_id, from).await;
+		}
+
 		let power_levels: RoomPowerLevelsEventContent = self
 			.services
 			.state_accessor
@@ -1118,7 +1170,7 @@ pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Re
 		.map(|alias| alias.server_name().to_owned())
 		.stream();
 
-		let mut servers = room_mods
+		let preferred: Vec<OwnedServerName> = room_mods
 			.stream()
 			.map(ToOwned::to_owned)
 			.chain(canonical_room_alias_server)
@@ -1139,35 +1191,41 @@ pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Re
 					.await
 					.then_some(server_name)
 			})
-			.boxed();
+			.collect()
+			.await;
 
-		while let Some(ref backfill_server) = servers.next().await {
-			info!("Asking {backfill_server} for backfill");
-			let response = self
-				.services
-				.sending
-				.send_federation_request(
-					backfill_server,
-					federation::backfill::get_backfill::v1::Request {
-						room_id: room_id.to_owned(),
-						v: vec![first_pdu.1.event_id.clone()],
-						limit: uint!(100),
-					},
-				)
-				.await;
-			match response {
-				| Ok(response) => {
-					for pdu in response.pdus {
-						if let Err(e) = self.backfill_pdu(backfill_server, pdu).boxed().await {
-							debug_warn!("Failed to add backfilled pdu in room {room_id}: {e}");
-						}
+		let servers = self
+			.services
+			.event_handler
+			.fetch_servers(room_id, preferred)
+			.await;
+
+		let request = federation::backfill::get_backfill::v1::Request {
+			room_id: room_id.to_owned(),
+			v: vec![first_pdu.1.event_id.clone()],
+			limit: uint!(100),
+		};
+
+		info!("Asking up to {} servers for backfill", servers.len());
+		match self
+			.services
+			.event_handler
+			.fetch_any(&servers, request, |_, response| Ok(response))
+			.await
+		{
+			| Ok((backfill_server, response)) => {
+				info!("Got backfill from {backfill_server}");
+				for pdu in response.pdus {
+					if let Err(e) = self.backfill_pdu(&backfill_server, pdu).boxed().await {
+						debug_warn!("Failed to add backfilled pdu in room {room_id}: {e}");
 					}
-					return Ok(());
-				},
-				| Err(e) => {
-					warn!("{backfill_server} failed to provide backfill for room {room_id}: {e}");
-				},
-			}
+				}
+
+				return Ok(());
+			},
+			| Err(e) => {
+				warn!("Servers failed to provide backfill for room {room_id}: {e}");
+			},
 		}
 
 		info!("No servers could backfill, but backfill was needed in room {room_id}");
@@ -1,20 +1,32 @@
-use std::sync::Arc;
+mod tests;
 
-use conduwuit::{Result, implement};
-use database::{Database, Deserialized, Map};
-use ruma::{RoomId, UserId};
+use std::{fmt::Write, sync::Arc};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Result, implement,
+	utils::{MutexMap, ReadyExt, stream::TryIgnore},
+};
+use database::{Database, Deserialized, Interfix, Map};
+use futures::{Stream, StreamExt};
+use ruma::{EventId, OwnedEventId, OwnedRoomId, RoomId, UserId, events::receipt::ReceiptThread};
 
 use crate::{Dep, globals, rooms, rooms::short::ShortStateHash};
 
 pub struct Service {
 	db: Data,
 	services: Services,
+
+	/// Serializes the updates of the notification counts of a room.
+	pub mutex_counts: RoomMutexMap,
 }
 
 struct Data {
 	db: Arc<Database>,
 	userroomid_notificationcount: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
 	roomuserid_lastnotificationread: Arc<Map>,
 	roomsynctoken_shortstatehash: Arc<Map>,
 }
@@ -24,6 +36,9 @@ struct Services {
 	short: Dep<rooms::short::Service>,
 }
 
+type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -31,6 +46,10 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				db: args.db.clone(),
 				userroomid_notificationcount: args.db["userroomid_notificationcount"].clone(),
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
+				userroomthreadid_notificationcount: args.db["userroomthreadid_notificationcount"]
+					.clone(),
+				userroomthreadid_highlightcount: args.db["userroomthreadid_highlightcount"]
+					.clone(),
 				roomuserid_lastnotificationread: args.db["userroomid_highlightcount"].clone(),
 				roomsynctoken_shortstatehash: args.db["roomsynctoken_shortstatehash"].clone(),
 			},
@@ -39,23 +58,101 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				globals: args.depend::<globals::Service>("globals"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 			},
+			mutex_counts: RoomMutexMap::new(),
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let mutex_counts = self.mutex_counts.len();
+		writeln!(out, "counts_mutex: {mutex_counts}")?;
+
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
+/// Resets the notification counts a receipt in the thread clears. The room's
+/// counts include those of its threads: an unthreaded receipt clears them all,
+/// a receipt in the main timeline clears those of the room outside threads,
+/// and a receipt in a thread clears those of the thread.
 #[implement(Service)]
-pub fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+pub async fn reset_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread: &ReceiptThread,
+) -> Result {
+	let count = self.services.globals.next_count()?;
+	let _lock = self.mutex_counts.lock(room_id).await;
+
 	let userroom_id = (user_id, room_id);
-	self.db.userroomid_highlightcount.put(userroom_id, 0_u64);
-	self.db.userroomid_notificationcount.put(userroom_id, 0_u64);
+	match thread {
+		| ReceiptThread::Thread(root_id) => {
+			let userroomthread_id = (user_id, room_id, root_id);
+			let thread_counts = self
+				.thread_notification_count(user_id, room_id, root_id)
+				.await;
+
+			let room_counts = (
+				self.notification_count(user_id, room_id).await,
+				self.highlight_count(user_id, room_id).await,
+			);
+
+			let (notification_count, highlight_count) =
+				subtract_counts(room_counts, thread_counts);
+
+			self.db
+				.userroomid_notificationcount
+				.put(userroom_id, notification_count);
+			self.db
+				.userroomid_highlightcount
+				.put(userroom_id, highlight_count);
+			self.db
+				.userroomthreadid_notificationcount
+				.del(userroomthread_id);
+			self.db
+				.userroomthreadid_highlightcount
+				.del(userroomthread_id);
+		},
+		| ReceiptThread::Main => {
+			let (notification_count, highlight_count) = self
+				.thread_notification_counts(user_id, room_id)
+				.fold((0_u64, 0_u64), |counts, (_, notifications, highlights)| async move {
+					add_counts(counts, (notifications, highlights))
+				})
+				.await;
+
+			self.db
+				.userroomid_notificationcount
+				.put(userroom_id, notification_count);
+			self.db
+				.userroomid_highlightcount
+				.put(userroom_id, highlight_count);
+		},
+		| _ => {
+			self.db.userroomid_highlightcount.put(userroom_id, 0_u64);
+			self.db.userroomid_notificationcount.put(userroom_id, 0_u64);
+
+			let prefix = (user_id, room_id, Interfix);
+			for map in [
+				&self.db.userroomthreadid_notificationcount,
+				&self.db.userroomthreadid_highlightcount,
+			] {
+				map.keys_prefix_raw(&prefix)
+					.ignore_err()
+					.ready_for_each(|key| map.remove(key))
+					.await;
+			}
+		},
+	}
 
 	let roomuser_id = (room_id, user_id);
-	let count = self.services.globals.next_count().unwrap();
 	self.db
 		.roomuserid_lastnotificationread
 		.put(roomuser_id, count);
+
+	Ok(())
 }
 
 #[implement(Service)]
@@ -80,6 +177,60 @@ pub async fn highlight_count(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 		.unwrap_or(0)
 }
 
+/// Returns the notification and highlight counts of a thread.
+#[implement(Service)]
+pub async fn thread_notification_count(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	root_id: &EventId,
+) -> (u64, u64) {
+	let key = (user_id, room_id, root_id);
+	let notifications = self
+		.db
+		.userroomthreadid_notificationcount
+		.qry(&key)
+		.await
+		.deserialized()
+		.unwrap_or(0);
+
+	let highlights = self
+		.db
+		.userroomthreadid_highlightcount
+		.qry(&key)
+		.await
+		.deserialized()
+		.unwrap_or(0);
+
+	(notifications, highlights)
+}
+
+/// Returns the threads of a room with notifications for the user, with their
+/// notification and highlight counts.
+#[implement(Service)]
+pub fn thread_notification_counts<'a>(
+	&'a self,
+	user_id: &'a UserId,
+	room_id: &'a RoomId,
+) -> impl Stream<Item = (OwnedEventId, u64, u64)> + Send + 'a {
+	type Key<'a> = (&'a UserId, &'a RoomId, &'a EventId);
+
+	let prefix = (user_id, room_id, Interfix);
+	self.db
+		.userroomthreadid_notificationcount
+		.keys_prefix(&prefix)
+		.ignore_err()
+		.map(|(_, _, root_id): Key<'_>| root_id.to_owned())
+		.then(move |root_id| async move {
+			let (notifications, highlights) = self
+				.thread_notification_count(user_id, room_id, &root_id)
+				.await;
+
+			(root_id, notifications, highlights)
+		})
+		.ready_filter(|(_, notifications, highlights)| *notifications > 0 || *highlights > 0)
+}
+
 #[implement(Service)]
 pub async fn last_notification_read(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 	let key = (room_id, user_id);
@@ -127,3 +278,14 @@ pub async fn get_token_shortstatehash(
 		.await
 		.deserialized()
 }
+
+/// Adds up notification and highlight counts.
+fn add_counts((notifications, highlights): (u64, u64), other: (u64, u64)) -> (u64, u64) {
+	(notifications.saturating_add(other.0), highlights.saturating_add(other.1))
+}
+
+/// Removes the notification and highlight counts of a thread from those of its
+/// room, which include them.
+fn subtract_counts((notifications, highlights): (u64, u64), thread: (u64, u64)) -> (u64, u64) {
+	(notifications.saturating_sub(thread.0), highlights.saturating_sub(thread.1))
+}
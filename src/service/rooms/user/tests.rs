@@ -0,0 +1,20 @@
+#![cfg(test)]
+
+use super::{add_counts, subtract_counts};
+
+#[test]
+fn thread_counts_add_up() {
+	let threads = [(3, 1), (2, 0), (0, 0)];
+	let total = threads.into_iter().fold((0, 0), add_counts);
+	assert_eq!(total, (5, 1));
+	assert_eq!(add_counts((u64::MAX, 1), (1, 1)), (u64::MAX, 2));
+}
+
+#[test]
+fn thread_counts_subtract_from_room() {
+	assert_eq!(subtract_counts((5, 2), (3, 1)), (2, 1));
+	assert_eq!(subtract_counts((5, 2), (5, 2)), (0, 0));
+
+	// the room's counts were reset by an unthreaded receipt since
+	assert_eq!(subtract_counts((1, 0), (3, 1)), (0, 0));
+}
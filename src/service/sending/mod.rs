@@ -2,6 +2,7 @@
 mod data;
 mod dest;
 mod sender;
+mod tests;
 
 use std::{
 	fmt::Debug,
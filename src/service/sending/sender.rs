@@ -28,7 +28,7 @@
 };
 use ruma::{
 	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedRoomId, OwnedServerName, OwnedUserId,
-	RoomId, RoomVersionId, ServerName, UInt,
+	RoomId, RoomVersionId, ServerName, UInt, UserId,
 	api::{
 		appservice::event::push_events::v1::EphemeralData,
 		federation::transactions::{
@@ -69,7 +69,8 @@ enum TransactionStatus {
 
 const SELECT_PRESENCE_LIMIT: usize = 256;
 const SELECT_RECEIPT_LIMIT: usize = 256;
-const SELECT_EDU_LIMIT: usize = EDU_LIMIT - 2;
+pub(super) const SELECT_RECEIPT_EDU_LIMIT: usize = 8;
+const SELECT_EDU_LIMIT: usize = EDU_LIMIT - 1 - SELECT_RECEIPT_EDU_LIMIT;
 const DEQUEUE_LIMIT: usize = 48;
 
 pub const PDU_LIMIT: usize = 50;
@@ -374,6 +375,7 @@ async fn select_edus(&self, server_name: &ServerName) -> Result<(EduVec, u64)> {
 
 		let events_len = AtomicUsize::default();
 		let max_edu_count = AtomicU64::new(since);
+		let edu_count_cap = AtomicU64::new(u64::MAX);
 
 		let device_changes =
 			self.select_edus_device_changes(server_name, batch, &max_edu_count, &events_len);
@@ -382,7 +384,9 @@ async fn select_edus(&self, server_name: &ServerName) -> Result<(EduVec, u64)> {
 			.server
 			.config
 			.allow_outgoing_read_receipts
-			.then(|| self.select_edus_receipts(server_name, batch, &max_edu_count))
+			.then(|| {
+				self.select_edus_receipts(server_name, batch, &max_edu_count, &edu_count_cap)
+			})
 			.into();
 
 		let presence: OptionFuture<_> = self
@@ -398,7 +402,11 @@ async fn select_edus(&self, server_name: &ServerName) -> Result<(EduVec, u64)> {
 		events.extend(presence.into_iter().flatten());
 		events.extend(receipts.into_iter().flatten());
 
-		Ok((events, max_edu_count.load(Ordering::Acquire)))
+		let max_edu_count = max_edu_count
+			.load(Ordering::Acquire)
+			.min(edu_count_cap.load(Ordering::Acquire));
+
+		Ok((events, max_edu_count))
 	}
 
 	/// Look for device changes
@@ -463,77 +471,110 @@ async fn select_edus_device_changes(
 		events
 	}
 
-	/// Look for read receipts in this room
+	/// Look for read receipts in the rooms shared with the server. A user's
+	/// receipts in several threads of a room are sent in as many EDUs.
 	#[tracing::instrument(
 		name = "receipts",
 		level = "trace",
-		skip(self, server_name, max_edu_count)
+		skip(self, server_name, max_edu_count, edu_count_cap)
 	)]
 	async fn select_edus_receipts(
 		&self,
 		server_name: &ServerName,
 		since: (u64, u64),
 		max_edu_count: &AtomicU64,
-	) -> Option<EduBuf> {
+		edu_count_cap: &AtomicU64,
+	) -> EduVec {
 		let mut num = 0;
-		let receipts: BTreeMap<OwnedRoomId, ReceiptMap> = self
+		let rooms: Vec<(OwnedRoomId, Vec<ReceiptMap>)> = self
 			.services
 			.state_cache
 			.server_rooms(server_name)
 			.map(ToOwned::to_owned)
 			.broad_filter_map(|room_id| async move {
-				let receipt_map = self
-					.select_edus_receipts_room(&room_id, since, max_edu_count, &mut num)
+				let receipt_maps = self
+					.select_edus_receipts_room(
+						&room_id,
+						since,
+						max_edu_count,
+						edu_count_cap,
+						&mut num,
+					)
 					.await;
 
-				receipt_map
-					.read
+				receipt_maps
 					.is_empty()
 					.eq(&false)
-					.then_some((room_id, receipt_map))
+					.then_some((room_id, receipt_maps))
 			})
 			.collect()
 			.await;
 
-		if receipts.is_empty() {
-			return None;
+		let mut edus = Vec::<BTreeMap<OwnedRoomId, ReceiptMap>>::new();
+		for (room_id, receipt_maps) in rooms {
+			if edus.len() < receipt_maps.len() {
+				edus.resize_with(receipt_maps.len(), BTreeMap::new);
+			}
+
+			for (receipts, receipt_map) in edus.iter_mut().zip(receipt_maps) {
+				receipts.insert(room_id.clone(), receipt_map);
+			}
 		}
 
-		let receipt_content = Edu::Receipt(ReceiptContent { receipts });
+		edus.into_iter()
+			.map(|receipts| {
+				let receipt_content = Edu::Receipt(ReceiptContent { receipts });
 
-		let mut buf = EduBuf::new();
-		serde_json::to_writer(&mut buf, &receipt_content)
-			.expect("Failed to serialize Receipt EDU to JSON vec");
+				let mut buf = EduBuf::new();
+				serde_json::to_writer(&mut buf, &receipt_content)
+					.expect("Failed to serialize Receipt EDU to JSON vec");
 
-		Some(buf)
+				buf
+			})
+			.collect()
 	}
 
-	/// Look for read receipts in this room
-	#[tracing::instrument(name = "receipts", level = "trace", skip(self, since, max_edu_count))]
+	/// Look for read receipts in this room. The EDU holds one receipt per user,
+	/// so each of a user's receipts in other threads goes in the next map.
+	/// Receipts past the last map are left for the next transaction, which
+	/// `edu_count_cap` starts from.
+	#[tracing::instrument(
+		name = "receipts",
+		level = "trace",
+		skip(self, since, max_edu_count, edu_count_cap)
+	)]
 	async fn select_edus_receipts_room(
 		&self,
 		room_id: &RoomId,
 		since: (u64, u64),
 		max_edu_count: &AtomicU64,
+		edu_count_cap: &AtomicU64,
 		num: &mut usize,
-	) -> ReceiptMap {
+	) -> Vec<ReceiptMap> {
 		let receipts = self
 			.services
 			.read_receipt
 			.readreceipts_since(room_id, since.0);
 
 		pin_mut!(receipts);
-		let mut read = BTreeMap::<OwnedUserId, ReceiptData>::new();
+		let mut reads = Vec::<BTreeMap<OwnedUserId, ReceiptData>>::new();
 		while let Some((user_id, count, read_receipt)) = receipts.next().await {
 			if count > since.1 {
 				break;
 			}
 
-			max_edu_count.fetch_max(count, Ordering::Relaxed);
 			if !self.services.globals.user_is_local(user_id) {
+				max_edu_count.fetch_max(count, Ordering::Relaxed);
 				continue;
 			}
 
+			let Some(layer) = receipt_layer(&mut reads, user_id) else {
+				edu_count_cap.fetch_min(count.saturating_sub(1), Ordering::Relaxed);
+				break;
+			};
+
+			max_edu_count.fetch_max(count, Ordering::Relaxed);
+
 			let Ok(event) = serde_json::from_str(read_receipt.json().get()) else {
 				error!(?user_id, ?count, ?read_receipt, "Invalid edu event in read_receipts.");
 				continue;
@@ -562,15 +603,19 @@ async fn select_edus_receipts_room(
 				event_ids: vec![event_id.clone()],
 			};
 
-			if read.insert(user_id.to_owned(), receipt_data).is_none() {
-				*num = num.saturating_add(1);
-				if *num >= SELECT_RECEIPT_LIMIT {
-					break;
-				}
+			layer.insert(user_id.to_owned(), receipt_data);
+			*num = num.saturating_add(1);
+			if *num >= SELECT_RECEIPT_LIMIT {
+				edu_count_cap.fetch_min(count, Ordering::Relaxed);
+				break;
 			}
 		}
 
-		ReceiptMap { read }
+		reads
+			.into_iter()
+			.filter(|read| !read.is_empty())
+			.map(|read| ReceiptMap { read })
+			.collect()
 	}
 
 	/// Look for presence
@@ -919,3 +964,21 @@ pub async fn convert_to_outgoing_federation_event(
 		to_raw_value(&pdu_json).expect("CanonicalJson is valid serde_json::Value")
 	}
 }
+
+/// The first map without a receipt of the user, added if there is room for
+/// another EDU.
+pub(super) fn receipt_layer<'a, T>(
+	layers: &'a mut Vec<BTreeMap<OwnedUserId, T>>,
+	user_id: &UserId,
+) -> Option<&'a mut BTreeMap<OwnedUserId, T>> {
+	let index = match layers.iter().position(|layer| !layer.contains_key(user_id)) {
+		| Some(index) => index,
+		| None if layers.len() < SELECT_RECEIPT_EDU_LIMIT => {
+			layers.push(BTreeMap::new());
+			layers.len().saturating_sub(1)
+		},
+		| None => return None,
+	};
+
+	layers.get_mut(index)
+}
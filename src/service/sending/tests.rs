@@ -0,0 +1,42 @@
+#![cfg(test)]
+
+use std::collections::BTreeMap;
+
+use ruma::{OwnedUserId, user_id};
+
+use super::sender::{SELECT_RECEIPT_EDU_LIMIT, receipt_layer};
+
+#[test]
+fn receipts_of_a_user_spread_over_layers() {
+	let alice = user_id!("@alice:example.com");
+	let bob = user_id!("@bob:example.com");
+	let mut layers = Vec::<BTreeMap<OwnedUserId, u64>>::new();
+
+	for (user_id, count) in [(alice, 1), (bob, 2), (alice, 3), (alice, 4), (bob, 5)] {
+		receipt_layer(&mut layers, user_id)
+			.expect("room for another layer")
+			.insert(user_id.to_owned(), count);
+	}
+
+	assert_eq!(layers.len(), 3);
+	assert_eq!(layers[0].get(alice), Some(&1));
+	assert_eq!(layers[0].get(bob), Some(&2));
+	assert_eq!(layers[1].get(alice), Some(&3));
+	assert_eq!(layers[1].get(bob), Some(&5));
+	assert_eq!(layers[2].get(alice), Some(&4));
+	assert_eq!(layers[2].get(bob), None);
+}
+
+#[test]
+fn receipt_layers_are_limited() {
+	let alice = user_id!("@alice:example.com");
+	let mut layers = Vec::<BTreeMap<OwnedUserId, ()>>::new();
+	for _ in 0..SELECT_RECEIPT_EDU_LIMIT {
+		receipt_layer(&mut layers, alice)
+			.expect("room for another layer")
+			.insert(alice.to_owned(), ());
+	}
+
+	assert!(receipt_layer(&mut layers, alice).is_none());
+	assert!(receipt_layer(&mut layers, user_id!("@bob:example.com")).is_some());
+}
@@ -33,6 +33,10 @@ fn interrupt(&self) {}
 	/// Clear any caches or similar runtime state.
 	async fn clear_cache(&self) {}
 
+	/// Columns the service's caches are derived from. The caches are cleared
+	/// when these columns are written without going through the service.
+	fn cached_columns(&self) -> &[&str] { &[] }
+
 	/// Memory usage report in a markdown string.
 	async fn memory_usage(&self, _out: &mut (dyn Write + Send)) -> Result { Ok(()) }
 
@@ -44,6 +48,11 @@ async fn memory_usage(&self, _out: &mut (dyn Write + Send)) -> Result { Ok(()) }
 	/// budgeting. This can reduce tail latency at the risk of event loop
 	/// starvation.
 	fn unconstrained(&self) -> bool { false }
+
+	/// Return true if the service worker also runs on a read replica. Most
+	/// workers write to the database or act on behalf of the server, which is
+	/// left to the primary.
+	fn on_replica(&self) -> bool { false }
 }
 
 /// Args are passed to `Service::build` when a service is constructed. This
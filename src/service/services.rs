@@ -4,36 +4,50 @@
 	sync::{Arc, RwLock},
 };
 
-use conduwuit::{Result, Server, debug, debug_info, info, trace, utils::stream::IterStream};
+use conduwuit::{
+	Result, Server, debug, debug_info, info, trace,
+	utils::stream::{IterStream, ReadyExt},
+};
 use database::Database;
 use futures::{Stream, StreamExt, TryStreamExt};
 use tokio::sync::Mutex;
 
 use crate::{
-	account_data, admin, appservice, client, config, emergency, federation, globals, key_backups,
+	account_data, admin, appservice, audit, backup, client, config, emergency, federation, fsck,
+	globals, jobs, key_backups,
 	manager::Manager,
-	media, presence, pusher, resolver, rooms, sending, server_keys, service,
+	media, policy, presence, pusher, registration_tokens, rendezvous, replica, resolver, rooms,
+	sending, server_keys, service,
 	service::{Args, Map, Service},
-	sync, transaction_ids, uiaa, updates, users,
+	spam_checker, sync, transaction_ids, uiaa, updates, users,
 };
 
 pub struct Services {
 	pub account_data: Arc<account_data::Service>,
 	pub admin: Arc<admin::Service>,
 	pub appservice: Arc<appservice::Service>,
+	pub audit: Arc<audit::Service>,
+	pub backup: Arc<backup::Service>,
 	pub config: Arc<config::Service>,
 	pub client: Arc<client::Service>,
 	pub emergency: Arc<emergency::Service>,
+	pub fsck: Arc<fsck::Service>,
 	pub globals: Arc<globals::Service>,
+	pub jobs: Arc<jobs::Service>,
 	pub key_backups: Arc<key_backups::Service>,
 	pub media: Arc<media::Service>,
+	pub policy: Arc<policy::Service>,
 	pub presence: Arc<presence::Service>,
 	pub pusher: Arc<pusher::Service>,
+	pub registration_tokens: Arc<registration_tokens::Service>,
+	pub rendezvous: Arc<rendezvous::Service>,
+	pub replica: Arc<replica::Service>,
 	pub resolver: Arc<resolver::Service>,
 	pub rooms: rooms::Service,
 	pub federation: Arc<federation::Service>,
 	pub sending: Arc<sending::Service>,
 	pub server_keys: Arc<server_keys::Service>,
+	pub spam_checker: Arc<spam_checker::Service>,
 	pub sync: Arc<sync::Service>,
 	pub transaction_ids: Arc<transaction_ids::Service>,
 	pub uiaa: Arc<uiaa::Service>,
@@ -50,6 +64,10 @@ impl Services {
 	#[allow(clippy::cognitive_complexity)]
 	pub async fn build(server: Arc<Server>) -> Result<Arc<Self>> {
 		let db = Database::open(&server).await?;
+		if let Some(path) = server.config.database_import_path.as_deref() {
+			db.import(path).await?;
+		}
+
 		let service: Arc<Map> = Arc::new(RwLock::new(BTreeMap::new()));
 		macro_rules! build {
 			($tyname:ty) => {{
@@ -67,15 +85,23 @@ macro_rules! build {
 			account_data: build!(account_data::Service),
 			admin: build!(admin::Service),
 			appservice: build!(appservice::Service),
+			audit: build!(audit::Service),
+			backup: build!(backup::Service),
 			resolver: build!(resolver::Service),
 			client: build!(client::Service),
 			config: build!(config::Service),
 			emergency: build!(emergency::Service),
+			fsck: build!(fsck::Service),
 			globals: build!(globals::Service),
+			jobs: build!(jobs::Service),
 			key_backups: build!(key_backups::Service),
 			media: build!(media::Service),
+			policy: build!(policy::Service),
 			presence: build!(presence::Service),
 			pusher: build!(pusher::Service),
+			registration_tokens: build!(registration_tokens::Service),
+			rendezvous: build!(rendezvous::Service),
+			replica: build!(replica::Service),
 			rooms: rooms::Service {
 				alias: build!(rooms::alias::Service),
 				auth_chain: build!(rooms::auth_chain::Service),
@@ -101,6 +127,7 @@ macro_rules! build {
 			federation: build!(federation::Service),
 			sending: build!(sending::Service),
 			server_keys: build!(server_keys::Service),
+			spam_checker: build!(spam_checker::Service),
 			sync: build!(sync::Service),
 			transaction_ids: build!(transaction_ids::Service),
 			uiaa: build!(uiaa::Service),
@@ -178,6 +205,21 @@ pub async fn clear_cache(&self) {
 			.await;
 	}
 
+	/// Clear the caches derived from any of the columns.
+	pub async fn clear_cache_of(&self, columns: &[&str]) {
+		self.services()
+			.ready_filter(|service| {
+				service
+					.cached_columns()
+					.iter()
+					.any(|column| columns.contains(column))
+			})
+			.for_each(|service| async move {
+				service.clear_cache().await;
+			})
+			.await;
+	}
+
 	pub async fn memory_usage(&self) -> Result<String> {
 		self.services()
 			.map(Ok)
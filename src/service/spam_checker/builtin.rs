@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use conduwuit::{Err, PduEvent, Result};
+use regex::{Regex, RegexSet};
+use serde::Deserialize;
+
+use super::SpamChecker;
+
+/// Rejects events whose `body` or `formatted_body` matches any of the
+/// `spam_content_filters` patterns.
+pub struct ContentFilter {
+	patterns: RegexSet,
+}
+
+/// Rejects events linking to a domain in `spam_link_denylist` or any of its
+/// subdomains. Hostnames without a scheme count as links, since clients link
+/// them too.
+pub struct LinkDenylist {
+	domains: Vec<String>,
+	links: Regex,
+}
+
+#[derive(Deserialize)]
+struct ExtractBody {
+	body: Option<String>,
+	formatted_body: Option<String>,
+}
+
+impl ContentFilter {
+	#[must_use]
+	pub fn new(patterns: RegexSet) -> Self { Self { patterns } }
+}
+
+#[async_trait]
+impl SpamChecker for ContentFilter {
+	fn name(&self) -> &str { "content_filter" }
+
+	async fn check_event_for_spam(&self, pdu: &PduEvent) -> Result {
+		if bodies(pdu).any(|body| self.patterns.is_match(&body)) {
+			return Err!(Request(Forbidden("Message content is not allowed on this server.")));
+		}
+
+		Ok(())
+	}
+}
+
+impl LinkDenylist {
+	#[must_use]
+	pub fn new(domains: &[String]) -> Self {
+		Self {
+			domains: domains
+				.iter()
+				.map(|domain| domain.trim_start_matches('.').to_lowercase())
+				.collect(),
+			// a URL with a scheme, then a bare hostname; the rest of each is
+			// consumed so that hostnames in paths and queries are not matched
+			links: Regex::new(concat!(
+				r#"(?i)\b[a-z][a-z0-9+.-]*://([^\s/?#<>"']+)[^\s<>"']*"#,
+				r#"|\b((?:[a-z0-9-]+\.)+[a-z0-9-]+)(?:[/?#][^\s<>"']*)?"#,
+			))
+			.expect("valid link regex"),
+		}
+	}
+
+	/// True if the text links to a denied domain.
+	pub(super) fn links_denied(&self, text: &str) -> bool {
+		self.links
+			.captures_iter(text)
+			.filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+			.map(|authority| host(authority.as_str()))
+			.any(|host| self.is_denied(&host))
+	}
+
+	pub(super) fn is_denied(&self, host: &str) -> bool {
+		self.domains.iter().any(|domain| {
+			host == domain
+				|| host
+					.strip_suffix(domain.as_str())
+					.is_some_and(|prefix| prefix.ends_with('.'))
+		})
+	}
+}
+
+#[async_trait]
+impl SpamChecker for LinkDenylist {
+	fn name(&self) -> &str { "link_denylist" }
+
+	async fn check_event_for_spam(&self, pdu: &PduEvent) -> Result {
+		if bodies(pdu).any(|body| self.links_denied(&body)) {
+			return Err!(Request(Forbidden(
+				"Links to this domain are not allowed on this server."
+			)));
+		}
+
+		Ok(())
+	}
+}
+
+fn bodies(pdu: &PduEvent) -> impl Iterator<Item = String> {
+	pdu.get_content::<ExtractBody>()
+		.ok()
+		.into_iter()
+		.flat_map(|content| [content.body, content.formatted_body])
+		.flatten()
+}
+
+/// Host of a URL authority without userinfo or port, lowercased.
+pub(super) fn host(authority: &str) -> String {
+	let host = authority
+		.rsplit_once('@')
+		.map_or(authority, |(_, host)| host);
+
+	let host = match host.strip_prefix('[') {
+		| Some(ipv6) => ipv6.split(']').next().unwrap_or(ipv6),
+		| None => host.split(':').next().unwrap_or(host),
+	};
+
+	host.trim_end_matches('.').to_lowercase()
+}
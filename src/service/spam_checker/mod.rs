@@ -0,0 +1,201 @@
+//! Extension point for anti-abuse logic.
+//!
+//! A [`SpamChecker`] is consulted before events are accepted and before users
+//! invite, join, create rooms, register or upload media; the first checker to
+//! return an error rejects the action. Checkers are either built in (see
+//! `builtin`) or registered at runtime, e.g. by a module's init function
+//! through `Services::spam_checker`, and unregistered again by its fini.
+
+mod builtin;
+mod tests;
+
+use std::{
+	fmt::Display,
+	sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use conduwuit::{Error, PduEvent, Result, debug_info};
+use ruma::{RoomId, UserId};
+
+pub use self::builtin::{ContentFilter, LinkDenylist};
+
+/// Callbacks deciding whether an action is allowed. Every callback allows by
+/// default; return an error (usually `M_FORBIDDEN`) to reject.
+#[async_trait]
+pub trait SpamChecker: Send + Sync {
+	/// Unique name of the checker, used to unregister it.
+	fn name(&self) -> &str;
+
+	/// Called for events created by local users before they are appended and
+	/// for events received over federation after they pass auth; rejected
+	/// remote events are soft failed.
+	async fn check_event_for_spam(&self, _pdu: &PduEvent) -> Result { Ok(()) }
+
+	/// Called when a local user invites someone, or a remote user invites a
+	/// local user.
+	async fn user_may_invite(
+		&self,
+		_inviter: &UserId,
+		_invitee: &UserId,
+		_room_id: &RoomId,
+	) -> Result {
+		Ok(())
+	}
+
+	/// Called when a local user joins a room.
+	async fn user_may_join_room(
+		&self,
+		_user_id: &UserId,
+		_room_id: &RoomId,
+		_is_invited: bool,
+	) -> Result {
+		Ok(())
+	}
+
+	/// Called when a local user creates a room.
+	async fn user_may_create_room(&self, _user_id: &UserId) -> Result { Ok(()) }
+
+	/// Called when a username is registered.
+	async fn check_username_for_spam(&self, _user_id: &UserId) -> Result { Ok(()) }
+
+	/// Called when a local user uploads media.
+	async fn check_media_file_for_spam(
+		&self,
+		_user_id: &UserId,
+		_content_type: Option<&str>,
+		_filename: Option<&str>,
+		_file: &[u8],
+	) -> Result {
+		Ok(())
+	}
+}
+
+pub struct Service {
+	checkers: RwLock<Vec<Arc<dyn SpamChecker>>>,
+}
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let mut checkers: Vec<Arc<dyn SpamChecker>> = Vec::new();
+		if !config.spam_content_filters.is_empty() {
+			checkers.push(Arc::new(ContentFilter::new(config.spam_content_filters.clone())));
+		}
+
+		if !config.spam_link_denylist.is_empty() {
+			checkers.push(Arc::new(LinkDenylist::new(&config.spam_link_denylist)));
+		}
+
+		Ok(Arc::new(Self { checkers: RwLock::new(checkers) }))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Add a checker, replacing any registered under the same name.
+	pub fn register(&self, checker: Arc<dyn SpamChecker>) {
+		let mut checkers = self.checkers.write().expect("locked for writing");
+		checkers.retain(|existing| existing.name() != checker.name());
+		checkers.push(checker);
+	}
+
+	/// Remove a checker. Returns false if none was registered under the name.
+	pub fn unregister(&self, name: &str) -> bool {
+		let mut checkers = self.checkers.write().expect("locked for writing");
+		let len = checkers.len();
+		checkers.retain(|checker| checker.name() != name);
+		checkers.len() != len
+	}
+
+	pub async fn check_event_for_spam(&self, pdu: &PduEvent) -> Result {
+		for checker in self.checkers() {
+			checker
+				.check_event_for_spam(pdu)
+				.await
+				.inspect_err(|e| rejected(&*checker, pdu.event_id.as_str(), e))?;
+		}
+
+		Ok(())
+	}
+
+	pub async fn user_may_invite(
+		&self,
+		inviter: &UserId,
+		invitee: &UserId,
+		room_id: &RoomId,
+	) -> Result {
+		for checker in self.checkers() {
+			checker
+				.user_may_invite(inviter, invitee, room_id)
+				.await
+				.inspect_err(|e| rejected(&*checker, inviter, e))?;
+		}
+
+		Ok(())
+	}
+
+	pub async fn user_may_join_room(
+		&self,
+		user_id: &UserId,
+		room_id: &RoomId,
+		is_invited: bool,
+	) -> Result {
+		for checker in self.checkers() {
+			checker
+				.user_may_join_room(user_id, room_id, is_invited)
+				.await
+				.inspect_err(|e| rejected(&*checker, user_id, e))?;
+		}
+
+		Ok(())
+	}
+
+	pub async fn user_may_create_room(&self, user_id: &UserId) -> Result {
+		for checker in self.checkers() {
+			checker
+				.user_may_create_room(user_id)
+				.await
+				.inspect_err(|e| rejected(&*checker, user_id, e))?;
+		}
+
+		Ok(())
+	}
+
+	pub async fn check_username_for_spam(&self, user_id: &UserId) -> Result {
+		for checker in self.checkers() {
+			checker
+				.check_username_for_spam(user_id)
+				.await
+				.inspect_err(|e| rejected(&*checker, user_id, e))?;
+		}
+
+		Ok(())
+	}
+
+	pub async fn check_media_file_for_spam(
+		&self,
+		user_id: &UserId,
+		content_type: Option<&str>,
+		filename: Option<&str>,
+		file: &[u8],
+	) -> Result {
+		for checker in self.checkers() {
+			checker
+				.check_media_file_for_spam(user_id, content_type, filename, file)
+				.await
+				.inspect_err(|e| rejected(&*checker, user_id, e))?;
+		}
+
+		Ok(())
+	}
+
+	fn checkers(&self) -> Vec<Arc<dyn SpamChecker>> {
+		self.checkers.read().expect("locked for reading").clone()
+	}
+}
+
+fn rejected<S: Display + ?Sized>(checker: &dyn SpamChecker, subject: &S, e: &Error) {
+	debug_info!(checker = checker.name(), "Rejected {subject}: {e}");
+}
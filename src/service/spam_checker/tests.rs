@@ -0,0 +1,56 @@
+#![cfg(test)]
+
+use super::builtin::{LinkDenylist, host};
+
+fn denylist() -> LinkDenylist {
+	LinkDenylist::new(&["spam.example".to_owned(), ".Bad.Example".to_owned()])
+}
+
+#[test]
+fn host_of_authority() {
+	assert_eq!(host("example.com"), "example.com");
+	assert_eq!(host("Example.COM."), "example.com");
+	assert_eq!(host("example.com:8448"), "example.com");
+	assert_eq!(host("user:password@example.com:443"), "example.com");
+	assert_eq!(host("me@example.com"), "example.com");
+	assert_eq!(host("[2001:db8::1]:8448"), "2001:db8::1");
+	assert_eq!(host("[2001:db8::1]"), "2001:db8::1");
+}
+
+#[test]
+fn denied_domains_and_subdomains() {
+	let denylist = denylist();
+	assert!(denylist.is_denied("spam.example"));
+	assert!(denylist.is_denied("www.spam.example"));
+	assert!(denylist.is_denied("a.b.spam.example"));
+
+	// configured domains are normalized
+	assert!(denylist.is_denied("bad.example"));
+	assert!(denylist.is_denied("www.bad.example"));
+
+	// only whole labels match
+	assert!(!denylist.is_denied("notspam.example"));
+	assert!(!denylist.is_denied("spam.example.org"));
+	assert!(!denylist.is_denied("example"));
+}
+
+#[test]
+fn denied_links() {
+	let denylist = denylist();
+	assert!(denylist.links_denied("see https://spam.example/offer"));
+	assert!(denylist.links_denied("HTTP://WWW.SPAM.EXAMPLE:8080?x=1"));
+	assert!(denylist.links_denied("<a href=\"https://user@spam.example\">here</a>"));
+	assert!(denylist.links_denied("ftp://ok.example and https://bad.example."));
+
+	// clients link hostnames written without a scheme
+	assert!(denylist.links_denied("mail me at spam.example"));
+	assert!(denylist.links_denied("see spam.example/offer"));
+	assert!(denylist.links_denied("(www.Bad.Example?ref=1)"));
+	assert!(denylist.links_denied("me@spam.example"));
+
+	// other hostnames, or denied ones only in the path of a link
+	assert!(!denylist.links_denied("spam and example, notspam.example"));
+	assert!(!denylist.links_denied("ok.example/spam.example"));
+	assert!(!denylist.links_denied("https://ok.example/spam.example"));
+	assert!(!denylist.links_denied("https://ok.example/?next=https%3A%2F%2Fspam.example"));
+}
@@ -0,0 +1,254 @@
+use conduwuit::{Err, Result, err, implement};
+use database::Deserialized;
+use ruma::{
+	CanonicalJsonObject, DeviceId, UserId,
+	encryption::CrossSigningKey,
+	serde::{Base64, Raw},
+	signatures::{PublicKeyMap, PublicKeySet},
+};
+use serde_json::Value as JsonValue;
+
+/// Checks cross-signing keys a user uploads: each must be the user's and have
+/// the usage of its kind, and the self-signing and user-signing keys must be
+/// signed by the master key, either the one uploaded with them or else the
+/// current one.
+#[implement(super::Service)]
+pub async fn verify_cross_signing_keys(
+	&self,
+	user_id: &UserId,
+	master_key: Option<&Raw<CrossSigningKey>>,
+	self_signing_key: Option<&Raw<CrossSigningKey>>,
+	user_signing_key: Option<&Raw<CrossSigningKey>>,
+) -> Result {
+	let master_key = match master_key {
+		| Some(master_key) => to_json(master_key)?,
+		| None if self_signing_key.is_none() && user_signing_key.is_none() => return Ok(()),
+		| None => self
+			.get_master_key(None, user_id, &|_| true)
+			.await
+			.map_err(|_| err!(Request(InvalidParam("{user_id} has no master key."))))
+			.and_then(|master_key| to_json(&master_key))?,
+	};
+
+	let (master_key_id, master_public_key) = public_key(user_id, &master_key, "master")?;
+	for (key, usage) in [(self_signing_key, "self_signing"), (user_signing_key, "user_signing")] {
+		let Some(key) = key else {
+			continue;
+		};
+
+		let key = to_json(key)?;
+		public_key(user_id, &key, usage)?;
+		verify_signature(&key, user_id, &master_key_id, &master_public_key)
+			.map_err(|e| err!(Request(Forbidden("Invalid {usage} key: {e}"))))?;
+	}
+
+	Ok(())
+}
+
+/// Verifies the signatures the sender made on a key of a user and adds them to
+/// the stored key. The key must be unchanged from the stored one apart from
+/// its signatures. The sender's own device keys must be signed by their
+/// self-signing key, their own master key by one of their devices, and the
+/// master keys of other users by the sender's user-signing key.
+#[implement(super::Service)]
+pub async fn add_signatures(
+	&self,
+	sender_id: &UserId,
+	target_id: &UserId,
+	key_id: &str,
+	signed_key: &JsonValue,
+) -> Result {
+	let stored_key: JsonValue = self
+		.db
+		.keyid_key
+		.qry(&(target_id, key_id))
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(NotFound("Key {key_id} of {target_id} not found."))))?;
+
+	if unsigned(&stored_key) != unsigned(signed_key) {
+		return Err!(Request(InvalidParam(
+			"Signed key {key_id} differs from the key of {target_id}."
+		)));
+	}
+
+	let signatures = signed_key
+		.get("signatures")
+		.and_then(|signatures| signatures.get(sender_id.as_str()))
+		.and_then(JsonValue::as_object)
+		.filter(|signatures| !signatures.is_empty())
+		.ok_or_else(|| {
+			err!(Request(InvalidParam("Key {key_id} has no signatures of {sender_id}.")))
+		})?;
+
+	let master_public_key = self
+		.get_master_key(None, target_id, &|_| true)
+		.await
+		.ok()
+		.and_then(|master_key| to_json(&master_key).ok())
+		.and_then(|master_key| public_key(target_id, &master_key, "master").ok())
+		.map(|(_, master_public_key)| master_public_key);
+
+	// cross-signing keys are stored by their public key
+	let is_master_key = master_public_key.as_deref() == Some(key_id);
+	let signer_key = if sender_id != target_id {
+		if !is_master_key {
+			return Err!(Request(InvalidParam(
+				"Only the master key of another user can be signed."
+			)));
+		}
+
+		let user_signing_key = self
+			.get_user_signing_key(sender_id)
+			.await
+			.map_err(|_| err!(Request(InvalidParam("{sender_id} has no user-signing key."))))?;
+
+		Some(public_key(sender_id, &to_json(&user_signing_key)?, "user_signing")?)
+	} else if !is_master_key {
+		let self_signing_key = self
+			.get_self_signing_key(None, sender_id, &|_| true)
+			.await
+			.map_err(|_| err!(Request(InvalidParam("{sender_id} has no self-signing key."))))?;
+
+		Some(public_key(sender_id, &to_json(&self_signing_key)?, "self_signing")?)
+	} else {
+		// the master key is signed by the sender's devices
+		None
+	};
+
+	// all signatures are verified before any is added
+	let mut verified = Vec::with_capacity(signatures.len());
+	for (signature_key_id, signature) in signatures {
+		let public_key = match &signer_key {
+			| Some((signer_key_id, public_key)) if signer_key_id == signature_key_id =>
+				public_key.clone(),
+			| Some(_) =>
+				return Err!(Request(Forbidden(
+					"Signature {signature_key_id} is not of the expected signing key."
+				))),
+			| None => self.device_public_key(sender_id, signature_key_id).await?,
+		};
+
+		verify_signature(signed_key, sender_id, signature_key_id, &public_key)
+			.map_err(|e| err!(Request(Forbidden("Invalid signature {signature_key_id}: {e}"))))?;
+
+		let signature = signature.as_str().ok_or_else(|| {
+			err!(Request(InvalidParam("Signature {signature_key_id} is not a string.")))
+		})?;
+
+		verified.push((signature_key_id.clone(), signature.to_owned()));
+	}
+
+	for signature in verified {
+		self.sign_key(target_id, key_id, signature, sender_id)
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// The ed25519 public key of a device of the user, given the key's ID
+/// (`ed25519:<device_id>`).
+#[implement(super::Service)]
+async fn device_public_key(&self, user_id: &UserId, key_id: &str) -> Result<String> {
+	let device_id: &DeviceId = key_id
+		.strip_prefix("ed25519:")
+		.ok_or_else(|| err!(Request(InvalidParam("Signature {key_id} is not of a device."))))?
+		.into();
+
+	let device_keys: JsonValue = self
+		.get_device_keys(user_id, device_id)
+		.await
+		.and_then(|device_keys| to_json(&device_keys))
+		.map_err(|_| err!(Request(InvalidParam("Device {device_id} has no keys."))))?;
+
+	device_keys
+		.get("keys")
+		.and_then(|keys| keys.get(key_id))
+		.and_then(JsonValue::as_str)
+		.map(ToOwned::to_owned)
+		.ok_or_else(|| err!(Request(InvalidParam("Device {device_id} has no key {key_id}."))))
+}
+
+/// The ID and public key of the one key of a cross-signing key, which must be
+/// the user's and have the usage.
+pub(super) fn public_key(
+	user_id: &UserId,
+	key: &JsonValue,
+	usage: &str,
+) -> Result<(String, String)> {
+	if key.get("user_id").and_then(JsonValue::as_str) != Some(user_id.as_str()) {
+		return Err!(Request(InvalidParam("The {usage} key is not of {user_id}.")));
+	}
+
+	let has_usage = key
+		.get("usage")
+		.and_then(JsonValue::as_array)
+		.is_some_and(|usages| usages.iter().any(|u| u.as_str() == Some(usage)));
+
+	if !has_usage {
+		return Err!(Request(InvalidParam("The {usage} key lacks the {usage} usage.")));
+	}
+
+	let mut keys = key
+		.get("keys")
+		.and_then(JsonValue::as_object)
+		.into_iter()
+		.flatten();
+
+	match (keys.next(), keys.next()) {
+		| (Some((key_id, JsonValue::String(public_key))), None) =>
+			Ok((key_id.clone(), public_key.clone())),
+		| _ => Err!(Request(InvalidParam("The {usage} key must contain exactly one key."))),
+	}
+}
+
+/// Verifies the signature of the signer's key on a JSON object, ignoring its
+/// other signatures.
+pub(super) fn verify_signature(
+	object: &JsonValue,
+	signer: &UserId,
+	key_id: &str,
+	public_key: &str,
+) -> Result {
+	let signature = object
+		.get("signatures")
+		.and_then(|signatures| signatures.get(signer.as_str()))
+		.and_then(|signatures| signatures.get(key_id))
+		.cloned()
+		.ok_or_else(|| err!("missing signature"))?;
+
+	let mut object = object.clone();
+	if let Some(object) = object.as_object_mut() {
+		object.remove("unsigned");
+		object.insert(
+			"signatures".to_owned(),
+			serde_json::json!({ signer.as_str(): { key_id: signature } }),
+		);
+	}
+
+	let object: CanonicalJsonObject = serde_json::from_value(object)?;
+	let public_key: Base64 =
+		Base64::parse(public_key).map_err(|e| err!("invalid public key {public_key:?}: {e}"))?;
+
+	let keys: PublicKeySet = [(key_id.to_owned(), public_key)].into();
+	let keys: PublicKeyMap = [(signer.to_string(), keys)].into();
+
+	ruma::signatures::verify_json(&keys, object).map_err(Into::into)
+}
+
+/// A key without its signatures and unsigned data, to compare the signed
+/// content of two copies.
+fn unsigned(key: &JsonValue) -> JsonValue {
+	let mut key = key.clone();
+	if let Some(key) = key.as_object_mut() {
+		key.remove("signatures");
+		key.remove("unsigned");
+	}
+
+	key
+}
+
+fn to_json<T>(raw: &Raw<T>) -> Result<JsonValue> {
+	serde_json::from_str(raw.json().get()).map_err(Into::into)
+}
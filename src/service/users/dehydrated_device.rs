@@ -0,0 +1,196 @@
+use conduwuit::{Err, Result, err, implement};
+use database::{Deserialized, Json};
+use futures::StreamExt;
+use ruma::{
+	DeviceId, OwnedDeviceId, UserId,
+	api::client::{
+		dehydrated_device::{DehydratedDeviceData, put_dehydrated_device},
+		device::Device,
+	},
+	events::AnyToDeviceEvent,
+	serde::Raw,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{IMPERSONATION_DEVICE_PREFIX, increment, is_impersonation_device};
+
+/// A device a user left on the server (MSC3814), which receives to-device
+/// messages and has its one-time keys claimed while none of their devices is
+/// online. Its pickled data is encrypted by the client; a new device of the
+/// user rehydrates it to read the messages it received.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DehydratedDevice {
+	pub device_id: OwnedDeviceId,
+	pub device_data: Raw<DehydratedDeviceData>,
+}
+
+/// Stores the dehydrated device of a user with its device, one-time and
+/// fallback keys, replacing the one they had before. The device is part of the
+/// user's device list like any other, but has no access token.
+#[implement(super::Service)]
+pub async fn set_dehydrated_device(
+	&self,
+	user_id: &UserId,
+	request: put_dehydrated_device::unstable::Request,
+) -> Result {
+	let existing_id = self.dehydrated_device_id(user_id).await.ok();
+	let device_id = &request.device_id;
+	let exists = self.get_device_metadata(user_id, device_id).await.is_ok();
+	check_device_id(device_id, existing_id.as_deref(), exists)?;
+
+	let device_keys = request
+		.device_keys
+		.deserialize()
+		.map_err(|e| err!(Request(BadJson("Invalid device keys: {e}"))))?;
+
+	if *device_keys.user_id != *user_id || device_keys.device_id != *device_id {
+		return Err!(Request(InvalidParam(
+			"Device keys are not of the dehydrated device {device_id}."
+		)));
+	}
+
+	if let Some(existing_id) = &existing_id {
+		self.remove_device(user_id, existing_id).await?;
+	}
+
+	// unlike devices created by logging in, it has no access token; it is only
+	// ever used through the dehydrated device endpoints
+	let device = Device {
+		device_id: device_id.clone(),
+		display_name: request.initial_device_display_name,
+		last_seen_ip: None,
+		last_seen_ts: None,
+	};
+
+	increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+	self.db
+		.userdeviceid_metadata
+		.put((user_id, device_id), Json(device));
+
+	for (key_id, one_time_key) in &request.one_time_keys {
+		self.add_one_time_key(user_id, device_id, key_id, one_time_key)
+			.await?;
+	}
+
+	for (key_id, fallback_key) in &request.fallback_keys {
+		self.set_fallback_key(user_id, device_id, key_id, fallback_key);
+	}
+
+	self.add_device_keys(user_id, device_id, &request.device_keys)
+		.await?;
+
+	let dehydrated_device = DehydratedDevice {
+		device_id: request.device_id,
+		device_data: request.device_data,
+	};
+
+	self.db
+		.userid_dehydrateddevice
+		.raw_put(user_id, Json(dehydrated_device));
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+pub async fn dehydrated_device(&self, user_id: &UserId) -> Result<DehydratedDevice> {
+	self.db
+		.userid_dehydrateddevice
+		.get(user_id)
+		.await
+		.deserialized()
+}
+
+#[implement(super::Service)]
+pub async fn dehydrated_device_id(&self, user_id: &UserId) -> Result<OwnedDeviceId> {
+	self.dehydrated_device(user_id)
+		.await
+		.map(|dehydrated_device| dehydrated_device.device_id)
+}
+
+/// Removes the dehydrated device of a user like any other device, returning
+/// its ID.
+#[implement(super::Service)]
+pub async fn remove_dehydrated_device(&self, user_id: &UserId) -> Result<OwnedDeviceId> {
+	let Ok(device_id) = self.dehydrated_device_id(user_id).await else {
+		return Err!(Request(NotFound("No dehydrated device.")));
+	};
+
+	self.remove_device(user_id, &device_id).await?;
+
+	Ok(device_id)
+}
+
+/// Returns a batch of at most `limit` to-device events of the dehydrated device
+/// of a user, with the `next_batch` token for the following batch. The events
+/// up to `next_batch` of the previous batch were received by the client, so
+/// they are removed.
+#[implement(super::Service)]
+pub async fn dehydrated_device_events(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	next_batch: Option<&str>,
+	limit: usize,
+) -> Result<(Vec<Raw<AnyToDeviceEvent>>, Option<String>)> {
+	if !self
+		.dehydrated_device_id(user_id)
+		.await
+		.is_ok_and(|dehydrated_id| dehydrated_id == *device_id)
+	{
+		return Err!(Request(Forbidden("{device_id} is not your dehydrated device.")));
+	}
+
+	let since = parse_next_batch(next_batch)?;
+	if let Some(since) = since {
+		self.remove_to_device_events(user_id, device_id, since)
+			.await;
+	}
+
+	let events = self
+		.get_to_device_events(user_id, device_id, since, None)
+		.take(limit)
+		.collect()
+		.await;
+
+	Ok(page(events, since))
+}
+
+/// Checks the ID of a new dehydrated device, which may replace the previous
+/// dehydrated device but no other device of the user.
+pub(super) fn check_device_id(
+	device_id: &DeviceId,
+	existing_id: Option<&DeviceId>,
+	exists: bool,
+) -> Result {
+	if exists && existing_id != Some(device_id) {
+		return Err!(Request(InvalidParam("Device {device_id} already exists.")));
+	}
+
+	if is_impersonation_device(device_id) {
+		return Err!(Request(InvalidParam(
+			"Device IDs starting with {IMPERSONATION_DEVICE_PREFIX} are reserved."
+		)));
+	}
+
+	Ok(())
+}
+
+pub(super) fn parse_next_batch(next_batch: Option<&str>) -> Result<Option<u64>> {
+	next_batch
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid next_batch."))))
+}
+
+/// Splits the counts off a batch of events, returning the `next_batch` token
+/// after the last of them. An empty batch keeps the token it was requested
+/// with, so the client can ask again later.
+pub(super) fn page<T>(events: Vec<(u64, T)>, since: Option<u64>) -> (Vec<T>, Option<String>) {
+	let next_batch = events
+		.last()
+		.map(|(count, _)| *count)
+		.or(since)
+		.map(|count| count.to_string());
+
+	(events.into_iter().map(|(_, event)| event).collect(), next_batch)
+}
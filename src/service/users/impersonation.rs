@@ -0,0 +1,158 @@
+use conduwuit::{
+	Err, Result, implement,
+	utils::{self, ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Ignore, Interfix, Json};
+use futures::{Stream, StreamExt};
+use ruma::{
+	DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId,
+	api::client::device::Device,
+};
+use serde::{Deserialize, Serialize};
+
+/// Device IDs of impersonation sessions start with this. Clients cannot create
+/// devices with it, and devices with it are left out of the user's device
+/// list.
+pub const IMPERSONATION_DEVICE_PREFIX: &str = "ADMIN_IMPERSONATION_";
+
+const DEVICE_ID_LENGTH: usize = 10;
+const TOKEN_LENGTH: usize = 32;
+
+/// A session an admin opened to act as a user, for example to see their exact
+/// `/sync` while debugging.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Impersonation {
+	pub user_id: OwnedUserId,
+	pub device_id: OwnedDeviceId,
+	pub created: MilliSecondsSinceUnixEpoch,
+
+	/// The access token stops working at this time.
+	pub expires_at: MilliSecondsSinceUnixEpoch,
+}
+
+#[must_use]
+pub fn is_impersonation_device(device_id: &DeviceId) -> bool {
+	device_id.as_str().starts_with(IMPERSONATION_DEVICE_PREFIX)
+}
+
+/// Creates an impersonation session for the user on a new device, returning it
+/// with its access token. Unlike devices created by logging in, the device
+/// list of the user does not change, so neither federation nor the user's
+/// other devices are told about it.
+#[implement(super::Service)]
+pub async fn create_impersonation(
+	&self,
+	user_id: &UserId,
+	expires_at: MilliSecondsSinceUnixEpoch,
+) -> Result<(Impersonation, String)> {
+	if !self.is_active_local(user_id).await {
+		return Err!(Request(NotFound("{user_id} is not an active local user.")));
+	}
+
+	let device_id: OwnedDeviceId =
+		format!("{IMPERSONATION_DEVICE_PREFIX}{}", utils::random_string(DEVICE_ID_LENGTH)).into();
+
+	let device = Device {
+		device_id: device_id.clone(),
+		display_name: Some("Admin impersonation session".to_owned()),
+		last_seen_ip: None,
+		last_seen_ts: None,
+	};
+
+	let impersonation = Impersonation {
+		user_id: user_id.to_owned(),
+		device_id,
+		created: MilliSecondsSinceUnixEpoch::now(),
+		expires_at,
+	};
+
+	let key = (user_id, &impersonation.device_id);
+	self.db.userdeviceid_metadata.put(key, Json(device));
+	self.db
+		.userdeviceid_impersonation
+		.put(key, Json(&impersonation));
+
+	let token = utils::random_string(TOKEN_LENGTH);
+	self.set_token(user_id, &impersonation.device_id, &token)
+		.await?;
+
+	Ok((impersonation, token))
+}
+
+#[implement(super::Service)]
+pub async fn impersonation(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> Result<Impersonation> {
+	self.db
+		.userdeviceid_impersonation
+		.qry(&(user_id, device_id))
+		.await
+		.deserialized()
+}
+
+/// Returns the impersonation sessions of every user, or of one.
+#[implement(super::Service)]
+pub fn impersonations<'a>(
+	&'a self,
+	user_id: Option<&'a UserId>,
+) -> impl Stream<Item = Impersonation> + Send + 'a {
+	self.db
+		.userdeviceid_impersonation
+		.stream()
+		.ignore_err()
+		.map(|(_, impersonation): (Ignore, Impersonation)| impersonation)
+		.ready_filter(move |impersonation| {
+			user_id.is_none_or(|user_id| *impersonation.user_id == *user_id)
+		})
+}
+
+/// Ends an impersonation session, removing its device and access token.
+#[implement(super::Service)]
+pub async fn revoke_impersonation(&self, user_id: &UserId, device_id: &DeviceId) -> Result {
+	if self.impersonation(user_id, device_id).await.is_err() {
+		return Err!(Request(NotFound(
+			"{device_id} is not an impersonation session of {user_id}."
+		)));
+	}
+
+	self.remove_device(user_id, device_id).await
+}
+
+/// Ends every impersonation session of the user.
+#[implement(super::Service)]
+pub(super) async fn revoke_impersonations(&self, user_id: &UserId) -> Result {
+	let device_ids: Vec<OwnedDeviceId> = self
+		.db
+		.userdeviceid_impersonation
+		.keys_prefix(&(user_id, Interfix))
+		.ignore_err()
+		.map(|(_, device_id): (Ignore, &DeviceId)| device_id.to_owned())
+		.collect()
+		.await;
+
+	for device_id in &device_ids {
+		self.remove_device(user_id, device_id).await?;
+	}
+
+	Ok(())
+}
+
+/// Ends the impersonation sessions which expired. Returns the number ended.
+#[implement(super::Service)]
+pub(super) async fn revoke_expired_impersonations(&self) -> Result<usize> {
+	let now = MilliSecondsSinceUnixEpoch::now();
+	let expired: Vec<Impersonation> = self
+		.impersonations(None)
+		.ready_filter(|impersonation| impersonation.expires_at <= now)
+		.collect()
+		.await;
+
+	for impersonation in &expired {
+		self.remove_device(&impersonation.user_id, &impersonation.device_id)
+			.await?;
+	}
+
+	Ok(expired.len())
+}
@@ -1,11 +1,21 @@
-use std::{collections::BTreeMap, mem, sync::Arc};
+mod cross_signing;
+mod dehydrated_device;
+mod impersonation;
+mod prune;
+mod restriction;
+mod seen;
+mod tests;
 
+use std::{collections::BTreeMap, fmt::Write, mem, sync::Arc};
+
+use async_trait::async_trait;
 use conduwuit::{
-	Err, Error, Result, Server, at, debug_warn, err, trace,
-	utils::{self, ReadyExt, stream::TryIgnore, string::Unquoted},
+	Err, Error, Result, Server, debug_warn, err, trace,
+	utils::{self, ReadyExt, math::usize_from_f64, stream::TryIgnore, string::Unquoted},
 };
 use database::{Deserialized, Ignore, Interfix, Json, Map};
-use futures::{Stream, StreamExt, TryFutureExt};
+use futures::{Stream, StreamExt, TryFutureExt, TryStreamExt};
+use lru_cache::LruCache;
 use ruma::{
 	DeviceId, KeyId, MilliSecondsSinceUnixEpoch, OneTimeKeyAlgorithm, OneTimeKeyId,
 	OneTimeKeyName, OwnedDeviceId, OwnedKeyId, OwnedMxcUri, OwnedUserId, RoomId, UInt, UserId,
@@ -18,11 +28,19 @@
 };
 use serde_json::json;
 
+pub use self::{
+	dehydrated_device::DehydratedDevice,
+	impersonation::{IMPERSONATION_DEVICE_PREFIX, Impersonation, is_impersonation_device},
+	prune::DevicePolicy,
+	restriction::{Restriction, shadow_event_id},
+	seen::Connection,
+};
 use crate::{Dep, account_data, admin, globals, rooms};
 
 pub struct Service {
 	services: Services,
 	db: Data,
+	seen: seen::Seen,
 }
 
 struct Services {
@@ -42,23 +60,35 @@ struct Data {
 	logintoken_expiresatuserid: Arc<Map>,
 	todeviceid_events: Arc<Map>,
 	token_userdeviceid: Arc<Map>,
+	userdeviceid_connections: Arc<Map>,
+	userdeviceid_fallbackkeys: Arc<Map>,
+	userdeviceid_impersonation: Arc<Map>,
+	userdeviceid_lastseen: Arc<Map>,
 	userdeviceid_metadata: Arc<Map>,
 	userdeviceid_token: Arc<Map>,
 	userfilterid_filter: Arc<Map>,
 	userid_avatarurl: Arc<Map>,
 	userid_blurhash: Arc<Map>,
+	userid_dehydrateddevice: Arc<Map>,
 	userid_devicelistversion: Arc<Map>,
 	userid_displayname: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
+	userid_locked: Arc<Map>,
 	userid_masterkeyid: Arc<Map>,
 	userid_password: Arc<Map>,
 	userid_selfsigningkeyid: Arc<Map>,
+	userid_shadowbanned: Arc<Map>,
+	userid_suspended: Arc<Map>,
 	userid_usersigningkeyid: Arc<Map>,
 	useridprofilekey_value: Arc<Map>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let seen_capacity =
+			f64::from(config.device_last_seen_cache_capacity) * config.cache_capacity_modifier;
 		Ok(Arc::new(Self {
 			services: Services {
 				server: args.server.clone(),
@@ -77,23 +107,45 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				logintoken_expiresatuserid: args.db["logintoken_expiresatuserid"].clone(),
 				todeviceid_events: args.db["todeviceid_events"].clone(),
 				token_userdeviceid: args.db["token_userdeviceid"].clone(),
+				userdeviceid_connections: args.db["userdeviceid_connections"].clone(),
+				userdeviceid_fallbackkeys: args.db["userdeviceid_fallbackkeys"].clone(),
+				userdeviceid_impersonation: args.db["userdeviceid_impersonation"].clone(),
+				userdeviceid_lastseen: args.db["userdeviceid_lastseen"].clone(),
 				userdeviceid_metadata: args.db["userdeviceid_metadata"].clone(),
 				userdeviceid_token: args.db["userdeviceid_token"].clone(),
 				userfilterid_filter: args.db["userfilterid_filter"].clone(),
 				userid_avatarurl: args.db["userid_avatarurl"].clone(),
 				userid_blurhash: args.db["userid_blurhash"].clone(),
+				userid_dehydrateddevice: args.db["userid_dehydrateddevice"].clone(),
 				userid_devicelistversion: args.db["userid_devicelistversion"].clone(),
 				userid_displayname: args.db["userid_displayname"].clone(),
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
+				userid_locked: args.db["userid_locked"].clone(),
 				userid_masterkeyid: args.db["userid_masterkeyid"].clone(),
 				userid_password: args.db["userid_password"].clone(),
 				userid_selfsigningkeyid: args.db["userid_selfsigningkeyid"].clone(),
+				userid_shadowbanned: args.db["userid_shadowbanned"].clone(),
+				userid_suspended: args.db["userid_suspended"].clone(),
 				userid_usersigningkeyid: args.db["userid_usersigningkeyid"].clone(),
 				useridprofilekey_value: args.db["useridprofilekey_value"].clone(),
 			},
+			seen: LruCache::new(usize_from_f64(seen_capacity)?).into(),
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let (len, capacity) = {
+			let seen = self.seen.lock().expect("locked");
+			(seen.len(), seen.capacity())
+		};
+
+		writeln!(out, "device_last_seen_cache: {len} / {capacity}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) { self.seen.lock().expect("locked").clear(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -130,8 +182,11 @@ pub fn create(&self, user_id: &UserId, password: Option<&str>) -> Result<()> {
 	pub async fn deactivate_account(&self, user_id: &UserId) -> Result<()> {
 		// Remove all associated devices
 		self.all_device_ids(user_id)
-			.for_each(|device_id| self.remove_device(user_id, device_id))
-			.await;
+			.map(Ok)
+			.try_for_each(|device_id| self.remove_device(user_id, device_id))
+			.await?;
+
+		self.revoke_impersonations(user_id).await?;
 
 		// Set the password to "" to indicate a deactivated account. Hashes will never
 		// result in an empty string, so the user will not be able to log in again.
@@ -173,9 +228,30 @@ pub async fn is_active_local(&self, user_id: &UserId) -> bool {
 	#[inline]
 	pub async fn count(&self) -> usize { self.db.userid_password.count().await }
 
-	/// Find out which user an access token belongs to.
+	/// Find out which user an access token belongs to. Tokens of expired
+	/// impersonation sessions are revoked.
 	pub async fn find_from_token(&self, token: &str) -> Result<(OwnedUserId, OwnedDeviceId)> {
-		self.db.token_userdeviceid.get(token).await.deserialized()
+		let (user_id, device_id): (OwnedUserId, OwnedDeviceId) =
+			self.db.token_userdeviceid.get(token).await.deserialized()?;
+
+		if is_impersonation_device(&device_id) {
+			// A session whose record is gone can no longer be accounted for, so it is
+			// treated the same as an expired one.
+			let expired = self
+				.impersonation(&user_id, &device_id)
+				.await
+				.ok()
+				.is_none_or(|impersonation| {
+					impersonation.expires_at <= MilliSecondsSinceUnixEpoch::now()
+				});
+
+			if expired {
+				self.remove_device(&user_id, &device_id).await?;
+				return Err!("Impersonation session of {user_id} on {device_id} has expired.");
+			}
+		}
+
+		Ok((user_id, device_id))
 	}
 
 	/// Returns an iterator over all users on this homeserver (offered for
@@ -284,6 +360,12 @@ pub async fn create_device(
 			))));
 		}
 
+		if is_impersonation_device(device_id) {
+			return Err!(Request(InvalidParam(
+				"Device IDs starting with {IMPERSONATION_DEVICE_PREFIX} are reserved."
+			)));
+		}
+
 		let key = (user_id, device_id);
 		let val = Device {
 			device_id: device_id.into(),
@@ -298,7 +380,7 @@ pub async fn create_device(
 	}
 
 	/// Removes a device from a user.
-	pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
+	pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) -> Result {
 		let userdeviceid = (user_id, device_id);
 
 		// Remove tokens
@@ -316,15 +398,56 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 			.ready_for_each(|key| self.db.todeviceid_events.remove(key))
 			.await;
 
-		// TODO: Remove onetimekeys
+		// Remove onetimekeys
+		self.db
+			.onetimekeyid_onetimekeys
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.onetimekeyid_onetimekeys.remove(key))
+			.await;
+
+		// Remove fallback keys
+		self.db
+			.userdeviceid_fallbackkeys
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.userdeviceid_fallbackkeys.remove(key))
+			.await;
+
+		// Remove device keys
+		self.db.keyid_key.del(userdeviceid);
 
-		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+		// Remove connections
+		self.db
+			.userdeviceid_connections
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.userdeviceid_connections.remove(key))
+			.await;
 
+		self.db.userdeviceid_lastseen.del(userdeviceid);
 		self.db.userdeviceid_metadata.del(userdeviceid);
-		self.mark_device_key_update(user_id).await;
+
+		if self
+			.dehydrated_device_id(user_id)
+			.await
+			.is_ok_and(|dehydrated_id| *dehydrated_id == *device_id)
+		{
+			self.db.userid_dehydrateddevice.remove(user_id);
+		}
+
+		// impersonation sessions were never part of the device list
+		if is_impersonation_device(device_id) {
+			self.db.userdeviceid_impersonation.del(userdeviceid);
+			return Ok(());
+		}
+
+		increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+		self.mark_device_key_update(user_id).await
 	}
 
-	/// Returns an iterator over all device ids of this user.
+	/// Returns an iterator over all device ids of this user, without those of
+	/// impersonation sessions.
 	pub fn all_device_ids<'a>(
 		&'a self,
 		user_id: &'a UserId,
@@ -335,6 +458,7 @@ pub fn all_device_ids<'a>(
 			.keys_prefix(&prefix)
 			.ignore_err()
 			.map(|(_, device_id): (Ignore, &DeviceId)| device_id)
+			.ready_filter(|device_id| !is_impersonation_device(device_id))
 	}
 
 	pub async fn get_token(&self, user_id: &UserId, device_id: &DeviceId) -> Result<String> {
@@ -406,7 +530,7 @@ pub async fn add_one_time_key(
 			.onetimekeyid_onetimekeys
 			.raw_put(key, Json(one_time_key_value));
 
-		let count = self.services.globals.next_count().unwrap();
+		let count = self.services.globals.next_count()?;
 		self.db.userid_lastonetimekeyupdate.raw_put(user_id, count);
 
 		Ok(())
@@ -465,7 +589,34 @@ pub async fn take_one_time_key(
 			.next()
 			.await;
 
-		one_time_key.ok_or_else(|| err!(Request(NotFound("No one-time-key found"))))
+		if let Some(one_time_key) = one_time_key {
+			return Ok(one_time_key);
+		}
+
+		// unlike one-time keys, the fallback key is kept until the device replaces it
+		let key = (user_id, device_id, key_algorithm.as_str());
+		self.db
+			.userdeviceid_fallbackkeys
+			.qry(&key)
+			.await
+			.deserialized()
+			.map_err(|_| err!(Request(NotFound("No one-time-key found"))))
+	}
+
+	/// Sets the fallback key of a device for the algorithm of the key, which is
+	/// claimed once the device has no one-time keys of that algorithm left.
+	pub fn set_fallback_key(
+		&self,
+		user_id: &UserId,
+		device_id: &DeviceId,
+		key_id: &KeyId<OneTimeKeyAlgorithm, OneTimeKeyName>,
+		fallback_key: &Raw<OneTimeKey>,
+	) {
+		let algorithm = key_id.algorithm();
+		let key = (user_id, device_id, algorithm.as_str());
+		self.db
+			.userdeviceid_fallbackkeys
+			.put(key, Json((key_id, fallback_key)));
 	}
 
 	pub async fn count_one_time_keys(
@@ -503,13 +654,15 @@ pub async fn add_device_keys(
 		user_id: &UserId,
 		device_id: &DeviceId,
 		device_keys: &Raw<DeviceKeys>,
-	) {
+	) -> Result {
 		let key = (user_id, device_id);
 
 		self.db.keyid_key.put(key, Json(device_keys));
-		self.mark_device_key_update(user_id).await;
+		self.mark_device_key_update(user_id).await
 	}
 
+	/// Stores cross-signing keys of a user. The keys of local users are checked
+	/// with `verify_cross_signing_keys` first.
 	pub async fn add_cross_signing_keys(
 		&self,
 		user_id: &UserId,
@@ -518,7 +671,6 @@ pub async fn add_cross_signing_keys(
 		user_signing_key: &Option<Raw<CrossSigningKey>>,
 		notify: bool,
 	) -> Result<()> {
-		// TODO: Check signatures
 		let mut prefix = user_id.as_bytes().to_vec();
 		prefix.push(0xFF);
 
@@ -581,7 +733,7 @@ pub async fn add_cross_signing_keys(
 		}
 
 		if notify {
-			self.mark_device_key_update(user_id).await;
+			self.mark_device_key_update(user_id).await?;
 		}
 
 		Ok(())
@@ -627,7 +779,7 @@ pub async fn sign_key(
 		let key = (target_id, key_id);
 		self.db.keyid_key.put(key, Json(cross_signing_key));
 
-		self.mark_device_key_update(target_id).await;
+		self.mark_device_key_update(target_id).await?;
 
 		Ok(())
 	}
@@ -673,8 +825,8 @@ fn keys_changed_user_or_room<'a>(
 			.map(|((_, count), user_id): KeyVal<'_>| (user_id, count))
 	}
 
-	pub async fn mark_device_key_update(&self, user_id: &UserId) {
-		let count = self.services.globals.next_count().unwrap();
+	pub async fn mark_device_key_update(&self, user_id: &UserId) -> Result {
+		let count = self.services.globals.next_count()?;
 
 		self.services
 			.state_cache
@@ -689,6 +841,8 @@ pub async fn mark_device_key_update(&self, user_id: &UserId) {
 
 		let key = (user_id, count);
 		self.db.keychangeid_userid.put_raw(key, user_id);
+
+		Ok(())
 	}
 
 	pub async fn get_device_keys<'a>(
@@ -763,8 +917,8 @@ pub async fn add_to_device_event(
 		target_device_id: &DeviceId,
 		event_type: &str,
 		content: serde_json::Value,
-	) {
-		let count = self.services.globals.next_count().unwrap();
+	) -> Result {
+		let count = self.services.globals.next_count()?;
 
 		let key = (target_user_id, target_device_id, count);
 		self.db.todeviceid_events.put(
@@ -775,15 +929,19 @@ pub async fn add_to_device_event(
 				"content": content,
 			})),
 		);
+
+		Ok(())
 	}
 
+	/// Returns the to-device events of a device after `since` and up to `to`,
+	/// with their counts, oldest first.
 	pub fn get_to_device_events<'a>(
 		&'a self,
 		user_id: &'a UserId,
 		device_id: &'a DeviceId,
 		since: Option<u64>,
 		to: Option<u64>,
-	) -> impl Stream<Item = Raw<AnyToDeviceEvent>> + Send + 'a {
+	) -> impl Stream<Item = (u64, Raw<AnyToDeviceEvent>)> + Send + 'a {
 		type Key<'a> = (&'a UserId, &'a DeviceId, u64);
 
 		let from = (user_id, device_id, since.map_or(0, |since| since.saturating_add(1)));
@@ -797,7 +955,7 @@ pub fn get_to_device_events<'a>(
 					&& device_id == *device_id_
 					&& to.is_none_or(|to| *count <= to)
 			})
-			.map(at!(1))
+			.map(|((_, _, count), event): (Key<'_>, _)| (count, event))
 	}
 
 	pub async fn remove_to_device_events<Until>(
@@ -845,11 +1003,14 @@ pub async fn get_device_metadata(
 		user_id: &UserId,
 		device_id: &DeviceId,
 	) -> Result<Device> {
-		self.db
+		let device = self
+			.db
 			.userdeviceid_metadata
 			.qry(&(user_id, device_id))
 			.await
-			.deserialized()
+			.deserialized()?;
+
+		Ok(self.with_last_seen(user_id, device).await)
 	}
 
 	pub async fn get_devicelist_version(&self, user_id: &UserId) -> Result<u64> {
@@ -860,6 +1021,8 @@ pub async fn get_devicelist_version(&self, user_id: &UserId) -> Result<u64> {
 			.deserialized()
 	}
 
+	/// Returns the metadata of all devices of this user, without those of
+	/// impersonation sessions.
 	pub fn all_devices_metadata<'a>(
 		&'a self,
 		user_id: &'a UserId,
@@ -870,6 +1033,8 @@ pub fn all_devices_metadata<'a>(
 			.stream_prefix(&key)
 			.ignore_err()
 			.map(|(_, val): (Ignore, Device)| val)
+			.ready_filter(|device| !is_impersonation_device(&device.device_id))
+			.then(move |device| self.with_last_seen(user_id, device))
 	}
 
 	/// Creates a new sync filter. Returns the filter id.
@@ -970,6 +1135,33 @@ pub async fn find_from_login_token(&self, token: &str) -> Result<OwnedUserId> {
 		Ok(user_id)
 	}
 
+	/// Removes expired OpenID and login tokens and ends expired impersonation
+	/// sessions. Returns the number of tokens removed.
+	pub async fn remove_expired_tokens(&self) -> Result<usize> {
+		let now = utils::millis_since_unix_epoch();
+		let mut removed = self.revoke_expired_impersonations().await?;
+		for map in [&self.db.openidtoken_expiresatuserid, &self.db.logintoken_expiresatuserid] {
+			// both values start with the big-endian expiry timestamp
+			let expired: Vec<Vec<u8>> = map
+				.raw_stream()
+				.ignore_err()
+				.ready_filter_map(|(token, value)| {
+					let expires_at = value.get(..8)?.try_into().map(u64::from_be_bytes).ok()?;
+					(expires_at < now).then(|| token.to_vec())
+				})
+				.collect()
+				.await;
+
+			for token in &expired {
+				map.remove(token);
+			}
+
+			removed = removed.saturating_add(expired.len());
+		}
+
+		Ok(removed)
+	}
+
 	/// Gets a specific user profile key
 	pub async fn profile_key(
 		&self,
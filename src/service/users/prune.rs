@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use conduwuit::{
+	Result,
+	config::JobsConfig,
+	err, implement,
+	utils::{ReadyExt, time::timepoint_ago},
+};
+use futures::StreamExt;
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId, api::client::device::Device,
+};
+
+/// Which devices of a user are stale and removed by pruning.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DevicePolicy {
+	/// Devices last seen before this time are stale. Devices never seen are
+	/// not.
+	pub inactive_before: Option<MilliSecondsSinceUnixEpoch>,
+
+	/// Devices beyond this many, keeping the most recently seen, are stale.
+	pub max_devices: Option<usize>,
+}
+
+impl DevicePolicy {
+	/// The policy of the device pruning job.
+	pub fn from_config(config: &JobsConfig) -> Result<Self> {
+		let inactive_for = (config.device_max_inactivity > 0)
+			.then(|| Duration::from_secs(config.device_max_inactivity));
+
+		Ok(Self {
+			inactive_before: inactive_for.map(Self::cutoff).transpose()?,
+			max_devices: (config.device_max_per_user > 0).then_some(config.device_max_per_user),
+		})
+	}
+
+	/// The time before which a device last seen is stale when devices may be
+	/// inactive for `inactive_for`.
+	pub fn cutoff(inactive_for: Duration) -> Result<MilliSecondsSinceUnixEpoch> {
+		let cutoff = timepoint_ago(inactive_for)?;
+		MilliSecondsSinceUnixEpoch::from_system_time(cutoff)
+			.ok_or_else(|| err!(Arithmetic("Cutoff {cutoff:?} is out of range")))
+	}
+
+	pub(super) fn is_stale(&self, position: usize, device: &Device) -> bool {
+		let inactive = self
+			.inactive_before
+			.zip(device.last_seen_ts)
+			.is_some_and(|(cutoff, last_seen)| last_seen < cutoff);
+
+		let excess = self.max_devices.is_some_and(|max| position >= max);
+
+		inactive || excess
+	}
+}
+
+/// Returns the devices of a user which are stale under the policy, least
+/// recently seen first.
+#[implement(super::Service)]
+pub async fn stale_devices(&self, user_id: &UserId, policy: &DevicePolicy) -> Vec<Device> {
+	// the dehydrated device is never seen, but is kept for the devices to come
+	let dehydrated_id = self.dehydrated_device_id(user_id).await.ok();
+	let devices: Vec<Device> = self
+		.all_devices_metadata(user_id)
+		.ready_filter(|device| dehydrated_id.as_ref() != Some(&device.device_id))
+		.collect()
+		.await;
+
+	stale_of(devices, policy)
+}
+
+/// The devices which are stale under the policy, least recently seen first.
+pub(super) fn stale_of(mut devices: Vec<Device>, policy: &DevicePolicy) -> Vec<Device> {
+	// most recently seen first; devices never seen sort last
+	devices.sort_by(|a, b| b.last_seen_ts.cmp(&a.last_seen_ts));
+
+	let mut stale: Vec<Device> = devices
+		.into_iter()
+		.enumerate()
+		.filter(|(position, device)| policy.is_stale(*position, device))
+		.map(|(_, device)| device)
+		.collect();
+
+	stale.reverse();
+	stale
+}
+
+/// Removes the devices of a user which are stale under the policy, along with
+/// their tokens, keys and pending to-device events. The user's device list is
+/// updated for the user and those sharing rooms with them. Returns the removed
+/// devices.
+#[implement(super::Service)]
+pub async fn prune_devices(
+	&self,
+	user_id: &UserId,
+	policy: &DevicePolicy,
+) -> Result<Vec<OwnedDeviceId>> {
+	let stale: Vec<OwnedDeviceId> = self
+		.stale_devices(user_id, policy)
+		.await
+		.into_iter()
+		.map(|device| device.device_id)
+		.collect();
+
+	for device_id in &stale {
+		self.remove_device(user_id, device_id).await?;
+	}
+
+	Ok(stale)
+}
+
+/// Prunes the devices of every local user under the policy. Returns the number
+/// of devices removed.
+#[implement(super::Service)]
+pub async fn prune_all_devices(&self, policy: &DevicePolicy) -> Result<usize> {
+	let users: Vec<OwnedUserId> = self
+		.stream()
+		.ready_filter(|user_id| self.services.globals.user_is_local(user_id))
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut removed: usize = 0;
+	for user_id in &users {
+		let pruned = self.prune_devices(user_id, policy).await?;
+		removed = removed.saturating_add(pruned.len());
+	}
+
+	Ok(removed)
+}
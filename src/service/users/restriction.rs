@@ -0,0 +1,109 @@
+use conduwuit::{implement, utils};
+use database::{Deserialized, Json};
+use ruma::{EventId, MilliSecondsSinceUnixEpoch, OwnedEventId, UserId};
+use serde::{Deserialize, Serialize};
+
+/// A lock (MSC3939), suspension (MSC3823) or shadow-ban of an account, which
+/// unlike deactivation can be lifted again.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Restriction {
+	pub reason: Option<String>,
+	pub since: MilliSecondsSinceUnixEpoch,
+}
+
+impl Restriction {
+	#[must_use]
+	pub fn new(reason: Option<String>) -> Self {
+		Self {
+			reason,
+			since: MilliSecondsSinceUnixEpoch::now(),
+		}
+	}
+}
+
+/// Locks an account. Every request of a locked account is refused with
+/// M_USER_LOCKED until it is unlocked.
+#[implement(super::Service)]
+pub fn lock_account(&self, user_id: &UserId, restriction: &Restriction) {
+	self.db.userid_locked.raw_put(user_id, Json(restriction));
+}
+
+#[implement(super::Service)]
+pub fn unlock_account(&self, user_id: &UserId) { self.db.userid_locked.remove(user_id); }
+
+/// Returns the lock of an account, if it is locked.
+#[implement(super::Service)]
+pub async fn locked(&self, user_id: &UserId) -> Option<Restriction> {
+	self.db.userid_locked.get(user_id).await.deserialized().ok()
+}
+
+#[implement(super::Service)]
+pub async fn is_locked(&self, user_id: &UserId) -> bool {
+	self.db.userid_locked.get(user_id).await.is_ok()
+}
+
+/// Suspends an account. A suspended account can still read, but requests
+/// which send, join, invite, upload or change the profile are refused with
+/// M_USER_SUSPENDED until it is unsuspended.
+#[implement(super::Service)]
+pub fn suspend_account(&self, user_id: &UserId, restriction: &Restriction) {
+	self.db.userid_suspended.raw_put(user_id, Json(restriction));
+}
+
+#[implement(super::Service)]
+pub fn unsuspend_account(&self, user_id: &UserId) { self.db.userid_suspended.remove(user_id); }
+
+/// Returns the suspension of an account, if it is suspended.
+#[implement(super::Service)]
+pub async fn suspended(&self, user_id: &UserId) -> Option<Restriction> {
+	self.db
+		.userid_suspended
+		.get(user_id)
+		.await
+		.deserialized()
+		.ok()
+}
+
+#[implement(super::Service)]
+pub async fn is_suspended(&self, user_id: &UserId) -> bool {
+	self.db.userid_suspended.get(user_id).await.is_ok()
+}
+
+/// Shadow-bans an account. The sends, invites and room creations of a
+/// shadow-banned account appear to succeed, but nothing is persisted,
+/// federated or delivered.
+#[implement(super::Service)]
+pub fn shadow_ban(&self, user_id: &UserId, restriction: &Restriction) {
+	self.db
+		.userid_shadowbanned
+		.raw_put(user_id, Json(restriction));
+}
+
+#[implement(super::Service)]
+pub fn unshadow_ban(&self, user_id: &UserId) { self.db.userid_shadowbanned.remove(user_id); }
+
+/// Returns the shadow-ban of an account, if it is shadow-banned.
+#[implement(super::Service)]
+pub async fn shadow_banned(&self, user_id: &UserId) -> Option<Restriction> {
+	self.db
+		.userid_shadowbanned
+		.get(user_id)
+		.await
+		.deserialized()
+		.ok()
+}
+
+#[implement(super::Service)]
+pub async fn is_shadow_banned(&self, user_id: &UserId) -> bool {
+	self.db.userid_shadowbanned.get(user_id).await.is_ok()
+}
+
+/// Returns a random event ID shaped like those of current room versions, for
+/// the responses to a shadow-banned account's sends.
+#[must_use]
+pub fn shadow_event_id() -> OwnedEventId {
+	const REFERENCE_HASH_LENGTH: usize = 43;
+
+	EventId::parse(format!("${}", utils::random_string(REFERENCE_HASH_LENGTH)))
+		.expect("random alphanumeric event ID is valid")
+}
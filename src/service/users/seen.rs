@@ -0,0 +1,205 @@
+use std::{
+	net::IpAddr,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use conduwuit::{
+	Result, implement,
+	result::LogErr,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use database::{Deserialized, Ignore, Interfix, Json};
+use futures::StreamExt;
+use lru_cache::LruCache;
+use ruma::{
+	DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId,
+	api::client::device::Device,
+};
+use serde::{Deserialize, Serialize};
+
+/// Devices recently recorded as seen, to throttle recording; bounded by
+/// "device_last_seen_cache_capacity".
+pub(super) type Seen = Mutex<LruCache<(OwnedUserId, OwnedDeviceId), LastSeen>>;
+
+pub(super) struct LastSeen {
+	pub(super) at: Instant,
+	pub(super) ip: IpAddr,
+	pub(super) user_agent: Option<String>,
+}
+
+/// Address and time a device was last seen with. This is kept apart from the
+/// device's metadata so that recording it does not race with changes to the
+/// device, and takes precedence over the metadata unless that is newer.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(super) struct LastSeenAt {
+	pub(super) ip: Option<String>,
+	pub(super) ts: Option<MilliSecondsSinceUnixEpoch>,
+}
+
+/// Address a device was seen with, for `!admin users whois`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Connection {
+	#[serde(skip)]
+	pub ip: String,
+	pub user_agent: Option<String>,
+	pub last_seen_ts: MilliSecondsSinceUnixEpoch,
+}
+
+/// User agents are truncated to this many characters.
+const USER_AGENT_MAX_LEN: usize = 512;
+
+/// Records that a device was seen making a request from the address with the
+/// user agent. The device's last-seen address and time are updated, and the
+/// connection is added to those of the device. Records are throttled to one
+/// per "device_last_seen_interval" unless the address or user agent changed.
+#[implement(super::Service)]
+pub async fn device_seen(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	ip: IpAddr,
+	user_agent: Option<&str>,
+) {
+	let user_agent: Option<String> =
+		user_agent.map(|user_agent| user_agent.chars().take(USER_AGENT_MAX_LEN).collect());
+
+	let interval = Duration::from_secs(self.services.server.config.device_last_seen_interval);
+	let now = Instant::now();
+	{
+		let mut seen = self.seen.lock().expect("locked");
+		let key = (user_id.to_owned(), device_id.to_owned());
+		if is_throttled(seen.get_mut(&key).as_deref(), ip, user_agent.as_deref(), now, interval) {
+			return;
+		}
+
+		let user_agent = user_agent.clone();
+		seen.insert(key, LastSeen { at: now, ip, user_agent });
+	}
+
+	let last_seen_ts = MilliSecondsSinceUnixEpoch::now();
+	let last_seen = LastSeenAt {
+		ip: Some(ip.to_string()),
+		ts: Some(last_seen_ts),
+	};
+	self.db
+		.userdeviceid_lastseen
+		.put((user_id, device_id), Json(last_seen));
+
+	let connection = Connection {
+		ip: ip.to_string(),
+		user_agent,
+		last_seen_ts,
+	};
+
+	let key = (user_id, device_id, connection.ip.as_str());
+	self.db.userdeviceid_connections.put(key, Json(&connection));
+}
+
+/// Whether a device seen again from the address with the user agent need not
+/// be recorded, having been recorded with the same less than `interval` ago.
+pub(super) fn is_throttled(
+	last: Option<&LastSeen>,
+	ip: IpAddr,
+	user_agent: Option<&str>,
+	now: Instant,
+	interval: Duration,
+) -> bool {
+	last.is_some_and(|last| {
+		last.ip == ip
+			&& last.user_agent.as_deref() == user_agent
+			&& now.saturating_duration_since(last.at) < interval
+	})
+}
+
+/// The device's metadata with the address and time it was last seen with.
+#[implement(super::Service)]
+pub(super) async fn with_last_seen(&self, user_id: &UserId, device: Device) -> Device {
+	let last_seen = self
+		.db
+		.userdeviceid_lastseen
+		.qry(&(user_id, &device.device_id))
+		.await
+		.deserialized()
+		.ok();
+
+	merge_last_seen(device, last_seen)
+}
+
+pub(super) fn merge_last_seen(mut device: Device, last_seen: Option<LastSeenAt>) -> Device {
+	if let Some(last_seen) = last_seen.filter(|last_seen| last_seen.ts >= device.last_seen_ts) {
+		device.last_seen_ip = last_seen.ip;
+		device.last_seen_ts = last_seen.ts;
+	}
+
+	device
+}
+
+/// Returns the addresses a device was seen with, most recent first.
+#[implement(super::Service)]
+pub async fn device_connections(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> Vec<Connection> {
+	let prefix = (user_id, device_id, Interfix);
+	let mut connections: Vec<Connection> =
+		self.db
+			.userdeviceid_connections
+			.stream_prefix(&prefix)
+			.ignore_err()
+			.map(|((_, _, ip), connection): ((&UserId, &DeviceId, &str), Connection)| {
+				Connection { ip: ip.to_owned(), ..connection }
+			})
+			.collect()
+			.await;
+
+	connections.sort_by(|a, b| b.last_seen_ts.cmp(&a.last_seen_ts));
+	connections
+}
+
+/// Removes the addresses and user agents devices were last seen with before
+/// `cutoff`. Returns the number of connections removed. Rows which cannot be
+/// read are logged and left alone.
+#[implement(super::Service)]
+pub async fn expire_connections(&self, cutoff: MilliSecondsSinceUnixEpoch) -> Result<usize> {
+	let expired: Vec<Vec<u8>> = self
+		.db
+		.userdeviceid_connections
+		.raw_stream()
+		.ignore_err()
+		.ready_filter_map(|(key, val)| {
+			let connection: Connection = serde_json::from_slice(val).log_err().ok()?;
+			(connection.last_seen_ts < cutoff).then(|| key.to_vec())
+		})
+		.collect()
+		.await;
+
+	for key in &expired {
+		self.db.userdeviceid_connections.remove(key);
+	}
+
+	// The last-seen address is removed by recording it as unknown at the time
+	// the device was last seen, which takes precedence over an older address in
+	// the device's metadata without rewriting that.
+	let devices: Vec<(OwnedUserId, Device)> = self
+		.db
+		.userdeviceid_metadata
+		.stream()
+		.ready_filter_map(|result: Result<((&UserId, Ignore), Device)>| result.log_err().ok())
+		.map(|((user_id, _), device)| (user_id.to_owned(), device))
+		.collect()
+		.await;
+
+	for (user_id, device) in devices {
+		let device = self.with_last_seen(&user_id, device).await;
+		if device.last_seen_ip.is_some() && device.last_seen_ts.is_none_or(|ts| ts < cutoff) {
+			let last_seen = LastSeenAt { ip: None, ts: device.last_seen_ts };
+			self.db
+				.userdeviceid_lastseen
+				.put((&user_id, &device.device_id), Json(last_seen));
+		}
+	}
+
+	Ok(expired.len())
+}
@@ -0,0 +1,305 @@
+#![cfg(test)]
+
+use std::{
+	net::{IpAddr, Ipv4Addr},
+	time::{Duration, Instant},
+};
+
+use lru_cache::LruCache;
+use ruma::{
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, UInt,
+	api::client::{device::Device, error::ErrorKind},
+	device_id,
+	serde::Base64,
+	signatures::{Ed25519KeyPair, sign_json},
+	user_id,
+};
+use serde_json::{Value as JsonValue, json};
+
+use super::{
+	cross_signing::{public_key, verify_signature},
+	dehydrated_device::{check_device_id, page, parse_next_batch},
+	prune::{DevicePolicy, stale_of},
+	seen::{LastSeen, LastSeenAt, is_throttled, merge_last_seen},
+};
+
+/// A keypair whose key ID is `ed25519:<public key>`, as for cross-signing keys,
+/// unless another version is given.
+fn keypair(version: Option<&str>) -> (Ed25519KeyPair, String) {
+	let document = Ed25519KeyPair::generate().expect("generated keypair");
+	let keypair = Ed25519KeyPair::from_der(&document, String::new()).expect("valid keypair");
+	let public_key: Base64 = Base64::new(keypair.public_key().to_vec());
+	let public_key = public_key.encode();
+
+	let version = version.unwrap_or(&public_key).to_owned();
+	let keypair = Ed25519KeyPair::from_der(&document, version).expect("valid keypair");
+
+	(keypair, public_key)
+}
+
+fn signed(signer: &str, keypair: &Ed25519KeyPair, object: JsonValue) -> JsonValue {
+	let mut object: CanonicalJsonObject = serde_json::from_value(object).expect("object");
+	sign_json(signer, keypair, &mut object).expect("signed object");
+
+	serde_json::to_value(object).expect("json")
+}
+
+#[test]
+fn cross_signing_public_key() {
+	let user_id = user_id!("@alice:example.com");
+	let key = json!({
+		"user_id": user_id,
+		"usage": ["self_signing"],
+		"keys": { "ed25519:abc": "abc" },
+	});
+
+	let (key_id, public_key_) = public_key(user_id, &key, "self_signing").expect("valid key");
+	assert_eq!(key_id, "ed25519:abc");
+	assert_eq!(public_key_, "abc");
+
+	assert!(public_key(user_id, &key, "master").is_err());
+	assert!(public_key(user_id!("@bob:example.com"), &key, "self_signing").is_err());
+
+	let two_keys = json!({
+		"user_id": user_id,
+		"usage": ["self_signing"],
+		"keys": { "ed25519:abc": "abc", "ed25519:def": "def" },
+	});
+	assert!(public_key(user_id, &two_keys, "self_signing").is_err());
+}
+
+#[test]
+fn signature_verification() {
+	let user_id = user_id!("@alice:example.com");
+	let (master, master_public_key) = keypair(None);
+	let (other, _) = keypair(None);
+	let (forger, _) = keypair(Some(&master_public_key));
+	let master_key_id = format!("ed25519:{master_public_key}");
+
+	let key = json!({
+		"user_id": user_id,
+		"usage": ["self_signing"],
+		"keys": { "ed25519:abc": "abc" },
+	});
+
+	let signed_key = signed(user_id.as_str(), &master, key.clone());
+	verify_signature(&signed_key, user_id, &master_key_id, &master_public_key)
+		.expect("valid signature");
+
+	// signatures of other keys are ignored
+	let mut also_signed = signed(user_id!("@bob:example.com").as_str(), &other, signed_key);
+	also_signed["unsigned"] = json!({ "device_display_name": "phone" });
+	verify_signature(&also_signed, user_id, &master_key_id, &master_public_key)
+		.expect("valid signature");
+
+	let mut tampered = also_signed.clone();
+	tampered["usage"] = json!(["user_signing"]);
+	assert!(verify_signature(&tampered, user_id, &master_key_id, &master_public_key).is_err());
+
+	let forged = signed(user_id.as_str(), &forger, key);
+	assert!(verify_signature(&forged, user_id, &master_key_id, &master_public_key).is_err());
+}
+
+fn ts(millis: u32) -> Option<MilliSecondsSinceUnixEpoch> {
+	Some(MilliSecondsSinceUnixEpoch(UInt::from(millis)))
+}
+
+fn device(
+	last_seen_ip: Option<&str>,
+	last_seen_ts: Option<MilliSecondsSinceUnixEpoch>,
+) -> Device {
+	Device {
+		device_id: device_id!("DEVICE").to_owned(),
+		display_name: Some("Phone".to_owned()),
+		last_seen_ip: last_seen_ip.map(ToOwned::to_owned),
+		last_seen_ts,
+	}
+}
+
+#[test]
+fn seen_throttled() {
+	let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+	let interval = Duration::from_secs(300);
+	let at = Instant::now();
+	let last = LastSeen {
+		at,
+		ip,
+		user_agent: Some("client".to_owned()),
+	};
+
+	assert!(!is_throttled(None, ip, Some("client"), at, interval));
+	assert!(is_throttled(Some(&last), ip, Some("client"), at, interval));
+
+	let later = at.checked_add(interval).expect("instant in range");
+	assert!(!is_throttled(Some(&last), ip, Some("client"), later, interval));
+
+	let other_ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+	assert!(!is_throttled(Some(&last), other_ip, Some("client"), at, interval));
+	assert!(!is_throttled(Some(&last), ip, Some("other client"), at, interval));
+	assert!(!is_throttled(Some(&last), ip, None, at, interval));
+}
+
+#[test]
+fn seen_cache_is_bounded() {
+	let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+	let mut seen = LruCache::new(2);
+	for device_id in ["A", "B", "C"] {
+		let last = LastSeen { at: Instant::now(), ip, user_agent: None };
+		seen.insert(device_id, last);
+	}
+
+	assert_eq!(seen.len(), 2);
+	assert!(!seen.contains_key(&"A"));
+	assert!(seen.contains_key(&"C"));
+}
+
+#[test]
+fn last_seen_merged() {
+	let last_seen = LastSeenAt {
+		ip: Some("192.0.2.1".to_owned()),
+		ts: ts(2000),
+	};
+
+	let merged = merge_last_seen(device(Some("192.0.2.2"), ts(1000)), Some(last_seen.clone()));
+	assert_eq!(merged.last_seen_ip.as_deref(), Some("192.0.2.1"));
+	assert_eq!(merged.last_seen_ts, ts(2000));
+	assert_eq!(merged.display_name.as_deref(), Some("Phone"));
+
+	// newer metadata, e.g. written when the device was renamed, wins
+	let merged = merge_last_seen(device(Some("192.0.2.2"), ts(3000)), Some(last_seen.clone()));
+	assert_eq!(merged.last_seen_ip.as_deref(), Some("192.0.2.2"));
+	assert_eq!(merged.last_seen_ts, ts(3000));
+
+	let merged = merge_last_seen(device(None, None), Some(last_seen));
+	assert_eq!(merged.last_seen_ts, ts(2000));
+
+	let merged = merge_last_seen(device(Some("192.0.2.2"), ts(1000)), None);
+	assert_eq!(merged.last_seen_ip.as_deref(), Some("192.0.2.2"));
+}
+
+#[test]
+fn last_seen_expired() {
+	// an expired address is recorded as unknown at the time it was last seen
+	let expired = LastSeenAt { ip: None, ts: ts(1000) };
+	let merged = merge_last_seen(device(Some("192.0.2.2"), ts(1000)), Some(expired));
+	assert_eq!(merged.last_seen_ip, None);
+	assert_eq!(merged.last_seen_ts, ts(1000));
+}
+
+#[test]
+fn dehydrated_device_id_conflict() {
+	let dehydrated = device_id!("DEHYDRATED");
+	let other = device_id!("PHONE");
+
+	// a new ID, or the ID of the dehydrated device it replaces
+	assert!(check_device_id(dehydrated, None, false).is_ok());
+	assert!(check_device_id(dehydrated, Some(other), false).is_ok());
+	assert!(check_device_id(dehydrated, Some(dehydrated), true).is_ok());
+
+	// any other device of the user is not replaced
+	let err = check_device_id(other, None, true).expect_err("conflict");
+	assert!(matches!(err.kind(), ErrorKind::InvalidParam));
+	let err = check_device_id(other, Some(dehydrated), true).expect_err("conflict");
+	assert!(matches!(err.kind(), ErrorKind::InvalidParam));
+
+	let reserved = device_id!("ADMIN_IMPERSONATION_DEVICE");
+	assert!(check_device_id(reserved, None, false).is_err());
+}
+
+#[test]
+fn dehydrated_events_pagination() {
+	let (events, next_batch) = page(vec![(3, "a"), (7, "b")], None);
+	assert_eq!(events, ["a", "b"]);
+	assert_eq!(next_batch.as_deref(), Some("7"));
+
+	// the token resumes after the last event received
+	let since = parse_next_batch(next_batch.as_deref()).expect("valid token");
+	assert_eq!(since, Some(7));
+
+	// no new events keeps the token
+	let (events, next_batch) = page(Vec::<(u64, &str)>::new(), since);
+	assert!(events.is_empty());
+	assert_eq!(next_batch.as_deref(), Some("7"));
+
+	let (_, next_batch) = page(Vec::<(u64, &str)>::new(), None);
+	assert_eq!(next_batch, None);
+
+	assert_eq!(parse_next_batch(None).expect("no token"), None);
+	let err = parse_next_batch(Some("s7")).expect_err("invalid token");
+	assert!(matches!(err.kind(), ErrorKind::InvalidParam));
+}
+
+fn seen_device(device_id: &str, last_seen_ts: Option<MilliSecondsSinceUnixEpoch>) -> Device {
+	Device {
+		device_id: device_id.into(),
+		..device(None, last_seen_ts)
+	}
+}
+
+fn device_ids(devices: &[Device]) -> Vec<&str> {
+	devices
+		.iter()
+		.map(|device| device.device_id.as_str())
+		.collect()
+}
+
+#[test]
+fn device_stale_when_inactive() {
+	let policy = DevicePolicy {
+		inactive_before: ts(2000),
+		max_devices: None,
+	};
+
+	assert!(policy.is_stale(0, &device(None, ts(1999))));
+	assert!(!policy.is_stale(0, &device(None, ts(2000))));
+	assert!(!policy.is_stale(5, &device(None, ts(3000))));
+
+	// devices never seen are kept
+	assert!(!policy.is_stale(0, &device(None, None)));
+	assert!(!DevicePolicy::default().is_stale(usize::MAX, &device(None, ts(0))));
+}
+
+#[test]
+fn device_stale_when_excess() {
+	let policy = DevicePolicy {
+		inactive_before: None,
+		max_devices: Some(2),
+	};
+
+	assert!(!policy.is_stale(0, &device(None, ts(1000))));
+	assert!(!policy.is_stale(1, &device(None, None)));
+	assert!(policy.is_stale(2, &device(None, ts(3000))));
+	assert!(policy.is_stale(3, &device(None, None)));
+
+	// either condition makes a device stale
+	let policy = DevicePolicy { inactive_before: ts(2000), ..policy };
+	assert!(policy.is_stale(0, &device(None, ts(1000))));
+}
+
+#[test]
+fn stale_devices_least_recently_seen_first() {
+	let devices = vec![
+		seen_device("B", ts(2000)),
+		seen_device("NEVER", None),
+		seen_device("D", ts(4000)),
+		seen_device("A", ts(1000)),
+		seen_device("C", ts(3000)),
+	];
+
+	// the two most recently seen are kept; devices never seen count as the
+	// least recently seen
+	let policy = DevicePolicy {
+		inactive_before: None,
+		max_devices: Some(2),
+	};
+	assert_eq!(device_ids(&stale_of(devices.clone(), &policy)), ["NEVER", "A", "B"]);
+
+	// devices never seen are not inactive
+	let policy = DevicePolicy {
+		inactive_before: ts(3000),
+		max_devices: None,
+	};
+	assert_eq!(device_ids(&stale_of(devices.clone(), &policy)), ["A", "B"]);
+
+	assert!(stale_of(devices, &DevicePolicy::default()).is_empty());
+}